@@ -0,0 +1,83 @@
+use std::collections::BTreeMap;
+
+use crate::{MoveKind, Toolpath};
+
+const Z_RESOLUTION: f64 = 1e-3;
+
+/// A Z height the program repeatedly rapids at or retracts to.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SafePlane {
+    pub z: f64,
+    /// Number of horizontal rapids and retracts ending at this height.
+    pub count: usize,
+}
+
+/// Finds clearance/retract heights used at least `min_count` times, highest first.
+pub fn safe_planes(toolpath: &Toolpath, min_count: usize) -> Vec<SafePlane> {
+    let mut counts: BTreeMap<i64, usize> = BTreeMap::new();
+    for seg in &toolpath.segments {
+        if seg.kind != MoveKind::Rapid {
+            continue;
+        }
+        let dz = seg.end.z - seg.start.z;
+        let horizontal = dz.abs() <= Z_RESOLUTION
+            && ((seg.end.x - seg.start.x).abs() > Z_RESOLUTION
+                || (seg.end.y - seg.start.y).abs() > Z_RESOLUTION);
+        if horizontal || dz > Z_RESOLUTION {
+            *counts.entry((seg.end.z / Z_RESOLUTION).round() as i64).or_default() += 1;
+        }
+    }
+    counts
+        .into_iter()
+        .rev()
+        .filter(|&(_, count)| count >= min_count.max(1))
+        .map(|(key, count)| SafePlane {
+            z: key as f64 * Z_RESOLUTION,
+            count,
+        })
+        .collect()
+}
+
+/// Rapids that move in XY while below `clearance` Z in work coordinates, where a rapid can run
+/// the tool into the part.
+pub fn low_rapids(toolpath: &Toolpath, clearance: f64) -> Vec<usize> {
+    let mut line = 0;
+    let mut low = Vec::new();
+    for (idx, seg) in toolpath.segments.iter().enumerate() {
+        while toolpath.line_segment_ends.get(line).is_some_and(|&end| end <= idx) {
+            line += 1;
+        }
+        if seg.kind != MoveKind::Rapid {
+            continue;
+        }
+        let origin = toolpath.line_states.get(line).map_or(0.0, |state| state.work_origin.z);
+        let moves_xy = (seg.end.x - seg.start.x).abs() > Z_RESOLUTION || (seg.end.y - seg.start.y).abs() > Z_RESOLUTION;
+        if moves_xy && seg.start.z.min(seg.end.z) - origin < clearance - Z_RESOLUTION {
+            low.push(idx);
+        }
+    }
+    low
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ParseOptions, Parser};
+
+    #[test]
+    fn counts_repeated_retract_heights() {
+        let mut parser = Parser::new(ParseOptions::default());
+        let program = [
+            "G0 Z15", "X10", "Z5", "G1 Z-1 F100", "X20", "G0 Z5", "X30", "G1 Z-1", "G0 Z5", "Z15",
+        ];
+        for (idx, line) in program.iter().enumerate() {
+            parser.parse_line(line, idx + 1).unwrap();
+        }
+        let toolpath = parser.finish().unwrap();
+
+        let planes = safe_planes(&toolpath, 2);
+        assert_eq!(planes, [SafePlane { z: 15.0, count: 3 }, SafePlane { z: 5.0, count: 3 }]);
+        assert!(low_rapids(&toolpath, 5.0).is_empty());
+        assert_eq!(low_rapids(&toolpath, 10.0), [6]);
+    }
+}
@@ -0,0 +1,130 @@
+use std::ops::Range;
+
+use cnc_geom::{Vec2, Vec3};
+
+use crate::{LineSegment, Plane};
+
+/// Cap on a corner's offset, in tool radii, so near-reversals do not shoot off.
+const MAX_MITER: f64 = 4.0;
+
+/// A run of segments cut under G41/G42 with the tool radius `offset`, positive to the left
+/// of travel (G41) and negative to the right (G42), within the G17/G18/G19 `plane`.
+#[derive(Debug, Clone)]
+pub(crate) struct CompSpan {
+    pub segments: Range<usize>,
+    pub offset: f64,
+    pub plane: Plane,
+}
+
+/// Moves each span's path sideways within its plane by its offset, mitering the corners. As
+/// on a controller, the span's first move ramps onto the offset path and the move after it
+/// ramps off again.
+pub(crate) fn apply_cutter_comp(segments: &mut [LineSegment], spans: &[CompSpan]) {
+    for span in spans {
+        let run = &segments[span.segments.clone()];
+        let normals = left_normals(run, span.plane);
+        let Some(last) = normals.last().copied() else {
+            continue;
+        };
+        let shift = |normal: Vec2| from_plane(normal, span.plane) * span.offset;
+        let mut corners: Vec<Vec3> = normals.windows(2).map(|pair| shift(miter(pair[0], pair[1]))).collect();
+        corners.push(shift(last));
+        let start = span.segments.start;
+        for (index, corner) in corners.into_iter().enumerate() {
+            segments[start + index].end = segments[start + index].end + corner;
+            if let Some(next) = segments.get_mut(start + index + 1) {
+                next.start = next.start + corner;
+            }
+        }
+    }
+}
+
+/// Coordinates of `p` in `plane`, ordered as G2/G3 see it: XY, ZX or YZ.
+fn in_plane(p: Vec3, plane: Plane) -> Vec2 {
+    match plane {
+        Plane::XY => Vec2::new(p.x, p.y),
+        Plane::XZ => Vec2::new(p.z, p.x),
+        Plane::YZ => Vec2::new(p.y, p.z),
+    }
+}
+
+fn from_plane(v: Vec2, plane: Plane) -> Vec3 {
+    match plane {
+        Plane::XY => Vec3::new(v.x, v.y, 0.0),
+        Plane::XZ => Vec3::new(v.y, 0.0, v.x),
+        Plane::YZ => Vec3::new(0.0, v.x, v.y),
+    }
+}
+
+/// Unit left normal of each segment's in-plane direction; segments without travel in the
+/// plane borrow the previous one's, or the next one's at the start of the run. Empty without
+/// any travel in the plane.
+fn left_normals(run: &[LineSegment], plane: Plane) -> Vec<Vec2> {
+    let own: Vec<Option<Vec2>> = run
+        .iter()
+        .map(|seg| {
+            let (start, end) = (in_plane(seg.start, plane), in_plane(seg.end, plane));
+            let (dx, dy) = (end.x - start.x, end.y - start.y);
+            let length = dx.hypot(dy);
+            (length > 1e-12).then(|| Vec2::new(-dy / length, dx / length))
+        })
+        .collect();
+    let Some(first) = own.iter().flatten().next().copied() else {
+        return Vec::new();
+    };
+    let mut current = first;
+    own.into_iter()
+        .map(|normal| {
+            current = normal.unwrap_or(current);
+            current
+        })
+        .collect()
+}
+
+/// Offset direction at the corner between segments with normals `a` and `b`, scaled so both
+/// offset lines pass through it.
+fn miter(a: Vec2, b: Vec2) -> Vec2 {
+    let sum = a + b;
+    let length = sum.x.hypot(sum.y);
+    if length < 1e-9 {
+        return a;
+    }
+    let bisector = sum * (1.0 / length);
+    let cos = bisector.x * a.x + bisector.y * a.y;
+    bisector * (1.0 / cos).min(MAX_MITER)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{parse_str, ParseOptions};
+    use cnc_geom::Vec3;
+
+    #[test]
+    fn g41_offsets_the_contour_left_of_travel() {
+        let options = ParseOptions::default().with_tool_diameter(1, 4.0);
+        let program = "T1 M6\nG0 X0 Y-5\nG41 D1 G1 X0 Y0 F100\nX10\nY10\nG40 G0 X10 Y20";
+        let toolpath = parse_str(program, options).unwrap();
+        let points: Vec<(Vec3, Vec3)> = toolpath.segments.iter().map(|seg| (seg.start, seg.end)).collect();
+        let p = |x, y| Vec3::new(x, y, 0.0);
+        assert_eq!(
+            points,
+            [
+                (p(0.0, 0.0), p(0.0, -5.0)),
+                (p(0.0, -5.0), p(-2.0, 2.0)),
+                (p(-2.0, 2.0), p(8.0, 2.0)),
+                (p(8.0, 2.0), p(8.0, 10.0)),
+                (p(8.0, 10.0), p(10.0, 20.0)),
+            ]
+        );
+        assert_eq!(toolpath.bounds.min, p(-2.0, -5.0));
+
+        let unknown = parse_str("G42 D3 G1 X5 F100", ParseOptions::default().with_tool_diameter(1, 4.0)).unwrap();
+        assert_eq!(unknown.segments[0].end, p(5.0, 0.0));
+        assert_eq!(unknown.diagnostics[0].message, "G42: T3 has no diameter in the tool table");
+
+        let lathe = "T1 M6\nG18 G0 X10 Z5\nG41 G1 X10 Z0 F100\nZ-20\nG40 G0 X20";
+        let toolpath = parse_str(lathe, ParseOptions::default().with_tool_diameter(1, 4.0)).unwrap();
+        assert!(toolpath.segments.iter().all(|seg| seg.start.y == 0.0 && seg.end.y == 0.0));
+        assert_eq!(toolpath.segments[2].end, Vec3::new(8.0, 0.0, -20.0));
+    }
+}
@@ -0,0 +1,78 @@
+use cnc_geom::Vec3;
+
+use crate::{MoveKind, Toolpath};
+
+const EPSILON: f64 = 1e-9;
+
+/// A direction change between two consecutive feed moves.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Corner {
+    /// The move leaving the corner; the corner is at its start.
+    pub segment: usize,
+    /// Turn in degrees, 0 for straight on and 180 for a full reversal.
+    pub angle: f64,
+}
+
+/// Corners between consecutive feed moves turning by at least `min_angle` degrees.
+///
+/// Zero-length moves are skipped; a rapid or probe move in between breaks the chain.
+pub fn sharp_corners(toolpath: &Toolpath, min_angle: f64) -> Vec<Corner> {
+    let mut corners = Vec::new();
+    let mut previous: Option<Vec3> = None;
+    for (idx, seg) in toolpath.segments.iter().enumerate() {
+        if seg.kind != MoveKind::Feed {
+            previous = None;
+            continue;
+        }
+        let Some(direction) = unit(seg.end - seg.start) else {
+            continue;
+        };
+        if let Some(prev) = previous {
+            let cos = (prev.x * direction.x + prev.y * direction.y + prev.z * direction.z).clamp(-1.0, 1.0);
+            let angle = cos.acos().to_degrees();
+            if angle >= min_angle {
+                corners.push(Corner { segment: idx, angle });
+            }
+        }
+        previous = Some(direction);
+    }
+    corners
+}
+
+fn unit(v: Vec3) -> Option<Vec3> {
+    let len = v.length();
+    (len > EPSILON).then(|| v * (1.0 / len))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::LineSegment;
+    use cnc_geom::RotaryAngles;
+
+    #[test]
+    fn reports_turns_above_the_threshold() {
+        let seg = |kind, start: (f64, f64), end: (f64, f64)| LineSegment {
+            start: Vec3::new(start.0, start.1, 0.0),
+            end: Vec3::new(end.0, end.1, 0.0),
+            kind,
+            feed: Some(500.0),
+            rotary: RotaryAngles::default(),
+        };
+        let toolpath = Toolpath {
+            segments: vec![
+                seg(MoveKind::Feed, (0.0, 0.0), (10.0, 0.0)),
+                seg(MoveKind::Feed, (10.0, 0.0), (20.0, 1.0)),
+                seg(MoveKind::Feed, (20.0, 1.0), (20.0, 1.0)),
+                seg(MoveKind::Feed, (20.0, 1.0), (20.0, 10.0)),
+                seg(MoveKind::Rapid, (20.0, 10.0), (0.0, 10.0)),
+                seg(MoveKind::Feed, (0.0, 10.0), (0.0, 0.0)),
+            ],
+            ..Toolpath::default()
+        };
+        let corners = sharp_corners(&toolpath, 45.0);
+        assert_eq!(corners.len(), 1);
+        assert_eq!(corners[0].segment, 3);
+        assert!((corners[0].angle - 84.29).abs() < 0.01);
+    }
+}
@@ -0,0 +1,99 @@
+use cnc_geom::{Bounds2, Vec2};
+
+use crate::{MoveKind, Toolpath};
+
+/// Lowest Z reached by feed moves in each cell of a square XY grid.
+#[derive(Debug, Clone)]
+pub struct DepthMap {
+    /// XY extent of the grid; cell `(0, 0)` is at `bounds.min`.
+    pub bounds: Bounds2,
+    pub cell: f64,
+    pub columns: usize,
+    pub rows: usize,
+    /// Row-major from the minimum Y row; `None` where no feed move passes.
+    pub cells: Vec<Option<f64>>,
+}
+
+impl DepthMap {
+    pub fn get(&self, column: usize, row: usize) -> Option<f64> {
+        self.cells.get(row * self.columns + column).copied().flatten()
+    }
+
+    /// Shallowest and deepest recorded floor.
+    pub fn z_range(&self) -> Option<(f64, f64)> {
+        self.cells.iter().flatten().fold(None, |range, &z| match range {
+            None => Some((z, z)),
+            Some((min, max)) => Some((min.min(z), max.max(z))),
+        })
+    }
+}
+
+/// Rasterizes the tool tip path of feed moves into at most `max_columns` × `max_rows` square
+/// cells around their XY extent.
+pub fn min_z_map(toolpath: &Toolpath, max_columns: usize, max_rows: usize) -> DepthMap {
+    let feeds = || toolpath.segments.iter().filter(|seg| seg.kind == MoveKind::Feed);
+    let mut bounds = Bounds2::new();
+    for seg in feeds() {
+        bounds.include(Vec2::new(seg.start.x, seg.start.y));
+        bounds.include(Vec2::new(seg.end.x, seg.end.y));
+    }
+    let (max_columns, max_rows) = (max_columns.max(1), max_rows.max(1));
+    let cell = (bounds.width() / max_columns as f64)
+        .max(bounds.height() / max_rows as f64)
+        .max(1e-6);
+    let columns = ((bounds.width() / cell).floor() as usize + 1).min(max_columns);
+    let rows = ((bounds.height() / cell).floor() as usize + 1).min(max_rows);
+    let mut cells = vec![None; columns * rows];
+    for seg in feeds() {
+        let d = seg.end - seg.start;
+        let steps = ((d.x.hypot(d.y) / (cell * 0.5)).ceil() as usize).max(1);
+        for step in 0..=steps {
+            let p = seg.start + d * (step as f64 / steps as f64);
+            let column = (((p.x - bounds.min.x) / cell) as usize).min(columns - 1);
+            let row = (((p.y - bounds.min.y) / cell) as usize).min(rows - 1);
+            let slot: &mut Option<f64> = &mut cells[row * columns + column];
+            *slot = Some(slot.map_or(p.z, |z| z.min(p.z)));
+        }
+    }
+    DepthMap {
+        bounds,
+        cell,
+        columns,
+        rows,
+        cells,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::LineSegment;
+    use cnc_geom::{RotaryAngles, Vec3};
+
+    #[test]
+    fn keeps_the_deepest_pass_per_cell() {
+        let seg = |kind, start: Vec3, end: Vec3| LineSegment {
+            start,
+            end,
+            kind,
+            feed: None,
+            rotary: RotaryAngles::default(),
+        };
+        let toolpath = Toolpath {
+            segments: vec![
+                seg(MoveKind::Feed, Vec3::new(0.0, 0.0, -1.0), Vec3::new(10.0, 0.0, -1.0)),
+                seg(MoveKind::Feed, Vec3::new(10.0, 0.0, -1.0), Vec3::new(10.0, 10.0, -2.0)),
+                seg(MoveKind::Rapid, Vec3::new(10.0, 10.0, 5.0), Vec3::new(0.0, 10.0, 5.0)),
+                seg(MoveKind::Feed, Vec3::new(0.0, 0.0, -3.0), Vec3::new(1.0, 0.0, -3.0)),
+            ],
+            ..Toolpath::default()
+        };
+        let map = min_z_map(&toolpath, 10, 10);
+        assert_eq!((map.columns, map.rows), (10, 10));
+        assert_eq!(map.get(0, 0), Some(-3.0));
+        assert_eq!(map.get(5, 0), Some(-1.0));
+        assert_eq!(map.get(9, 9), Some(-2.0));
+        assert_eq!(map.get(0, 9), None);
+        assert_eq!(map.z_range(), Some((-3.0, -1.0)));
+    }
+}
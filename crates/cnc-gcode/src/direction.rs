@@ -0,0 +1,155 @@
+use std::ops::Range;
+
+use cnc_geom::Vec3;
+
+use crate::{MoveKind, Toolpath};
+
+const CLOSE_TOLERANCE: f64 = 1e-3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CutDirection {
+    Climb,
+    Conventional,
+}
+
+/// A closed constant-Z feed loop in the XY plane.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProfileLoop {
+    pub segments: Range<usize>,
+    /// Shoelace area in the XY plane; positive for counter-clockwise loops.
+    pub signed_area: f64,
+    /// True when the loop is nested inside an odd number of larger loops (a pocket or hole wall).
+    pub inside: bool,
+    pub direction: CutDirection,
+}
+
+/// Finds closed profiles and classifies them assuming a clockwise (M3) spindle.
+///
+/// Outer profiles are cut with the material on the right of travel when climbing, so a
+/// clockwise outer loop is climb and a counter-clockwise one is conventional; nested loops
+/// are treated as inside walls and flip.
+pub fn analyze_cut_direction(toolpath: &Toolpath) -> Vec<ProfileLoop> {
+    let mut loops: Vec<(Range<usize>, f64)> = Vec::new();
+    let segments = &toolpath.segments;
+    let mut run_start = 0;
+    for (idx, seg) in segments.iter().enumerate() {
+        let planar = seg.kind == MoveKind::Feed && (seg.end.z - seg.start.z).abs() <= CLOSE_TOLERANCE;
+        let continuous = idx == run_start
+            || (close(seg.start, segments[idx - 1].end)
+                && (seg.start.z - segments[run_start].start.z).abs() <= CLOSE_TOLERANCE);
+        if !planar {
+            run_start = idx + 1;
+            continue;
+        }
+        if !continuous {
+            run_start = idx;
+        }
+        if idx >= run_start + 2 && close(seg.end, segments[run_start].start) {
+            let range = run_start..idx + 1;
+            let area = signed_area(toolpath, range.clone());
+            if area.abs() > CLOSE_TOLERANCE {
+                loops.push((range, area));
+            }
+            run_start = idx + 1;
+        }
+    }
+
+    loops
+        .iter()
+        .map(|(range, area)| {
+            let probe = segments[range.start].start;
+            let depth = loops
+                .iter()
+                .filter(|(other, other_area)| {
+                    other_area.abs() > area.abs() * 1.01 && contains(toolpath, other.clone(), probe)
+                })
+                .count();
+            let inside = depth % 2 == 1;
+            let clockwise = *area < 0.0;
+            let direction = if clockwise != inside {
+                CutDirection::Climb
+            } else {
+                CutDirection::Conventional
+            };
+            ProfileLoop {
+                segments: range.clone(),
+                signed_area: *area,
+                inside,
+                direction,
+            }
+        })
+        .collect()
+}
+
+fn close(a: Vec3, b: Vec3) -> bool {
+    (a.x - b.x).abs() <= CLOSE_TOLERANCE && (a.y - b.y).abs() <= CLOSE_TOLERANCE
+}
+
+fn signed_area(toolpath: &Toolpath, range: Range<usize>) -> f64 {
+    toolpath.segments[range]
+        .iter()
+        .map(|seg| seg.start.x * seg.end.y - seg.end.x * seg.start.y)
+        .sum::<f64>()
+        * 0.5
+}
+
+fn contains(toolpath: &Toolpath, range: Range<usize>, point: Vec3) -> bool {
+    let mut inside = false;
+    for seg in &toolpath.segments[range] {
+        let (a, b) = (seg.start, seg.end);
+        if (a.y > point.y) != (b.y > point.y) {
+            let x = a.x + (point.y - a.y) / (b.y - a.y) * (b.x - a.x);
+            if point.x < x {
+                inside = !inside;
+            }
+        }
+    }
+    inside
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::LineSegment;
+    use cnc_geom::RotaryAngles;
+
+    fn square(points: &[(f64, f64)]) -> Vec<LineSegment> {
+        points
+            .iter()
+            .zip(points.iter().cycle().skip(1))
+            .map(|(a, b)| LineSegment {
+                start: Vec3::new(a.0, a.1, -1.0),
+                end: Vec3::new(b.0, b.1, -1.0),
+                kind: MoveKind::Feed,
+                feed: Some(500.0),
+                rotary: RotaryAngles::default(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn classifies_outer_and_inner_loops() {
+        let mut segments = square(&[(0.0, 0.0), (0.0, 10.0), (10.0, 10.0), (10.0, 0.0)]);
+        segments.push(LineSegment {
+            start: Vec3::new(0.0, 0.0, -1.0),
+            end: Vec3::new(3.0, 3.0, 5.0),
+            kind: MoveKind::Rapid,
+            feed: None,
+            rotary: RotaryAngles::default(),
+        });
+        segments.extend(square(&[(3.0, 3.0), (3.0, 6.0), (6.0, 6.0), (6.0, 3.0)]));
+        let toolpath = Toolpath {
+            segments,
+            ..Toolpath::default()
+        };
+
+        let loops = analyze_cut_direction(&toolpath);
+        assert_eq!(loops.len(), 2);
+        assert_eq!(loops[0].segments, 0..4);
+        assert!(!loops[0].inside);
+        assert_eq!(loops[0].direction, CutDirection::Climb);
+        assert_eq!(loops[1].segments, 5..9);
+        assert!(loops[1].inside);
+        assert_eq!(loops[1].direction, CutDirection::Conventional);
+    }
+}
@@ -0,0 +1,82 @@
+use crate::{Coolant, Toolpath};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ProgramEventKind {
+    /// M0, or with `optional`, M1.
+    Stop { optional: bool },
+    /// M2, or with `rewind`, M30.
+    End { rewind: bool },
+    /// M7, M8 or M9, as the coolant it leaves on.
+    Coolant(Coolant),
+}
+
+impl ProgramEventKind {
+    pub fn code(self) -> &'static str {
+        match self {
+            ProgramEventKind::Stop { optional: false } => "M0",
+            ProgramEventKind::Stop { optional: true } => "M1",
+            ProgramEventKind::End { rewind: false } => "M2",
+            ProgramEventKind::End { rewind: true } => "M30",
+            ProgramEventKind::Coolant(Coolant::Mist) => "M7",
+            ProgramEventKind::Coolant(Coolant::Flood) => "M8",
+            ProgramEventKind::Coolant(Coolant::Off) => "M9",
+        }
+    }
+}
+
+/// A stop, program end or coolant change.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ProgramEvent {
+    /// 0-based source line.
+    pub line: usize,
+    /// Segments executed before the event: a coolant change takes effect before the line's
+    /// moves, a stop or end after them.
+    pub segment: usize,
+    pub kind: ProgramEventKind,
+}
+
+/// Stops, ends and coolant changes in program order; coolant regions run from one coolant
+/// event to the next.
+pub fn program_events(toolpath: &Toolpath) -> Vec<ProgramEvent> {
+    let mut events = Vec::new();
+    let mut coolant = Coolant::Off;
+    for (line, state) in toolpath.line_states.iter().enumerate() {
+        if state.coolant != coolant {
+            coolant = state.coolant;
+            events.push(ProgramEvent {
+                line,
+                segment: line.checked_sub(1).map_or(0, |prev| toolpath.line_segment_ends[prev]),
+                kind: ProgramEventKind::Coolant(coolant),
+            });
+        }
+    }
+    events.extend(toolpath.stops.iter().map(|stop| ProgramEvent {
+        line: stop.line,
+        segment: stop.segment,
+        kind: ProgramEventKind::Stop { optional: stop.optional },
+    }));
+    events.extend(toolpath.ends.iter().map(|end| ProgramEvent {
+        line: end.line,
+        segment: end.segment,
+        kind: ProgramEventKind::End { rewind: end.rewind },
+    }));
+    events.sort_by_key(|event| event.line);
+    events
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse_str, ParseOptions};
+
+    #[test]
+    fn events_list_coolant_stops_and_ends_by_line() {
+        let program = "G0 X1\nM8\nG1 X2 F100\nM1\nM9 G0 X0\nM30\n";
+        let toolpath = parse_str(program, ParseOptions::default()).unwrap();
+        let events: Vec<(usize, usize, &str)> = program_events(&toolpath)
+            .into_iter()
+            .map(|event| (event.line, event.segment, event.kind.code()))
+            .collect();
+        assert_eq!(events, [(1, 1, "M8"), (3, 2, "M1"), (4, 2, "M9"), (5, 3, "M30")]);
+    }
+}
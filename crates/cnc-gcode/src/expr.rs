@@ -0,0 +1,306 @@
+use std::collections::HashMap;
+
+use anyhow::{anyhow, Result};
+
+/// Numbered parameters a LinuxCNC `call` passes arguments in and scopes to the subroutine.
+const CALL_PARAMETERS: u32 = 30;
+
+/// Binary operators from loosest to tightest binding.
+const PRECEDENCE: &[&[&str]] = &[
+    &["AND", "OR", "XOR"],
+    &["EQ", "NE", "GT", "GE", "LT", "LE"],
+    &["+", "-"],
+    &["*", "/", "MOD"],
+    &["**"],
+];
+
+/// A LinuxCNC `#` parameter: numbered (`#1`) or named (`#<depth>`, global when it starts
+/// with `_`).
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub(crate) enum Parameter {
+    Numbered(u32),
+    Named(String),
+}
+
+/// A `#param = value` setting, applied once its whole line has been read.
+pub(crate) type Assignment = (Parameter, f64);
+
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Parameters {
+    numbered: HashMap<u32, f64>,
+    named: HashMap<String, f64>,
+}
+
+/// The caller's values of the parameters a subroutine call shadows.
+pub(crate) struct Scope {
+    numbered: HashMap<u32, f64>,
+    named: HashMap<String, f64>,
+}
+
+impl Parameters {
+    fn get(&self, param: &Parameter) -> Result<f64> {
+        match param {
+            Parameter::Numbered(number) => Ok(self.numbered.get(number).copied().unwrap_or(0.0)),
+            Parameter::Named(name) => self
+                .named
+                .get(name)
+                .copied()
+                .ok_or_else(|| anyhow!("#<{}> is not set", name)),
+        }
+    }
+
+    pub(crate) fn set(&mut self, param: Parameter, value: f64) {
+        match param {
+            Parameter::Numbered(number) => self.numbered.insert(number, value),
+            Parameter::Named(name) => self.named.insert(name, value),
+        };
+    }
+
+    /// Enters a subroutine: `#1`, `#2`… hold `args` and local named parameters start unset.
+    pub(crate) fn enter(&mut self, args: &[f64]) -> Scope {
+        let numbered = (1..=CALL_PARAMETERS)
+            .filter_map(|number| self.numbered.remove(&number).map(|value| (number, value)))
+            .collect();
+        let (named, globals) = std::mem::take(&mut self.named)
+            .into_iter()
+            .partition(|(name, _)| !name.starts_with('_'));
+        self.named = globals;
+        self.numbered.extend((1..=CALL_PARAMETERS).zip(args.iter().copied()));
+        Scope { numbered, named }
+    }
+
+    pub(crate) fn leave(&mut self, scope: Scope) {
+        self.numbered.retain(|number, _| !(1..=CALL_PARAMETERS).contains(number));
+        self.named.retain(|name, _| name.starts_with('_'));
+        self.numbered.extend(scope.numbered);
+        self.named.extend(scope.named);
+    }
+}
+
+/// Reads one value from the start of `text`: a number, `#` parameter, `[expression]`, function
+/// or a signed one of those. Returns it with the bytes read.
+pub(crate) fn read_value(text: &str, params: &Parameters) -> Result<(f64, usize)> {
+    let mut reader = Reader::new(text, params);
+    let value = reader.value()?;
+    Ok((value, reader.pos))
+}
+
+/// Reads the parameter named at the start of `text`, just after its `#`.
+pub(crate) fn read_parameter(text: &str, params: &Parameters) -> Result<(Parameter, usize)> {
+    let mut reader = Reader::new(text, params);
+    let param = reader.parameter()?;
+    Ok((param, reader.pos))
+}
+
+/// Evaluates `text` as a single value.
+pub(crate) fn evaluate(text: &str, params: &Parameters) -> Result<f64> {
+    let (value, used) = read_value(text, params)?;
+    match text[used..].trim() {
+        "" => Ok(value),
+        rest => Err(anyhow!("unexpected '{}' after expression", rest)),
+    }
+}
+
+/// Values of the `[a] [b] …` arguments of a `call`.
+pub(crate) fn read_arguments(text: &str, params: &Parameters) -> Result<Vec<f64>> {
+    let mut reader = Reader::new(text, params);
+    let mut args = Vec::new();
+    while reader.peek().is_some() {
+        args.push(reader.value()?);
+    }
+    Ok(args)
+}
+
+struct Reader<'a> {
+    text: &'a str,
+    pos: usize,
+    params: &'a Parameters,
+}
+
+impl<'a> Reader<'a> {
+    fn new(text: &'a str, params: &'a Parameters) -> Self {
+        Self { text, pos: 0, params }
+    }
+
+    fn rest(&self) -> &'a str {
+        &self.text[self.pos..]
+    }
+
+    fn peek(&mut self) -> Option<char> {
+        let rest = self.rest();
+        self.pos += rest.len() - rest.trim_start().len();
+        self.rest().chars().next()
+    }
+
+    fn eat(&mut self, ch: char) -> bool {
+        let found = self.peek() == Some(ch);
+        if found {
+            self.pos += ch.len_utf8();
+        }
+        found
+    }
+
+    fn expect(&mut self, ch: char) -> Result<()> {
+        if self.eat(ch) {
+            Ok(())
+        } else {
+            Err(anyhow!("expected '{}' at '{}'", ch, self.rest()))
+        }
+    }
+
+    fn take_while(&mut self, keep: impl Fn(char) -> bool) -> &'a str {
+        let rest = self.rest();
+        let len = rest.find(|ch: char| !keep(ch)).unwrap_or(rest.len());
+        self.pos += len;
+        &rest[..len]
+    }
+
+    fn value(&mut self) -> Result<f64> {
+        match self.peek() {
+            Some('[') => {
+                self.pos += 1;
+                let value = self.binary(0)?;
+                self.expect(']')?;
+                Ok(value)
+            }
+            Some('#') => {
+                self.pos += 1;
+                let param = self.parameter()?;
+                self.params.get(&param)
+            }
+            Some('-') => {
+                self.pos += 1;
+                Ok(-self.value()?)
+            }
+            Some('+') => {
+                self.pos += 1;
+                self.value()
+            }
+            Some(ch) if ch.is_ascii_digit() || ch == '.' => {
+                let digits = self.take_while(|ch| ch.is_ascii_digit() || ch == '.');
+                Ok(digits.parse::<f64>()?)
+            }
+            Some(ch) if ch.is_ascii_alphabetic() => self.function(),
+            _ => Err(anyhow!("expected a value at '{}'", self.rest())),
+        }
+    }
+
+    fn parameter(&mut self) -> Result<Parameter> {
+        if self.eat('<') {
+            let name: String = self
+                .take_while(|ch| ch != '>')
+                .chars()
+                .filter(|ch| !ch.is_whitespace())
+                .collect();
+            self.expect('>')?;
+            return Ok(Parameter::Named(name.to_ascii_lowercase()));
+        }
+        let number = self.value()?.round();
+        if number < 0.0 {
+            return Err(anyhow!("negative parameter number #{}", number));
+        }
+        Ok(Parameter::Numbered(number as u32))
+    }
+
+    fn binary(&mut self, level: usize) -> Result<f64> {
+        let Some(operators) = PRECEDENCE.get(level) else {
+            return self.value();
+        };
+        let mut left = self.binary(level + 1)?;
+        while let Some(op) = self.operator(operators) {
+            let right = self.binary(level + 1)?;
+            left = apply(op, left, right)?;
+        }
+        Ok(left)
+    }
+
+    fn operator(&mut self, operators: &[&'static str]) -> Option<&'static str> {
+        self.peek()?;
+        let rest = self.rest();
+        let op = operators.iter().copied().find(|op| {
+            rest.get(..op.len()).is_some_and(|head| head.eq_ignore_ascii_case(op))
+                && !(*op == "*" && rest[1..].starts_with('*'))
+        })?;
+        self.pos += op.len();
+        Some(op)
+    }
+
+    fn function(&mut self) -> Result<f64> {
+        let name = self.take_while(|ch| ch.is_ascii_alphabetic()).to_ascii_uppercase();
+        self.expect('[')?;
+        if name == "EXISTS" {
+            self.expect('#')?;
+            let param = self.parameter()?;
+            self.expect(']')?;
+            return Ok(truth(self.params.get(&param).is_ok()));
+        }
+        let arg = self.binary(0)?;
+        self.expect(']')?;
+        Ok(match name.as_str() {
+            "ATAN" => {
+                self.expect('/')?;
+                arg.atan2(self.value()?).to_degrees()
+            }
+            "ABS" => arg.abs(),
+            "ACOS" => arg.acos().to_degrees(),
+            "ASIN" => arg.asin().to_degrees(),
+            "COS" => arg.to_radians().cos(),
+            "SIN" => arg.to_radians().sin(),
+            "TAN" => arg.to_radians().tan(),
+            "EXP" => arg.exp(),
+            "LN" => arg.ln(),
+            "SQRT" => arg.sqrt(),
+            "FIX" => arg.floor(),
+            "FUP" => arg.ceil(),
+            "ROUND" => arg.round(),
+            _ => return Err(anyhow!("unknown function {}", name)),
+        })
+    }
+}
+
+fn apply(op: &str, a: f64, b: f64) -> Result<f64> {
+    Ok(match op {
+        "**" => a.powf(b),
+        "*" => a * b,
+        "/" if b == 0.0 => return Err(anyhow!("division by zero")),
+        "/" => a / b,
+        "MOD" => a.rem_euclid(b),
+        "+" => a + b,
+        "-" => a - b,
+        "EQ" => truth(a == b),
+        "NE" => truth(a != b),
+        "GT" => truth(a > b),
+        "GE" => truth(a >= b),
+        "LT" => truth(a < b),
+        "LE" => truth(a <= b),
+        "AND" => truth(a != 0.0 && b != 0.0),
+        "OR" => truth(a != 0.0 || b != 0.0),
+        _ => truth((a != 0.0) != (b != 0.0)),
+    })
+}
+
+fn truth(value: bool) -> f64 {
+    if value { 1.0 } else { 0.0 }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn evaluates_expressions_with_precedence_and_parameters() {
+        let mut params = Parameters::default();
+        params.set(Parameter::Numbered(1), 4.0);
+        params.set(Parameter::Named("_depth".to_string()), -2.0);
+        assert_eq!(evaluate("[1 + 2 * 3 ** 2]", &params).unwrap(), 19.0);
+        assert_eq!(evaluate("[#1 GT 3 AND #< _Depth > LT 0]", &params).unwrap(), 1.0);
+        assert_eq!(evaluate("[ATAN[1]/[1] + SQRT[#1] + -#[#1 - 3]]", &params).unwrap(), 43.0);
+        assert_eq!(read_value("#1 Y2", &params).unwrap(), (4.0, 2));
+        assert!(evaluate("[#<depth>]", &params).is_err());
+
+        let scope = params.enter(&[7.0]);
+        assert_eq!(evaluate("[#1 + #2 + #<_depth>]", &params).unwrap(), 5.0);
+        params.leave(scope);
+        assert_eq!(evaluate("#1", &params).unwrap(), 4.0);
+    }
+}
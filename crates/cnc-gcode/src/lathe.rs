@@ -0,0 +1,207 @@
+use crate::MoveKind;
+
+/// Moves beyond this count are dropped, so a tiny depth of cut cannot stall the parse.
+const MAX_CYCLE_MOVES: usize = 20_000;
+
+/// X is a diameter on Fanuc lathes, so radial amounts move X twice as far.
+const DIAMETER: f64 = 2.0;
+
+/// A lathe point as (X, Z).
+pub(crate) type Point = (f64, f64);
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct LatheMove {
+    pub kind: MoveKind,
+    pub x: f64,
+    pub z: f64,
+}
+
+/// Parameters set by the first block of a two-block G71–G75 cycle, in mm.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct LatheParams {
+    /// G71 `U` / G72 `W`: radial depth of cut per roughing pass.
+    pub depth: f64,
+    /// G71/G72 `R`: retract after each roughing pass.
+    pub retract: f64,
+    /// G73 `U`/`W`: total relief in X (radius) and Z.
+    pub relief: Point,
+    /// G73 `R`: number of pattern passes.
+    pub passes: u32,
+    /// G74/G75 `R`: retract after each peck.
+    pub peck_retract: f64,
+    /// G76 `P` first digits: passes at full depth.
+    pub thread_finish_passes: u32,
+    /// G76 `P` last digits: tool angle in degrees.
+    pub thread_angle: f64,
+}
+
+impl Default for LatheParams {
+    fn default() -> Self {
+        Self {
+            depth: 1.0,
+            retract: 0.5,
+            relief: (0.0, 0.0),
+            passes: 1,
+            peck_retract: 0.5,
+            thread_finish_passes: 1,
+            thread_angle: 60.0,
+        }
+    }
+}
+
+/// G71 (`facing == false`) and G72 stock removal: passes parallel to Z (X for facing) from
+/// `start` until they meet `profile`, then one contour pass along the profile.
+pub(crate) fn rough_passes(start: Point, profile: &[Point], params: &LatheParams, facing: bool) -> Vec<LatheMove> {
+    // Work in (step, cut) coordinates: G71 steps X and cuts along Z, G72 the reverse.
+    let swap = |(a, b): Point| if facing { (b, a) } else { (a, b) };
+    let (step_scale, cut_scale) = if facing { (1.0, DIAMETER) } else { (DIAMETER, 1.0) };
+    let (Some(&first), Some(&last)) = (profile.first(), profile.last()) else {
+        return Vec::new();
+    };
+    let (s0, c0) = swap(start);
+    let points: Vec<Point> = profile.iter().map(|&point| swap(point)).collect();
+    let (dir, cut_dir) = ((swap(first).0 - s0).signum(), (swap(last).1 - c0).signum());
+    let extreme = points.iter().map(|p| p.0 * dir).fold(f64::MIN, f64::max) * dir;
+    let (step, back_s, back_c) = (
+        params.depth * step_scale,
+        params.retract * step_scale * dir,
+        params.retract * cut_scale * cut_dir,
+    );
+    let mut moves = Vec::new();
+    let mut push = |kind, point: Point| {
+        let (x, z) = swap(point);
+        moves.push(LatheMove { kind, x, z });
+    };
+    let mut level = s0 + dir * step;
+    let mut count = 0;
+    while step > 0.0 && dir != 0.0 && (extreme - level) * dir > 1e-9 && count < MAX_CYCLE_MOVES {
+        let end = crossing(&points, level).unwrap_or(swap(last).1);
+        push(MoveKind::Rapid, (level, c0));
+        push(MoveKind::Feed, (level, end));
+        push(MoveKind::Feed, (level - back_s, end - back_c));
+        push(MoveKind::Rapid, (level - back_s, c0));
+        level += dir * step;
+        count += 4;
+    }
+    push(MoveKind::Rapid, points[0]);
+    for &point in &points[1..] {
+        push(MoveKind::Feed, point);
+    }
+    push(MoveKind::Rapid, (s0, c0));
+    moves
+}
+
+/// Cut coordinate where the profile first reaches step coordinate `level`.
+fn crossing(points: &[Point], level: f64) -> Option<f64> {
+    points.windows(2).find_map(|pair| {
+        let ((s1, c1), (s2, c2)) = (pair[0], pair[1]);
+        ((s1 - level) * (s2 - level) <= 0.0 && s1 != s2).then(|| c1 + (c2 - c1) * (level - s1) / (s2 - s1))
+    })
+}
+
+/// G73 pattern repeat: the profile traced `passes` times, from the full relief down to none.
+pub(crate) fn pattern_passes(start: Point, profile: &[Point], params: &LatheParams) -> Vec<LatheMove> {
+    let passes = params.passes.max(1);
+    let mut moves = Vec::new();
+    for pass in 1..=passes {
+        let remaining = if passes > 1 {
+            (passes - pass) as f64 / (passes - 1) as f64
+        } else {
+            0.0
+        };
+        let (dx, dz) = (params.relief.0 * DIAMETER * remaining, params.relief.1 * remaining);
+        for (idx, &(x, z)) in profile.iter().enumerate() {
+            let kind = if idx == 0 { MoveKind::Rapid } else { MoveKind::Feed };
+            moves.push(LatheMove { kind, x: x + dx, z: z + dz });
+        }
+        moves.push(LatheMove {
+            kind: MoveKind::Rapid,
+            x: start.0,
+            z: start.1,
+        });
+    }
+    moves
+}
+
+/// G74 (`face == true`, pecking along Z and shifting X) and G75 (pecking along X, shifting Z)
+/// from `start` to the far corner `end`.
+pub(crate) fn groove_passes(
+    start: Point,
+    end: Point,
+    shift: f64,
+    peck: f64,
+    params: &LatheParams,
+    face: bool,
+) -> Vec<LatheMove> {
+    // Work in (shift, peck) coordinates.
+    let swap = |(a, b): Point| if face { (a, b) } else { (b, a) };
+    let (shift_scale, peck_scale) = if face { (DIAMETER, 1.0) } else { (1.0, DIAMETER) };
+    let ((q0, p0), (q1, p1)) = (swap(start), swap(end));
+    let (shift, peck) = (shift * shift_scale, peck * peck_scale);
+    let retract = params.peck_retract * peck_scale * (p1 - p0).signum();
+    let mut moves = Vec::new();
+    let mut push = |kind, point: Point| {
+        let (x, z) = swap(point);
+        moves.push(LatheMove { kind, x, z });
+    };
+    let mut column = q0;
+    let mut count = 0;
+    loop {
+        push(MoveKind::Rapid, (column, p0));
+        let mut depth = p0;
+        while count < MAX_CYCLE_MOVES {
+            depth = if peck > 0.0 && (p1 - depth).abs() > peck {
+                depth + peck * (p1 - p0).signum()
+            } else {
+                p1
+            };
+            push(MoveKind::Feed, (column, depth));
+            count += 2;
+            if depth == p1 {
+                break;
+            }
+            push(MoveKind::Rapid, (column, depth - retract));
+        }
+        push(MoveKind::Rapid, (column, p0));
+        if column == q1 || shift <= 0.0 || count >= MAX_CYCLE_MOVES {
+            break;
+        }
+        column = if (q1 - column).abs() > shift {
+            column + shift * (q1 - q0).signum()
+        } else {
+            q1
+        };
+    }
+    push(MoveKind::Rapid, (q0, p0));
+    moves
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn roughing_steps_down_to_the_profile() {
+        let profile = [(20.0, 2.0), (20.0, -10.0), (30.0, -15.0), (40.0, -15.0)];
+        let params = LatheParams {
+            depth: 2.5,
+            retract: 0.5,
+            ..LatheParams::default()
+        };
+        let moves = rough_passes((40.0, 2.0), &profile, &params, false);
+        let cuts: Vec<Point> = moves
+            .iter()
+            .filter(|m| m.kind == MoveKind::Feed)
+            .step_by(2)
+            .take(4)
+            .map(|m| (m.x, m.z))
+            .collect();
+        assert_eq!(cuts, [(35.0, -15.0), (30.0, -15.0), (25.0, -12.5), (20.0, -10.0)]);
+        let end = moves.last().unwrap();
+        assert_eq!((end.x, end.z), (40.0, 2.0));
+
+        let pecks = groove_passes((0.0, 0.0), (0.0, -5.0), 0.0, 2.0, &params, true);
+        let depths: Vec<f64> = pecks.iter().filter(|m| m.kind == MoveKind::Feed).map(|m| m.z).collect();
+        assert_eq!(depths, [-2.0, -4.0, -5.0]);
+    }
+}
@@ -0,0 +1,106 @@
+use std::ops::Range;
+
+use crate::{LineSegment, MoveKind};
+
+/// Z difference below which extruding moves are on the same layer, in mm.
+const LAYER_EPSILON: f64 = 1e-4;
+
+/// A printed layer: the travel leading up to it and its extruding moves.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LayerInfo {
+    /// Height of the layer's first extruding move, in mm.
+    pub z: f64,
+    pub segments: Range<usize>,
+}
+
+/// Recognizes slicer layer comments: Cura's `;LAYER:3`, PrusaSlicer's `;LAYER_CHANGE` and
+/// Simplify3D's `; layer 3, Z = 0.6`.
+pub(crate) fn layer_marker(line: &str) -> bool {
+    let Some(comment) = line.trim().strip_prefix(';') else {
+        return false;
+    };
+    let upper = comment.trim_start().to_ascii_uppercase();
+    upper.starts_with("LAYER:")
+        || upper.starts_with("LAYER_CHANGE")
+        || upper
+            .strip_prefix("LAYER ")
+            .is_some_and(|rest| rest.starts_with(|ch: char| ch.is_ascii_digit()))
+}
+
+/// Layers split at `markers` (segment counts at each layer comment) or, without markers, at
+/// every change in the height of extruding moves. Layers with no extruding move are dropped.
+pub(crate) fn detect_layers(segments: &[LineSegment], markers: &[usize]) -> Vec<LayerInfo> {
+    if !markers.is_empty() {
+        return markers
+            .iter()
+            .enumerate()
+            .filter_map(|(idx, &start)| {
+                let end = markers.get(idx + 1).copied().unwrap_or(segments.len());
+                let z = segments[start..end].iter().find(|seg| seg.kind == MoveKind::Feed)?.end.z;
+                Some(LayerInfo { z, segments: start..end })
+            })
+            .collect();
+    }
+    let mut layers = Vec::new();
+    let (mut start, mut last_feed_end) = (0, 0);
+    let mut current: Option<f64> = None;
+    for (index, segment) in segments.iter().enumerate() {
+        if segment.kind != MoveKind::Feed {
+            continue;
+        }
+        let z = segment.end.z;
+        match current {
+            Some(layer_z) if (z - layer_z).abs() <= LAYER_EPSILON => {}
+            Some(layer_z) => {
+                layers.push(LayerInfo {
+                    z: layer_z,
+                    segments: start..last_feed_end,
+                });
+                start = last_feed_end;
+                current = Some(z);
+            }
+            None => current = Some(z),
+        }
+        last_feed_end = index + 1;
+    }
+    if let Some(z) = current {
+        layers.push(LayerInfo {
+            z,
+            segments: start..segments.len(),
+        });
+    }
+    layers
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse_str, Dialect, ParseOptions};
+
+    #[test]
+    fn layers_follow_comments_or_extrusion_height() {
+        let marlin = || ParseOptions::default().with_dialect(Dialect::Marlin);
+        let program = [
+            "G1 Z0.2 F600",
+            "G1 X10 E1",
+            "G1 Y10 E2",
+            "G1 Z0.6",
+            "G1 Z0.4",
+            "G1 X0 E3",
+            "G1 X5 Z0.4 E4",
+        ]
+        .join("\n");
+        let layers = parse_str(&program, marlin()).unwrap().layers;
+        let summary: Vec<(f64, Range<usize>)> = layers.into_iter().map(|layer| (layer.z, layer.segments)).collect();
+        assert_eq!(summary, [(0.2, 0..3), (0.4, 3..7)]);
+
+        let sliced = format!(";LAYER:0\n{}\n;LAYER:1\nG1 X9 E5", program);
+        let layers = parse_str(&sliced, marlin()).unwrap().layers;
+        let summary: Vec<(f64, Range<usize>)> = layers.into_iter().map(|layer| (layer.z, layer.segments)).collect();
+        assert_eq!(summary, [(0.2, 0..7), (0.4, 7..8)]);
+
+        assert!(parse_str(&program, ParseOptions::default()).unwrap().layers.is_empty());
+        assert!(layer_marker("; layer 3, Z = 0.6") && layer_marker(";LAYER_CHANGE"));
+        assert!(!layer_marker(";LAYER_COUNT:120") && !layer_marker("G1 X1 ;LAYER:2"));
+    }
+}
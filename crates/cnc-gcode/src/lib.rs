@@ -1,23 +1,242 @@
 use anyhow::{anyhow, Context, Result};
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::fs::File;
 use std::io::{BufRead, BufReader};
 use std::iter::Peekable;
-use std::path::Path;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
 use std::str::Chars;
-
-use cnc_geom::{Bounds3, Vec3};
-
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use cnc_geom::{workpiece_point, Bounds3, RotaryAngles, Vec3};
+
+use comp::{apply_cutter_comp, CompSpan};
+use expr::{read_arguments, read_parameter, read_value, Assignment, Parameters};
+use ocode::{find_o_word, o_word, OKind, OWord};
+use layers::{detect_layers, layer_marker};
+use lint::conflicting_words;
+use operations::operation_marker;
+use programs::subprogram_bodies;
+use lathe::{groove_passes, pattern_passes, rough_passes, LatheMove, LatheParams, Point};
+use threading::ThreadCycle;
+
+mod clearance;
+mod comp;
+mod corners;
+mod depth;
+mod direction;
+mod events;
+mod expr;
+mod lathe;
+mod layers;
+mod lint;
 mod obj;
-
+mod ocode;
+mod operations;
+mod programs;
+mod restart;
+mod spatial;
+mod stock;
+mod threading;
+mod time;
+mod trace;
+
+pub use clearance::{low_rapids, safe_planes, SafePlane};
+pub use corners::{sharp_corners, Corner};
+pub use depth::{min_z_map, DepthMap};
+pub use direction::{analyze_cut_direction, CutDirection, ProfileLoop};
+pub use events::{program_events, ProgramEvent, ProgramEventKind};
+pub use layers::LayerInfo;
+pub use lint::Lint;
 pub use obj::{export_toolpath_obj, ObjExportOptions};
+pub use operations::Operation;
+pub use programs::{split_programs, Program};
+pub use restart::restart_preamble;
+pub use spatial::SpatialIndex;
+pub use stock::air_cuts;
+pub use time::{
+    estimate_duration, feed_limit_zones, line_durations, pause_times, segment_durations, FeedLimitZone,
+    TimeEstimateOptions,
+};
+pub use trace::{parse_position_trace, trace_deviations};
 
 const ARC_SEGMENT_LENGTH: f64 = 0.5;
 
+/// Widest angle one chord of an adaptive arc may span, so loose tolerances still draw a curve.
+const MAX_CHORD_ANGLE: f64 = std::f64::consts::FRAC_PI_2;
+
+/// Smallest chord length or chord error in mm honoured, so zero or negative settings stay finite.
+const MIN_ARC_TOLERANCE: f64 = 0.001;
+
+const ARC_RADIUS_TOLERANCE: f64 = 0.005;
+
+/// Degrees of table rotation per segment of a move that turns a rotary axis.
+const ROTARY_STEP: f64 = 1.0;
+
+const MAX_CALL_DEPTH: usize = 8;
+
+/// Iterations after which a LinuxCNC loop is taken to never end.
+const MAX_LOOP_ITERATIONS: usize = 100_000;
+
 #[derive(Debug, Clone)]
 pub struct ParseOptions {
     ignore_missing_value: HashSet<char>,
     ignore_unknown_words: bool,
+    arc_radius_tolerance: f64,
+    dialect: Dialect,
+    initial_units: Units,
+    initial_plane: Plane,
+    initial_distance_mode: DistanceMode,
+    initial_feed_rate: Option<f64>,
+    lenient: bool,
+    line_range: Option<Range<usize>>,
+    cancel: Option<CancelToken>,
+    work_offsets: [Vec3; WorkOffset::COUNT],
+    reference_positions: [Vec3; 2],
+    tool_lengths: HashMap<u32, f64>,
+    tool_diameters: HashMap<u32, f64>,
+    comments: Option<CommentSyntax>,
+    validate_checksums: bool,
+    arc_tessellation: ArcTessellation,
+    keep_words: bool,
+    lint: bool,
+    unwrap_diameter: Option<f64>,
+}
+
+impl Default for ParseOptions {
+    fn default() -> Self {
+        Self {
+            ignore_missing_value: HashSet::new(),
+            ignore_unknown_words: false,
+            arc_radius_tolerance: ARC_RADIUS_TOLERANCE,
+            dialect: Dialect::Generic,
+            initial_units: Units::Millimeters,
+            initial_plane: Plane::XY,
+            initial_distance_mode: DistanceMode::Absolute,
+            initial_feed_rate: None,
+            lenient: false,
+            line_range: None,
+            cancel: None,
+            work_offsets: [Vec3::default(); WorkOffset::COUNT],
+            reference_positions: [Vec3::default(); 2],
+            tool_lengths: HashMap::new(),
+            tool_diameters: HashMap::new(),
+            comments: None,
+            validate_checksums: false,
+            arc_tessellation: ArcTessellation::Length(ARC_SEGMENT_LENGTH),
+            keep_words: false,
+            lint: false,
+            unwrap_diameter: None,
+        }
+    }
+}
+
+/// How arcs are split into line segments.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ArcTessellation {
+    /// Chords of at most this length in mm.
+    Length(f64),
+    /// As few chords as keep the path within this distance in mm of the true arc.
+    ChordError(f64),
+}
+
+impl ArcTessellation {
+    fn steps(self, radius: f64, sweep: f64) -> usize {
+        let steps = match self {
+            ArcTessellation::Length(length) => radius * sweep.abs() / length.max(MIN_ARC_TOLERANCE),
+            ArcTessellation::ChordError(error) => {
+                let error = error.max(MIN_ARC_TOLERANCE);
+                let angle = 2.0 * (1.0 - error / radius).max(-1.0).acos();
+                sweep.abs() / angle.min(MAX_CHORD_ANGLE)
+            }
+        };
+        (steps.ceil() as usize).max(1)
+    }
+}
+
+/// Controller flavor, for words whose meaning differs between firmwares.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Dialect {
+    #[default]
+    Generic,
+    /// 3D-printer firmware: `E` is extrusion, so G1 moves that extrude nothing are travel
+    /// drawn as rapids and backward `E` is a retraction; unknown words are skipped and `S`
+    /// on other M-codes (temperatures, fans) is not a spindle speed.
+    Marlin,
+    /// LinuxCNC: `#` parameters, `[expression]` values and `o` subroutines, conditionals and
+    /// loops. G70–G75 are not Fanuc lathe cycles here.
+    LinuxCnc,
+    /// Fanuc lathe: G76 is the two-block threading cycle rather than LinuxCNC's single block.
+    Fanuc,
+}
+
+/// What a controller reads as comments.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommentSyntax {
+    /// `( … )` comments.
+    pub parentheses: bool,
+    /// Prefixes that comment out the rest of the line, such as `;`, `!` or `*`.
+    pub line_prefixes: Vec<String>,
+}
+
+impl Default for CommentSyntax {
+    fn default() -> Self {
+        Self::for_dialect(Dialect::Generic)
+    }
+}
+
+impl CommentSyntax {
+    /// The comments a dialect's controllers accept out of the box; Marlin only has `;`.
+    pub fn for_dialect(dialect: Dialect) -> Self {
+        Self {
+            parentheses: dialect != Dialect::Marlin,
+            line_prefixes: vec![";".to_string()],
+        }
+    }
+
+    /// Byte length of the line comment prefix `text` starts with.
+    pub fn line_prefix_len(&self, text: &str) -> Option<usize> {
+        self.line_prefixes
+            .iter()
+            .find(|prefix| !prefix.is_empty() && text.starts_with(prefix.as_str()))
+            .map(String::len)
+    }
+
+    fn strip(&self, line: &str) -> String {
+        let mut out = String::new();
+        let mut in_paren = false;
+        for (idx, ch) in line.char_indices() {
+            if in_paren {
+                if ch == ')' {
+                    in_paren = false;
+                }
+                continue;
+            }
+            if self.parentheses && ch == '(' {
+                in_paren = true;
+                continue;
+            }
+            if self.line_prefix_len(&line[idx..]).is_some() {
+                break;
+            }
+            out.push(ch);
+        }
+        out
+    }
+
+    /// Text of the first comment on a line.
+    fn comment<'a>(&self, line: &'a str) -> Option<&'a str> {
+        let text = line.char_indices().find_map(|(idx, ch)| {
+            if self.parentheses && ch == '(' {
+                let rest = &line[idx + 1..];
+                return Some(&rest[..rest.find(')').unwrap_or(rest.len())]);
+            }
+            self.line_prefix_len(&line[idx..]).map(|len| &line[idx + len..])
+        })?;
+        let text = text.trim();
+        (!text.is_empty()).then_some(text)
+    }
 }
 
 impl ParseOptions {
@@ -31,7 +250,7 @@ impl ParseOptions {
             .collect();
         Self {
             ignore_missing_value,
-            ignore_unknown_words: false,
+            ..Self::default()
         }
     }
 
@@ -40,24 +259,199 @@ impl ParseOptions {
         self
     }
 
+    /// Maximum start/end radius difference in mm before an IJK arc is flagged.
+    pub fn with_arc_radius_tolerance(mut self, tolerance: f64) -> Self {
+        self.arc_radius_tolerance = tolerance;
+        self
+    }
+
+    pub fn with_dialect(mut self, dialect: Dialect) -> Self {
+        self.dialect = dialect;
+        self
+    }
+
+    /// Units in effect before the program's first G20/G21.
+    pub fn with_initial_units(mut self, units: Units) -> Self {
+        self.initial_units = units;
+        self
+    }
+
+    /// Plane in effect before the program's first G17/G18/G19.
+    pub fn with_initial_plane(mut self, plane: Plane) -> Self {
+        self.initial_plane = plane;
+        self
+    }
+
+    /// Distance mode in effect before the program's first G90/G91.
+    pub fn with_initial_distance_mode(mut self, mode: DistanceMode) -> Self {
+        self.initial_distance_mode = mode;
+        self
+    }
+
+    /// Feed rate, in the initial units per minute, in effect before the program's first `F`.
+    pub fn with_initial_feed_rate(mut self, feed: Option<f64>) -> Self {
+        self.initial_feed_rate = feed;
+        self
+    }
+
+    /// Report lines that fail to parse as error diagnostics instead of aborting, and read
+    /// lines that are not valid UTF-8 with their bad bytes replaced.
+    pub fn with_lenient(mut self, lenient: bool) -> Self {
+        self.lenient = lenient;
+        self
+    }
+
+    /// Only parse the 0-based source lines in `range`, e.g. one [`Program`]; other lines are
+    /// treated as blank so line numbers still match the file.
+    pub fn with_line_range(mut self, range: Range<usize>) -> Self {
+        self.line_range = Some(range);
+        self
+    }
+
+    /// Checked before every line of a file, string or reader parse; see [`ParseCancelled`].
+    pub fn with_cancel_token(mut self, token: CancelToken) -> Self {
+        self.cancel = Some(token);
+        self
+    }
+
+    /// Origin of `offset` in machine coordinates (mm); unset offsets are at the machine origin.
+    pub fn with_work_offset(mut self, offset: WorkOffset, origin: Vec3) -> Self {
+        self.work_offsets[offset.index()] = origin;
+        self
+    }
+
+    /// Machine coordinates (mm) G28 (or with `secondary`, G30) returns to; both default to the
+    /// machine origin.
+    pub fn with_reference_position(mut self, secondary: bool, position: Vec3) -> Self {
+        self.reference_positions[secondary as usize] = position;
+        self
+    }
+
+    /// Replaces the dialect's comment rules.
+    pub fn with_comment_syntax(mut self, comments: CommentSyntax) -> Self {
+        self.comments = Some(comments);
+        self
+    }
+
+    pub fn comment_syntax(&self) -> CommentSyntax {
+        self.comments.clone().unwrap_or_else(|| CommentSyntax::for_dialect(self.dialect))
+    }
+
+    /// Length in mm stored in tool length offset register `h`, applied by G43/G44 `H`.
+    pub fn with_tool_length(mut self, h: u32, length: f64) -> Self {
+        self.tool_lengths.insert(h, length);
+        self
+    }
+
+    /// Diameter in mm of tool `tool`, which G41/G42 `D` (or the current tool) offsets the
+    /// path by.
+    pub fn with_tool_diameter(mut self, tool: u32, diameter: f64) -> Self {
+        self.tool_diameters.insert(tool, diameter);
+        self
+    }
+
+    /// Arc tessellation; the default is chords of at most 0.5 mm.
+    pub fn with_arc_tessellation(mut self, tessellation: ArcTessellation) -> Self {
+        self.arc_tessellation = tessellation;
+        self
+    }
+
+    /// Report lines whose RepRap-style `*checksum` does not match as error diagnostics.
+    pub fn with_checksum_validation(mut self, validate: bool) -> Self {
+        self.validate_checksums = validate;
+        self
+    }
+
+    /// Keep every line's words in [`Toolpath::words`] for tools that inspect the program.
+    pub fn with_words(mut self, keep: bool) -> Self {
+        self.keep_words = keep;
+        self
+    }
+
+    /// Report suspicious blocks as `lint:` warnings: conflicting words and feed moves before
+    /// any `F` or with the spindle off. Arcs without a center are always errors.
+    pub fn with_lint(mut self, lint: bool) -> Self {
+        self.lint = lint;
+        self
+    }
+
+    /// Lay A rotation out flat instead of turning the path around X: each degree of A moves the
+    /// path along Y by the arc length it covers on a cylinder of `diameter` mm.
+    pub fn with_unwrap_diameter(mut self, diameter: Option<f64>) -> Self {
+        self.unwrap_diameter = diameter;
+        self
+    }
+
     fn should_ignore_missing(&self, letter: char) -> bool {
-        self.ignore_missing_value.contains(&letter)
+        self.ignore_missing_value.contains(&letter) || (self.dialect == Dialect::Marlin && letter == 'E')
+    }
+
+    fn ignores_unknown_words(&self) -> bool {
+        self.ignore_unknown_words || self.dialect == Dialect::Marlin
     }
 }
 
-impl Default for ParseOptions {
-    fn default() -> Self {
-        Self {
-            ignore_missing_value: HashSet::new(),
-            ignore_unknown_words: false,
+/// Work coordinate system selected by G54–G59 or G59.1–G59.3.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct WorkOffset(u8);
+
+impl WorkOffset {
+    pub const COUNT: usize = 9;
+    /// Selected until the program picks another one, as on most controllers.
+    pub const G54: WorkOffset = WorkOffset(0);
+
+    /// The offset selected by a G word value, e.g. `55.0` or `59.2`.
+    pub fn from_code(code: f64) -> Option<Self> {
+        let tenths = (code * 10.0).round() as i64;
+        match tenths {
+            540 | 550 | 560 | 570 | 580 | 590 => Some(Self((tenths / 10 - 54) as u8)),
+            591..=593 => Some(Self((tenths - 585) as u8)),
+            _ => None,
         }
     }
+
+    /// 0-based slot: G54 is 0, G59 is 5 and G59.3 is 8.
+    pub fn index(self) -> usize {
+        self.0 as usize
+    }
+
+    pub fn code(self) -> &'static str {
+        ["G54", "G55", "G56", "G57", "G58", "G59", "G59.1", "G59.2", "G59.3"][self.index()]
+    }
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum MoveKind {
     Rapid,
     Feed,
+    /// G38.2–G38.5 probing move; see [`Toolpath::probes`].
+    Probe,
+}
+
+/// G38.2–G38.5 variant of a probing move.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ProbeMode {
+    /// Moves until contact (G38.2/G38.3) rather than until contact is lost (G38.4/G38.5).
+    pub toward: bool,
+    /// Alarms when the move ends without the expected change (G38.2/G38.4).
+    pub signal_error: bool,
+}
+
+impl ProbeMode {
+    pub fn code(self) -> &'static str {
+        match (self.toward, self.signal_error) {
+            (true, true) => "G38.2",
+            (true, false) => "G38.3",
+            (false, true) => "G38.4",
+            (false, false) => "G38.5",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ProbeMove {
+    pub segment: usize,
+    pub mode: ProbeMode,
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -65,6 +459,12 @@ pub struct LineSegment {
     pub start: Vec3,
     pub end: Vec3,
     pub kind: MoveKind,
+    /// Feed rate in mm/min, converted from G93 inverse time or G95 per-revolution feeds;
+    /// `None` for rapids, before any F word, or for G95 before any spindle speed.
+    pub feed: Option<f64>,
+    /// Table angles at `end`; `start` and `end` are in workpiece coordinates, with the
+    /// rotation undone so 4th/5th-axis moves draw around the part.
+    pub rotary: RotaryAngles,
 }
 
 #[derive(Debug, Default, Clone)]
@@ -74,54 +474,433 @@ pub struct ToolpathStats {
     pub rapid_moves: usize,
     pub feed_moves: usize,
     pub arc_moves: usize,
+    pub probe_moves: usize,
+    pub tools_used: Vec<u32>,
+    pub spindle_speed_range: Option<(f64, f64)>,
+    /// Units selected by G20/G21, in order of first use.
+    pub units_used: Vec<Units>,
+    /// G20/G21 words that switched away from previously selected units.
+    pub unit_switches: usize,
+    /// Filament pushed by Marlin `E` words, in mm, net of retractions.
+    pub extruded_length: f64,
+    /// Path length in mm of feed (and probe) moves and of rapids.
+    pub feed_distance: f64,
+    pub rapid_distance: f64,
+    /// Summed absolute movement along each axis, in mm.
+    pub axis_travel: Vec3,
 }
 
-#[derive(Debug, Clone)]
+impl ToolpathStats {
+    pub fn mixes_units(&self) -> bool {
+        self.units_used.len() > 1
+    }
+
+    /// The G20/G21 units the whole program ran in; `None` without a G20/G21 or when it mixes
+    /// them.
+    pub fn program_units(&self) -> Option<Units> {
+        match self.units_used[..] {
+            [units] => Some(units),
+            _ => None,
+        }
+    }
+
+    pub fn feed_distance_in(&self, units: Units) -> f64 {
+        units.from_mm(self.feed_distance)
+    }
+
+    pub fn rapid_distance_in(&self, units: Units) -> f64 {
+        units.from_mm(self.rapid_distance)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    /// 1-based source line number.
+    pub line: usize,
+    pub severity: Severity,
+    pub message: String,
+    /// The lint finding this warning reports, if any.
+    pub lint: Option<Lint>,
+}
+
+/// An M2 or M30 program end.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ProgramEnd {
+    /// 0-based source line.
+    pub line: usize,
+    /// Segments executed before the end.
+    pub segment: usize,
+    /// M30, which also rewinds the program.
+    pub rewind: bool,
+}
+
+/// An M0 program stop or M1 optional stop.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProgramStop {
+    /// 0-based source line.
+    pub line: usize,
+    /// Segments executed before the stop.
+    pub segment: usize,
+    /// M1, which only stops with the machine's optional stop switch on.
+    pub optional: bool,
+    /// Comment on the stop's line, or on the comment-only line just above it.
+    pub comment: Option<String>,
+}
+
+/// Active G43/G44 tool length compensation.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ToolLengthOffset {
+    /// `H` register read from the tool length table.
+    pub register: u32,
+    /// G44, which subtracts the length instead of adding it.
+    pub negative: bool,
+    /// Z shift in mm: the register's length, negated for G44.
+    pub shift: f64,
+}
+
+impl ToolLengthOffset {
+    pub fn code(&self) -> String {
+        format!("G{} H{}", if self.negative { 44 } else { 43 }, self.register)
+    }
+}
+
+/// A G28 or G30 return to a reference position.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReferenceReturn {
+    /// 0-based source line.
+    pub line: usize,
+    /// The rapid through the intermediate point, if any, and on to the reference position.
+    pub segments: Range<usize>,
+    /// G30, the secondary reference position.
+    pub secondary: bool,
+}
+
+/// A Marlin block that pulls filament back (`E` decreasing).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Retraction {
+    /// 0-based source line.
+    pub line: usize,
+    /// Segments executed before the block's retraction.
+    pub segment: usize,
+    /// Filament pulled back, in mm.
+    pub length: f64,
+}
+
+/// A loop or repeated M98 call, whose passes all run as part of one source line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Repeat {
+    /// 0-based source lines of the repeated region: the loop block or the calling line.
+    pub lines: Range<usize>,
+    pub passes: usize,
+    /// Segments of every pass; those from `first_pass_end` on come from later passes.
+    pub segments: Range<usize>,
+    pub first_pass_end: usize,
+}
+
+/// A point where motion waits before continuing.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Pause {
+    /// 0-based source line.
+    pub line: usize,
+    /// Segments executed before the pause.
+    pub segment: usize,
+    pub kind: PauseKind,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PauseKind {
+    /// G4 dwell, in seconds.
+    Dwell(f64),
+    /// M6 tool change, timed by [`TimeEstimateOptions::tool_change_time`].
+    ToolChange,
+}
+
+/// Modal state in effect after a source line has executed.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct LineState {
+    /// Machine XYZ, before any table rotation is undone.
+    pub position: Vec3,
+    pub rotary: RotaryAngles,
+    pub tool: Option<u32>,
+    pub spindle_speed: Option<f64>,
+    pub spindle: Spindle,
+    pub coolant: Coolant,
+    /// Feed rate in mm/min; `None` under G93, where it depends on each move's length.
+    pub feed_rate: Option<f64>,
+    pub feed_mode: FeedMode,
+    /// The `F` word in effect, in mm/min, mm/rev or (under G93) 1/min.
+    pub programmed_feed: Option<f64>,
+    pub units: Units,
+    pub distance_mode: DistanceMode,
+    pub plane: Plane,
+    pub motion_mode: MotionMode,
+    /// Work offset selected by the program so far.
+    pub work_offset: Option<WorkOffset>,
+    /// Machine coordinates of the active work offset's origin, including any G92 shift;
+    /// `position` minus this is the position in work coordinates.
+    pub work_origin: Vec3,
+    pub tool_length: Option<ToolLengthOffset>,
+    /// The block's `N` sequence number, as controllers report it.
+    pub line_number: Option<u32>,
+}
+
+#[derive(Debug, Clone, Default)]
 pub struct Toolpath {
     pub segments: Vec<LineSegment>,
     pub bounds: Bounds3,
     pub stats: ToolpathStats,
     pub line_segment_ends: Vec<usize>,
+    pub line_states: Vec<LineState>,
+    pub operations: Vec<Operation>,
+    /// Sorted by line.
+    pub diagnostics: Vec<Diagnostic>,
+    /// Probing moves in program order; the expected touch point is the segment end for
+    /// moves toward the workpiece and the start for moves away from it.
+    pub probes: Vec<ProbeMove>,
+    /// Byte ranges of the X/Y/Z/A/B/C/I/J/K/R words of lines that produced segments; each
+    /// segment's words are the `segment_spans` range of them, empty for called subprograms.
+    pub motion_spans: Vec<Range<usize>>,
+    pub segment_spans: Vec<Range<usize>>,
+    /// M0/M1 stops in program order.
+    pub stops: Vec<ProgramStop>,
+    /// M2/M30 ends in program order; files with several programs have one each.
+    pub ends: Vec<ProgramEnd>,
+    /// G4 dwells and M6 tool changes in program order.
+    pub pauses: Vec<Pause>,
+    /// G28/G30 reference returns in program order.
+    pub reference_returns: Vec<ReferenceReturn>,
+    /// Marlin retractions in program order.
+    pub retractions: Vec<Retraction>,
+    /// Print layers, from slicer layer comments or, for Marlin, changes in extrusion height.
+    pub layers: Vec<LayerInfo>,
+    /// Loops and repeated calls that ran more than once, in the order they finished.
+    pub repeats: Vec<Repeat>,
+    /// Words of every line, grouped by line through `line_word_ends`; empty unless
+    /// [`ParseOptions::with_words`] was set.
+    pub words: Vec<Word>,
+    pub line_word_ends: Vec<usize>,
 }
 
-#[derive(Debug, Clone, Copy)]
-enum DistanceMode {
+impl Toolpath {
+    /// `bounds`, which are in mm, converted to `units`.
+    pub fn bounds_in(&self, units: Units) -> Bounds3 {
+        let convert = |p: Vec3| Vec3::new(units.from_mm(p.x), units.from_mm(p.y), units.from_mm(p.z));
+        Bounds3 {
+            min: convert(self.bounds.min),
+            max: convert(self.bounds.max),
+            initialized: self.bounds.initialized,
+        }
+    }
+
+    /// Source spans, within the segment's line, of the motion words behind a segment.
+    pub fn segment_motion_spans(&self, segment: usize) -> &[Range<usize>] {
+        self.segment_spans
+            .get(segment)
+            .map_or(&[], |spans| &self.motion_spans[spans.clone()])
+    }
+
+    /// The 0-based source line a segment came from; segments of subprogram calls belong to
+    /// the calling line and loop passes after the first to the loop's closing line.
+    pub fn segment_line(&self, index: usize) -> Option<usize> {
+        let line = self.line_segment_ends.partition_point(|&end| end <= index);
+        (line < self.line_segment_ends.len() && index < self.segments.len()).then_some(line)
+    }
+
+    /// Words of a 0-based line, in source order, with parameters and expressions evaluated.
+    pub fn line_words(&self, line: usize) -> &[Word] {
+        let Some(&end) = self.line_word_ends.get(line) else {
+            return &[];
+        };
+        let start = line.checked_sub(1).map_or(0, |prev| self.line_word_ends[prev]);
+        &self.words[start..end]
+    }
+
+    /// FNV-1a hash of segment kinds and endpoints, stable across runs and platforms.
+    ///
+    /// Feeds, comments and modal state are ignored, so re-posted files with identical motion
+    /// hash the same.
+    pub fn geometry_hash(&self) -> u64 {
+        let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+        let mut feed = |bytes: &[u8]| {
+            for &byte in bytes {
+                hash ^= byte as u64;
+                hash = hash.wrapping_mul(0x0000_0100_0000_01b3);
+            }
+        };
+        for seg in &self.segments {
+            feed(&[seg.kind as u8]);
+            for value in [seg.start.x, seg.start.y, seg.start.z, seg.end.x, seg.end.y, seg.end.z] {
+                // Adding 0.0 folds -0.0 into 0.0.
+                feed(&(value + 0.0).to_bits().to_le_bytes());
+            }
+        }
+        hash
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DistanceMode {
+    #[default]
     Absolute,
     Relative,
 }
 
-#[derive(Debug, Clone, Copy)]
-enum Plane {
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Plane {
+    #[default]
     XY,
     XZ,
     YZ,
 }
 
-#[derive(Debug, Clone, Copy)]
-enum MotionMode {
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum MotionMode {
+    #[default]
     Rapid,
     Feed,
     ArcCW,
     ArcCCW,
+    Probe(ProbeMode),
+    /// G33 spindle-synchronized motion.
+    Thread,
+}
+
+/// How `F` is read, selected by G93/G94/G95.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum FeedMode {
+    /// G93: each move takes `1/F` minutes, so `F` is needed on every feed block.
+    InverseTime,
+    /// G94: length units per minute.
+    #[default]
+    UnitsPerMinute,
+    /// G95: length units per spindle revolution.
+    UnitsPerRevolution,
+}
+
+impl FeedMode {
+    pub fn from_code(code: f64) -> Option<Self> {
+        match code {
+            93.0 => Some(Self::InverseTime),
+            94.0 => Some(Self::UnitsPerMinute),
+            95.0 => Some(Self::UnitsPerRevolution),
+            _ => None,
+        }
+    }
+
+    pub fn code(self) -> &'static str {
+        match self {
+            Self::InverseTime => "G93",
+            Self::UnitsPerMinute => "G94",
+            Self::UnitsPerRevolution => "G95",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Units {
+    #[default]
+    Millimeters,
+    Inches,
+}
+
+impl Units {
+    pub fn mm_per_unit(self) -> f64 {
+        match self {
+            Units::Millimeters => 1.0,
+            Units::Inches => 25.4,
+        }
+    }
+
+    /// A length in mm, in these units.
+    pub fn from_mm(self, mm: f64) -> f64 {
+        mm / self.mm_per_unit()
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Coolant {
+    #[default]
+    Off,
+    Mist,
+    Flood,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Spindle {
+    #[default]
+    Off,
+    Clockwise,
+    CounterClockwise,
 }
 
 #[derive(Debug, Clone, Copy)]
 struct ParserState {
     pos: Vec3,
+    rotary: RotaryAngles,
     units_scale: f64,
     distance_mode: DistanceMode,
     plane: Plane,
     motion_mode: MotionMode,
+    feed_mode: FeedMode,
+    /// Last `F`, scaled to mm unless it is a G93 inverse time.
+    feed_rate: Option<f64>,
+    spindle_speed: Option<f64>,
+    spindle: Spindle,
+    coolant: Coolant,
+    tool: Option<u32>,
+    work_offset: Option<WorkOffset>,
+    /// Machine coordinates of the G54–G59.3 origins, which G10 L2/L20 overwrite.
+    work_offsets: [Vec3; WorkOffset::COUNT],
+    /// G92 shift added to every work offset's origin.
+    coordinate_offset: Vec3,
+    /// G28 and G30 positions, which G28.1/G30.1 overwrite.
+    reference_positions: [Vec3; 2],
+    tool_length: Option<ToolLengthOffset>,
+    /// Last G33 `K` pitch in mm.
+    thread_pitch: Option<f64>,
+    /// Marlin extruder position in mm, and whether `E` is relative (M83).
+    extruder: f64,
+    relative_extrusion: bool,
 }
 
 impl ParserState {
-    fn new() -> Self {
+    fn units(&self) -> Units {
+        if self.units_scale == 1.0 {
+            Units::Millimeters
+        } else {
+            Units::Inches
+        }
+    }
+
+    fn new(options: &ParseOptions) -> Self {
+        let units_scale = options.initial_units.mm_per_unit();
         Self {
             pos: Vec3::new(0.0, 0.0, 0.0),
-            units_scale: 1.0,
-            distance_mode: DistanceMode::Absolute,
-            plane: Plane::XY,
+            rotary: RotaryAngles::default(),
+            units_scale,
+            distance_mode: options.initial_distance_mode,
+            plane: options.initial_plane,
             motion_mode: MotionMode::Rapid,
+            feed_mode: FeedMode::UnitsPerMinute,
+            feed_rate: options.initial_feed_rate.map(|feed| feed * units_scale),
+            spindle_speed: None,
+            spindle: Spindle::Off,
+            coolant: Coolant::Off,
+            tool: None,
+            work_offset: None,
+            work_offsets: options.work_offsets,
+            coordinate_offset: Vec3::default(),
+            reference_positions: options.reference_positions,
+            tool_length: None,
+            thread_pitch: None,
+            extruder: 0.0,
+            relative_extrusion: false,
         }
     }
 }
@@ -131,65 +910,676 @@ pub fn parse_file(path: &Path) -> Result<Toolpath> {
 }
 
 pub fn parse_file_with_options(path: &Path, options: ParseOptions) -> Result<Toolpath> {
+    parse_file_with_progress(path, options, |_| {})
+}
+
+/// Parses G-code already in memory; M98 subprograms are looked up in `source` itself.
+pub fn parse_str(source: &str, options: ParseOptions) -> Result<Toolpath> {
+    parse_str_with_progress(source, options, |_| {})
+}
+
+pub fn parse_str_with_progress(
+    source: &str,
+    options: ParseOptions,
+    progress: impl FnMut(ParseProgress),
+) -> Result<Toolpath> {
+    parse_bytes_with_progress(source.as_bytes(), options, progress)
+}
+
+/// Like [`parse_str`] for raw file contents, so lines that are not UTF-8 are reported per line
+/// (and survive with a warning when lenient) instead of failing the whole read.
+pub fn parse_bytes(source: &[u8], options: ParseOptions) -> Result<Toolpath> {
+    parse_bytes_with_progress(source, options, |_| {})
+}
+
+pub fn parse_bytes_with_progress(
+    source: &[u8],
+    options: ParseOptions,
+    progress: impl FnMut(ParseProgress),
+) -> Result<Toolpath> {
+    let mut parser = Parser::new(options);
+    parser.subprograms = Some(subprogram_bodies(String::from_utf8_lossy(source).lines(), &parser.comments));
+    parse_lines(source, parser, source.len() as u64, progress)
+}
+
+/// Parses G-code from any line source, such as stdin or a decompressor; M98 subprograms are
+/// not available since the source cannot be read twice.
+pub fn parse_reader<R: BufRead>(reader: R, options: ParseOptions) -> Result<Toolpath> {
+    let mut parser = Parser::new(options);
+    parser.subprograms = Some(HashMap::new());
+    parse_lines(reader, parser, 0, |_| {})
+}
+
+/// How far a parse has got; reported every [`PROGRESS_INTERVAL`] lines and at the end.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParseProgress {
+    pub lines: usize,
+    pub bytes: u64,
+    pub total_bytes: u64,
+}
+
+pub const PROGRESS_INTERVAL: usize = 4096;
+
+/// Shared flag that aborts a parse from another thread.
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// Error returned when a [`CancelToken`] stops a parse; detect it with `err.is::<ParseCancelled>()`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParseCancelled;
+
+impl std::fmt::Display for ParseCancelled {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("parse cancelled")
+    }
+}
+
+impl std::error::Error for ParseCancelled {}
+
+pub fn parse_file_with_progress(
+    path: &Path,
+    options: ParseOptions,
+    progress: impl FnMut(ParseProgress),
+) -> Result<Toolpath> {
     let file = File::open(path)
         .with_context(|| format!("failed to open g-code: {}", path.display()))?;
-    let reader = BufReader::new(file);
+    let total_bytes = file.metadata().map(|meta| meta.len()).unwrap_or(0);
     let mut parser = Parser::new(options);
+    parser.source = Some(path.to_path_buf());
+    parse_lines(BufReader::new(file), parser, total_bytes, progress)
+}
 
-    for (idx, line) in reader.lines().enumerate() {
-        let line = line.with_context(|| format!("failed to read line {}", idx + 1))?;
-        parser.parse_line(&line, idx + 1)?;
+fn parse_lines(
+    mut reader: impl BufRead,
+    mut parser: Parser,
+    total_bytes: u64,
+    mut progress: impl FnMut(ParseProgress),
+) -> Result<Toolpath> {
+    let cancel = parser.options.cancel.clone();
+    let mut report = ParseProgress {
+        lines: 0,
+        bytes: 0,
+        total_bytes,
+    };
+    let mut line = Vec::new();
+
+    loop {
+        line.clear();
+        let read = reader
+            .read_until(b'\n', &mut line)
+            .with_context(|| format!("failed to read line {}", report.lines + 1))?;
+        if read == 0 {
+            break;
+        }
+        if cancel.as_ref().is_some_and(CancelToken::is_cancelled) {
+            return Err(ParseCancelled.into());
+        }
+        report.lines += 1;
+        report.bytes += read as u64;
+        let bytes = line.strip_suffix(b"\n").unwrap_or(&line);
+        let bytes = bytes.strip_suffix(b"\r").unwrap_or(bytes);
+        match std::str::from_utf8(bytes) {
+            Ok(text) => parser.push_line(text)?,
+            Err(_) if parser.options.lenient => {
+                parser.diagnostics.push(Diagnostic {
+                    line: report.lines,
+                    severity: Severity::Warning,
+                    message: "line is not valid UTF-8; unreadable bytes were replaced".to_string(),
+                    lint: None,
+                });
+                parser.push_line(&String::from_utf8_lossy(bytes))?;
+            }
+            Err(err) => return Err(anyhow!(err).context(format!("failed to read line {}", report.lines))),
+        }
+        if report.lines.is_multiple_of(PROGRESS_INTERVAL) {
+            progress(report);
+        }
     }
+    progress(report);
 
     parser.finish()
 }
 
-struct Parser {
+/// Builds a [`Toolpath`] one source line at a time, for input that arrives as a stream (a
+/// socket, a serial port, another thread). M98 subprograms are only found by the file parsers.
+pub struct Parser {
     state: ParserState,
     segments: Vec<LineSegment>,
     bounds: Bounds3,
     stats: ToolpathStats,
+    comments: CommentSyntax,
     options: ParseOptions,
     line_segment_ends: Vec<usize>,
+    line_states: Vec<LineState>,
+    operations: Vec<Operation>,
+    diagnostics: Vec<Diagnostic>,
+    probes: Vec<ProbeMove>,
+    motion_spans: Vec<Range<usize>>,
+    words: Vec<Word>,
+    line_word_ends: Vec<usize>,
+    segment_spans: Vec<Range<usize>>,
+    stops: Vec<ProgramStop>,
+    ends: Vec<ProgramEnd>,
+    pauses: Vec<Pause>,
+    reference_returns: Vec<ReferenceReturn>,
+    retractions: Vec<Retraction>,
+    /// Segment counts at each slicer layer comment.
+    layer_marks: Vec<usize>,
+    repeats: Vec<Repeat>,
+    /// Comment of the previous line when it held nothing else.
+    last_comment: Option<String>,
+    lathe: LatheParams,
+    profile_capture: Option<ProfileCapture>,
+    /// G71–G73 profiles by first sequence number, for G70 to finish along.
+    profiles: HashMap<u32, Vec<LineSegment>>,
+    /// M98 targets by `O` number, read from `source` on the first call.
+    subprograms: Option<HashMap<u32, Arc<[String]>>>,
+    source: Option<PathBuf>,
+    /// Blocks running on behalf of the current line: subprogram calls and closed `o` blocks.
+    call_depth: usize,
+    /// A top-level M2/M30 ran; later lines, such as `O` subprogram bodies, are not executed.
+    ended: bool,
+    /// LinuxCNC `o` subroutines by label.
+    subroutines: HashMap<String, Arc<[String]>>,
+    /// An open `o` block, run when its closing line is read.
+    open_block: Option<OpenBlock>,
+    /// First source line of a closed `o` block whose segments are not yet known.
+    next_block_line: usize,
+    /// Source line of the `o` block line running for the first time, which owns its moves.
+    block_line: Option<usize>,
+    parameters: Parameters,
+    line_no: usize,
+    /// `N` number of the current source line.
+    line_number: Option<u32>,
+    /// G41/G42 runs closed so far and the open one's first segment and offset.
+    comp_spans: Vec<CompSpan>,
+    open_comp: Option<(usize, f64, Plane)>,
+    /// Lint findings already given: a feed move without a feed rate, and one with the
+    /// spindle off since it last stopped.
+    missing_feed_linted: bool,
+    spindle_off_linted: bool,
+}
+
+struct OpenBlock {
+    line_no: usize,
+    label: String,
+    closer: OKind,
+    lines: Vec<String>,
+}
+
+/// How a run of LinuxCNC blocks ended.
+enum Flow {
+    Next,
+    Break(String),
+    Continue(String),
+    Return,
+}
+
+/// A G71–G73 cycle collecting its `P`..`Q` profile blocks, whose moves are replaced by the
+/// cycle's passes once the last block is reached.
+struct ProfileCapture {
+    code: u32,
+    line_no: usize,
+    start: Vec3,
+    first_line: usize,
+    first_segment: usize,
+    first_span: usize,
+    first_block: u32,
+    last_block: u32,
+    /// Finishing allowance in X and Z.
+    allowance: Point,
+    feed: Option<f64>,
+    /// Rapid, feed and arc move counts before the profile blocks, restored when they are replaced.
+    moves: (usize, usize, usize),
 }
 
 impl Parser {
-    fn new(options: ParseOptions) -> Self {
+    pub fn new(options: ParseOptions) -> Self {
         Self {
-            state: ParserState::new(),
+            state: ParserState::new(&options),
             segments: Vec::new(),
             bounds: Bounds3::new(),
             stats: ToolpathStats::default(),
+            comments: options.comment_syntax(),
             options,
             line_segment_ends: Vec::new(),
+            line_states: Vec::new(),
+            operations: Vec::new(),
+            diagnostics: Vec::new(),
+            probes: Vec::new(),
+            motion_spans: Vec::new(),
+            segment_spans: Vec::new(),
+            words: Vec::new(),
+            line_word_ends: Vec::new(),
+            stops: Vec::new(),
+            ends: Vec::new(),
+            pauses: Vec::new(),
+            reference_returns: Vec::new(),
+            retractions: Vec::new(),
+            layer_marks: Vec::new(),
+            repeats: Vec::new(),
+            last_comment: None,
+            lathe: LatheParams::default(),
+            profile_capture: None,
+            profiles: HashMap::new(),
+            subprograms: None,
+            source: None,
+            call_depth: 0,
+            ended: false,
+            subroutines: HashMap::new(),
+            open_block: None,
+            next_block_line: 0,
+            block_line: None,
+            parameters: Parameters::default(),
+            line_no: 0,
+            line_number: None,
+            comp_spans: Vec::new(),
+            open_comp: None,
+            missing_feed_linted: false,
+            spindle_off_linted: false,
         }
     }
 
-    fn finish(mut self) -> Result<Toolpath> {
+    /// Parses the next source line, without its line break. Fails like the file parsers: on a
+    /// bad line unless lenient, after which the parser should be dropped.
+    pub fn push_line(&mut self, line: &str) -> Result<()> {
+        self.parse_line_or_report(line, self.stats.line_count + 1)
+    }
+
+    /// Segments of the lines pushed so far.
+    pub fn segments(&self) -> &[LineSegment] {
+        &self.segments
+    }
+
+    pub fn finish(mut self) -> Result<Toolpath> {
+        if let Some(capture) = self.profile_capture.take() {
+            self.diagnostics.push(Diagnostic {
+                line: capture.line_no,
+                severity: Severity::Warning,
+                message: format!("G{} profile block N{} never reached", capture.code, capture.last_block),
+                lint: None,
+            });
+        }
+        if let Some(block) = self.open_block.take() {
+            self.diagnostics.push(Diagnostic {
+                line: block.line_no,
+                severity: Severity::Warning,
+                message: format!("o{} block is never closed", block.label),
+                lint: None,
+            });
+        }
+        self.close_operation();
+        self.select_cutter_comp(40, None);
+        if !self.comp_spans.is_empty() {
+            apply_cutter_comp(&mut self.segments, &self.comp_spans);
+            self.bounds = Bounds3::new();
+            for segment in &self.segments {
+                self.bounds.include(segment.start);
+                self.bounds.include(segment.end);
+            }
+        }
+        self.diagnostics.sort_by_key(|diagnostic| diagnostic.line);
         self.stats.segment_count = self.segments.len();
+        let layers = if self.layer_marks.is_empty() && self.options.dialect != Dialect::Marlin {
+            Vec::new()
+        } else {
+            detect_layers(&self.segments, &self.layer_marks)
+        };
+        for segment in &self.segments {
+            match segment.kind {
+                MoveKind::Rapid => self.stats.rapid_distance += segment_length(segment),
+                MoveKind::Feed | MoveKind::Probe => self.stats.feed_distance += segment_length(segment),
+            }
+            let delta = segment.end - segment.start;
+            self.stats.axis_travel = self.stats.axis_travel + Vec3::new(delta.x.abs(), delta.y.abs(), delta.z.abs());
+        }
         Ok(Toolpath {
             segments: self.segments,
             bounds: self.bounds,
             stats: self.stats,
             line_segment_ends: self.line_segment_ends,
+            line_states: self.line_states,
+            operations: self.operations,
+            diagnostics: self.diagnostics,
+            probes: self.probes,
+            motion_spans: self.motion_spans,
+            segment_spans: self.segment_spans,
+            stops: self.stops,
+            ends: self.ends,
+            pauses: self.pauses,
+            reference_returns: self.reference_returns,
+            retractions: self.retractions,
+            layers,
+            repeats: self.repeats,
+            words: self.words,
+            line_word_ends: self.line_word_ends,
         })
     }
 
+    /// In lenient mode a failing line becomes an error diagnostic instead of aborting the parse.
+    fn parse_line_or_report(&mut self, line: &str, line_no: usize) -> Result<()> {
+        let lines = self.line_segment_ends.len();
+        match self.parse_line(line, line_no) {
+            Err(err) if self.options.lenient => {
+                if self.line_segment_ends.len() == lines {
+                    self.end_line();
+                }
+                self.diagnostics.push(Diagnostic {
+                    line: line_no,
+                    severity: Severity::Error,
+                    message: err.root_cause().to_string(),
+                    lint: None,
+                });
+                Ok(())
+            }
+            result => result,
+        }
+    }
+
     fn parse_line(&mut self, line: &str, line_no: usize) -> Result<()> {
         self.stats.line_count += 1;
-        let cleaned = strip_comments(line);
+        self.line_no = line_no;
+        let line = match split_checksum(line) {
+            Some((body, checksum)) if self.options.dialect != Dialect::LinuxCnc => {
+                let expected = body.bytes().fold(0, |sum, byte| sum ^ byte);
+                if self.options.validate_checksums && checksum != expected {
+                    self.diagnostics.push(Diagnostic {
+                        line: line_no,
+                        severity: Severity::Error,
+                        message: format!("checksum *{} does not match the line (*{})", checksum, expected),
+                        lint: None,
+                    });
+                }
+                body
+            }
+            _ => line,
+        };
+        if let Some(name) = operation_marker(line) {
+            self.close_operation();
+            let (line, segment) = (self.line_segment_ends.len(), self.segments.len());
+            self.operations.push(Operation {
+                name,
+                lines: line..line,
+                segments: segment..segment,
+            });
+        }
+        if layer_marker(line) {
+            self.layer_marks.push(self.segments.len());
+        }
+        let skipped = self
+            .options
+            .line_range
+            .as_ref()
+            .is_some_and(|range| !range.contains(&self.line_segment_ends.len()));
+        if skipped || self.ended {
+            self.last_comment = self.comments.comment(line).map(str::to_string);
+        } else if self.options.dialect == Dialect::LinuxCnc {
+            self.read_ngc_line(line)?;
+        } else {
+            self.execute_block(line)?;
+        }
+        self.end_line();
+        Ok(())
+    }
+
+    /// Collects an `o` block until its closing line, which then runs the whole block.
+    fn read_ngc_line(&mut self, line: &str) -> Result<()> {
+        let word = o_word(line, &self.comments);
+        if let Some(block) = self.open_block.as_mut() {
+            block.lines.push(line.to_string());
+            if !word.is_some_and(|word| word.label == block.label && word.kind == block.closer) {
+                return Ok(());
+            }
+            let Some(block) = self.open_block.take() else {
+                return Ok(());
+            };
+            self.call_depth += 1;
+            self.next_block_line = block.line_no - 1;
+            let result = self.run_ngc(&block.lines, Some(block.line_no - 1));
+            self.call_depth -= 1;
+            return result.map(drop);
+        }
+        match word {
+            Some(word) if let Some(closer) = word.kind.closer() => {
+                self.open_block = Some(OpenBlock {
+                    line_no: self.line_no,
+                    label: word.label,
+                    closer,
+                    lines: vec![line.to_string()],
+                });
+                Ok(())
+            }
+            _ => self.run_ngc(&[line.to_string()], Some(self.line_no - 1)).map(drop),
+        }
+    }
+
+    /// `first_line` is the 0-based source line of `lines[0]`, unknown in subroutine bodies.
+    fn run_ngc(&mut self, lines: &[String], first_line: Option<usize>) -> Result<Flow> {
+        let mut idx = 0;
+        while idx < lines.len() {
+            let Some(word) = o_word(&lines[idx], &self.comments) else {
+                let source = first_line.map(|line| line + idx);
+                self.block_line = source.filter(|&line| line >= self.next_block_line);
+                let result = self.execute_block(&lines[idx]);
+                self.block_line = None;
+                result?;
+                self.end_block_lines(source);
+                idx += 1;
+                continue;
+            };
+            let end = match word.kind.closer() {
+                Some(closer) => find_o_word(lines, idx, &word.label, closer, &self.comments)
+                    .ok_or_else(|| anyhow!("o{} {:?} is never closed", word.label, word.kind))?,
+                None => idx,
+            };
+            let body = &lines[(idx + 1).min(end)..end];
+            if end > idx {
+                self.end_block_lines(first_line.map(|line| line + idx));
+            }
+            let flow = match word.kind {
+                OKind::Sub => {
+                    self.subroutines.insert(word.label.clone(), body.into());
+                    Flow::Next
+                }
+                OKind::Call => {
+                    self.call_subroutine(&word)?;
+                    Flow::Next
+                }
+                OKind::Return | OKind::EndSub => Flow::Return,
+                OKind::Break => Flow::Break(word.label.clone()),
+                OKind::Continue => Flow::Continue(word.label.clone()),
+                OKind::If => self.run_if(&lines[idx..=end], first_line.map(|line| line + idx), &word)?,
+                OKind::While | OKind::Do | OKind::Repeat => {
+                    let region = first_line.map(|line| line + idx..line + end + 1);
+                    self.run_loop(body, region, &word, &lines[end])?
+                }
+                OKind::ElseIf | OKind::Else | OKind::EndIf | OKind::EndWhile | OKind::EndRepeat => Flow::Next,
+            };
+            self.end_block_lines(first_line.map(|line| line + end));
+            if !matches!(flow, Flow::Next) {
+                return Ok(flow);
+            }
+            idx = end + 1;
+        }
+        Ok(Flow::Next)
+    }
+
+    /// Gives the `o` block lines up to `line` the moves made so far: each line keeps what its
+    /// first run added, and later loop passes fall to the loop's closing line.
+    fn end_block_lines(&mut self, line: Option<usize>) {
+        let Some(line) = line.filter(|&line| line >= self.next_block_line) else {
+            return;
+        };
+        for source in self.next_block_line..(line + 1).min(self.line_segment_ends.len()) {
+            self.line_segment_ends[source] = self.segments.len();
+            self.line_states[source] = self.line_state();
+            if self.options.keep_words {
+                self.line_word_ends[source] = self.words.len();
+            }
+        }
+        self.next_block_line = line + 1;
+    }
+
+    /// Runs the first branch of an `if` … `endif` block whose condition holds.
+    fn run_if(&mut self, lines: &[String], first_line: Option<usize>, word: &OWord) -> Result<Flow> {
+        let mut branch = (0, word.kind, word.args.clone());
+        for idx in 1..lines.len() {
+            let Some(next) = o_word(&lines[idx], &self.comments).filter(|next| {
+                next.label == word.label && matches!(next.kind, OKind::ElseIf | OKind::Else | OKind::EndIf)
+            }) else {
+                continue;
+            };
+            let (start, kind, condition) = std::mem::replace(&mut branch, (idx, next.kind, next.args));
+            if kind == OKind::Else || self.evaluate(&condition)? != 0.0 {
+                return self.run_ngc(&lines[start + 1..idx], first_line.map(|line| line + start + 1));
+            }
+        }
+        Ok(Flow::Next)
+    }
+
+    /// Runs a `while`, `do` … `while` or `repeat` body; `close` is the closing line and
+    /// `region` the source lines from the opening one to it.
+    fn run_loop(&mut self, body: &[String], region: Option<Range<usize>>, word: &OWord, close: &str) -> Result<Flow> {
+        let count = match word.kind {
+            OKind::Repeat => Some(self.evaluate(&word.args)?.round().max(0.0) as usize),
+            _ => None,
+        };
+        let condition = match word.kind {
+            OKind::Do => o_word(close, &self.comments).map(|close| close.args).unwrap_or_default(),
+            _ => word.args.clone(),
+        };
+        let first_segment = self.segments.len();
+        let mut first_pass_end = None;
+        let mut passes = 0;
+        for iteration in 0.. {
+            let more = match (word.kind, count) {
+                (_, Some(count)) => iteration < count,
+                (OKind::Do, _) => iteration == 0 || self.evaluate(&condition)? != 0.0,
+                _ => self.evaluate(&condition)? != 0.0,
+            };
+            if !more {
+                break;
+            }
+            if iteration == MAX_LOOP_ITERATIONS {
+                self.diagnostics.push(Diagnostic {
+                    line: self.line_no,
+                    severity: Severity::Warning,
+                    message: format!("o{}: loop stopped after {} iterations", word.label, MAX_LOOP_ITERATIONS),
+                    lint: None,
+                });
+                break;
+            }
+            let flow = self.run_ngc(body, region.as_ref().map(|lines| lines.start + 1))?;
+            passes += 1;
+            first_pass_end.get_or_insert(self.segments.len());
+            match flow {
+                Flow::Break(label) if label == word.label => break,
+                Flow::Next => {}
+                Flow::Continue(label) if label == word.label => {}
+                flow => return Ok(flow),
+            }
+        }
+        self.record_repeat(region, passes, first_segment, first_pass_end);
+        Ok(Flow::Next)
+    }
+
+    /// `o… call`: runs a subroutine with its arguments in `#1`, `#2`… as part of the calling
+    /// line.
+    fn call_subroutine(&mut self, word: &OWord) -> Result<()> {
+        let args = read_arguments(&word.args, &self.parameters)?;
+        let warning = match self.subroutines.get(&word.label).cloned() {
+            None => format!("o{} call: subroutine not defined", word.label),
+            Some(_) if self.call_depth == MAX_CALL_DEPTH => {
+                format!("o{} call: subroutines nested more than {} deep", word.label, MAX_CALL_DEPTH)
+            }
+            Some(body) => {
+                self.call_depth += 1;
+                let scope = self.parameters.enter(&args);
+                let result = self.run_ngc(&body, None);
+                self.parameters.leave(scope);
+                self.call_depth -= 1;
+                return result.map(drop);
+            }
+        };
+        self.diagnostics.push(Diagnostic {
+            line: self.line_no,
+            severity: Severity::Warning,
+            message: warning,
+            lint: None,
+        });
+        Ok(())
+    }
+
+    /// `region` defaults to the current source line.
+    fn record_repeat(
+        &mut self,
+        region: Option<Range<usize>>,
+        passes: usize,
+        first_segment: usize,
+        first_pass_end: Option<usize>,
+    ) {
+        if passes < 2 {
+            return;
+        }
+        self.repeats.push(Repeat {
+            lines: region.unwrap_or(self.line_no - 1..self.line_no),
+            passes,
+            segments: first_segment..self.segments.len(),
+            first_pass_end: first_pass_end.unwrap_or(first_segment),
+        });
+    }
+
+    fn evaluate(&self, text: &str) -> Result<f64> {
+        expr::evaluate(text, &self.parameters).with_context(|| format!("line {}", self.line_no))
+    }
+
+    /// Runs one block's words; the caller ends the source line, so subprogram blocks add to
+    /// the line that called them.
+    fn execute_block(&mut self, line: &str) -> Result<()> {
+        let cleaned = self.comments.strip(line);
         let cleaned = cleaned.trim();
+        let comment = self.comments.comment(line).map(str::to_string);
+        let preceding_comment = self.last_comment.take();
         if cleaned.is_empty() {
-            self.line_segment_ends.push(self.segments.len());
+            self.last_comment = comment;
             return Ok(());
         }
 
-        let words =
-            parse_words(cleaned, &self.options).with_context(|| format!("line {}", line_no))?;
+        let words = if self.options.dialect == Dialect::LinuxCnc {
+            let (words, assignments) =
+                parse_ngc_words(cleaned, &self.parameters).with_context(|| format!("line {}", self.line_no))?;
+            for (param, value) in assignments {
+                self.parameters.set(param, value);
+            }
+            words
+        } else {
+            parse_words(cleaned, &self.options).with_context(|| format!("line {}", self.line_no))?
+        };
         if words.is_empty() {
-            self.line_segment_ends.push(self.segments.len());
             return Ok(());
         }
+        if self.options.keep_words && (self.call_depth == 0 || self.block_line.is_some()) {
+            self.words.extend_from_slice(&words);
+        }
+        if self.options.lint {
+            for finding in conflicting_words(&words) {
+                self.lint(finding);
+            }
+        }
 
         let mut motion_override: Option<MotionMode> = None;
         let mut x: Option<f64> = None;
@@ -199,9 +1589,84 @@ impl Parser {
         let mut j: Option<f64> = None;
         let mut k: Option<f64> = None;
         let mut r: Option<f64> = None;
+        let mut a: Option<f64> = None;
+        let mut b: Option<f64> = None;
+        let mut c: Option<f64> = None;
+        let mut e: Option<f64> = None;
+        let marlin = self.options.dialect == Dialect::Marlin;
+        let non_spindle_m_code = self.options.dialect == Dialect::Marlin
+            && words
+                .iter()
+                .any(|word| word.letter == 'M' && !(3..=5).contains(&(word.value.round() as i32)));
+
+        let fanuc = self.options.dialect == Dialect::Fanuc;
+        let thread_cycle = (!fanuc && words.iter().any(|word| word.letter == 'G' && word.value == 76.0))
+            .then(|| self.thread_cycle(&words));
+        let last_lathe_cycle = if fanuc { 76.0 } else { 75.0 };
+        let lathe_cycle = words
+            .iter()
+            .filter(|_| fanuc)
+            .find(|word| {
+                word.letter == 'G' && (70.0..=last_lathe_cycle).contains(&word.value) && word.value.fract() == 0.0
+            })
+            .map(|word| (word.value as u32, words.clone()));
+        let sequence = word_value(&words, 'N');
+        if self.call_depth == 0 || self.block_line.is_some() {
+            self.line_number = sequence.map(|n| n as u32);
+        }
+        let offset_setting = words
+            .iter()
+            .any(|word| word.letter == 'G' && word.value == 10.0)
+            .then(|| (word_value(&words, 'L'), word_value(&words, 'P')));
+        let coordinate_offset = words
+            .iter()
+            .find(|word| word.letter == 'G' && (word.value == 92.0 || word.value == 92.1))
+            .map(|word| word.value);
+        let reference = words
+            .iter()
+            .find(|word| word.letter == 'G' && [28.0, 28.1, 30.0, 30.1].contains(&word.value))
+            .map(|word| word.value);
+        let tool_length = words
+            .iter()
+            .find(|word| word.letter == 'G' && [43.0, 44.0, 49.0].contains(&word.value))
+            .map(|word| (word.value as u32, word_value(&words, 'H')));
+        let cutter_comp = words
+            .iter()
+            .find(|word| word.letter == 'G' && [40.0, 41.0, 42.0].contains(&word.value))
+            .map(|word| (word.value as u32, word_value(&words, 'D')));
+        let call = words
+            .iter()
+            .any(|word| word.letter == 'M' && word.value == 98.0)
+            .then(|| subprogram_call(&words));
+        let dwell = words
+            .iter()
+            .any(|word| word.letter == 'G' && word.value == 4.0)
+            .then(|| self.dwell_seconds(&words));
+        if let Some(mode) = words
+            .iter()
+            .filter(|word| word.letter == 'G')
+            .find_map(|word| FeedMode::from_code(word.value))
+        {
+            self.state.feed_mode = mode;
+        }
+        let inverse_time = self.state.feed_mode == FeedMode::InverseTime;
+        let mut block_feed = None;
+        let segment_count = self.segments.len();
+        let mut stop = None;
+        let mut end = None;
+        let mut tool_change = false;
 
         for word in words {
             match word.letter {
+                'G' if word.value.floor() == 38.0 => {
+                    let mode = probe_mode(word.value)
+                        .ok_or_else(|| anyhow!("unsupported probe code G{}", word.value))?;
+                    motion_override = Some(MotionMode::Probe(mode));
+                    self.state.motion_mode = MotionMode::Probe(mode);
+                }
+                'G' if WorkOffset::from_code(word.value).is_some() => {
+                    self.state.work_offset = WorkOffset::from_code(word.value);
+                }
                 'G' => {
                     let code = word.value.round() as i32;
                     match code {
@@ -221,16 +1686,43 @@ impl Parser {
                             motion_override = Some(MotionMode::ArcCCW);
                             self.state.motion_mode = MotionMode::ArcCCW;
                         }
+                        33 => {
+                            motion_override = Some(MotionMode::Thread);
+                            self.state.motion_mode = MotionMode::Thread;
+                        }
                         17 => self.state.plane = Plane::XY,
                         18 => self.state.plane = Plane::XZ,
                         19 => self.state.plane = Plane::YZ,
-                        20 => self.state.units_scale = 25.4,
-                        21 => self.state.units_scale = 1.0,
-                        90 => self.state.distance_mode = DistanceMode::Absolute,
-                        91 => self.state.distance_mode = DistanceMode::Relative,
+                        20 => self.select_units(Units::Inches),
+                        21 => self.select_units(Units::Millimeters),
+                        90 => {
+                            self.state.distance_mode = DistanceMode::Absolute;
+                            self.state.relative_extrusion = false;
+                        }
+                        91 => {
+                            self.state.distance_mode = DistanceMode::Relative;
+                            self.state.relative_extrusion = true;
+                        }
                         _ => {}
                     }
                 }
+                'M' => match word.value.round() as i32 {
+                    0 => stop = Some(false),
+                    1 => stop = Some(true),
+                    2 => end = Some(false),
+                    30 => end = Some(true),
+                    6 => tool_change = true,
+                    3 => self.state.spindle = Spindle::Clockwise,
+                    4 => self.state.spindle = Spindle::CounterClockwise,
+                    5 => self.state.spindle = Spindle::Off,
+                    7 => self.state.coolant = Coolant::Mist,
+                    8 => self.state.coolant = Coolant::Flood,
+                    9 => self.state.coolant = Coolant::Off,
+                    82 if marlin => self.state.relative_extrusion = false,
+                    83 if marlin => self.state.relative_extrusion = true,
+                    _ => {}
+                },
+                'X' | 'S' if dwell.is_some() => {}
                 'X' => x = Some(word.value * self.state.units_scale),
                 'Y' => y = Some(word.value * self.state.units_scale),
                 'Z' => z = Some(word.value * self.state.units_scale),
@@ -238,61 +1730,636 @@ impl Parser {
                 'J' => j = Some(word.value * self.state.units_scale),
                 'K' => k = Some(word.value * self.state.units_scale),
                 'R' => r = Some(word.value * self.state.units_scale),
+                'A' => a = Some(word.value),
+                'B' => b = Some(word.value),
+                'C' => c = Some(word.value),
+                'E' if marlin => e = Some(word.value * self.state.units_scale),
+                'F' if inverse_time => {
+                    block_feed = Some(word.value);
+                    self.state.feed_rate = block_feed;
+                }
+                'F' => self.state.feed_rate = Some(word.value * self.state.units_scale),
+                'S' if non_spindle_m_code => {}
+                'S' => self.record_spindle_speed(word.value),
+                'T' => self.record_tool(word.value),
                 _ => {}
             }
         }
 
+        match tool_length {
+            Some((49, _)) => self.state.tool_length = None,
+            Some((code, register)) => self.select_tool_length(code == 44, register),
+            None => {}
+        }
+        if let Some((code, register)) = cutter_comp {
+            self.select_cutter_comp(code, register);
+        }
+        if let Some((l, p)) = offset_setting {
+            self.set_work_offset(l, p, x.take(), y.take(), z.take());
+        }
+        match coordinate_offset {
+            Some(92.0) => {
+                self.set_coordinate_offset(x.take(), y.take(), z.take());
+                if let Some(e) = e.take() {
+                    self.state.extruder = e;
+                }
+            }
+            Some(_) => self.state.coordinate_offset = Vec3::default(),
+            None => {}
+        }
+        if self.state.distance_mode == DistanceMode::Absolute {
+            let origin = self.work_origin();
+            x = x.map(|x| x + origin.x);
+            y = y.map(|y| y + origin.y);
+            z = z.map(|z| z + origin.z);
+        }
+
+        let extrusion = e.map(|e| {
+            let delta = if self.state.relative_extrusion { e } else { e - self.state.extruder };
+            self.state.extruder += delta;
+            delta
+        });
+        if let Some(delta) = extrusion {
+            self.stats.extruded_length += delta;
+            if delta < 0.0 {
+                self.retractions.push(Retraction {
+                    line: self.line_segment_ends.len(),
+                    segment: self.segments.len(),
+                    length: -delta,
+                });
+            }
+        }
+        let rotary = self.rotary_end(a, b, c);
+        if self.state.spindle != Spindle::Off {
+            self.spindle_off_linted = false;
+        }
         let motion = if motion_override.is_some() {
             motion_override
-        } else if x.is_some() || y.is_some() || z.is_some() || i.is_some() || j.is_some() || k.is_some() {
+        } else if [x, y, z, i, j, k, a, b, c].iter().any(Option::is_some) {
             Some(self.state.motion_mode)
         } else {
             None
         };
 
-        if let Some(mode) = motion {
+        if let Some(code) = reference {
+            let secondary = code.floor() == 30.0;
+            if code.fract() == 0.0 {
+                self.add_reference_return(x, y, z, secondary);
+            } else {
+                self.state.reference_positions[secondary as usize] = self.state.pos;
+            }
+        } else if let Some(cycle) = thread_cycle {
+            self.add_thread_cycle(z, cycle)?;
+        } else if let Some((code, words)) = lathe_cycle {
+            self.add_lathe_cycle(code, &words, x, z)?;
+        } else if let Some(mode) = motion {
             match mode {
                 MotionMode::Rapid => {
-                    self.add_linear_move(x, y, z, MoveKind::Rapid);
+                    self.add_linear_move(x, y, z, rotary, MoveKind::Rapid);
+                }
+                MotionMode::Feed if marlin && !extrusion.is_some_and(|delta| delta > 0.0) => {
+                    self.add_linear_move(x, y, z, rotary, MoveKind::Rapid);
                 }
                 MotionMode::Feed => {
-                    self.add_linear_move(x, y, z, MoveKind::Feed);
+                    self.add_linear_move(x, y, z, rotary, MoveKind::Feed);
+                }
+                MotionMode::ArcCW | MotionMode::ArcCCW if [x, y, z, i, j, k, r].iter().all(Option::is_none) => {
+                    self.add_linear_move(None, None, None, rotary, MoveKind::Feed);
                 }
                 MotionMode::ArcCW => {
-                    self.add_arc_move(x, y, z, i, j, k, r, true)?;
+                    self.add_arc_move(x, y, z, i, j, k, r, rotary, true)?;
                 }
                 MotionMode::ArcCCW => {
-                    self.add_arc_move(x, y, z, i, j, k, r, false)?;
+                    self.add_arc_move(x, y, z, i, j, k, r, rotary, false)?;
+                }
+                MotionMode::Probe(mode) => {
+                    let segment = self.segments.len();
+                    self.add_linear_move(x, y, z, rotary, MoveKind::Probe);
+                    if self.segments.len() > segment {
+                        self.probes.push(ProbeMove { segment, mode });
+                    }
+                }
+                MotionMode::Thread => {
+                    let pitch = k
+                        .or(self.state.thread_pitch)
+                        .ok_or_else(|| anyhow!("G33 needs a K pitch"))?;
+                    self.state.thread_pitch = Some(pitch);
+                    let end = self.linear_end(x, y, z);
+                    self.push_move(end, MoveKind::Feed, self.thread_feed(pitch));
+                }
+            }
+        }
+        self.state.rotary = rotary;
+        if self.options.lint {
+            self.lint_cutting(segment_count, inverse_time);
+        }
+        if inverse_time {
+            self.apply_inverse_time(segment_count, block_feed);
+        }
+        let profile_ends = self
+            .profile_capture
+            .as_ref()
+            .is_some_and(|capture| sequence == Some(capture.last_block as f64));
+        if profile_ends {
+            self.finish_profile();
+        }
+        let first_span = self.motion_spans.len();
+        if self.segments.len() != segment_count && (self.call_depth == 0 || self.block_line.is_some()) {
+            push_motion_spans(line, &self.comments, &mut self.motion_spans);
+        }
+        self.segment_spans.resize(self.segments.len(), first_span..self.motion_spans.len());
+        if let Some(optional) = stop {
+            self.stops.push(ProgramStop {
+                line: self.line_segment_ends.len(),
+                segment: self.segments.len(),
+                optional,
+                comment: comment.or(preceding_comment),
+            });
+        }
+        if let Some(rewind) = end {
+            self.ends.push(ProgramEnd {
+                line: self.line_segment_ends.len(),
+                segment: self.segments.len(),
+                rewind,
+            });
+            self.ended |= self.call_depth == 0;
+        }
+        let pauses = dwell
+            .map(PauseKind::Dwell)
+            .into_iter()
+            .chain(tool_change.then_some(PauseKind::ToolChange));
+        for kind in pauses {
+            self.pauses.push(Pause {
+                line: self.line_segment_ends.len(),
+                segment: self.segments.len(),
+                kind,
+            });
+        }
+        if let Some(call) = call {
+            self.call_subprogram(call)?;
+        }
+        Ok(())
+    }
+
+    /// M98: runs an `O` program's blocks `repeats` times as part of the calling line.
+    fn call_subprogram(&mut self, call: Option<(u32, u32)>) -> Result<()> {
+        let (number, repeats) = call.ok_or_else(|| anyhow!("M98 needs a P program number"))?;
+        let (source, comments) = (self.source.as_deref(), &self.comments);
+        let body = self
+            .subprograms
+            .get_or_insert_with(|| {
+                source
+                    .and_then(|path| File::open(path).ok())
+                    .map(|file| subprogram_bodies(BufReader::new(file).lines().map_while(Result::ok), comments))
+                    .unwrap_or_default()
+            })
+            .get(&number)
+            .cloned();
+        let warning = match body {
+            None => format!("M98: subprogram O{} not found", number),
+            Some(_) if self.call_depth == MAX_CALL_DEPTH => {
+                format!("M98 P{}: subprograms nested more than {} deep", number, MAX_CALL_DEPTH)
+            }
+            Some(body) => {
+                let passes = (repeats as usize).min(MAX_LOOP_ITERATIONS);
+                if passes < repeats as usize {
+                    self.diagnostics.push(Diagnostic {
+                        line: self.line_no,
+                        severity: Severity::Warning,
+                        message: format!("M98 P{}: repeats stopped after {}", number, MAX_LOOP_ITERATIONS),
+                        lint: None,
+                    });
                 }
+                let first_segment = self.segments.len();
+                let mut first_pass_end = None;
+                self.call_depth += 1;
+                let caller = self.block_line.take();
+                let result = (0..passes).try_for_each(|_| {
+                    body.iter().try_for_each(|line| self.execute_block(line))?;
+                    first_pass_end.get_or_insert(self.segments.len());
+                    Ok(())
+                });
+                self.block_line = caller;
+                self.call_depth -= 1;
+                self.record_repeat(None, passes, first_segment, first_pass_end);
+                return result;
             }
+        };
+        self.diagnostics.push(Diagnostic {
+            line: self.line_no,
+            severity: Severity::Warning,
+            message: warning,
+            lint: None,
+        });
+        Ok(())
+    }
+
+    fn close_operation(&mut self) {
+        if let Some(op) = self.operations.last_mut() {
+            op.lines.end = self.line_segment_ends.len();
+            op.segments.end = self.segments.len();
         }
+    }
 
+    fn end_line(&mut self) {
         self.line_segment_ends.push(self.segments.len());
-        Ok(())
+        if self.options.keep_words {
+            self.line_word_ends.push(self.words.len());
+        }
+        let state = self.line_state();
+        self.line_states.push(state);
+    }
+
+    fn line_state(&mut self) -> LineState {
+        LineState {
+            position: self.state.pos,
+            rotary: self.state.rotary,
+            tool: self.state.tool,
+            spindle_speed: self.state.spindle_speed,
+            spindle: self.state.spindle,
+            coolant: self.state.coolant,
+            feed_rate: self.feed_per_minute(),
+            feed_mode: self.state.feed_mode,
+            programmed_feed: self.state.feed_rate,
+            units: self.state.units(),
+            distance_mode: self.state.distance_mode,
+            plane: self.state.plane,
+            motion_mode: self.state.motion_mode,
+            work_offset: self.state.work_offset,
+            work_origin: self.work_origin(),
+            tool_length: self.state.tool_length,
+            line_number: self.line_number.take(),
+        }
+    }
+
+    fn work_origin(&self) -> Vec3 {
+        self.state.work_offsets[self.state.work_offset.unwrap_or(WorkOffset::G54).index()]
+            + self.state.coordinate_offset
+    }
+
+    /// G43/G44: compensates by the `H` register, or the current tool's without one.
+    fn select_tool_length(&mut self, negative: bool, register: Option<f64>) {
+        let Some(register) = register.map(|h| h.round() as u32).or(self.state.tool) else {
+            return;
+        };
+        let length = self.options.tool_lengths.get(&register).copied();
+        if length.is_none() && !self.options.tool_lengths.is_empty() {
+            self.diagnostics.push(Diagnostic {
+                line: self.line_no,
+                severity: Severity::Warning,
+                message: format!("H{} is not in the tool length table", register),
+                lint: None,
+            });
+        }
+        let length = length.unwrap_or(0.0);
+        self.state.tool_length = Some(ToolLengthOffset {
+            register,
+            negative,
+            shift: if negative { -length } else { length },
+        });
+    }
+
+    /// G40 ends cutter compensation; G41/G42 start it on the left/right of the path with the
+    /// radius of tool `D`, or of the current tool without one.
+    fn select_cutter_comp(&mut self, code: u32, register: Option<f64>) {
+        if let Some((start, offset, plane)) = self.open_comp.take().filter(|&(start, ..)| start < self.segments.len()) {
+            self.comp_spans.push(CompSpan {
+                segments: start..self.segments.len(),
+                offset,
+                plane,
+            });
+        }
+        if code == 40 {
+            return;
+        }
+        let Some(tool) = register.map(|d| d.round() as u32).or(self.state.tool) else {
+            return;
+        };
+        match self.options.tool_diameters.get(&tool) {
+            Some(diameter) => {
+                let radius = diameter / 2.0;
+                let offset = if code == 41 { radius } else { -radius };
+                self.open_comp = Some((self.segments.len(), offset, self.state.plane));
+            }
+            None if !self.options.tool_diameters.is_empty() => self.diagnostics.push(Diagnostic {
+                line: self.line_no,
+                severity: Severity::Warning,
+                message: format!("G{}: T{} has no diameter in the tool table", code, tool),
+                lint: None,
+            }),
+            None => {}
+        }
+    }
+
+    /// G10 L2 moves work offset `p` (P0 is the current one) to the given machine coordinates;
+    /// L20 moves it so the current position reads as them. Other G10 forms are ignored.
+    fn set_work_offset(&mut self, l: Option<f64>, p: Option<f64>, x: Option<f64>, y: Option<f64>, z: Option<f64>) {
+        let current = self.state.work_offset.unwrap_or(WorkOffset::G54).index();
+        let index = match p.map(|p| p.round() as usize) {
+            None | Some(0) => current,
+            Some(p) if p <= WorkOffset::COUNT => p - 1,
+            Some(p) => {
+                self.diagnostics.push(Diagnostic {
+                    line: self.line_no,
+                    severity: Severity::Warning,
+                    message: format!("G10: no work offset P{}", p),
+                    lint: None,
+                });
+                return;
+            }
+        };
+        let (base, sign) = match l.map(|l| l.round() as i32) {
+            Some(2) => (Vec3::default(), 1.0),
+            // A G92 shift stays on top of the new origin.
+            Some(20) => (self.state.pos - (self.work_origin() - self.state.work_offsets[current]), -1.0),
+            _ => return,
+        };
+        let origin = &mut self.state.work_offsets[index];
+        for (axis, value, base) in [(&mut origin.x, x, base.x), (&mut origin.y, y, base.y), (&mut origin.z, z, base.z)] {
+            if let Some(value) = value {
+                *axis = base + sign * value;
+            }
+        }
+    }
+
+    /// G92: shifts the origin so the current position reads as the given coordinates.
+    fn set_coordinate_offset(&mut self, x: Option<f64>, y: Option<f64>, z: Option<f64>) {
+        let base = self.work_origin() - self.state.coordinate_offset;
+        let pos = self.state.pos;
+        let offset = &mut self.state.coordinate_offset;
+        if let Some(x) = x {
+            offset.x = pos.x - base.x - x;
+        }
+        if let Some(y) = y {
+            offset.y = pos.y - base.y - y;
+        }
+        if let Some(z) = z {
+            offset.z = pos.z - base.z - z;
+        }
+    }
+
+    fn select_units(&mut self, units: Units) {
+        if !self.stats.units_used.is_empty() && self.state.units() != units {
+            self.stats.unit_switches += 1;
+            let code = match units {
+                Units::Millimeters => "G21",
+                Units::Inches => "G20",
+            };
+            self.diagnostics.push(Diagnostic {
+                line: self.line_no,
+                severity: Severity::Warning,
+                message: format!("units switch to {} mid-program", code),
+                lint: None,
+            });
+        }
+        if !self.stats.units_used.contains(&units) {
+            self.stats.units_used.push(units);
+        }
+        self.state.units_scale = units.mm_per_unit();
+    }
+
+    fn record_spindle_speed(&mut self, speed: f64) {
+        self.state.spindle_speed = Some(speed);
+        let range = self.stats.spindle_speed_range.get_or_insert((speed, speed));
+        range.0 = range.0.min(speed);
+        range.1 = range.1.max(speed);
+    }
+
+    fn record_tool(&mut self, value: f64) {
+        if value < 0.0 {
+            return;
+        }
+        let tool = value.round() as u32;
+        self.state.tool = Some(tool);
+        if let Err(pos) = self.stats.tools_used.binary_search(&tool) {
+            self.stats.tools_used.insert(pos, tool);
+        }
+    }
+
+    fn add_linear_move(
+        &mut self,
+        x: Option<f64>,
+        y: Option<f64>,
+        z: Option<f64>,
+        rotary: RotaryAngles,
+        kind: MoveKind,
+    ) {
+        let feed = match kind {
+            MoveKind::Rapid => None,
+            MoveKind::Feed | MoveKind::Probe => self.feed_per_minute(),
+        };
+        self.push_rotary_move(self.linear_end(x, y, z), rotary, kind, feed);
     }
 
-    fn add_linear_move(&mut self, x: Option<f64>, y: Option<f64>, z: Option<f64>, kind: MoveKind) {
+    /// Rapids through the programmed point, then on to the reference position along the
+    /// programmed axes, or along all axes when none is given.
+    fn add_reference_return(&mut self, x: Option<f64>, y: Option<f64>, z: Option<f64>, secondary: bool) {
+        let first = self.segments.len();
+        self.add_linear_move(x, y, z, self.state.rotary, MoveKind::Rapid);
+        let target = self.state.reference_positions[secondary as usize];
+        let all = x.is_none() && y.is_none() && z.is_none();
+        let mut end = self.state.pos;
+        for (axis, word, home) in [(&mut end.x, x, target.x), (&mut end.y, y, target.y), (&mut end.z, z, target.z)] {
+            if all || word.is_some() {
+                *axis = home;
+            }
+        }
+        self.push_move(end, MoveKind::Rapid, None);
+        self.reference_returns.push(ReferenceReturn {
+            line: self.line_segment_ends.len(),
+            segments: first..self.segments.len(),
+            secondary,
+        });
+    }
+
+    fn linear_end(&self, x: Option<f64>, y: Option<f64>, z: Option<f64>) -> Vec3 {
         let start = self.state.pos;
         let mut end = start;
-
         apply_axis(&mut end.x, x, start.x, self.state.distance_mode);
         apply_axis(&mut end.y, y, start.y, self.state.distance_mode);
         apply_axis(&mut end.z, z, start.z, self.state.distance_mode);
+        end
+    }
 
-        if end == start {
+    fn rotary_end(&self, a: Option<f64>, b: Option<f64>, c: Option<f64>) -> RotaryAngles {
+        let start = self.state.rotary;
+        let mut end = start;
+        apply_axis(&mut end.a, a, start.a, self.state.distance_mode);
+        apply_axis(&mut end.b, b, start.b, self.state.distance_mode);
+        apply_axis(&mut end.c, c, start.c, self.state.distance_mode);
+        end
+    }
+
+    /// Where machine point `p` with the table at `angles` is drawn: in workpiece coordinates,
+    /// or with A unwrapped onto Y.
+    fn placed_point(&self, p: Vec3, angles: RotaryAngles) -> Vec3 {
+        let Some(diameter) = self.options.unwrap_diameter else {
+            return workpiece_point(p, angles);
+        };
+        let p = workpiece_point(p, RotaryAngles { a: 0.0, ..angles });
+        Vec3::new(p.x, p.y + angles.a.to_radians() * diameter / 2.0, p.z)
+    }
+
+    fn push_move(&mut self, end: Vec3, kind: MoveKind, feed: Option<f64>) {
+        self.push_rotary_move(end, self.state.rotary, kind, feed);
+    }
+
+    /// Moves to machine point `end` while turning the table to `rotary`, in steps of
+    /// [`ROTARY_STEP`] so the turn draws as a curve around the part.
+    fn push_rotary_move(&mut self, end: Vec3, rotary: RotaryAngles, kind: MoveKind, feed: Option<f64>) {
+        let (start, from) = (self.state.pos, self.state.rotary);
+        if end == start && rotary == from {
             return;
         }
-
-        self.segments.push(LineSegment { start, end, kind });
-        self.bounds.include(start);
-        self.bounds.include(end);
+        let first = self.segments.len();
+        let steps = (from.max_delta(rotary) / ROTARY_STEP).ceil().max(1.0) as usize;
+        let mut prev = self.placed_point(start, from);
+        for step in 1..=steps {
+            let (point, angles) = if step == steps {
+                (end, rotary)
+            } else {
+                let t = step as f64 / steps as f64;
+                (start + (end - start) * t, from.lerp(rotary, t))
+            };
+            let point = self.placed_point(point, angles);
+            if point == prev {
+                continue;
+            }
+            self.segments.push(LineSegment {
+                start: prev,
+                end: point,
+                kind,
+                feed,
+                rotary: angles,
+            });
+            self.bounds.include(prev);
+            self.bounds.include(point);
+            prev = point;
+        }
         self.state.pos = end;
+        self.state.rotary = rotary;
+        if self.segments.len() == first {
+            return;
+        }
         match kind {
             MoveKind::Rapid => self.stats.rapid_moves += 1,
             MoveKind::Feed => self.stats.feed_moves += 1,
+            MoveKind::Probe => self.stats.probe_moves += 1,
+        }
+    }
+
+    /// Feed of a spindle-synchronized move: pitch times spindle speed, or the programmed F.
+    fn thread_feed(&self, pitch: f64) -> Option<f64> {
+        self.state
+            .spindle_speed
+            .map(|speed| pitch * speed)
+            .or(self.feed_per_minute())
+    }
+
+    /// Feed in mm/min for modes where it does not depend on the move's length.
+    fn feed_per_minute(&self) -> Option<f64> {
+        match self.state.feed_mode {
+            FeedMode::InverseTime => None,
+            FeedMode::UnitsPerMinute => self.state.feed_rate,
+            FeedMode::UnitsPerRevolution => self.state.feed_rate.zip(self.state.spindle_speed).map(|(f, s)| f * s),
+        }
+    }
+
+    fn lint(&mut self, finding: Lint) {
+        self.diagnostics.push(Diagnostic {
+            line: self.line_no,
+            severity: Severity::Warning,
+            message: format!("lint: {}", finding),
+            lint: Some(finding),
+        });
+    }
+
+    /// Flags the block's feed moves from `first` on when no feed rate is set or the spindle
+    /// is off; Marlin prints without a spindle.
+    fn lint_cutting(&mut self, first: usize, inverse_time: bool) {
+        if !self.segments[first..].iter().any(|seg| seg.kind == MoveKind::Feed) {
+            return;
+        }
+        if !inverse_time && self.state.feed_rate.is_none() && !self.missing_feed_linted {
+            self.missing_feed_linted = true;
+            self.lint(Lint::MissingFeed);
+        }
+        let idle = self.state.spindle == Spindle::Off && self.options.dialect != Dialect::Marlin;
+        if idle && !self.spindle_off_linted {
+            self.spindle_off_linted = true;
+            self.lint(Lint::SpindleOff);
+        }
+    }
+
+    /// G93: gives the block's feed moves from `first` on the feed that makes them take
+    /// `1/F` minutes together.
+    fn apply_inverse_time(&mut self, first: usize, per_minute: Option<f64>) {
+        let moves: Vec<&mut LineSegment> =
+            self.segments[first..].iter_mut().filter(|seg| seg.kind != MoveKind::Rapid).collect();
+        let length: f64 = moves.iter().map(|seg| segment_length(seg)).sum();
+        if length == 0.0 {
+            return;
         }
+        let Some(per_minute) = per_minute else {
+            self.diagnostics.push(Diagnostic {
+                line: self.line_no,
+                severity: Severity::Warning,
+                message: "G93 feed move without an F word".to_string(),
+                lint: None,
+            });
+            return;
+        };
+        for seg in moves {
+            seg.feed = Some(length * per_minute);
+        }
+    }
+
+    /// G4 dwell time: `P` (or Fanuc-style `X`) seconds, or for Marlin `S` seconds or `P` milliseconds.
+    fn dwell_seconds(&self, words: &[Word]) -> f64 {
+        let seconds = match self.options.dialect {
+            Dialect::Generic | Dialect::LinuxCnc | Dialect::Fanuc => word_value(words, 'P').or_else(|| word_value(words, 'X')),
+            Dialect::Marlin => word_value(words, 'S').or_else(|| word_value(words, 'P').map(|ms| ms / 1000.0)),
+        };
+        seconds.unwrap_or(0.0).max(0.0)
+    }
+
+    fn thread_cycle(&self, words: &[Word]) -> ThreadCycle {
+        let value = |letter| word_value(words, letter);
+        let length = |letter| value(letter).unwrap_or(0.0) * self.state.units_scale;
+        ThreadCycle {
+            pitch: length('P'),
+            peak_offset: length('I'),
+            initial_depth: length('J'),
+            full_depth: length('K'),
+            degression: value('R').unwrap_or(1.0),
+            compound_angle: value('Q').unwrap_or(0.0),
+            spring_passes: value('H').unwrap_or(0.0).max(0.0).round() as u32,
+        }
+    }
+
+    /// Expands G76 into its passes: in along X at the start Z, cut to the end Z, retract to the
+    /// drive line, and finally return to the start point.
+    fn add_thread_cycle(&mut self, z: Option<f64>, cycle: ThreadCycle) -> Result<()> {
+        cycle.validate()?;
+        let start = self.state.pos;
+        let end_z = self.linear_end(None, None, z).z;
+        if (end_z - start.z).abs() < 1e-9 {
+            return Err(anyhow!("G76 needs a Z end point"));
+        }
+        let feed = self.thread_feed(cycle.pitch);
+        let at = |x: f64, z: f64| Vec3::new(x, start.y, z);
+        for pass in cycle.passes(start.x, end_z - start.z) {
+            let (pass_start, pass_end) = (start.z + pass.z_offset, end_z + pass.z_offset);
+            self.push_move(at(start.x, pass_start), MoveKind::Rapid, None);
+            self.push_move(at(pass.x, pass_start), MoveKind::Rapid, None);
+            self.push_move(at(pass.x, pass_end), MoveKind::Feed, feed);
+            self.push_move(at(start.x, pass_end), MoveKind::Rapid, None);
+        }
+        self.push_move(start, MoveKind::Rapid, None);
+        Ok(())
     }
 
+    #[allow(clippy::too_many_arguments)]
     fn add_arc_move(
         &mut self,
         x: Option<f64>,
@@ -302,6 +2369,7 @@ impl Parser {
         j: Option<f64>,
         k: Option<f64>,
         r: Option<f64>,
+        rotary: RotaryAngles,
         clockwise: bool,
     ) -> Result<()> {
         let start = self.state.pos;
@@ -311,28 +2379,205 @@ impl Parser {
         apply_axis(&mut end.y, y, start.y, self.state.distance_mode);
         apply_axis(&mut end.z, z, start.z, self.state.distance_mode);
 
-        if end == start {
+        // Identical endpoints are a full circle with IJK but leave an R arc undefined.
+        if end == start && i.is_none() && j.is_none() && k.is_none() {
             return Ok(());
         }
 
         let center = arc_center(start, end, i, j, k, r, self.state.plane, clockwise)?;
-        let segments = arc_to_segments(start, end, center, clockwise, self.state.plane);
+        if i.is_some() || j.is_some() || k.is_some() {
+            self.check_arc_radius(start, end, center);
+        }
+        let segments = arc_to_segments(
+            start,
+            end,
+            center,
+            clockwise,
+            self.state.plane,
+            self.feed_per_minute(),
+            self.options.arc_tessellation,
+        );
 
         if segments.is_empty() {
             return Ok(());
         }
 
-        for seg in segments {
-            self.segments.push(seg);
-            self.bounds.include(seg.start);
-            self.bounds.include(seg.end);
+        // A table turning during the arc is spread over its chords, split to ROTARY_STEP.
+        let from = self.state.rotary;
+        let count = segments.len();
+        let splits = (from.max_delta(rotary) / ROTARY_STEP / count as f64).ceil().max(1.0) as usize;
+        let angles = |step: usize| from.lerp(rotary, step as f64 / (count * splits) as f64);
+        for (n, seg) in segments.iter().enumerate() {
+            for split in 0..splits {
+                let step = n * splits + split;
+                let at = |t: usize| seg.start.lerp(seg.end, t as f64 / splits as f64);
+                let piece = LineSegment {
+                    start: self.placed_point(at(split), angles(step)),
+                    end: self.placed_point(at(split + 1), angles(step + 1)),
+                    rotary: angles(step + 1),
+                    ..*seg
+                };
+                self.segments.push(piece);
+                self.bounds.include(piece.start);
+                self.bounds.include(piece.end);
+            }
         }
 
         self.state.pos = end;
+        self.state.rotary = rotary;
         self.stats.arc_moves += 1;
         self.stats.feed_moves += 1;
         Ok(())
     }
+
+    /// Fanuc G70–G76. The first block of a two-block cycle only sets [`LatheParams`]; G71–G73
+    /// start collecting their profile and G74–G76 expand immediately.
+    fn add_lathe_cycle(&mut self, code: u32, words: &[Word], x: Option<f64>, z: Option<f64>) -> Result<()> {
+        let value = |letter| word_value(words, letter);
+        let scale = self.state.units_scale;
+        let length = |letter| value(letter).map(|value| value * scale);
+        let block = value('P').map(|p| p.round() as u32);
+        // G74–G76 P and Q are in least input increments (0.001 mm / 0.0001 in) without a sign.
+        let increment = if scale == 1.0 { 0.001 } else { 0.0001 * scale };
+        match (code, block) {
+            (71, None) => {
+                self.lathe.depth = length('U').unwrap_or(self.lathe.depth);
+                self.lathe.retract = length('R').unwrap_or(self.lathe.retract);
+            }
+            (72, None) => {
+                self.lathe.depth = length('W').unwrap_or(self.lathe.depth);
+                self.lathe.retract = length('R').unwrap_or(self.lathe.retract);
+            }
+            (73, None) => {
+                self.lathe.relief = (length('U').unwrap_or(0.0), length('W').unwrap_or(0.0));
+                self.lathe.passes = value('R').map_or(self.lathe.passes, |passes| passes.max(1.0).round() as u32);
+            }
+            (70, Some(block)) => self.finish_along_profile(block),
+            (71..=73, Some(first_block)) => {
+                self.profile_capture = Some(ProfileCapture {
+                    code,
+                    line_no: self.line_no,
+                    start: self.state.pos,
+                    first_line: self.line_segment_ends.len() + 1,
+                    first_segment: self.segments.len(),
+                    first_span: self.motion_spans.len(),
+                    first_block,
+                    last_block: value('Q').map_or(first_block, |q| q.round() as u32),
+                    allowance: (length('U').unwrap_or(0.0), length('W').unwrap_or(0.0)),
+                    feed: self.feed_per_minute(),
+                    moves: (self.stats.rapid_moves, self.stats.feed_moves, self.stats.arc_moves),
+                });
+            }
+            (74 | 75, _) if x.is_none() && z.is_none() => {
+                self.lathe.peck_retract = length('R').unwrap_or(self.lathe.peck_retract);
+            }
+            (74 | 75, _) => {
+                let amount = |letter| value(letter).unwrap_or(0.0).abs() * increment;
+                let start = self.state.pos;
+                let end = self.linear_end(x, None, z);
+                let face = code == 74;
+                let (shift, peck) = if face { (amount('P'), amount('Q')) } else { (amount('Q'), amount('P')) };
+                let moves = groove_passes((start.x, start.z), (end.x, end.z), shift, peck, &self.lathe, face);
+                self.push_lathe_moves(&moves, start.y, self.feed_per_minute());
+            }
+            (76, _) if x.is_none() && z.is_none() => {
+                // P packs finishing passes, chamfer and tool angle as mmrraa.
+                if let Some(packed) = value('P').map(|p| p.round() as u32) {
+                    self.lathe.thread_finish_passes = (packed / 10_000).max(1);
+                    self.lathe.thread_angle = (packed % 100) as f64;
+                }
+            }
+            (76, _) => {
+                let root_x = self.linear_end(x, None, z).x;
+                let height = value('P').unwrap_or(0.0) * increment * 2.0;
+                let inward = if root_x < self.state.pos.x { 1.0 } else { -1.0 };
+                let cycle = ThreadCycle {
+                    pitch: value('F').unwrap_or(0.0) * scale,
+                    peak_offset: root_x + inward * height - self.state.pos.x,
+                    initial_depth: value('Q').unwrap_or(0.0) * increment * 2.0,
+                    full_depth: height,
+                    degression: 2.0,
+                    compound_angle: self.lathe.thread_angle / 2.0,
+                    spring_passes: self.lathe.thread_finish_passes - 1,
+                };
+                self.add_thread_cycle(z, cycle)?;
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Swaps the moves traced by the captured profile blocks for the cycle's passes.
+    fn finish_profile(&mut self) {
+        let Some(capture) = self.profile_capture.take() else {
+            return;
+        };
+        let traced: Vec<LineSegment> = self.segments.drain(capture.first_segment..).collect();
+        self.segment_spans.truncate(capture.first_segment);
+        self.motion_spans.truncate(capture.first_span);
+        (self.stats.rapid_moves, self.stats.feed_moves, self.stats.arc_moves) = capture.moves;
+        for end in self.line_segment_ends.iter_mut().skip(capture.first_line) {
+            *end = capture.first_segment;
+        }
+        self.state.pos = capture.start;
+        let (u, w) = capture.allowance;
+        let profile: Vec<Point> = traced.iter().map(|seg| (seg.end.x + u, seg.end.z + w)).collect();
+        let start = (capture.start.x, capture.start.z);
+        let moves = match capture.code {
+            71 => rough_passes(start, &profile, &self.lathe, false),
+            72 => rough_passes(start, &profile, &self.lathe, true),
+            _ => pattern_passes(start, &profile, &self.lathe),
+        };
+        self.push_lathe_moves(&moves, capture.start.y, capture.feed);
+        self.profiles.insert(capture.first_block, traced);
+    }
+
+    /// G70: follows a captured profile at its programmed feeds, then returns to the start.
+    fn finish_along_profile(&mut self, block: u32) {
+        let Some(profile) = self.profiles.get(&block).cloned() else {
+            self.diagnostics.push(Diagnostic {
+                line: self.line_no,
+                severity: Severity::Warning,
+                message: format!("G70 profile N{} is not defined by an earlier G71-G73", block),
+                lint: None,
+            });
+            return;
+        };
+        let start = self.state.pos;
+        for seg in profile {
+            self.push_move(seg.end, seg.kind, seg.feed);
+        }
+        self.push_move(start, MoveKind::Rapid, None);
+    }
+
+    fn push_lathe_moves(&mut self, moves: &[LatheMove], y: f64, feed: Option<f64>) {
+        for step in moves {
+            let feed = if step.kind == MoveKind::Rapid { None } else { feed };
+            self.push_move(Vec3::new(step.x, y, step.z), step.kind, feed);
+        }
+    }
+
+    fn check_arc_radius(&mut self, start: Vec3, end: Vec3, center: Vec3) {
+        let plane = self.state.plane;
+        let radius = |p: Vec3| {
+            let (px, py) = plane_coords(p, plane);
+            let (cx, cy) = plane_coords(center, plane);
+            ((px - cx).powi(2) + (py - cy).powi(2)).sqrt()
+        };
+        let (start_radius, end_radius) = (radius(start), radius(end));
+        let delta = (start_radius - end_radius).abs();
+        if delta > self.options.arc_radius_tolerance {
+            self.diagnostics.push(Diagnostic {
+                line: self.line_no,
+                severity: Severity::Error,
+                message: format!(
+                    "arc radius mismatch: start {:.4}, end {:.4} (diff {:.4})",
+                    start_radius, end_radius, delta
+                ),
+                lint: None,
+            });
+        }
+    }
 }
 
 fn apply_axis(axis: &mut f64, input: Option<f64>, current: f64, mode: DistanceMode) {
@@ -344,6 +2589,7 @@ fn apply_axis(axis: &mut f64, input: Option<f64>, current: f64, mode: DistanceMo
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 fn arc_center(
     start: Vec3,
     end: Vec3,
@@ -497,6 +2743,8 @@ fn arc_to_segments(
     center: Vec3,
     clockwise: bool,
     plane: Plane,
+    feed: Option<f64>,
+    tessellation: ArcTessellation,
 ) -> Vec<LineSegment> {
     let (sx, sy) = plane_coords(start, plane);
     let (ex, ey) = plane_coords(end, plane);
@@ -519,9 +2767,7 @@ fn arc_to_segments(
         sweep += std::f64::consts::TAU;
     }
 
-    let arc_length = radius * sweep.abs();
-    let steps = (arc_length / ARC_SEGMENT_LENGTH).ceil() as usize;
-    let steps = steps.max(1);
+    let steps = tessellation.steps(radius, sweep);
 
     let mut segments = Vec::with_capacity(steps);
     let mut prev = start;
@@ -554,6 +2800,8 @@ fn arc_to_segments(
             start: prev,
             end: point,
             kind: MoveKind::Feed,
+            feed,
+            rotary: RotaryAngles::default(),
         });
         prev = point;
     }
@@ -561,6 +2809,21 @@ fn arc_to_segments(
     segments
 }
 
+fn segment_length(seg: &LineSegment) -> f64 {
+    (seg.end - seg.start).length()
+}
+
+fn probe_mode(code: f64) -> Option<ProbeMode> {
+    let (toward, signal_error) = match (code * 10.0).round() as i32 {
+        382 => (true, true),
+        383 => (true, false),
+        384 => (false, true),
+        385 => (false, false),
+        _ => return None,
+    };
+    Some(ProbeMode { toward, signal_error })
+}
+
 fn plane_coords(p: Vec3, plane: Plane) -> (f64, f64) {
     match plane {
         Plane::XY => (p.x, p.y),
@@ -569,10 +2832,35 @@ fn plane_coords(p: Vec3, plane: Plane) -> (f64, f64) {
     }
 }
 
-#[derive(Debug, Clone, Copy)]
-struct Word {
-    letter: char,
-    value: f64,
+/// A letter and its value, e.g. `X` `12.5`; the letter is upper case.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Word {
+    pub letter: char,
+    pub value: f64,
+}
+
+/// Program number and repeat count of an M98: `P` with an `L` count, or Fanuc's `P` with the
+/// count in the digits above the four-digit program number.
+fn subprogram_call(words: &[Word]) -> Option<(u32, u32)> {
+    let program = word_value(words, 'P')?.round() as u32;
+    Some(match word_value(words, 'L') {
+        Some(repeats) => (program, repeats.round() as u32),
+        None if program > 9999 => (program % 10000, program / 10000),
+        None => (program, 1),
+    })
+}
+
+/// Splits a RepRap/grbl-streamer `*checksum` suffix off a line.
+fn split_checksum(line: &str) -> Option<(&str, u8)> {
+    let (body, digits) = line.trim_end().rsplit_once('*')?;
+    if digits.is_empty() || !digits.bytes().all(|byte| byte.is_ascii_digit()) {
+        return None;
+    }
+    Some((body, digits.parse().ok()?))
+}
+
+fn word_value(words: &[Word], letter: char) -> Option<f64> {
+    words.iter().find(|word| word.letter == letter).map(|word| word.value)
 }
 
 fn parse_words(line: &str, options: &ParseOptions) -> Result<Vec<Word>> {
@@ -600,7 +2888,7 @@ fn parse_words(line: &str, options: &ParseOptions) -> Result<Vec<Word>> {
             }
             if num.trim().is_empty() {
                 if options.should_ignore_missing(letter)
-                    || (options.ignore_unknown_words && !is_known_letter(letter))
+                    || (options.ignores_unknown_words() && !is_known_letter(letter))
                 {
                     continue;
                 }
@@ -615,6 +2903,34 @@ fn parse_words(line: &str, options: &ParseOptions) -> Result<Vec<Word>> {
     Ok(words)
 }
 
+/// LinuxCNC words, whose values may be `#` parameters or `[expressions]`, and the line's
+/// `#param = value` assignments.
+fn parse_ngc_words(line: &str, params: &Parameters) -> Result<(Vec<Word>, Vec<Assignment>)> {
+    let (mut words, mut assignments) = (Vec::new(), Vec::new());
+    let mut rest = line.trim_start();
+    while let Some(ch) = rest.chars().next() {
+        if ch == '#' {
+            let (param, used) = read_parameter(&rest[1..], params)?;
+            let value = rest[1 + used..]
+                .trim_start()
+                .strip_prefix('=')
+                .ok_or_else(|| anyhow!("expected '=' after parameter"))?;
+            let (number, used) = read_value(value, params)?;
+            assignments.push((param, number));
+            rest = &value[used..];
+        } else if ch.is_ascii_alphabetic() {
+            let letter = ch.to_ascii_uppercase();
+            let (value, used) = read_value(&rest[1..], params).with_context(|| format!("value for {}", letter))?;
+            words.push(Word { letter, value });
+            rest = &rest[1 + used..];
+        } else {
+            return Err(anyhow!("unexpected '{}'", ch));
+        }
+        rest = rest.trim_start();
+    }
+    Ok((words, assignments))
+}
+
 fn should_skip_label_token(chars: &Peekable<Chars<'_>>) -> bool {
     let mut lookahead = chars.clone();
     match lookahead.next() {
@@ -638,27 +2954,32 @@ fn skip_token_tail(chars: &mut Peekable<Chars<'_>>) {
     }
 }
 
-fn strip_comments(line: &str) -> String {
-    let mut out = String::new();
-    let mut in_paren = false;
-    let mut chars = line.chars().peekable();
-    while let Some(ch) = chars.next() {
-        if in_paren {
-            if ch == ')' {
-                in_paren = false;
-            }
-            continue;
-        }
-        if ch == '(' {
-            in_paren = true;
-            continue;
-        }
-        if ch == ';' {
+/// Records the byte ranges of the motion words in a raw line, skipping comments and
+/// multi-letter tokens the way `parse_words` does.
+fn push_motion_spans(line: &str, comments: &CommentSyntax, spans: &mut Vec<Range<usize>>) {
+    let bytes = line.as_bytes();
+    let mut idx = 0;
+    while idx < bytes.len() {
+        let byte = bytes[idx];
+        if line.get(idx..).is_some_and(|rest| comments.line_prefix_len(rest).is_some()) {
             break;
         }
-        out.push(ch);
+        if comments.parentheses && byte == b'(' {
+            idx += bytes[idx..].iter().position(|&b| b == b')').unwrap_or(bytes.len() - idx);
+        } else if b"XYZABCIJKR".contains(&byte.to_ascii_uppercase())
+            && (idx == 0 || !bytes[idx - 1].is_ascii_alphabetic())
+        {
+            let len = bytes[idx + 1..]
+                .iter()
+                .take_while(|b| b.is_ascii_digit() || matches!(b, b'.' | b'-' | b'+'))
+                .count();
+            if len > 0 {
+                spans.push(idx..idx + 1 + len);
+                idx += len;
+            }
+        }
+        idx += 1;
     }
-    out
 }
 
 fn is_known_letter(letter: char) -> bool {
@@ -680,6 +3001,746 @@ mod tests {
         assert_eq!(seg.end, Vec3::new(10.0, 5.0, 0.0));
     }
 
+    #[test]
+    fn pushed_lines_match_a_file_parse() {
+        let source = "G21 G90\nG0 X5 Y5\nG1 Z-1 F300\nG2 X15 Y5 I5 J0\nG1 X1..2\nG0 Z5\n";
+        let path = std::env::temp_dir().join(format!("cnc_gcode_stream_{}.nc", std::process::id()));
+        std::fs::write(&path, source).unwrap();
+        let options = ParseOptions::default().with_lenient(true);
+        let from_file = parse_file_with_options(&path, options.clone()).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let mut parser = Parser::new(options);
+        let mut counts = Vec::new();
+        for line in source.lines() {
+            parser.push_line(line).unwrap();
+            counts.push(parser.segments().len());
+        }
+        let streamed = parser.finish().unwrap();
+        assert_eq!(counts, streamed.line_segment_ends);
+        let ends = |toolpath: &Toolpath| toolpath.segments.iter().map(|seg| seg.end).collect::<Vec<_>>();
+        assert_eq!(ends(&streamed), ends(&from_file));
+        assert_eq!(streamed.line_segment_ends, from_file.line_segment_ends);
+        let lines = |toolpath: &Toolpath| toolpath.diagnostics.iter().map(|diag| diag.line).collect::<Vec<_>>();
+        assert_eq!(lines(&streamed), [5]);
+        assert_eq!(lines(&streamed), lines(&from_file));
+    }
+
+    #[test]
+    fn strings_and_readers_parse_like_files() {
+        let source = "O1000\nM98 P2000 L2\nM30\nO2000\nG91 G1 X10 F100\nG90 M99\n";
+        let options = ParseOptions::default().with_line_range(0..3);
+        let from_str = parse_str(source, options.clone()).unwrap();
+        let ends: Vec<f64> = from_str.segments.iter().map(|seg| seg.end.x).collect();
+        assert_eq!(ends, [10.0, 20.0]);
+        assert!(from_str.diagnostics.is_empty());
+
+        let from_reader = parse_reader(source.as_bytes(), options).unwrap();
+        assert!(from_reader.segments.is_empty());
+        assert!(from_reader.diagnostics[0].message.contains("O2000 not found"));
+    }
+
+    #[test]
+    fn lenient_parses_record_bad_lines_and_keep_going() {
+        let source: &[u8] = b"G1 X10 F100\nG1 X1..2\n(\xff end)\nG1 X20\n";
+        assert!(parse_reader(source, ParseOptions::default()).is_err());
+
+        let toolpath = parse_reader(source, ParseOptions::default().with_lenient(true)).unwrap();
+        assert_eq!(toolpath.segments.len(), 2);
+        let diagnostics: Vec<(usize, Severity)> =
+            toolpath.diagnostics.iter().map(|diag| (diag.line, diag.severity)).collect();
+        assert_eq!(diagnostics, [(2, Severity::Error), (3, Severity::Warning)]);
+        let from_bytes = parse_bytes(source, ParseOptions::default().with_lenient(true)).unwrap();
+        assert_eq!(from_bytes.diagnostics, toolpath.diagnostics);
+    }
+
+    #[test]
+    fn track_feed_tools_and_spindle() {
+        let mut parser = Parser::new(ParseOptions::default());
+        parser.parse_line("T2 M6 S12000 M3", 1).unwrap();
+        parser.parse_line("G0 X1", 2).unwrap();
+        parser.parse_line("G20 G1 X2 F10", 3).unwrap();
+        parser.parse_line("T1 S8000", 4).unwrap();
+        let toolpath = parser.finish().unwrap();
+        assert_eq!(toolpath.segments[0].feed, None);
+        assert!((toolpath.segments[1].feed.unwrap() - 254.0).abs() < 1e-9);
+        assert_eq!(toolpath.stats.tools_used, vec![1, 2]);
+        assert_eq!(toolpath.stats.spindle_speed_range, Some((8000.0, 12000.0)));
+        assert_eq!(toolpath.line_states.len(), 4);
+        assert_eq!(toolpath.line_states[2].tool, Some(2));
+        assert_eq!(toolpath.line_states[2].spindle_speed, Some(12000.0));
+        assert_eq!(toolpath.line_states[3].tool, Some(1));
+        assert!((toolpath.line_states[3].feed_rate.unwrap() - 254.0).abs() < 1e-9);
+        assert_eq!(toolpath.line_states[0].spindle, Spindle::Clockwise);
+        assert_eq!(toolpath.line_states[2].units, Units::Inches);
+        assert_eq!(toolpath.line_states[2].motion_mode, MotionMode::Feed);
+    }
+
+    #[test]
+    fn tracks_coolant_per_line() {
+        let toolpath = parse_str("M3 S1000\nG0 X1 M8\nM7\nG1 X2 F100 M9", ParseOptions::default()).unwrap();
+        let coolant: Vec<Coolant> = toolpath.line_states.iter().map(|state| state.coolant).collect();
+        assert_eq!(coolant, [Coolant::Off, Coolant::Flood, Coolant::Mist, Coolant::Off]);
+    }
+
+    #[test]
+    fn dialect_units_and_lenient_options() {
+        let options = ParseOptions::default()
+            .with_dialect(Dialect::Marlin)
+            .with_initial_units(Units::Inches)
+            .with_lenient(true);
+        let mut parser = Parser::new(options);
+        parser.parse_line_or_report("M104 S200", 1).unwrap();
+        parser.parse_line_or_report("G1 X1 E", 2).unwrap();
+        parser.parse_line_or_report("G1 X2 Y", 3).unwrap();
+        parser.parse_line_or_report("G1 X3", 4).unwrap();
+        let toolpath = parser.finish().unwrap();
+        assert_eq!(toolpath.stats.spindle_speed_range, None);
+        assert!((toolpath.segments[0].end.x - 25.4).abs() < 1e-9);
+        assert_eq!(toolpath.segments.len(), 2);
+        assert_eq!(toolpath.line_segment_ends, vec![0, 1, 1, 2]);
+        assert_eq!(toolpath.diagnostics.len(), 1);
+        assert_eq!(toolpath.diagnostics[0].line, 3);
+        assert_eq!(toolpath.diagnostics[0].message, "missing value for Y");
+    }
+
+    #[test]
+    fn marlin_extrusion_separates_travel_and_retractions() {
+        let program = [
+            "G90 M82",
+            "G1 X10 Y0 E1.5 F1800",
+            "G1 E0.7",
+            "G1 X20 Y5 F6000",
+            "G1 E1.5",
+            "G92 E0",
+            "M83",
+            "G1 X30 E2",
+            "G1 X30 Y10 E-0.5",
+        ]
+        .join("\n");
+        let toolpath = parse_str(&program, ParseOptions::default().with_dialect(Dialect::Marlin)).unwrap();
+        let kinds: Vec<MoveKind> = toolpath.segments.iter().map(|seg| seg.kind).collect();
+        assert_eq!(kinds, [MoveKind::Feed, MoveKind::Rapid, MoveKind::Feed, MoveKind::Rapid]);
+        let retractions: Vec<(usize, f64)> = toolpath.retractions.iter().map(|r| (r.line, r.length)).collect();
+        assert_eq!(retractions.len(), 2);
+        assert_eq!(retractions[0].0, 2);
+        assert!((retractions[0].1 - 0.8).abs() < 1e-9);
+        assert_eq!(retractions[1], (8, 0.5));
+        assert!((toolpath.stats.extruded_length - 3.0).abs() < 1e-9);
+
+        let generic = parse_str(&program, ParseOptions::default().with_ignore_unknown_words(true)).unwrap();
+        assert!(generic.segments.iter().all(|seg| seg.kind == MoveKind::Feed));
+        assert!(generic.retractions.is_empty());
+    }
+
+    #[test]
+    fn initial_modal_state_from_options() {
+        let options = ParseOptions::default()
+            .with_initial_units(Units::Inches)
+            .with_initial_plane(Plane::XZ)
+            .with_initial_distance_mode(DistanceMode::Relative)
+            .with_initial_feed_rate(Some(10.0));
+        let mut parser = Parser::new(options);
+        parser.parse_line("G1 X1", 1).unwrap();
+        parser.parse_line("X1", 2).unwrap();
+        let toolpath = parser.finish().unwrap();
+        assert!((toolpath.segments[1].end.x - 50.8).abs() < 1e-9);
+        assert_eq!(toolpath.segments[0].feed, Some(254.0));
+        assert_eq!(toolpath.line_states[1].plane, Plane::XZ);
+        assert_eq!(toolpath.line_states[1].distance_mode, DistanceMode::Relative);
+    }
+
+    #[test]
+    fn line_range_parses_one_program() {
+        let mut parser = Parser::new(ParseOptions::default().with_line_range(2..4));
+        for (idx, line) in ["O1", "G0 X5 Y5", "O2", "G0 X1"].iter().enumerate() {
+            parser.parse_line(line, idx + 1).unwrap();
+        }
+        let toolpath = parser.finish().unwrap();
+        assert_eq!(toolpath.segments.len(), 1);
+        assert_eq!(toolpath.segments[0].start, Vec3::new(0.0, 0.0, 0.0));
+        assert_eq!(toolpath.line_segment_ends, [0, 0, 0, 1]);
+    }
+
+    #[test]
+    fn probe_moves_are_modal_and_recorded() {
+        let mut parser = Parser::new(ParseOptions::default());
+        for (idx, line) in ["G0 Z5", "G38.2 Z-10 F50", "Z-12", "G38.5 Z0", "G0 X1"].iter().enumerate() {
+            parser.parse_line(line, idx + 1).unwrap();
+        }
+        let toolpath = parser.finish().unwrap();
+        let kinds: Vec<MoveKind> = toolpath.segments.iter().map(|seg| seg.kind).collect();
+        assert_eq!(
+            kinds,
+            [MoveKind::Rapid, MoveKind::Probe, MoveKind::Probe, MoveKind::Probe, MoveKind::Rapid]
+        );
+        assert_eq!(toolpath.stats.probe_moves, 3);
+        assert_eq!(toolpath.segments[1].feed, Some(50.0));
+        let probes: Vec<(usize, &str)> = toolpath.probes.iter().map(|p| (p.segment, p.mode.code())).collect();
+        assert_eq!(probes, [(1, "G38.2"), (2, "G38.2"), (3, "G38.5")]);
+        assert!(!toolpath.probes[2].mode.toward);
+        assert!(Parser::new(ParseOptions::default()).parse_line("G38.1 Z1", 1).is_err());
+    }
+
+    #[test]
+    fn records_motion_word_spans_per_segment() {
+        let mut parser = Parser::new(ParseOptions::default());
+        for (idx, line) in ["G0 X1 (Y9) F100 Y2", "M3 S1000", "G2X5y0I2 ; X7", "G1 X8 A90 F100"].iter().enumerate() {
+            parser.parse_line(line, idx + 1).unwrap();
+        }
+        let toolpath = parser.finish().unwrap();
+        assert_eq!(toolpath.segment_spans.len(), toolpath.segments.len());
+        assert_eq!(toolpath.segment_motion_spans(0), [3..5, 16..18]);
+        let arc_end = toolpath.line_segment_ends[2];
+        assert!(arc_end > 2);
+        assert_eq!(toolpath.segment_motion_spans(1), [2..4, 4..6, 6..8]);
+        assert_eq!(toolpath.segment_motion_spans(arc_end - 1), [2..4, 4..6, 6..8]);
+        assert_eq!(toolpath.segment_motion_spans(arc_end), [3..5, 6..9]);
+        assert!(toolpath.segment_motion_spans(toolpath.segments.len()).is_empty());
+    }
+
+    #[test]
+    fn expands_threading_moves() {
+        let mut parser = Parser::new(ParseOptions::default());
+        for (idx, line) in ["S500 M3", "G0 X10 Z2", "G33 Z-20 K1.5", "G0 X12 Z2", "G76 P1.5 Z-20 I-1 J0.2 K0.5"]
+            .iter()
+            .enumerate()
+        {
+            parser.parse_line(line, idx + 1).unwrap();
+        }
+        let toolpath = parser.finish().unwrap();
+        let thread = &toolpath.segments[1];
+        assert_eq!((thread.kind, thread.feed), (MoveKind::Feed, Some(750.0)));
+        let cuts: Vec<(f64, f64)> = toolpath.segments[3..]
+            .iter()
+            .filter(|seg| seg.kind == MoveKind::Feed)
+            .map(|seg| ((seg.start.x * 1e9).round() / 1e9, seg.end.z))
+            .collect();
+        assert_eq!(cuts, [(10.8, -20.0), (10.6, -20.0), (10.5, -20.0)]);
+        assert_eq!(toolpath.segments.last().unwrap().end, Vec3::new(12.0, 0.0, 2.0));
+        assert!(Parser::new(ParseOptions::default()).parse_line("G33 Z-5", 1).is_err());
+    }
+
+    #[test]
+    fn expands_lathe_cycles_from_profile_blocks() {
+        let program = [
+            "G0 X40 Z2",
+            "G71 U2.5 R0.5",
+            "G71 P10 Q30 U0 W0 F0.2",
+            "N10 G0 X20",
+            "N20 G1 Z-10",
+            "N30 X40",
+            "G70 P10 Q30",
+        ];
+        let mut parser = Parser::new(ParseOptions::default().with_dialect(Dialect::Fanuc));
+        for (idx, line) in program.iter().enumerate() {
+            parser.parse_line(line, idx + 1).unwrap();
+        }
+        let toolpath = parser.finish().unwrap();
+        // The profile blocks draw nothing themselves; the passes belong to the last block.
+        assert_eq!(&toolpath.line_segment_ends[..5], [1, 1, 1, 1, 1]);
+        let roughing = &toolpath.segments[1..toolpath.line_segment_ends[5]];
+        let depths: Vec<f64> = roughing
+            .iter()
+            .filter(|seg| seg.kind == MoveKind::Feed && seg.start.x == seg.end.x)
+            .map(|seg| seg.end.x)
+            .collect();
+        assert_eq!(depths, [35.0, 30.0, 25.0, 20.0]);
+        let finishing = &toolpath.segments[toolpath.line_segment_ends[5]..];
+        assert_eq!(finishing.len(), 4);
+        assert_eq!(finishing[1].end, Vec3::new(20.0, 0.0, -10.0));
+        assert_eq!(finishing.last().unwrap().end, Vec3::new(40.0, 0.0, 2.0));
+        assert!(toolpath.diagnostics.is_empty());
+        let count = |kind| toolpath.segments.iter().filter(|seg| seg.kind == kind).count();
+        assert_eq!(toolpath.stats.rapid_moves, count(MoveKind::Rapid));
+        assert_eq!(toolpath.stats.feed_moves, count(MoveKind::Feed));
+        assert_eq!(toolpath.motion_spans, [3..6, 7..9, 4..7]);
+    }
+
+    #[test]
+    fn g76_follows_the_dialect() {
+        let linuxcnc = ["G0 X12 Z2", "G76 P1.5 Z-20 I-1 J0.2 K0.5"];
+        let fanuc = ["G0 X12 Z2", "G76 P010060 Q100 R0.05", "G76 X9 Z-20 P500 Q200 F1.5"];
+        let cuts = |lines: &[&str], dialect| {
+            let toolpath = parse_str(&lines.join("\n"), ParseOptions::default().with_dialect(dialect)).unwrap();
+            let feeds = toolpath.segments.iter().filter(|seg| seg.kind == MoveKind::Feed);
+            feeds.map(|seg| (seg.start.x * 1e9).round() / 1e9).collect::<Vec<_>>()
+        };
+        assert_eq!(cuts(&linuxcnc, Dialect::Generic), [10.8, 10.6, 10.5]);
+        assert_eq!(cuts(&fanuc, Dialect::Fanuc).last(), Some(&9.0));
+        assert!(parse_str(&fanuc.join("\n"), ParseOptions::default()).is_err());
+        for dialect in [Dialect::Generic, Dialect::LinuxCnc] {
+            let g71 = parse_str("G0 X40 Z2\nG71 U2.5 R0.5", ParseOptions::default().with_dialect(dialect));
+            assert_eq!(g71.unwrap().segments.len(), 1);
+        }
+        let peck = parse_str("G0 X5 Y5 Z2\nG73 X5 Y5 Z-6 R2 Q2 F100", ParseOptions::default()).unwrap();
+        assert!(peck.segments.iter().any(|seg| seg.end.z == -6.0));
+    }
+
+    #[test]
+    fn diagnostics_come_back_in_line_order() {
+        let program = "G71 U2.5 R0.5\nG71 P10 Q30 U0 W0 F0.2\nG10 L2 P12 X0\nG10 L2 P13 X0";
+        let toolpath = parse_str(program, ParseOptions::default().with_dialect(Dialect::Fanuc)).unwrap();
+        let lines: Vec<usize> = toolpath.diagnostics.iter().map(|diagnostic| diagnostic.line).collect();
+        assert_eq!(lines, [2, 3, 4]);
+    }
+
+    #[test]
+    fn records_unit_systems_and_switches() {
+        let mut parser = Parser::new(ParseOptions::default());
+        for (idx, line) in ["G21", "G1 X1 F100", "G21", "G20 X1", "G21"].iter().enumerate() {
+            parser.parse_line(line, idx + 1).unwrap();
+        }
+        let toolpath = parser.finish().unwrap();
+        assert_eq!(toolpath.stats.units_used, [Units::Millimeters, Units::Inches]);
+        assert_eq!(toolpath.stats.unit_switches, 2);
+        assert!(toolpath.stats.mixes_units());
+        let lines: Vec<usize> = toolpath.diagnostics.iter().map(|diag| diag.line).collect();
+        assert_eq!(lines, [4, 5]);
+        assert_eq!(toolpath.diagnostics[0].message, "units switch to G20 mid-program");
+        assert_eq!(toolpath.stats.program_units(), None);
+
+        let inch = parse_str("G20\nG1 X2 F10", ParseOptions::default()).unwrap();
+        assert_eq!(inch.stats.program_units(), Some(Units::Inches));
+        assert_eq!(inch.bounds.max.x, 50.8);
+        assert_eq!(inch.bounds_in(Units::Inches).max.x, 2.0);
+        assert_eq!(inch.stats.feed_distance_in(Units::Inches), 2.0);
+        assert_eq!(inch.stats.feed_distance_in(Units::Millimeters), 50.8);
+    }
+
+    #[test]
+    fn kept_words_are_grouped_by_line() {
+        let program = "G21 (mm)\n\ng1 x1.5 F200\nM30\n";
+        let toolpath = parse_str(program, ParseOptions::default().with_words(true)).unwrap();
+        let word = |letter, value| Word { letter, value };
+        assert_eq!(toolpath.line_words(0), [word('G', 21.0)]);
+        assert!(toolpath.line_words(1).is_empty());
+        assert_eq!(toolpath.line_words(2), [word('G', 1.0), word('X', 1.5), word('F', 200.0)]);
+        assert_eq!(toolpath.line_words(3), [word('M', 30.0)]);
+        assert!(parse_str(program, ParseOptions::default()).unwrap().words.is_empty());
+
+        let program = "#1 = 0\no100 while [#1 LT 2]\n  #1 = [#1 + 1]\n  G1 X#1 F100\no100 endwhile\nM30";
+        let options = ParseOptions::default().with_dialect(Dialect::LinuxCnc).with_words(true);
+        let toolpath = parse_str(program, options).unwrap();
+        assert_eq!(toolpath.line_words(3), [word('G', 1.0), word('X', 1.0), word('F', 100.0)]);
+        assert!(toolpath.line_words(4).is_empty());
+        assert_eq!(toolpath.line_words(5), [word('M', 30.0)]);
+    }
+
+    #[test]
+    fn segments_map_back_to_their_source_line() {
+        let mut parser = Parser::new(ParseOptions::default());
+        for (idx, line) in ["G0 X1", "(comment)", "G2 X5 Y0 I2 J0 F100", "G1 Y3"].iter().enumerate() {
+            parser.parse_line(line, idx + 1).unwrap();
+        }
+        let toolpath = parser.finish().unwrap();
+        let count = toolpath.segments.len();
+        assert_eq!(toolpath.segment_line(0), Some(0));
+        assert_eq!(toolpath.segment_line(1), Some(2));
+        assert_eq!(toolpath.segment_line(count - 2), Some(2));
+        assert_eq!(toolpath.segment_line(count - 1), Some(3));
+        assert_eq!(toolpath.segment_line(count), None);
+    }
+
+    #[test]
+    fn totals_feed_and_rapid_distance_and_axis_travel() {
+        let mut parser = Parser::new(ParseOptions::default());
+        for (idx, line) in ["G0 X3 Y4", "G1 X0 Y0 F100", "G1 Z-2", "G0 Z5"].iter().enumerate() {
+            parser.parse_line(line, idx + 1).unwrap();
+        }
+        let stats = parser.finish().unwrap().stats;
+        assert!((stats.rapid_distance - 12.0).abs() < 1e-9);
+        assert!((stats.feed_distance - 7.0).abs() < 1e-9);
+        assert_eq!(stats.axis_travel, Vec3::new(6.0, 8.0, 9.0));
+    }
+
+    #[test]
+    fn geometry_hash_ignores_feeds_and_comments() {
+        let hash = |lines: &[&str]| {
+            let mut parser = Parser::new(ParseOptions::default());
+            for (idx, line) in lines.iter().enumerate() {
+                parser.parse_line(line, idx + 1).unwrap();
+            }
+            parser.finish().unwrap().geometry_hash()
+        };
+        let base = hash(&["G0 X1", "G1 Y2 F100"]);
+        assert_eq!(base, hash(&["(posted again)", "G0 X1", "G1 Y2 F300"]));
+        assert_ne!(base, hash(&["G0 X1", "G1 Y2.001 F100"]));
+        assert_ne!(base, hash(&["G1 X1", "G1 Y2 F100"]));
+    }
+
+    #[test]
+    fn records_program_stops_with_their_comment() {
+        let mut parser = Parser::new(ParseOptions::default());
+        for (idx, line) in ["G1 X10 F100", "(FLIP PART)", "M0", "G1 Y5 M01 ; check depth", "M1"].iter().enumerate() {
+            parser.parse_line(line, idx + 1).unwrap();
+        }
+        let toolpath = parser.finish().unwrap();
+        let stop = |line, segment, optional, comment: Option<&str>| ProgramStop {
+            line,
+            segment,
+            optional,
+            comment: comment.map(String::from),
+        };
+        assert_eq!(
+            toolpath.stops,
+            [
+                stop(2, 1, false, Some("FLIP PART")),
+                stop(3, 2, true, Some("check depth")),
+                stop(4, 2, true, None),
+            ]
+        );
+    }
+
+    #[test]
+    fn dwells_and_tool_changes_add_to_the_run_time() {
+        let mut parser = Parser::new(ParseOptions::default());
+        for (idx, line) in ["G1 X10 F600", "G4 P2.5", "T2 M6", "G4 X1", "G1 X20"].iter().enumerate() {
+            parser.parse_line(line, idx + 1).unwrap();
+        }
+        let toolpath = parser.finish().unwrap();
+        let pause = |line, kind| Pause { line, segment: 1, kind };
+        assert_eq!(
+            toolpath.pauses,
+            [pause(1, PauseKind::Dwell(2.5)), pause(2, PauseKind::ToolChange), pause(3, PauseKind::Dwell(1.0))]
+        );
+        assert_eq!(toolpath.segments.len(), 2);
+        let options = TimeEstimateOptions {
+            tool_change_time: 8.0,
+            ..TimeEstimateOptions::default()
+        };
+        let durations = segment_durations(&toolpath, &options);
+        assert!((durations[0] - 1.0).abs() < 1e-9);
+        assert!((durations[1] - 12.5).abs() < 1e-9);
+
+        let options = ParseOptions::default().with_dialect(Dialect::Marlin);
+        let mut parser = Parser::new(options);
+        for (idx, line) in ["G4 P500", "G4 S2"].iter().enumerate() {
+            parser.parse_line(line, idx + 1).unwrap();
+        }
+        let kinds: Vec<PauseKind> = parser.finish().unwrap().pauses.iter().map(|pause| pause.kind).collect();
+        assert_eq!(kinds, [PauseKind::Dwell(0.5), PauseKind::Dwell(2.0)]);
+    }
+
+    #[test]
+    fn inverse_time_and_per_revolution_feeds_are_timed_per_minute() {
+        let mut parser = Parser::new(ParseOptions::default());
+        let lines = ["G1 X10 F600", "G93 G1 X20 F6", "G3 X30 I5 F60", "G1 X40", "S1000 M3 G95 F0.1", "G1 X50"];
+        for (idx, line) in lines.iter().enumerate() {
+            parser.parse_line(line, idx + 1).unwrap();
+        }
+        let toolpath = parser.finish().unwrap();
+        let seconds = line_durations(&toolpath, &TimeEstimateOptions::default());
+        let expected = [1.0, 10.0, 1.0, 0.6, 0.0, 6.0];
+        assert!(seconds.iter().zip(expected).all(|(got, want)| (got - want).abs() < 1e-9), "{:?}", seconds);
+        assert_eq!(toolpath.diagnostics.len(), 1);
+        assert_eq!(toolpath.diagnostics[0].line, 4);
+        assert_eq!(toolpath.line_states[1].feed_mode, FeedMode::InverseTime);
+        assert_eq!(toolpath.line_states[1].feed_rate, None);
+        assert_eq!(toolpath.line_states[4].feed_rate, Some(100.0));
+        assert_eq!(toolpath.line_states[4].programmed_feed, Some(0.1));
+    }
+
+    #[test]
+    fn sequence_numbers_are_kept_and_checksums_checked() {
+        let mut parser = Parser::new(ParseOptions::default().with_checksum_validation(true));
+        let lines = ["N10 G1 X10 F600*48", "N11 G1 Y10*0", "G0 Z5", "N12 G1 X20*97  "];
+        for (idx, line) in lines.iter().enumerate() {
+            parser.parse_line(line, idx + 1).unwrap();
+        }
+        let toolpath = parser.finish().unwrap();
+        let numbers: Vec<Option<u32>> = toolpath.line_states.iter().map(|state| state.line_number).collect();
+        assert_eq!(numbers, [Some(10), Some(11), None, Some(12)]);
+        assert_eq!(toolpath.segments.last().map(|seg| seg.end), Some(Vec3::new(20.0, 10.0, 5.0)));
+        let lines: Vec<usize> = toolpath.diagnostics.iter().map(|diag| diag.line).collect();
+        assert_eq!(lines, [2]);
+    }
+
+    #[test]
+    fn g92_shifts_the_origin_until_cleared() {
+        let mut parser = Parser::new(ParseOptions::default());
+        for (idx, line) in ["G0 X10 Y10 Z5", "G92 X0 Y0", "G1 X5 F100", "G0 Z0", "G92.1", "G1 X5"].iter().enumerate() {
+            parser.parse_line(line, idx + 1).unwrap();
+        }
+        let toolpath = parser.finish().unwrap();
+        let ends: Vec<Vec3> = toolpath.segments.iter().map(|seg| seg.end).collect();
+        assert_eq!(
+            ends,
+            [
+                Vec3::new(10.0, 10.0, 5.0),
+                Vec3::new(15.0, 10.0, 5.0),
+                Vec3::new(15.0, 10.0, 0.0),
+                Vec3::new(5.0, 10.0, 0.0),
+            ]
+        );
+        assert_eq!(toolpath.line_states[1].work_origin, Vec3::new(10.0, 10.0, 0.0));
+        assert_eq!(toolpath.line_states[4].work_origin, Vec3::default());
+    }
+
+    #[test]
+    fn subprogram_calls_expand_into_the_calling_line() {
+        let lines = [
+            "O1000",
+            "G0 X0 Y0",
+            "M98 P2000 L2",
+            "M98 P12000",
+            "M98 P9",
+            "M30",
+            "O2000",
+            "G91 G1 X10 F100",
+            "G90 M99",
+        ];
+        let mut parser = Parser::new(ParseOptions::default());
+        parser.subprograms = Some(subprogram_bodies(lines, &CommentSyntax::default()));
+        for (idx, line) in lines.iter().enumerate() {
+            parser.parse_line(line, idx + 1).unwrap();
+        }
+        let toolpath = parser.finish().unwrap();
+        let ends: Vec<f64> = toolpath.segments.iter().map(|seg| seg.end.x).collect();
+        assert_eq!(ends, [10.0, 20.0, 30.0]);
+        assert_eq!(toolpath.line_segment_ends[..6], [0, 0, 2, 3, 3, 3]);
+        assert_eq!(
+            toolpath.repeats,
+            [Repeat {
+                lines: 2..3,
+                passes: 2,
+                segments: 0..2,
+                first_pass_end: 1,
+            }]
+        );
+        assert_eq!(toolpath.diagnostics.len(), 1);
+        assert!(toolpath.diagnostics[0].message.contains("O9 not found"));
+        assert_eq!(toolpath.line_segment_ends, [0, 0, 2, 3, 3, 3, 3, 3, 3]);
+
+        let program = "O1000\nG0 X0 Y0\nM98 P2000 L2\nM30\nO2000\nG91 G1 X10 F100\nG90 M99";
+        assert_eq!(parse_str(program, ParseOptions::default()).unwrap().segments.len(), 2);
+        let endless = parse_str(&program.replace("L2", "L999999999"), ParseOptions::default()).unwrap();
+        assert_eq!(endless.segments.len(), MAX_LOOP_ITERATIONS);
+        assert_eq!(endless.diagnostics[0].message, "M98 P2000: repeats stopped after 100000");
+    }
+
+    #[test]
+    fn linuxcnc_o_codes_run_subroutines_and_loops() {
+        let lines = [
+            "#<depth> = 0",
+            "o<pass> sub",
+            "  G1 Z#1 F100",
+            "  G1 X10",
+            "  G0 Z1",
+            "  G0 X0",
+            "o<pass> endsub",
+            "o100 while [#<depth> GT -3]",
+            "  #<depth> = [#<depth> - 1]",
+            "  o<pass> call [#<depth>]",
+            "o100 endwhile",
+            "o101 if [#<depth> EQ -3]",
+            "  G0 Y5",
+            "o101 else",
+            "  G0 Y-5",
+            "o101 endif",
+            "o102 repeat [2]",
+            "  G91 G0 X[1 + 0]",
+            "  G90",
+            "o102 endrepeat",
+        ];
+        let mut parser = Parser::new(ParseOptions::default().with_dialect(Dialect::LinuxCnc));
+        for (idx, line) in lines.iter().enumerate() {
+            parser.parse_line(line, idx + 1).unwrap();
+        }
+        let toolpath = parser.finish().unwrap();
+        assert!(toolpath.diagnostics.is_empty());
+        let depths: Vec<f64> = toolpath
+            .segments
+            .iter()
+            .filter(|seg| seg.kind == MoveKind::Feed && seg.end.x == 0.0)
+            .map(|seg| seg.end.z)
+            .collect();
+        assert_eq!(depths, [-1.0, -2.0, -3.0]);
+        assert_eq!(toolpath.segments.len(), 15);
+        assert_eq!(toolpath.segments[14].end, Vec3::new(2.0, 5.0, 1.0));
+        // Each line keeps its first pass; later passes belong to the loop's closing line.
+        assert_eq!(toolpath.line_segment_ends[7..11], [0, 0, 4, 12]);
+        assert_eq!(toolpath.line_segment_ends[11..20], [12, 13, 13, 13, 13, 13, 14, 14, 15]);
+        assert_eq!(toolpath.line_states[12].position.y, 5.0);
+        let repeats: Vec<_> = toolpath
+            .repeats
+            .iter()
+            .map(|repeat| (repeat.lines.clone(), repeat.passes, repeat.segments.clone(), repeat.first_pass_end))
+            .collect();
+        assert_eq!(repeats, [(7..11, 3, 0..12, 4), (16..20, 2, 13..15, 14)]);
+    }
+
+    #[test]
+    fn comment_syntax_follows_the_dialect_or_custom_prefixes() {
+        let ends = |options: ParseOptions, lines: &[&str]| {
+            let mut parser = Parser::new(options);
+            for (idx, line) in lines.iter().enumerate() {
+                parser.parse_line(line, idx + 1).unwrap();
+            }
+            let toolpath = parser.finish().unwrap();
+            toolpath.segments.iter().map(|seg| seg.end).collect::<Vec<_>>()
+        };
+        let custom = CommentSyntax {
+            parentheses: false,
+            line_prefixes: vec!["!".to_string(), "*".to_string()],
+        };
+        let lines = ["! G1 X99", "G1 X10 F100 * Y99", "G1 X20 ; Y5"];
+        assert_eq!(
+            ends(ParseOptions::default().with_comment_syntax(custom), &lines),
+            [Vec3::new(10.0, 0.0, 0.0), Vec3::new(20.0, 5.0, 0.0)]
+        );
+        let marlin = ParseOptions::default().with_dialect(Dialect::Marlin).comment_syntax();
+        assert!(!marlin.parentheses && marlin.line_prefix_len("; fan") == Some(1));
+        assert_eq!(ends(ParseOptions::default(), &["G1 X5 (Y9) ; Y1"]), [Vec3::new(5.0, 0.0, 0.0)]);
+    }
+
+    #[test]
+    fn reference_returns_rapid_home() {
+        let options = ParseOptions::default()
+            .with_reference_position(false, Vec3::new(0.0, 0.0, 50.0))
+            .with_reference_position(true, Vec3::new(100.0, 100.0, 50.0));
+        let mut parser = Parser::new(options);
+        let lines = ["G0 X10 Y10 Z5", "G91 G28 Z0", "G90 G28 X20", "G1 X30 F100", "G30", "G0 X5 Y5 Z5", "G28.1", "G28"];
+        for (idx, line) in lines.iter().enumerate() {
+            parser.parse_line(line, idx + 1).unwrap();
+        }
+        let toolpath = parser.finish().unwrap();
+        let ends: Vec<Vec3> = toolpath.segments.iter().map(|seg| seg.end).collect();
+        assert_eq!(
+            ends,
+            [
+                Vec3::new(10.0, 10.0, 5.0),
+                Vec3::new(10.0, 10.0, 50.0),
+                Vec3::new(20.0, 10.0, 50.0),
+                Vec3::new(0.0, 10.0, 50.0),
+                Vec3::new(30.0, 10.0, 50.0),
+                Vec3::new(100.0, 100.0, 50.0),
+                Vec3::new(5.0, 5.0, 5.0),
+            ]
+        );
+        assert!(toolpath.segments[1..4].iter().all(|seg| seg.kind == MoveKind::Rapid));
+        let returns = |secondary| {
+            toolpath
+                .reference_returns
+                .iter()
+                .filter(|ret| ret.secondary == secondary)
+                .map(|ret| (ret.line, ret.segments.clone()))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(returns(false), [(1, 1..2), (2, 2..4), (7, 7..7)]);
+        assert_eq!(returns(true), [(4, 5..6)]);
+    }
+
+    #[test]
+    fn tool_length_offsets_leave_the_path_at_the_tool_tip() {
+        let options = ParseOptions::default().with_tool_length(1, 100.0).with_tool_length(2, 50.0);
+        let mut parser = Parser::new(options);
+        let lines = ["T1 M6", "G43 H1 G0 Z10", "G1 Z-1 F100", "G49 G0 Z20", "T2 M6 G43", "G0 Z0", "G44 H2 Z0", "G43 H7"];
+        for (idx, line) in lines.iter().enumerate() {
+            parser.parse_line(line, idx + 1).unwrap();
+        }
+        let toolpath = parser.finish().unwrap();
+        let depths: Vec<f64> = toolpath.segments.iter().map(|seg| seg.end.z).collect();
+        assert_eq!(depths, [10.0, -1.0, 20.0, 0.0]);
+        let shifts: Vec<Option<f64>> = toolpath.line_states.iter().map(|state| state.tool_length.map(|o| o.shift)).collect();
+        assert_eq!(shifts, [None, Some(100.0), Some(100.0), None, Some(50.0), Some(50.0), Some(-50.0), Some(0.0)]);
+        assert_eq!(toolpath.line_states[4].tool_length.map(|offset| offset.code()).as_deref(), Some("G43 H2"));
+        assert_eq!(toolpath.line_states[3].tool_length, None);
+        assert_eq!(toolpath.diagnostics.len(), 1);
+        assert_eq!(toolpath.diagnostics[0].line, 8);
+    }
+
+    #[test]
+    fn work_offsets_place_each_fixture_at_its_origin() {
+        let options = ParseOptions::default()
+            .with_work_offset(WorkOffset::G54, Vec3::new(100.0, 0.0, -50.0))
+            .with_work_offset(WorkOffset::from_code(59.2).unwrap(), Vec3::new(300.0, 20.0, -50.0));
+        let mut parser = Parser::new(options);
+        for (idx, line) in ["G0 X0 Y0 Z0", "G1 X10", "G59.2 G0 X0 Y0 Z0", "G91 X5", "G90 G55 X1"].iter().enumerate() {
+            parser.parse_line(line, idx + 1).unwrap();
+        }
+        let toolpath = parser.finish().unwrap();
+        let ends: Vec<Vec3> = toolpath.segments.iter().map(|seg| seg.end).collect();
+        assert_eq!(
+            ends,
+            [
+                Vec3::new(100.0, 0.0, -50.0),
+                Vec3::new(110.0, 0.0, -50.0),
+                Vec3::new(300.0, 20.0, -50.0),
+                Vec3::new(305.0, 20.0, -50.0),
+                Vec3::new(1.0, 20.0, -50.0),
+            ]
+        );
+        assert_eq!(toolpath.line_states[2].work_offset.map(WorkOffset::code), Some("G59.2"));
+        assert_eq!(toolpath.line_states[3].work_origin, Vec3::new(300.0, 20.0, -50.0));
+        assert_eq!(WorkOffset::from_code(59.4), None);
+    }
+
+    #[test]
+    fn g10_moves_work_offsets_for_later_moves() {
+        let program = "G10 L2 P1 X100 Z-50\nG0 X0 Y0 Z0\nG10 L2 P2 X200\nG55 G0 X1\nG0 X5 Y5\nG10 L20 P0 X0 Y0\nG0 X1 Y1";
+        let toolpath = parse_str(program, ParseOptions::default()).unwrap();
+        let ends: Vec<Vec3> = toolpath.segments.iter().map(|seg| seg.end).collect();
+        assert_eq!(
+            ends,
+            [
+                Vec3::new(100.0, 0.0, -50.0),
+                Vec3::new(201.0, 0.0, -50.0),
+                Vec3::new(205.0, 5.0, -50.0),
+                Vec3::new(206.0, 6.0, -50.0),
+            ]
+        );
+        assert_eq!(toolpath.line_states[5].work_origin, Vec3::new(205.0, 5.0, 0.0));
+    }
+
+    #[test]
+    fn rotary_moves_turn_the_path_around_the_part() {
+        let mut parser = Parser::new(ParseOptions::default());
+        for (idx, line) in ["G0 Y0 Z10", "G1 A90 F100", "G91 A-45", "G90 G0 X5"].iter().enumerate() {
+            parser.parse_line(line, idx + 1).unwrap();
+        }
+        let toolpath = parser.finish().unwrap();
+        assert_eq!(toolpath.segments.len(), 1 + 90 + 45 + 1);
+        let turned = toolpath.segments[90];
+        assert_eq!(turned.rotary.a, 90.0);
+        assert!(turned.end.y.abs() > 9.999 && turned.end.z.abs() < 1e-9);
+        assert!(toolpath.segments[1..91].iter().all(|seg| seg.kind == MoveKind::Feed));
+        let radius = |p: Vec3| p.y.hypot(p.z);
+        assert!(toolpath.segments[1..].iter().all(|seg| (radius(seg.end) - 10.0).abs() < 1e-9));
+        let last = toolpath.segments.last().unwrap();
+        assert!((last.end.x - 5.0).abs() < 1e-9 && (last.end.y - last.end.z.abs()).abs() < 1e-9);
+        assert_eq!(toolpath.line_states[3].rotary, RotaryAngles::new(45.0, 0.0, 0.0));
+        assert_eq!(toolpath.line_states[3].position, Vec3::new(5.0, 0.0, 10.0));
+    }
+
+    #[test]
+    fn rotary_words_turn_arcs_and_rotary_only_arc_blocks() {
+        let program = "G0 X10 Y0 Z10\nG2 X-10 Y0 I-10 A90 F100\nA180";
+        let toolpath = parse_str(program, ParseOptions::default()).unwrap();
+        let arc = &toolpath.segments[1..toolpath.line_segment_ends[1]];
+        assert!(arc.len() >= 90);
+        assert_eq!(arc.last().unwrap().rotary.a, 90.0);
+        assert!(arc.windows(2).all(|pair| pair[0].end == pair[1].start && pair[0].rotary.a < pair[1].rotary.a));
+        let end = arc.last().unwrap().end;
+        assert!((end - Vec3::new(-10.0, 10.0, 0.0)).length() < 1e-9, "{:?}", end);
+        let turn = &toolpath.segments[toolpath.line_segment_ends[1]..];
+        assert_eq!(turn.len(), 90);
+        assert!(turn.iter().all(|seg| seg.kind == MoveKind::Feed));
+        assert_eq!(toolpath.line_states[2].rotary.a, 180.0);
+    }
+
+    #[test]
+    fn unwrapped_rotary_moves_run_along_y() {
+        let program = "G0 Y0 Z10\nG1 A90 F100\nG1 X5 A180 Z9";
+        let options = ParseOptions::default().with_unwrap_diameter(Some(40.0));
+        let toolpath = parse_str(program, options).unwrap();
+        let quarter = std::f64::consts::PI * 10.0;
+        assert!(toolpath.segments[1..].iter().all(|seg| seg.start.z >= 9.0 && seg.end.x <= 5.0));
+        assert!((toolpath.segments[90].end.y - quarter).abs() < 1e-9);
+        let last = toolpath.segments.last().unwrap().end;
+        assert!((last.x - 5.0).abs() < 1e-9 && (last.y - 2.0 * quarter).abs() < 1e-9 && (last.z - 9.0).abs() < 1e-9);
+    }
+
     #[test]
     fn parse_arc_move() {
         let mut parser = Parser::new(ParseOptions::default());
@@ -688,6 +3749,76 @@ mod tests {
         assert!(!toolpath.segments.is_empty());
     }
 
+    #[test]
+    fn chord_error_tessellation_scales_with_radius() {
+        let arc_segments = |tessellation, radius: f64| {
+            let options = ParseOptions::default().with_arc_tessellation(tessellation);
+            let mut parser = Parser::new(options);
+            parser
+                .parse_line(&format!("G2 X{} Y0 I{} J0", 2.0 * radius, radius), 1)
+                .unwrap();
+            parser.finish().unwrap().segments.len()
+        };
+        assert_eq!(arc_segments(ArcTessellation::Length(0.5), 1.0), 7);
+        assert_eq!(arc_segments(ArcTessellation::Length(0.5), 1000.0), 6284);
+        let small = arc_segments(ArcTessellation::ChordError(0.01), 1.0);
+        let large = arc_segments(ArcTessellation::ChordError(0.01), 1000.0);
+        assert_eq!((small, large), (12, 352));
+        assert_eq!(arc_segments(ArcTessellation::ChordError(5.0), 1.0), 2);
+        assert_eq!(arc_segments(ArcTessellation::Length(0.0), 1.0), 3142);
+        assert_eq!(arc_segments(ArcTessellation::ChordError(0.0), 1.0), 36);
+    }
+
+    #[test]
+    fn arc_back_to_its_start_is_a_full_circle() {
+        let mut parser = Parser::new(ParseOptions::default());
+        parser.parse_line("G2 X0 Y0 I5 J0", 1).unwrap();
+        parser.parse_line("G3 I0 J-2", 2).unwrap();
+        let toolpath = parser.finish().unwrap();
+        assert_eq!(toolpath.stats.arc_moves, 2);
+        assert!((toolpath.bounds.max.x - 10.0).abs() < 0.01);
+        assert!((toolpath.bounds.min.y + 5.0).abs() < 0.01);
+        let last = toolpath.segments.last().unwrap();
+        assert!(last.end.x.abs() < 1e-9 && last.end.y.abs() < 1e-9);
+        let length: f64 = toolpath
+            .segments
+            .iter()
+            .map(|seg| (seg.end.x - seg.start.x).hypot(seg.end.y - seg.start.y))
+            .sum();
+        let expected = std::f64::consts::TAU * 7.0;
+        assert!((length - expected).abs() < 0.05 * expected);
+    }
+
+    #[test]
+    fn flag_arc_radius_mismatch() {
+        let mut parser = Parser::new(ParseOptions::default());
+        parser.parse_line("G2 X10 Y0 I5 J0", 1).unwrap();
+        parser.parse_line("G2 X20 Y0.5 I5 J0", 2).unwrap();
+        let toolpath = parser.finish().unwrap();
+        assert_eq!(toolpath.diagnostics.len(), 1);
+        assert_eq!(toolpath.diagnostics[0].line, 2);
+        assert_eq!(toolpath.diagnostics[0].severity, Severity::Error);
+    }
+
+    #[test]
+    fn split_operations_at_markers() {
+        let mut parser = Parser::new(ParseOptions::default());
+        parser.parse_line("G0 X1", 1).unwrap();
+        parser.parse_line("(2D Contour1)", 2).unwrap();
+        parser.parse_line("G1 X2 F100", 3).unwrap();
+        parser.parse_line("(OPERATION 2: DRILL)", 4).unwrap();
+        parser.parse_line("G1 Z-1", 5).unwrap();
+        parser.parse_line("G0 Z5", 6).unwrap();
+        let toolpath = parser.finish().unwrap();
+        assert_eq!(toolpath.operations.len(), 2);
+        assert_eq!(toolpath.operations[0].name, "2D Contour1");
+        assert_eq!(toolpath.operations[0].lines, 1..3);
+        assert_eq!(toolpath.operations[0].segments, 1..2);
+        assert_eq!(toolpath.operations[1].name, "DRILL");
+        assert_eq!(toolpath.operations[1].lines, 3..6);
+        assert_eq!(toolpath.operations[1].segments, 2..4);
+    }
+
     #[test]
     fn parse_arc_move_radius() {
         let mut parser = Parser::new(ParseOptions::default());
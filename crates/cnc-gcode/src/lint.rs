@@ -0,0 +1,126 @@
+use std::fmt;
+
+use crate::Word;
+
+/// G-code modal groups that allow one code per block, after RS274/NGC.
+const G_GROUPS: [(&str, &[f64]); 6] = [
+    ("motion", &[0.0, 1.0, 2.0, 3.0, 33.0, 38.2, 38.3, 38.4, 38.5, 73.0, 76.0, 80.0, 81.0, 82.0, 83.0, 84.0, 85.0]),
+    ("plane", &[17.0, 18.0, 19.0]),
+    ("units", &[20.0, 21.0]),
+    ("distance", &[90.0, 91.0]),
+    ("feed mode", &[93.0, 94.0, 95.0]),
+    ("work offset", &[54.0, 55.0, 56.0, 57.0, 58.0, 59.0, 59.1, 59.2, 59.3]),
+];
+
+/// Letters that may appear once per block.
+const SINGLE_LETTERS: &str = "XYZABCIJKRFSTPQHDLE";
+
+/// A lint finding, attached to a warning diagnostic when linting is on.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Lint {
+    /// Codes of one modal group sharing a block, e.g. `G0` and `G1` in the motion group.
+    ModalConflict { group: &'static str, codes: Vec<Word> },
+    /// A letter that may appear once per block given again.
+    RepeatedWord(char),
+    /// A feed move before any `F` word.
+    MissingFeed,
+    /// A feed move with the spindle off.
+    SpindleOff,
+}
+
+impl fmt::Display for Lint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Lint::ModalConflict { group, codes } => {
+                let codes: Vec<String> = codes.iter().map(|word| format!("{}{}", word.letter, word.value)).collect();
+                write!(f, "{} in one block ({} group)", codes.join(" and "), group)
+            }
+            Lint::RepeatedWord(letter) => write!(f, "{} given more than once", letter),
+            Lint::MissingFeed => write!(f, "feed move before any F word"),
+            Lint::SpindleOff => write!(f, "feed move with the spindle off"),
+        }
+    }
+}
+
+/// Findings for words that cannot share a block: two codes of one modal group, or a letter
+/// given twice.
+pub(crate) fn conflicting_words(words: &[Word]) -> Vec<Lint> {
+    let mut findings = Vec::new();
+    for (group, codes) in G_GROUPS {
+        let found: Vec<Word> = words
+            .iter()
+            .filter(|word| word.letter == 'G' && codes.iter().any(|&code| (word.value - code).abs() < 1e-6))
+            .copied()
+            .collect();
+        if found.len() > 1 {
+            findings.push(Lint::ModalConflict { group, codes: found });
+        }
+    }
+    let spindle: Vec<Word> = words
+        .iter()
+        .filter(|word| word.letter == 'M' && (3.0..=5.0).contains(&word.value) && word.value.fract() == 0.0)
+        .copied()
+        .collect();
+    if spindle.len() > 1 {
+        findings.push(Lint::ModalConflict { group: "spindle", codes: spindle });
+    }
+    for letter in SINGLE_LETTERS.chars() {
+        if words.iter().filter(|word| word.letter == letter).count() > 1 {
+            findings.push(Lint::RepeatedWord(letter));
+        }
+    }
+    findings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Lint;
+    use crate::{parse_str, ParseOptions, Severity, Word};
+
+    #[test]
+    fn lint_flags_conflicts_missing_feed_and_idle_spindle() {
+        let program = [
+            "G0 G1 X1",
+            "G1 X2 X3",
+            "F100",
+            "M3 S1000",
+            "G1 Y5",
+            "M5",
+            "G1 Y6",
+            "G1 Y7",
+        ]
+        .join("\n");
+        let lint = ParseOptions::default().with_lint(true);
+        let toolpath = parse_str(&program, lint).unwrap();
+        let findings: Vec<(usize, &str)> = toolpath
+            .diagnostics
+            .iter()
+            .filter(|diagnostic| diagnostic.severity == Severity::Warning)
+            .map(|diagnostic| (diagnostic.line, diagnostic.message.as_str()))
+            .collect();
+        assert_eq!(
+            findings,
+            [
+                (1, "lint: G0 and G1 in one block (motion group)"),
+                (1, "lint: feed move before any F word"),
+                (1, "lint: feed move with the spindle off"),
+                (2, "lint: X given more than once"),
+                (7, "lint: feed move with the spindle off"),
+            ]
+        );
+        let kinds: Vec<Lint> = toolpath.diagnostics.iter().filter_map(|diagnostic| diagnostic.lint.clone()).collect();
+        let g = |value| Word { letter: 'G', value };
+        assert_eq!(
+            kinds,
+            [
+                Lint::ModalConflict { group: "motion", codes: vec![g(0.0), g(1.0)] },
+                Lint::MissingFeed,
+                Lint::SpindleOff,
+                Lint::RepeatedWord('X'),
+                Lint::SpindleOff,
+            ]
+        );
+        let plain = parse_str(&program, ParseOptions::default()).unwrap();
+        assert!(plain.diagnostics.iter().all(|diagnostic| diagnostic.lint.is_none()));
+    }
+}
@@ -49,12 +49,12 @@ pub fn export_toolpath_obj(
         return Err(anyhow!("no feed segments to export"));
     }
 
-    if let Some(parent) = obj_path.parent() {
-        if !parent.as_os_str().is_empty() {
-            fs::create_dir_all(parent).with_context(|| {
-                format!("failed to create output directory: {}", parent.display())
-            })?;
-        }
+    if let Some(parent) = obj_path.parent()
+        && !parent.as_os_str().is_empty()
+    {
+        fs::create_dir_all(parent).with_context(|| {
+            format!("failed to create output directory: {}", parent.display())
+        })?;
     }
 
     let mtl_path = obj_path.with_extension("mtl");
@@ -92,7 +92,7 @@ impl Mesh {
 
 fn add_segment_tube(mesh: &mut Mesh, start: Vec3, end: Vec3, options: &ObjExportOptions) -> bool {
     let axis = end - start;
-    let axis_len = length(axis);
+    let axis_len = axis.length();
     if axis_len <= EPSILON {
         return false;
     }
@@ -198,12 +198,8 @@ fn cross(a: Vec3, b: Vec3) -> Vec3 {
     )
 }
 
-fn length(v: Vec3) -> f64 {
-    (v.x * v.x + v.y * v.y + v.z * v.z).sqrt()
-}
-
 fn normalize(v: Vec3) -> Option<Vec3> {
-    let len = length(v);
+    let len = v.length();
     if len <= EPSILON {
         return None;
     }
@@ -213,8 +209,8 @@ fn normalize(v: Vec3) -> Option<Vec3> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::{LineSegment, MoveKind, Toolpath, ToolpathStats};
-    use cnc_geom::{Bounds3, Vec3};
+    use crate::{LineSegment, MoveKind, Toolpath};
+    use cnc_geom::{Bounds3, RotaryAngles, Vec3};
     use std::path::PathBuf;
 
     fn test_toolpath(segments: Vec<LineSegment>) -> Toolpath {
@@ -226,8 +222,7 @@ mod tests {
         Toolpath {
             segments,
             bounds,
-            stats: ToolpathStats::default(),
-            line_segment_ends: Vec::new(),
+            ..Toolpath::default()
         }
     }
 
@@ -254,6 +249,8 @@ mod tests {
             start: Vec3::new(0.0, 0.0, 0.0),
             end: Vec3::new(1.0, 0.0, 0.0),
             kind: MoveKind::Feed,
+            feed: None,
+            rotary: RotaryAngles::default(),
         }]);
 
         let invalid_radius = ObjExportOptions {
@@ -278,6 +275,8 @@ mod tests {
             start: Vec3::new(0.0, 0.0, 0.0),
             end: Vec3::new(2.0, 0.0, 0.0),
             kind: MoveKind::Rapid,
+            feed: None,
+            rotary: RotaryAngles::default(),
         }]);
 
         let err = export_toolpath_obj(&toolpath, &path, &ObjExportOptions::default()).unwrap_err();
@@ -291,6 +290,8 @@ mod tests {
             start: Vec3::new(0.0, 0.0, 0.0),
             end: Vec3::new(0.0, 0.0, 5.0),
             kind: MoveKind::Feed,
+            feed: None,
+            rotary: RotaryAngles::default(),
         }]);
 
         export_toolpath_obj(&toolpath, &path, &ObjExportOptions::default()).unwrap();
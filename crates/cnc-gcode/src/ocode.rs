@@ -0,0 +1,97 @@
+use crate::CommentSyntax;
+
+/// What a LinuxCNC `o` word does.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum OKind {
+    Sub,
+    EndSub,
+    Call,
+    Return,
+    If,
+    ElseIf,
+    Else,
+    EndIf,
+    Do,
+    While,
+    EndWhile,
+    Repeat,
+    EndRepeat,
+    Break,
+    Continue,
+}
+
+impl OKind {
+    /// The word that ends the block this one opens.
+    pub(crate) fn closer(self) -> Option<OKind> {
+        match self {
+            OKind::Sub => Some(OKind::EndSub),
+            OKind::If => Some(OKind::EndIf),
+            OKind::Do => Some(OKind::While),
+            OKind::While => Some(OKind::EndWhile),
+            OKind::Repeat => Some(OKind::EndRepeat),
+            _ => None,
+        }
+    }
+}
+
+/// A LinuxCNC `o` control line, such as `o100 while [#1 LT 5]` or `o<drill> call [2]`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct OWord {
+    /// Number or `<name>`, lowercased.
+    pub label: String,
+    pub kind: OKind,
+    /// Condition, count, arguments or return value after the keyword.
+    pub args: String,
+}
+
+pub(crate) fn o_word(line: &str, comments: &CommentSyntax) -> Option<OWord> {
+    let code = comments.strip(line);
+    let rest = code.trim_start().strip_prefix(['o', 'O'])?.trim_start();
+    let (label, rest) = match rest.strip_prefix('<') {
+        Some(named) => {
+            let end = named.find('>')?;
+            (named[..end].replace(char::is_whitespace, ""), &named[end + 1..])
+        }
+        None => {
+            let end = rest.find(|ch: char| !ch.is_ascii_digit()).unwrap_or(rest.len());
+            (rest[..end].to_string(), &rest[end..])
+        }
+    };
+    let rest = rest.trim_start();
+    let end = rest.find(|ch: char| !ch.is_ascii_alphabetic()).unwrap_or(rest.len());
+    let kind = match rest[..end].to_ascii_lowercase().as_str() {
+        "sub" => OKind::Sub,
+        "endsub" => OKind::EndSub,
+        "call" => OKind::Call,
+        "return" => OKind::Return,
+        "if" => OKind::If,
+        "elseif" => OKind::ElseIf,
+        "else" => OKind::Else,
+        "endif" => OKind::EndIf,
+        "do" => OKind::Do,
+        "while" => OKind::While,
+        "endwhile" => OKind::EndWhile,
+        "repeat" => OKind::Repeat,
+        "endrepeat" => OKind::EndRepeat,
+        "break" => OKind::Break,
+        "continue" => OKind::Continue,
+        _ => return None,
+    };
+    (!label.is_empty()).then(|| OWord {
+        label: label.to_ascii_lowercase(),
+        kind,
+        args: rest[end..].trim().to_string(),
+    })
+}
+
+/// Index of the first line after `from` that is `kind` for `label`.
+pub(crate) fn find_o_word(
+    lines: &[String],
+    from: usize,
+    label: &str,
+    kind: OKind,
+    comments: &CommentSyntax,
+) -> Option<usize> {
+    (from + 1..lines.len())
+        .find(|&idx| o_word(&lines[idx], comments).is_some_and(|word| word.label == label && word.kind == kind))
+}
@@ -0,0 +1,74 @@
+use std::ops::Range;
+
+/// A CAM operation delimited by marker comments.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Operation {
+    pub name: String,
+    /// 0-based source line range, starting at the marker comment.
+    pub lines: Range<usize>,
+    pub segments: Range<usize>,
+}
+
+const FUSION_STRATEGIES: &[&str] = &[
+    "2d ", "3d ", "adaptive", "pocket", "contour", "face", "drill", "bore", "circular", "thread",
+    "slot", "trace", "engrave", "chamfer", "parallel", "scallop", "pencil", "spiral", "radial",
+    "ramp", "morph", "flow", "steep", "horizontal", "project", "swarf", "rest", "multi-axis",
+];
+
+/// Recognizes operation markers on comment-only lines.
+///
+/// Handles Mastercam-style `(OPERATION 3: CONTOUR)` / `(OP: Face)` comments and Fusion 360
+/// operation names such as `(2D Contour1)`, which are a strategy name with a numeric suffix.
+pub(crate) fn operation_marker(line: &str) -> Option<String> {
+    let comment = comment_text(line)?;
+    let lower = comment.to_ascii_lowercase();
+    for prefix in ["operation", "op"] {
+        let Some(rest) = lower.strip_prefix(prefix) else {
+            continue;
+        };
+        let rest = rest.trim_start_matches(|ch: char| ch.is_ascii_digit() || ch == ' ');
+        if let Some(name) = rest.strip_prefix(':').or_else(|| rest.strip_prefix('-')) {
+            let offset = comment.len() - name.len();
+            let name = comment[offset..].trim();
+            if !name.is_empty() {
+                return Some(name.to_string());
+            }
+        }
+    }
+    let fusion = FUSION_STRATEGIES
+        .iter()
+        .any(|strategy| lower.starts_with(strategy))
+        && comment.ends_with(|ch: char| ch.is_ascii_digit())
+        && comment.len() <= 48;
+    fusion.then(|| comment.to_string())
+}
+
+fn comment_text(line: &str) -> Option<&str> {
+    let trimmed = line.trim();
+    let inner = if let Some(rest) = trimmed.strip_prefix('(') {
+        rest.strip_suffix(')')?
+    } else {
+        trimmed.strip_prefix(';')?
+    };
+    if inner.contains(['(', ')']) {
+        return None;
+    }
+    let inner = inner.trim();
+    (!inner.is_empty()).then_some(inner)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn recognizes_cam_markers() {
+        assert_eq!(operation_marker("(OPERATION 3: CONTOUR)").as_deref(), Some("CONTOUR"));
+        assert_eq!(operation_marker("; OP - Face mill").as_deref(), Some("Face mill"));
+        assert_eq!(operation_marker("(2D Contour1)").as_deref(), Some("2D Contour1"));
+        assert_eq!(operation_marker("(Adaptive2)").as_deref(), Some("Adaptive2"));
+        assert_eq!(operation_marker("(Contour the part)"), None);
+        assert_eq!(operation_marker("G1 X1 (2D Contour1)"), None);
+        assert_eq!(operation_marker("(T1 D=6 CR=0)"), None);
+    }
+}
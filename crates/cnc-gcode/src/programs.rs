@@ -0,0 +1,181 @@
+use std::collections::HashMap;
+use std::ops::Range;
+use std::sync::Arc;
+
+use crate::CommentSyntax;
+
+/// One program of a file holding several, delimited by `%` lines and/or `O` numbers.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Program {
+    /// The `O` number, if the program has one.
+    pub number: Option<u32>,
+    /// Comment on the `O` line, e.g. `PART A` for `O1000 (PART A)`.
+    pub name: Option<String>,
+    /// 0-based source line range, starting at the `%` or `O` header.
+    pub lines: Range<usize>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum ProgramMarker {
+    Percent,
+    Number(u32, Option<String>),
+}
+
+/// Splits a file into programs.
+///
+/// `%` opens and closes a tape block; an `O` line names the program of a block that has no
+/// number yet and otherwise starts a new program. Files without headers yield no programs.
+pub fn split_programs<S: AsRef<str>>(lines: &[S]) -> Vec<Program> {
+    let mut programs: Vec<Program> = Vec::new();
+    let mut open = false;
+    let mut tape = false;
+    for (idx, line) in lines.iter().enumerate() {
+        let Some(marker) = program_marker(line.as_ref()) else {
+            continue;
+        };
+        match marker {
+            ProgramMarker::Percent if tape => {
+                close(&mut programs, open, idx + 1);
+                open = false;
+                tape = false;
+            }
+            ProgramMarker::Percent => {
+                close(&mut programs, open, idx);
+                programs.push(Program {
+                    number: None,
+                    name: None,
+                    lines: idx..idx,
+                });
+                open = true;
+                tape = true;
+            }
+            ProgramMarker::Number(number, name) => {
+                if open && let Some(last) = programs.last_mut().filter(|last| last.number.is_none()) {
+                    last.number = Some(number);
+                    last.name = name;
+                    continue;
+                }
+                close(&mut programs, open, idx);
+                programs.push(Program {
+                    number: Some(number),
+                    name,
+                    lines: idx..idx,
+                });
+                open = true;
+            }
+        }
+    }
+    close(&mut programs, open, lines.len());
+    // A `%` on the last line opens an empty block rather than a program.
+    programs.retain(|program| program.lines.len() > 1 || program.number.is_some());
+    programs
+}
+
+/// Bodies of the `O` programs that end in M99, by number: the lines after the `O` line up to
+/// and including the M99, for M98 to call.
+pub(crate) fn subprogram_bodies<S: AsRef<str>>(
+    lines: impl IntoIterator<Item = S>,
+    comments: &CommentSyntax,
+) -> HashMap<u32, Arc<[String]>> {
+    let mut bodies = HashMap::new();
+    let mut current: Option<(u32, Vec<String>)> = None;
+    for line in lines {
+        let line = line.as_ref();
+        match program_marker(line) {
+            Some(ProgramMarker::Number(number, _)) => current = Some((number, Vec::new())),
+            Some(ProgramMarker::Percent) => current = None,
+            None => {
+                let Some((_, body)) = current.as_mut() else {
+                    continue;
+                };
+                body.push(line.to_string());
+                if returns_to_caller(line, comments)
+                    && let Some((number, body)) = current.take()
+                {
+                    bodies.insert(number, body.into());
+                }
+            }
+        }
+    }
+    bodies
+}
+
+fn returns_to_caller(line: &str, comments: &CommentSyntax) -> bool {
+    let code = comments.strip(line).to_ascii_uppercase();
+    code.match_indices('M').any(|(at, _)| {
+        let digits: String = code[at + 1..]
+            .trim_start()
+            .chars()
+            .take_while(|ch| ch.is_ascii_digit())
+            .collect();
+        digits.parse::<u32>() == Ok(99)
+    })
+}
+
+fn close(programs: &mut [Program], open: bool, end: usize) {
+    if open && let Some(last) = programs.last_mut() {
+        last.lines.end = end;
+    }
+}
+
+fn program_marker(line: &str) -> Option<ProgramMarker> {
+    let trimmed = line.trim();
+    if trimmed.starts_with('%') {
+        return Some(ProgramMarker::Percent);
+    }
+    let rest = trimmed.strip_prefix(['O', 'o'])?;
+    let digits = rest.len() - rest.trim_start_matches(|ch: char| ch.is_ascii_digit()).len();
+    let number = rest[..digits].parse().ok()?;
+    let tail = rest[digits..].trim();
+    let name = if tail.is_empty() {
+        None
+    } else {
+        let comment = match tail.strip_prefix(';') {
+            Some(comment) => comment,
+            None => tail.strip_prefix('(')?.strip_suffix(')')?,
+        }
+        .trim();
+        (!comment.is_empty()).then(|| comment.to_string())
+    };
+    Some(ProgramMarker::Number(number, name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn splits_tape_blocks_and_o_numbers() {
+        let lines = [
+            "%",
+            "O1000 (PART A)",
+            "G0 X1",
+            "M30",
+            "O2000",
+            "G0 X2",
+            "M30",
+            "%",
+            "o100 sub",
+        ];
+        let programs = split_programs(&lines);
+        assert_eq!(
+            programs,
+            [
+                Program {
+                    number: Some(1000),
+                    name: Some("PART A".to_string()),
+                    lines: 0..4,
+                },
+                Program {
+                    number: Some(2000),
+                    name: None,
+                    lines: 4..8,
+                },
+            ]
+        );
+
+        let unnamed = split_programs(&["%", "G0 X1", "%", "%", "G0 X2", "%"]);
+        assert_eq!(unnamed.iter().map(|p| p.lines.clone()).collect::<Vec<_>>(), [0..3, 3..6]);
+        assert!(split_programs(&["G0 X1", "G1 X2"]).is_empty());
+    }
+}
@@ -0,0 +1,150 @@
+use crate::{Coolant, DistanceMode, FeedMode, MotionMode, Plane, Spindle, Toolpath, Units};
+
+/// Builds a preamble that restores the modal state in effect before a 0-based source line
+/// and approaches that line's start point from `safe_z` (mm, in toolpath coordinates).
+///
+/// The approach is rapid at `safe_z`, then a feed plunge to the start depth in units per
+/// minute; a G93 or G95 feed mode is restored after it.
+pub fn restart_preamble(toolpath: &Toolpath, line: usize, safe_z: f64) -> Option<Vec<String>> {
+    if line >= toolpath.line_states.len() {
+        return None;
+    }
+    let state = line
+        .checked_sub(1)
+        .map(|prev| toolpath.line_states[prev])
+        .unwrap_or_default();
+    let (units_code, precision) = match state.units {
+        Units::Millimeters => (21, 3),
+        Units::Inches => (20, 4),
+    };
+    let value = |mm: f64| format!("{:.*}", precision, state.units.from_mm(mm));
+    let plane = match state.plane {
+        Plane::XY => 17,
+        Plane::XZ => 18,
+        Plane::YZ => 19,
+    };
+
+    let mut modal = format!("G90 G{} G{}", plane, units_code);
+    if state.feed_mode != FeedMode::UnitsPerMinute {
+        modal.push_str(" G94");
+    }
+    let mut out = vec![format!("(restart at line {})", line + 1), modal];
+    if let Some(offset) = state.work_offset {
+        out.push(offset.code().to_string());
+    }
+    if let Some(tool) = state.tool {
+        out.push(format!("T{} M6", tool));
+    }
+    if let Some(offset) = state.tool_length {
+        out.push(offset.code());
+    }
+    let spindle = match state.spindle {
+        Spindle::Off => None,
+        Spindle::Clockwise => Some("M3"),
+        Spindle::CounterClockwise => Some("M4"),
+    };
+    match (state.spindle_speed, spindle) {
+        (Some(speed), Some(code)) => out.push(format!("S{:.0} {}", speed, code)),
+        (Some(speed), None) => out.push(format!("S{:.0}", speed)),
+        (None, Some(code)) => out.push(code.to_string()),
+        (None, None) => {}
+    }
+    match state.coolant {
+        Coolant::Off => {}
+        Coolant::Mist => out.push("M7".to_string()),
+        Coolant::Flood => out.push("M8".to_string()),
+    }
+    let start = state.position - state.work_origin;
+    let safe_z = safe_z - state.work_origin.z;
+    out.push(format!("G0 Z{}", value(safe_z.max(start.z))));
+    let rotary: Vec<String> = [('A', state.rotary.a), ('B', state.rotary.b), ('C', state.rotary.c)]
+        .iter()
+        .filter(|(_, angle)| *angle != 0.0)
+        .map(|(letter, angle)| format!("{}{:.3}", letter, angle))
+        .collect();
+    if !rotary.is_empty() {
+        out.push(format!("G0 {}", rotary.join(" ")));
+    }
+    out.push(format!("G0 X{} Y{}", value(start.x), value(start.y)));
+    let mut plunge = format!("G1 Z{}", value(start.z));
+    if let Some(feed) = state.feed_rate {
+        plunge.push_str(&format!(" F{}", value(feed)));
+    }
+    out.push(plunge);
+    // Probing and threading need their parameters on every block, so the plunge's G1 stays modal.
+    let motion = match state.motion_mode {
+        MotionMode::Rapid => Some("G0"),
+        MotionMode::Feed | MotionMode::Probe(_) | MotionMode::Thread => None,
+        MotionMode::ArcCW => Some("G2"),
+        MotionMode::ArcCCW => Some("G3"),
+    };
+    if let Some(motion) = motion {
+        out.push(motion.to_string());
+    }
+    if state.distance_mode == DistanceMode::Relative {
+        out.push("G91".to_string());
+    }
+    match (state.feed_mode, state.programmed_feed) {
+        (FeedMode::UnitsPerMinute, _) => {}
+        (FeedMode::UnitsPerRevolution, Some(feed)) => out.push(format!("G95 F{}", value(feed))),
+        (mode, _) => out.push(mode.code().to_string()),
+    }
+    Some(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ParseOptions, Parser};
+
+    #[test]
+    fn restores_modal_state_before_line() {
+        let mut parser = Parser::new(ParseOptions::default());
+        for (idx, line) in ["G20 G55", "T3 M6 S9000 M3 M8", "G0 X1 Y2 Z0.5", "G1 Z-0.1 F20", "X3"]
+            .iter()
+            .enumerate()
+        {
+            parser.parse_line(line, idx + 1).unwrap();
+        }
+        let toolpath = parser.finish().unwrap();
+
+        let preamble = restart_preamble(&toolpath, 4, 25.4).unwrap();
+        assert_eq!(
+            preamble,
+            [
+                "(restart at line 5)",
+                "G90 G17 G20",
+                "G55",
+                "T3 M6",
+                "S9000 M3",
+                "M8",
+                "G0 Z1.0000",
+                "G0 X1.0000 Y2.0000",
+                "G1 Z-0.1000 F20.0000",
+            ]
+        );
+        assert_eq!(restart_preamble(&toolpath, 5, 25.4), None);
+    }
+
+    #[test]
+    fn plunges_per_minute_and_restores_the_feed_mode() {
+        let mut parser = Parser::new(ParseOptions::default());
+        for (idx, line) in ["S1000 M3", "G95 G1 Z-1 F0.1", "X5"].iter().enumerate() {
+            parser.parse_line(line, idx + 1).unwrap();
+        }
+        let toolpath = parser.finish().unwrap();
+        let preamble = restart_preamble(&toolpath, 2, 5.0).unwrap();
+        assert_eq!(preamble[1], "G90 G17 G21 G94");
+        assert_eq!(preamble[preamble.len() - 2], "G1 Z-1.000 F100.000");
+        assert_eq!(preamble.last().unwrap(), "G95 F0.100");
+    }
+
+    #[test]
+    fn probe_and_thread_modes_restart_as_feed_moves() {
+        for program in ["G38.2 Z-5 F100\nG0 Z5\nG1 X1", "G33 Z-5 K1\nX1"] {
+            let toolpath = crate::parse_str(program, ParseOptions::default()).unwrap();
+            let preamble = restart_preamble(&toolpath, 1, 10.0).unwrap();
+            assert!(preamble.last().unwrap().starts_with("G1 Z"), "{:?}", preamble);
+        }
+    }
+}
@@ -0,0 +1,178 @@
+use cnc_geom::{Bounds2, Vec2};
+
+use crate::Toolpath;
+
+/// Upper bound on grid cells per axis.
+const MAX_CELLS: usize = 256;
+
+/// Uniform XY grid of segment bounding boxes, answering "which moves pass through here?".
+#[derive(Debug, Clone)]
+pub struct SpatialIndex {
+    bounds: Bounds2,
+    cell: f64,
+    columns: usize,
+    rows: usize,
+    cells: Vec<Vec<u32>>,
+    /// XY start and end of each segment.
+    segments: Vec<(Vec2, Vec2)>,
+}
+
+impl SpatialIndex {
+    pub fn new(toolpath: &Toolpath) -> Self {
+        let segments: Vec<(Vec2, Vec2)> = toolpath
+            .segments
+            .iter()
+            .map(|seg| (Vec2::new(seg.start.x, seg.start.y), Vec2::new(seg.end.x, seg.end.y)))
+            .collect();
+        let mut bounds = Bounds2::new();
+        for &(start, end) in &segments {
+            bounds.include(start);
+            bounds.include(end);
+        }
+        let per_axis = ((segments.len() as f64).sqrt().ceil() as usize).clamp(1, MAX_CELLS);
+        let cell = (bounds.width().max(bounds.height()) / per_axis as f64).max(1e-6);
+        let columns = (bounds.width() / cell) as usize + 1;
+        let rows = (bounds.height() / cell) as usize + 1;
+        let mut index = Self {
+            bounds,
+            cell,
+            columns,
+            rows,
+            cells: vec![Vec::new(); columns * rows],
+            segments,
+        };
+        for (idx, &(start, end)) in index.segments.iter().enumerate() {
+            let (c0, r0) = index.cell_of(Vec2::new(start.x.min(end.x), start.y.min(end.y)));
+            let (c1, r1) = index.cell_of(Vec2::new(start.x.max(end.x), start.y.max(end.y)));
+            for row in r0..=r1 {
+                for column in c0..=c1 {
+                    index.cells[row * columns + column].push(idx as u32);
+                }
+            }
+        }
+        index
+    }
+
+    /// Segments with any part inside the rectangle spanned by `a` and `b`, in program order.
+    pub fn segments_in_rect(&self, a: Vec2, b: Vec2) -> Vec<usize> {
+        let (min, max) = (Vec2::new(a.x.min(b.x), a.y.min(b.y)), Vec2::new(a.x.max(b.x), a.y.max(b.y)));
+        self.candidates(min, max)
+            .into_iter()
+            .filter(|&idx| {
+                let (start, end) = self.segments[idx];
+                crosses_rect(start, end, min, max)
+            })
+            .collect()
+    }
+
+    /// Segments passing within `radius` of `point` in XY, in program order.
+    pub fn segments_near(&self, point: Vec2, radius: f64) -> Vec<usize> {
+        let reach = Vec2::new(radius, radius);
+        self.candidates(point - reach, point + reach)
+            .into_iter()
+            .filter(|&idx| {
+                let (start, end) = self.segments[idx];
+                distance_to_segment(point, start, end) <= radius
+            })
+            .collect()
+    }
+
+    fn candidates(&self, min: Vec2, max: Vec2) -> Vec<usize> {
+        let outside = max.x < self.bounds.min.x
+            || max.y < self.bounds.min.y
+            || min.x > self.bounds.max.x
+            || min.y > self.bounds.max.y;
+        if !self.bounds.initialized || outside {
+            return Vec::new();
+        }
+        let (c0, r0) = self.cell_of(min);
+        let (c1, r1) = self.cell_of(max);
+        let mut found: Vec<usize> = (r0..=r1)
+            .flat_map(|row| (c0..=c1).map(move |column| row * self.columns + column))
+            .flat_map(|cell| self.cells[cell].iter().map(|&idx| idx as usize))
+            .collect();
+        found.sort_unstable();
+        found.dedup();
+        found
+    }
+
+    fn cell_of(&self, point: Vec2) -> (usize, usize) {
+        let axis = |value: f64, origin: f64, count: usize| {
+            (((value - origin) / self.cell).floor().max(0.0) as usize).min(count - 1)
+        };
+        (
+            axis(point.x, self.bounds.min.x, self.columns),
+            axis(point.y, self.bounds.min.y, self.rows),
+        )
+    }
+}
+
+/// Liang–Barsky clip of segment `a`-`b` against an axis-aligned rectangle.
+fn crosses_rect(a: Vec2, b: Vec2, min: Vec2, max: Vec2) -> bool {
+    let d = b - a;
+    let (mut enter, mut exit) = (0.0_f64, 1.0_f64);
+    for (p, q) in [(-d.x, a.x - min.x), (d.x, max.x - a.x), (-d.y, a.y - min.y), (d.y, max.y - a.y)] {
+        if p == 0.0 {
+            if q < 0.0 {
+                return false;
+            }
+            continue;
+        }
+        let t = q / p;
+        if p < 0.0 {
+            enter = enter.max(t);
+        } else {
+            exit = exit.min(t);
+        }
+        if enter > exit {
+            return false;
+        }
+    }
+    true
+}
+
+fn distance_to_segment(point: Vec2, a: Vec2, b: Vec2) -> f64 {
+    let d = b - a;
+    let length_sq = d.x * d.x + d.y * d.y;
+    let offset = point - a;
+    let t = if length_sq > 0.0 {
+        ((offset.x * d.x + offset.y * d.y) / length_sq).clamp(0.0, 1.0)
+    } else {
+        0.0
+    };
+    let closest = a + d * t;
+    (point.x - closest.x).hypot(point.y - closest.y)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{LineSegment, MoveKind};
+    use cnc_geom::{RotaryAngles, Vec3};
+
+    #[test]
+    fn finds_segments_crossing_a_region() {
+        let seg = |start: Vec3, end: Vec3| LineSegment {
+            start,
+            end,
+            kind: MoveKind::Feed,
+            feed: None,
+            rotary: RotaryAngles::default(),
+        };
+        let toolpath = Toolpath {
+            segments: vec![
+                seg(Vec3::new(0.0, 0.0, 0.0), Vec3::new(10.0, 0.0, 0.0)),
+                seg(Vec3::new(10.0, 0.0, 0.0), Vec3::new(10.0, 10.0, 0.0)),
+                seg(Vec3::new(10.0, 10.0, 0.0), Vec3::new(0.0, 0.0, 0.0)),
+                seg(Vec3::new(2.0, 8.0, 5.0), Vec3::new(2.0, 8.0, -1.0)),
+            ],
+            ..Toolpath::default()
+        };
+        let index = SpatialIndex::new(&toolpath);
+        assert_eq!(index.segments_in_rect(Vec2::new(4.0, -1.0), Vec2::new(6.0, 1.0)), [0]);
+        assert_eq!(index.segments_in_rect(Vec2::new(4.0, 4.0), Vec2::new(6.0, 6.0)), [2]);
+        assert_eq!(index.segments_in_rect(Vec2::new(1.0, 7.0), Vec2::new(3.0, 9.0)), [3]);
+        assert_eq!(index.segments_near(Vec2::new(10.5, 5.0), 1.0), [1]);
+        assert!(index.segments_near(Vec2::new(50.0, 50.0), 1.0).is_empty());
+    }
+}
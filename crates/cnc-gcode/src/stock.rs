@@ -0,0 +1,129 @@
+use cnc_geom::{Bounds3, Vec3};
+
+use crate::{MoveKind, Toolpath};
+
+/// Height-map cells per axis beyond which the resolution is coarsened.
+const MAX_CELLS: usize = 1000;
+
+/// Material thinner than this does not count as cut.
+const CUT_EPSILON: f64 = 1e-3;
+
+/// Flat-bottomed cutter simulation over a block of stock, one height per XY cell.
+#[derive(Debug, Clone)]
+pub(crate) struct StockModel {
+    bounds: Bounds3,
+    cell: f64,
+    columns: usize,
+    rows: usize,
+    heights: Vec<f64>,
+}
+
+impl StockModel {
+    /// An uncut block; `resolution` is the cell size in mm.
+    pub fn new(bounds: Bounds3, resolution: f64) -> Self {
+        let size = bounds.size();
+        let cell = resolution.max(size.x.max(size.y) / MAX_CELLS as f64).max(1e-6);
+        let columns = (size.x / cell).ceil().max(1.0) as usize;
+        let rows = (size.y / cell).ceil().max(1.0) as usize;
+        Self {
+            bounds,
+            cell,
+            columns,
+            rows,
+            heights: vec![bounds.max.z; columns * rows],
+        }
+    }
+
+    /// Lowers the stock under a cutter of `radius` moved from `start` to `end`; returns
+    /// whether any material was removed.
+    pub fn cut(&mut self, start: Vec3, end: Vec3, radius: f64) -> bool {
+        let d = end - start;
+        let length = (d.x * d.x + d.y * d.y).sqrt();
+        let steps = (length / (self.cell * 0.5)).ceil().max(1.0) as usize;
+        // A cutter narrower than a cell still clears the cell it is in.
+        let reach = radius.max(self.cell * 0.5);
+        let mut removed = false;
+        for step in 0..=steps {
+            let p = start + d * (step as f64 / steps as f64);
+            if p.z >= self.bounds.max.z - CUT_EPSILON {
+                continue;
+            }
+            let column = |x: f64| ((x - self.bounds.min.x) / self.cell).floor();
+            let row = |y: f64| ((y - self.bounds.min.y) / self.cell).floor();
+            let (c0, c1) = (column(p.x - reach).max(0.0), column(p.x + reach).min(self.columns as f64 - 1.0));
+            let (r0, r1) = (row(p.y - reach).max(0.0), row(p.y + reach).min(self.rows as f64 - 1.0));
+            if c0 > c1 || r0 > r1 {
+                continue;
+            }
+            for r in r0 as usize..=r1 as usize {
+                for c in c0 as usize..=c1 as usize {
+                    let cx = self.bounds.min.x + (c as f64 + 0.5) * self.cell;
+                    let cy = self.bounds.min.y + (r as f64 + 0.5) * self.cell;
+                    let height = &mut self.heights[r * self.columns + c];
+                    if (cx - p.x).hypot(cy - p.y) <= reach && *height > p.z + CUT_EPSILON {
+                        *height = p.z.max(self.bounds.min.z);
+                        removed = true;
+                    }
+                }
+            }
+        }
+        removed
+    }
+}
+
+/// Marks feed moves that remove no material from `stock` as air cuts, simulating the program
+/// in order. `tool_radius` maps the active tool number to a cutter radius in mm.
+pub fn air_cuts(
+    toolpath: &Toolpath,
+    stock: Bounds3,
+    resolution: f64,
+    tool_radius: impl Fn(Option<u32>) -> f64,
+) -> Vec<bool> {
+    let mut model = StockModel::new(stock, resolution);
+    let mut air = vec![false; toolpath.segments.len()];
+    let mut first = 0;
+    for (line, &end) in toolpath.line_segment_ends.iter().enumerate() {
+        let tool = toolpath.line_states.get(line).and_then(|state| state.tool);
+        let radius = tool_radius(tool);
+        for index in first..end.min(air.len()) {
+            let seg = &toolpath.segments[index];
+            air[index] = seg.kind == MoveKind::Feed && !model.cut(seg.start, seg.end, radius);
+        }
+        first = end;
+    }
+    air
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::LineSegment;
+    use cnc_geom::RotaryAngles;
+
+    #[test]
+    fn repeated_and_above_stock_passes_are_air_cuts() {
+        let feed = |start: Vec3, end: Vec3| LineSegment {
+            start,
+            end,
+            kind: MoveKind::Feed,
+            feed: Some(500.0),
+            rotary: RotaryAngles::default(),
+        };
+        let toolpath = Toolpath {
+            segments: vec![
+                feed(Vec3::new(0.0, 5.0, 2.0), Vec3::new(20.0, 5.0, 2.0)),
+                feed(Vec3::new(0.0, 5.0, -1.0), Vec3::new(20.0, 5.0, -1.0)),
+                feed(Vec3::new(20.0, 5.0, -1.0), Vec3::new(0.0, 5.0, -1.0)),
+                feed(Vec3::new(0.0, 5.0, -2.0), Vec3::new(20.0, 5.0, -2.0)),
+                feed(Vec3::new(40.0, 5.0, -2.0), Vec3::new(60.0, 5.0, -2.0)),
+            ],
+            line_segment_ends: vec![5],
+            ..Toolpath::default()
+        };
+        let mut stock = Bounds3::new();
+        stock.include(Vec3::new(0.0, 0.0, -10.0));
+        stock.include(Vec3::new(30.0, 10.0, 0.0));
+        let air = air_cuts(&toolpath, stock, 0.5, |_| 3.0);
+        assert_eq!(air, [true, false, true, false, true]);
+    }
+}
@@ -0,0 +1,95 @@
+use anyhow::{anyhow, Result};
+
+/// Passes beyond this count are dropped, so a tiny `J` cannot stall the parse.
+const MAX_THREAD_PASSES: usize = 1000;
+
+/// LinuxCNC-style G76 threading cycle parameters, lengths in mm.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct ThreadCycle {
+    /// `P`: thread pitch (mm per revolution).
+    pub pitch: f64,
+    /// `I`: thread crest offset from the drive line; negative for external threads.
+    pub peak_offset: f64,
+    /// `J`: depth of the first pass beyond the crest.
+    pub initial_depth: f64,
+    /// `K`: full thread depth beyond the crest.
+    pub full_depth: f64,
+    /// `R`: depth degression, pass `n` is at `J * n^(1/R)`.
+    pub degression: f64,
+    /// `Q`: compound slide angle in degrees.
+    pub compound_angle: f64,
+    /// `H`: extra passes at full depth.
+    pub spring_passes: u32,
+}
+
+/// A threading pass cut at `x`, with its Z start and end shifted by `z_offset`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct ThreadPass {
+    pub x: f64,
+    pub z_offset: f64,
+}
+
+impl ThreadCycle {
+    pub fn validate(&self) -> Result<()> {
+        if self.pitch <= 0.0 || self.initial_depth <= 0.0 || self.full_depth <= 0.0 {
+            return Err(anyhow!("G76 needs positive P, J and K"));
+        }
+        if self.degression < 1.0 {
+            return Err(anyhow!("G76 R must be at least 1"));
+        }
+        Ok(())
+    }
+
+    /// Passes from the first cut to the spring passes, for a drive line at `drive_x` and a
+    /// thread running `travel` along Z.
+    pub fn passes(&self, drive_x: f64, travel: f64) -> Vec<ThreadPass> {
+        let direction = if self.peak_offset < 0.0 { -1.0 } else { 1.0 };
+        let crest = drive_x + self.peak_offset;
+        let flank = self.compound_angle.to_radians().tan();
+        let pass = |depth: f64| ThreadPass {
+            x: crest + direction * depth,
+            z_offset: -travel.signum() * depth * flank,
+        };
+        let mut passes: Vec<ThreadPass> = (1..MAX_THREAD_PASSES)
+            .map(|n| self.initial_depth * (n as f64).powf(1.0 / self.degression))
+            .take_while(|&depth| depth < self.full_depth)
+            .map(pass)
+            .collect();
+        let full = pass(self.full_depth);
+        passes.extend(std::iter::repeat_n(full, 1 + self.spring_passes as usize));
+        passes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn passes_deepen_to_full_depth_then_spring() {
+        let cycle = ThreadCycle {
+            pitch: 1.5,
+            peak_offset: -1.0,
+            initial_depth: 0.2,
+            full_depth: 0.5,
+            degression: 1.0,
+            compound_angle: 0.0,
+            spring_passes: 1,
+        };
+        cycle.validate().unwrap();
+        let xs: Vec<f64> = cycle
+            .passes(10.0, -20.0)
+            .iter()
+            .map(|pass| (pass.x * 1e9).round() / 1e9)
+            .collect();
+        assert_eq!(xs, [8.8, 8.6, 8.5, 8.5]);
+
+        let angled = ThreadCycle {
+            compound_angle: 45.0,
+            ..cycle
+        };
+        let offset = angled.passes(10.0, -20.0)[0].z_offset;
+        assert!((offset - 0.2).abs() < 1e-9);
+        assert!(ThreadCycle { initial_depth: 0.0, ..cycle }.validate().is_err());
+    }
+}
@@ -0,0 +1,349 @@
+use std::ops::Range;
+
+use cnc_geom::Vec3;
+
+use crate::{LineSegment, MoveKind, PauseKind, Toolpath};
+
+const EPSILON: f64 = 1e-9;
+
+#[derive(Debug, Clone, Copy)]
+pub struct TimeEstimateOptions {
+    /// Rapid traverse rate in mm/min.
+    pub rapid_rate: f64,
+    /// Feed rate in mm/min used for feed moves programmed before any F word.
+    pub default_feed_rate: f64,
+    /// Upper bound applied to programmed feeds, in mm/min.
+    pub max_feed_rate: Option<f64>,
+    /// Acceleration in mm/s²; `None` assumes instant speed changes.
+    pub acceleration: Option<f64>,
+    /// Junction deviation in mm used to limit cornering speed.
+    pub junction_deviation: f64,
+    /// Seconds spent on each M6 tool change.
+    pub tool_change_time: f64,
+    /// Correction factors on the planned time of feed (and probe) moves and of rapids.
+    pub feed_time_factor: f64,
+    pub rapid_time_factor: f64,
+}
+
+impl Default for TimeEstimateOptions {
+    fn default() -> Self {
+        Self {
+            rapid_rate: 5000.0,
+            default_feed_rate: 1000.0,
+            max_feed_rate: None,
+            acceleration: None,
+            junction_deviation: 0.01,
+            tool_change_time: 0.0,
+            feed_time_factor: 1.0,
+            rapid_time_factor: 1.0,
+        }
+    }
+}
+
+impl TimeEstimateOptions {
+    fn target_speed(&self, segment: &LineSegment) -> f64 {
+        let per_min = match segment.kind {
+            MoveKind::Rapid => self.rapid_rate,
+            MoveKind::Feed | MoveKind::Probe => {
+                let feed = segment.feed.unwrap_or(self.default_feed_rate);
+                match self.max_feed_rate {
+                    Some(max) => feed.min(max),
+                    None => feed,
+                }
+            }
+        };
+        (per_min / 60.0).max(EPSILON)
+    }
+}
+
+pub fn estimate_duration(toolpath: &Toolpath, options: &TimeEstimateOptions) -> f64 {
+    segment_durations(toolpath, options).iter().sum()
+}
+
+/// Per-segment durations in seconds, using a trapezoidal planner when acceleration is known,
+/// including the [`pause_times`] spent before each move.
+pub fn segment_durations(toolpath: &Toolpath, options: &TimeEstimateOptions) -> Vec<f64> {
+    motion_durations(toolpath, options)
+        .into_iter()
+        .zip(&toolpath.segments)
+        .zip(pause_times(toolpath, options))
+        .map(|((motion, seg), pause)| {
+            let factor = match seg.kind {
+                MoveKind::Rapid => options.rapid_time_factor,
+                MoveKind::Feed | MoveKind::Probe => options.feed_time_factor,
+            };
+            motion * factor + pause
+        })
+        .collect()
+}
+
+/// Estimated seconds spent on each 0-based source line: the motion time of its moves, so
+/// without acceleration a G93 block takes exactly its `1/F` minutes, plus its own dwells and
+/// tool changes.
+pub fn line_durations(toolpath: &Toolpath, options: &TimeEstimateOptions) -> Vec<f64> {
+    let durations = segment_durations(toolpath, options);
+    let pauses = pause_times(toolpath, options);
+    let mut start = 0;
+    let mut lines: Vec<f64> = toolpath
+        .line_segment_ends
+        .iter()
+        .map(|&end| {
+            let motion = durations[start..end].iter().zip(&pauses[start..end]);
+            let seconds = motion.map(|(total, pause)| total - pause).sum();
+            start = end;
+            seconds
+        })
+        .collect();
+    for pause in &toolpath.pauses {
+        if let Some(seconds) = lines.get_mut(pause.line) {
+            *seconds += pause_seconds(pause.kind, options);
+        }
+    }
+    lines
+}
+
+/// Seconds of dwells and tool changes before each segment; pauses after the last move count
+/// toward the last segment.
+pub fn pause_times(toolpath: &Toolpath, options: &TimeEstimateOptions) -> Vec<f64> {
+    let mut times = vec![0.0; toolpath.segments.len()];
+    let last = times.len().saturating_sub(1);
+    for pause in &toolpath.pauses {
+        if let Some(time) = times.get_mut(pause.segment.min(last)) {
+            *time += pause_seconds(pause.kind, options);
+        }
+    }
+    times
+}
+
+fn pause_seconds(kind: PauseKind, options: &TimeEstimateOptions) -> f64 {
+    match kind {
+        PauseKind::Dwell(seconds) => seconds,
+        PauseKind::ToolChange => options.tool_change_time,
+    }
+}
+
+fn motion_durations(toolpath: &Toolpath, options: &TimeEstimateOptions) -> Vec<f64> {
+    let segments = &toolpath.segments;
+    let lengths: Vec<f64> = segments.iter().map(|seg| (seg.end - seg.start).length()).collect();
+    let speeds: Vec<f64> = segments.iter().map(|seg| options.target_speed(seg)).collect();
+
+    let Some(accel) = options.acceleration.filter(|a| *a > 0.0) else {
+        return lengths
+            .iter()
+            .zip(&speeds)
+            .map(|(len, speed)| len / speed)
+            .collect();
+    };
+
+    let junction = junction_speeds(segments, &lengths, &speeds, accel, options);
+    (0..segments.len())
+        .map(|i| trapezoid_time(lengths[i], junction[i], junction[i + 1], speeds[i], accel))
+        .collect()
+}
+
+/// A run of consecutive feed moves too short, or too sharply joined, to reach their feed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FeedLimitZone {
+    pub segments: Range<usize>,
+    /// Highest programmed feed in the run, in mm/min.
+    pub programmed: f64,
+    /// Highest speed reached in the run, in mm/min.
+    pub reached: f64,
+    /// Seconds beyond running the moves at their programmed feed.
+    pub time_lost: f64,
+}
+
+/// Runs of feed moves whose peak speed stays below `ratio` of the programmed feed (capped at
+/// the machine maximum). Empty without an acceleration limit.
+pub fn feed_limit_zones(toolpath: &Toolpath, options: &TimeEstimateOptions, ratio: f64) -> Vec<FeedLimitZone> {
+    let Some(accel) = options.acceleration.filter(|a| *a > 0.0) else {
+        return Vec::new();
+    };
+    let segments = &toolpath.segments;
+    let lengths: Vec<f64> = segments.iter().map(|seg| (seg.end - seg.start).length()).collect();
+    let speeds: Vec<f64> = segments.iter().map(|seg| options.target_speed(seg)).collect();
+    let junction = junction_speeds(segments, &lengths, &speeds, accel, options);
+    let mut zones: Vec<FeedLimitZone> = Vec::new();
+    for i in 0..segments.len() {
+        let peak = ((2.0 * accel * lengths[i] + junction[i].powi(2) + junction[i + 1].powi(2)) * 0.5)
+            .sqrt()
+            .min(speeds[i]);
+        let limited = segments[i].kind == MoveKind::Feed && lengths[i] > EPSILON && peak < speeds[i] * ratio;
+        if !limited {
+            continue;
+        }
+        let time_lost = trapezoid_time(lengths[i], junction[i], junction[i + 1], speeds[i], accel)
+            - lengths[i] / speeds[i];
+        let (programmed, reached) = (speeds[i] * 60.0, peak * 60.0);
+        match zones.last_mut() {
+            Some(zone) if zone.segments.end == i => {
+                zone.segments.end = i + 1;
+                zone.programmed = zone.programmed.max(programmed);
+                zone.reached = zone.reached.max(reached);
+                zone.time_lost += time_lost;
+            }
+            _ => zones.push(FeedLimitZone {
+                segments: i..i + 1,
+                programmed,
+                reached,
+                time_lost,
+            }),
+        }
+    }
+    zones
+}
+
+/// Speed limits in mm/s entering each segment, plus the final stop, for a path that starts
+/// and ends at rest.
+fn junction_speeds(
+    segments: &[LineSegment],
+    lengths: &[f64],
+    speeds: &[f64],
+    accel: f64,
+    options: &TimeEstimateOptions,
+) -> Vec<f64> {
+    let count = segments.len();
+    let mut junction = vec![0.0; count + 1];
+    for i in 1..count {
+        junction[i] = junction_speed(&segments[i - 1], &segments[i], accel, options)
+            .min(speeds[i - 1])
+            .min(speeds[i]);
+    }
+    for i in (0..count).rev() {
+        let reachable = (junction[i + 1].powi(2) + 2.0 * accel * lengths[i]).sqrt();
+        junction[i] = junction[i].min(reachable);
+    }
+    for i in 0..count {
+        let reachable = (junction[i].powi(2) + 2.0 * accel * lengths[i]).sqrt();
+        junction[i + 1] = junction[i + 1].min(reachable);
+    }
+    junction
+}
+
+fn junction_speed(
+    prev: &LineSegment,
+    next: &LineSegment,
+    accel: f64,
+    options: &TimeEstimateOptions,
+) -> f64 {
+    let (Some(a), Some(b)) = (
+        normalize(prev.end - prev.start),
+        normalize(next.end - next.start),
+    ) else {
+        return 0.0;
+    };
+    let cos_theta = -(a.x * b.x + a.y * b.y + a.z * b.z);
+    if cos_theta > 1.0 - 1e-6 {
+        return 0.0;
+    }
+    if cos_theta < -1.0 + 1e-6 {
+        return f64::INFINITY;
+    }
+    let sin_half = (0.5 * (1.0 - cos_theta)).sqrt();
+    (accel * options.junction_deviation * sin_half / (1.0 - sin_half)).sqrt()
+}
+
+fn trapezoid_time(len: f64, v0: f64, v1: f64, vmax: f64, accel: f64) -> f64 {
+    if len <= EPSILON {
+        return 0.0;
+    }
+    let accel_dist = (vmax * vmax - v0 * v0).max(0.0) / (2.0 * accel);
+    let decel_dist = (vmax * vmax - v1 * v1).max(0.0) / (2.0 * accel);
+    if accel_dist + decel_dist <= len {
+        return (vmax - v0).max(0.0) / accel
+            + (vmax - v1).max(0.0) / accel
+            + (len - accel_dist - decel_dist) / vmax;
+    }
+    let peak = ((2.0 * accel * len + v0 * v0 + v1 * v1) * 0.5).sqrt();
+    (peak - v0).max(0.0) / accel + (peak - v1).max(0.0) / accel
+}
+
+fn normalize(v: Vec3) -> Option<Vec3> {
+    let len = v.length();
+    if len <= EPSILON {
+        return None;
+    }
+    Some(v * (1.0 / len))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cnc_geom::RotaryAngles;
+
+    fn toolpath(segments: Vec<LineSegment>) -> Toolpath {
+        Toolpath {
+            segments,
+            ..Toolpath::default()
+        }
+    }
+
+    fn feed(start: Vec3, end: Vec3, feed: f64) -> LineSegment {
+        LineSegment {
+            start,
+            end,
+            kind: MoveKind::Feed,
+            feed: Some(feed),
+            rotary: RotaryAngles::default(),
+        }
+    }
+
+    #[test]
+    fn constant_speed_without_acceleration() {
+        let path = toolpath(vec![feed(Vec3::new(0.0, 0.0, 0.0), Vec3::new(100.0, 0.0, 0.0), 600.0)]);
+        let total = estimate_duration(&path, &TimeEstimateOptions::default());
+        assert!((total - 10.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn line_durations_keep_pauses_on_their_own_line() {
+        let mut path = toolpath(vec![
+            feed(Vec3::new(0.0, 0.0, 0.0), Vec3::new(100.0, 0.0, 0.0), 600.0),
+            feed(Vec3::new(100.0, 0.0, 0.0), Vec3::new(200.0, 0.0, 0.0), 600.0),
+        ]);
+        path.line_segment_ends = vec![1, 1, 2];
+        path.pauses = vec![crate::Pause {
+            line: 1,
+            segment: 1,
+            kind: PauseKind::Dwell(2.5),
+        }];
+        assert_eq!(line_durations(&path, &TimeEstimateOptions::default()), [10.0, 2.5, 10.0]);
+    }
+
+    #[test]
+    fn acceleration_adds_time_and_collinear_segments_keep_speed() {
+        let options = TimeEstimateOptions {
+            acceleration: Some(100.0),
+            ..TimeEstimateOptions::default()
+        };
+        let single = toolpath(vec![feed(Vec3::new(0.0, 0.0, 0.0), Vec3::new(100.0, 0.0, 0.0), 600.0)]);
+        let split = toolpath(vec![
+            feed(Vec3::new(0.0, 0.0, 0.0), Vec3::new(50.0, 0.0, 0.0), 600.0),
+            feed(Vec3::new(50.0, 0.0, 0.0), Vec3::new(100.0, 0.0, 0.0), 600.0),
+        ]);
+        let single_time = estimate_duration(&single, &options);
+        let split_time = estimate_duration(&split, &options);
+        assert!(single_time > 10.0);
+        assert!((single_time - split_time).abs() < 1e-6);
+    }
+
+    #[test]
+    fn short_zigzag_moves_are_feed_limited() {
+        let options = TimeEstimateOptions {
+            acceleration: Some(100.0),
+            ..TimeEstimateOptions::default()
+        };
+        let mut segments = vec![feed(Vec3::new(-100.0, 0.0, 0.0), Vec3::new(0.0, 0.0, 0.0), 600.0)];
+        for step in 0..10 {
+            let (x, y) = (step as f64 * 0.2, (step % 2) as f64 * 0.2);
+            segments.push(feed(Vec3::new(x, y, 0.0), Vec3::new(x + 0.2, 0.2 - y, 0.0), 3000.0));
+        }
+        let zones = feed_limit_zones(&toolpath(segments), &options, 0.95);
+        assert_eq!(zones.len(), 1);
+        assert_eq!(zones[0].segments, 1..11);
+        assert!((zones[0].programmed - 3000.0).abs() < 1e-9);
+        assert!(zones[0].reached < 600.0);
+        assert!(zones[0].time_lost > 0.0);
+        assert!(feed_limit_zones(&toolpath(Vec::new()), &TimeEstimateOptions::default(), 0.95).is_empty());
+    }
+}
@@ -0,0 +1,105 @@
+use cnc_geom::{distance_to_segment, Vec2, Vec3};
+
+use crate::{SpatialIndex, Toolpath};
+
+/// Reads machine-reported tool positions, one per line: Grbl status reports (`WPos:` or
+/// `MPos:` fields) or three numbers separated by commas or whitespace. Other lines are skipped.
+///
+/// Work positions and bare numbers are shifted by `work_origin` into toolpath coordinates.
+/// `MPos:` is already there, unless a `WCO:` report gave the controller's own work offset.
+pub fn parse_position_trace(text: &str, work_origin: Vec3) -> Vec<Vec3> {
+    let mut controller_offset = None;
+    text.lines()
+        .filter_map(|line| {
+            if let Some(wco) = status_field(line, "WCO:") {
+                controller_offset = Some(wco);
+            }
+            if let Some(point) = status_field(line, "MPos:") {
+                return Some(controller_offset.map_or(point, |wco| point - wco + work_origin));
+            }
+            let point = status_field(line, "WPos:").or_else(|| (!line.contains("Pos:")).then(|| xyz(line)).flatten());
+            point.map(|point| point + work_origin)
+        })
+        .collect()
+}
+
+/// The X, Y and Z of a `key` field in a Grbl status report.
+fn status_field(line: &str, key: &str) -> Option<Vec3> {
+    let rest = &line[line.find(key)? + key.len()..];
+    xyz(rest.split(['|', '>']).next().unwrap_or(rest))
+}
+
+fn xyz(fields: &str) -> Option<Vec3> {
+    let mut numbers = fields
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|field| !field.is_empty())
+        .map(|field| field.parse::<f64>());
+    match (numbers.next(), numbers.next(), numbers.next()) {
+        (Some(Ok(x)), Some(Ok(y)), Some(Ok(z))) => Some(Vec3::new(x, y, z)),
+        _ => None,
+    }
+}
+
+/// 3D distance from each trace point to the nearest programmed move, or `None` when no move
+/// passes within `search` in XY.
+pub fn trace_deviations(toolpath: &Toolpath, index: &SpatialIndex, trace: &[Vec3], search: f64) -> Vec<Option<f64>> {
+    trace
+        .iter()
+        .map(|&point| {
+            index
+                .segments_near(Vec2::new(point.x, point.y), search)
+                .into_iter()
+                .map(|idx| {
+                    let seg = &toolpath.segments[idx];
+                    distance_to_segment(point, seg.start, seg.end)
+                })
+                .min_by(f64::total_cmp)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{LineSegment, MoveKind};
+    use cnc_geom::RotaryAngles;
+
+    #[test]
+    fn parses_status_reports_and_measures_deviation() {
+        let trace = parse_position_trace(
+            "x,y,z\n<Run|MPos:0.000,0.000,-1.000|FS:500,0>\n5 0.2 -1\n<Run|WPos:10.000,3.000,-1.000|F:500>\nok\n",
+            Vec3::default(),
+        );
+        assert_eq!(
+            trace,
+            [Vec3::new(0.0, 0.0, -1.0), Vec3::new(5.0, 0.2, -1.0), Vec3::new(10.0, 3.0, -1.0)]
+        );
+        let toolpath = Toolpath {
+            segments: vec![LineSegment {
+                start: Vec3::new(0.0, 0.0, -1.0),
+                end: Vec3::new(10.0, 0.0, -1.0),
+                kind: MoveKind::Feed,
+                feed: Some(500.0),
+                rotary: RotaryAngles::default(),
+            }],
+            ..Toolpath::default()
+        };
+        let index = SpatialIndex::new(&toolpath);
+        let deviations = trace_deviations(&toolpath, &index, &trace, 1.0);
+        assert_eq!(deviations[0], Some(0.0));
+        assert!((deviations[1].unwrap() - 0.2).abs() < 1e-9);
+        assert_eq!(deviations[2], None);
+    }
+
+    #[test]
+    fn machine_and_work_positions_land_in_toolpath_coordinates() {
+        let origin = Vec3::new(100.0, 50.0, 0.0);
+        let log = "<Idle|MPos:100.000,50.000,0.000|FS:0,0>\n<Idle|WPos:1.000,2.000,0.000|FS:0,0>\n\
+                   <Run|MPos:-200.000,-90.000,5.000|FS:500,0|WCO:-210.000,-100.000,0.000>\n";
+        let trace = parse_position_trace(log, origin);
+        assert_eq!(
+            trace,
+            [Vec3::new(100.0, 50.0, 0.0), Vec3::new(101.0, 52.0, 0.0), Vec3::new(110.0, 60.0, 5.0)]
+        );
+    }
+}
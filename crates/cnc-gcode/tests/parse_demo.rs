@@ -1,11 +1,18 @@
 use std::path::PathBuf;
 
-use cnc_gcode::{parse_file_with_options, ParseOptions};
+use cnc_gcode::{
+    parse_file_with_options, parse_file_with_progress, CancelToken, ParseCancelled, ParseOptions,
+};
 
-#[test]
-fn parse_demo_file() {
+fn demo_path() -> PathBuf {
     let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
     path.push("tests/fixtures/demo.nc");
+    path
+}
+
+#[test]
+fn parse_demo_file() {
+    let path = demo_path();
 
     let options = ParseOptions::with_ignore_missing(['E']).with_ignore_unknown_words(true);
     let toolpath = parse_file_with_options(&path, options).unwrap();
@@ -13,3 +20,29 @@ fn parse_demo_file() {
     assert!(!toolpath.segments.is_empty());
     assert_eq!(toolpath.line_segment_ends.len(), 7);
 }
+
+#[test]
+fn parse_demo_file_reports_final_progress() {
+    let path = demo_path();
+
+    let options = ParseOptions::with_ignore_missing(['E']).with_ignore_unknown_words(true);
+    let mut reports = Vec::new();
+    parse_file_with_progress(&path, options, |progress| reports.push(progress)).unwrap();
+
+    let last = reports.last().unwrap();
+    assert_eq!(last.lines, 7);
+    assert_eq!(last.bytes, 75);
+    assert_eq!(last.total_bytes, 75);
+}
+
+#[test]
+fn cancelled_token_stops_parse() {
+    let token = CancelToken::new();
+    token.cancel();
+    let options = ParseOptions::with_ignore_missing(['E'])
+        .with_ignore_unknown_words(true)
+        .with_cancel_token(token);
+
+    let err = parse_file_with_options(&demo_path(), options).unwrap_err();
+    assert!(err.is::<ParseCancelled>());
+}
@@ -45,6 +45,18 @@ impl Vec3 {
     pub fn new(x: f64, y: f64, z: f64) -> Self {
         Self { x, y, z }
     }
+
+    pub fn dot(self, other: Self) -> f64 {
+        self.x * other.x + self.y * other.y + self.z * other.z
+    }
+
+    pub fn length(self) -> f64 {
+        self.dot(self).sqrt()
+    }
+
+    pub fn lerp(self, to: Self, t: f64) -> Self {
+        self + (to - self) * t
+    }
 }
 
 impl std::ops::Add for Vec3 {
@@ -71,6 +83,33 @@ impl std::ops::Mul<f64> for Vec3 {
     }
 }
 
+/// Rotary axis angles in degrees: A turns about X, B about Y and C about Z.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct RotaryAngles {
+    pub a: f64,
+    pub b: f64,
+    pub c: f64,
+}
+
+impl RotaryAngles {
+    pub fn new(a: f64, b: f64, c: f64) -> Self {
+        Self { a, b, c }
+    }
+
+    pub fn lerp(self, to: Self, t: f64) -> Self {
+        Self::new(
+            self.a + (to.a - self.a) * t,
+            self.b + (to.b - self.b) * t,
+            self.c + (to.c - self.c) * t,
+        )
+    }
+
+    /// Largest single-axis turn, in degrees, between `self` and `to`.
+    pub fn max_delta(self, to: Self) -> f64 {
+        (to.a - self.a).abs().max((to.b - self.b).abs()).max((to.c - self.c).abs())
+    }
+}
+
 #[derive(Debug, Clone, Copy)]
 pub struct Bounds2 {
     pub min: Vec2,
@@ -113,6 +152,12 @@ impl Bounds2 {
     }
 }
 
+impl Default for Bounds2 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 #[derive(Debug, Clone, Copy)]
 pub struct Bounds3 {
     pub min: Vec3,
@@ -155,5 +200,10 @@ impl Bounds3 {
             (self.min.z + self.max.z) * 0.5,
         )
     }
+}
 
+impl Default for Bounds3 {
+    fn default() -> Self {
+        Self::new()
+    }
 }
@@ -0,0 +1,48 @@
+use crate::geom::Vec2;
+
+/// Convex hull of `points`, counter-clockwise from the lowest-x point with collinear points
+/// dropped. Fewer than three distinct points come back sorted and deduplicated.
+pub fn convex_hull(points: &[Vec2]) -> Vec<Vec2> {
+    let mut sorted = points.to_vec();
+    sorted.sort_by(|a, b| a.x.total_cmp(&b.x).then(a.y.total_cmp(&b.y)));
+    sorted.dedup();
+    if sorted.len() < 3 {
+        return sorted;
+    }
+    let turns_left = |hull: &[Vec2], p: Vec2| {
+        let (o, a) = (hull[hull.len() - 2], hull[hull.len() - 1]);
+        (a.x - o.x) * (p.y - o.y) - (a.y - o.y) * (p.x - o.x) > 0.0
+    };
+    // Andrew's monotone chain: the lower hull left to right, then the upper hull back.
+    let mut hull: Vec<Vec2> = Vec::with_capacity(sorted.len() + 1);
+    for &point in &sorted {
+        while hull.len() >= 2 && !turns_left(&hull, point) {
+            hull.pop();
+        }
+        hull.push(point);
+    }
+    let lower = hull.len() + 1;
+    for &point in sorted.iter().rev().skip(1) {
+        while hull.len() >= lower && !turns_left(&hull, point) {
+            hull.pop();
+        }
+        hull.push(point);
+    }
+    hull.pop();
+    hull
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hull_keeps_outer_corners_counter_clockwise() {
+        let points = [(0.0, 0.0), (2.0, 0.0), (4.0, 0.0), (1.0, 1.0), (4.0, 3.0), (0.0, 3.0), (2.0, 2.0), (0.0, 0.0)]
+            .map(|(x, y)| Vec2::new(x, y));
+        let hull = convex_hull(&points);
+        let expected = [(0.0, 0.0), (4.0, 0.0), (4.0, 3.0), (0.0, 3.0)].map(|(x, y)| Vec2::new(x, y));
+        assert_eq!(hull, expected);
+        assert_eq!(convex_hull(&points[..2]).len(), 2);
+    }
+}
@@ -0,0 +1,76 @@
+use crate::geom::{Bounds3, Vec3};
+
+/// Distance from `p` to the closest point of the segment from `a` to `b`.
+pub fn distance_to_segment(p: Vec3, a: Vec3, b: Vec3) -> f64 {
+    let (ab, ap) = (b - a, p - a);
+    let len_sq = ab.dot(ab);
+    let t = if len_sq > 0.0 { (ap.dot(ab) / len_sq).clamp(0.0, 1.0) } else { 0.0 };
+    (ap - ab * t).length()
+}
+
+/// Part of the segment from `a` to `b` inside `bounds`, as fractions `(enter, exit)` along it,
+/// or `None` when it misses the box. Touching a face counts as inside.
+pub fn segment_box_intersection(a: Vec3, b: Vec3, bounds: &Bounds3) -> Option<(f64, f64)> {
+    if !bounds.initialized {
+        return None;
+    }
+    let (mut enter, mut exit) = (0.0_f64, 1.0_f64);
+    for (start, delta, min, max) in [
+        (a.x, b.x - a.x, bounds.min.x, bounds.max.x),
+        (a.y, b.y - a.y, bounds.min.y, bounds.max.y),
+        (a.z, b.z - a.z, bounds.min.z, bounds.max.z),
+    ] {
+        if delta == 0.0 {
+            if start < min || start > max {
+                return None;
+            }
+            continue;
+        }
+        let (t0, t1) = ((min - start) / delta, (max - start) / delta);
+        enter = enter.max(t0.min(t1));
+        exit = exit.min(t0.max(t1));
+        if enter > exit {
+            return None;
+        }
+    }
+    Some((enter, exit))
+}
+
+/// Fraction along the segment from `a` to `b` where it crosses the plane through `origin`
+/// with `normal`, or `None` when it stays on one side or lies in the plane.
+pub fn segment_plane_intersection(a: Vec3, b: Vec3, origin: Vec3, normal: Vec3) -> Option<f64> {
+    let denom = (b - a).dot(normal);
+    if denom == 0.0 {
+        return None;
+    }
+    let t = (origin - a).dot(normal) / denom;
+    (0.0..=1.0).contains(&t).then_some(t)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn segments_meet_points_boxes_and_planes() {
+        let (a, b) = (Vec3::new(0.0, 0.0, 0.0), Vec3::new(10.0, 0.0, 0.0));
+        assert_eq!(distance_to_segment(Vec3::new(5.0, 3.0, 4.0), a, b), 5.0);
+        assert_eq!(distance_to_segment(Vec3::new(-3.0, 4.0, 0.0), a, b), 5.0);
+        assert_eq!(distance_to_segment(Vec3::new(0.0, 2.0, 0.0), a, a), 2.0);
+
+        let mut bounds = Bounds3::new();
+        bounds.include(Vec3::new(2.0, -1.0, -1.0));
+        bounds.include(Vec3::new(4.0, 1.0, 1.0));
+        assert_eq!(segment_box_intersection(a, b, &bounds), Some((0.2, 0.4)));
+        assert_eq!(segment_box_intersection(b, a, &bounds), Some((0.6, 0.8)));
+        let above = Vec3::new(0.0, 0.0, 2.0);
+        assert_eq!(segment_box_intersection(a + above, b + above, &bounds), None);
+        assert_eq!(segment_box_intersection(a, Vec3::new(1.0, 0.0, 0.0), &bounds), None);
+
+        let z_up = Vec3::new(0.0, 0.0, 1.0);
+        let down = (Vec3::new(0.0, 0.0, 5.0), Vec3::new(0.0, 0.0, -5.0));
+        assert_eq!(segment_plane_intersection(down.0, down.1, Vec3::new(0.0, 0.0, 1.0), z_up), Some(0.4));
+        assert_eq!(segment_plane_intersection(a, b, Vec3::default(), z_up), None);
+        assert_eq!(segment_plane_intersection(down.0, down.1, Vec3::new(0.0, 0.0, 6.0), z_up), None);
+    }
+}
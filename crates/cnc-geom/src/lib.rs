@@ -1,5 +1,11 @@
 mod geom;
+mod hull;
+mod intersect;
 mod projection;
+mod resample;
 
-pub use geom::{Bounds2, Bounds3, Vec2, Vec3};
-pub use projection::{project_point, ProjectionMode, ProjectionParams, ViewAngles};
+pub use geom::{Bounds2, Bounds3, RotaryAngles, Vec2, Vec3};
+pub use hull::convex_hull;
+pub use intersect::{distance_to_segment, segment_box_intersection, segment_plane_intersection};
+pub use projection::{machine_point, project_point, workpiece_point, ProjectionMode, ProjectionParams, ViewAngles};
+pub use resample::{resample_polyline, PolylineSample};
@@ -1,4 +1,4 @@
-use crate::geom::{Vec2, Vec3};
+use crate::geom::{RotaryAngles, Vec2, Vec3};
 use std::str::FromStr;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -31,10 +31,13 @@ pub struct ProjectionParams {
     pub angles: ViewAngles,
     pub camera_distance: f64,
     pub target: Vec3,
+    /// Exaggeration applied to Z offsets from `target` before rotating.
+    pub z_scale: f64,
 }
 
 pub fn project_point(p: Vec3, params: ProjectionParams) -> Vec2 {
-    let translated = p - params.target;
+    let mut translated = p - params.target;
+    translated.z *= params.z_scale;
     let rotated = rotate_point(translated, params.angles);
     match params.mode {
         ProjectionMode::Orthographic => Vec2::new(rotated.x, rotated.y),
@@ -50,6 +53,46 @@ pub fn project_point(p: Vec3, params: ProjectionParams) -> Vec2 {
     }
 }
 
+/// Workpiece coordinates of machine point `p` with the rotary table turned to `angles`.
+///
+/// Undoes A, then B, then C about axes through the origin, as on a trunnion whose C platter
+/// sits on the A/B tilt.
+pub fn workpiece_point(p: Vec3, angles: RotaryAngles) -> Vec3 {
+    let mut out = p;
+    if angles.a != 0.0 {
+        let (s, c) = (-angles.a).to_radians().sin_cos();
+        (out.y, out.z) = (out.y * c - out.z * s, out.y * s + out.z * c);
+    }
+    if angles.b != 0.0 {
+        let (s, c) = (-angles.b).to_radians().sin_cos();
+        (out.z, out.x) = (out.z * c - out.x * s, out.z * s + out.x * c);
+    }
+    if angles.c != 0.0 {
+        let (s, c) = (-angles.c).to_radians().sin_cos();
+        (out.x, out.y) = (out.x * c - out.y * s, out.x * s + out.y * c);
+    }
+    out
+}
+
+/// Machine point of workpiece point `p` with the table turned to `angles`; the inverse of
+/// [`workpiece_point`].
+pub fn machine_point(p: Vec3, angles: RotaryAngles) -> Vec3 {
+    let mut out = p;
+    if angles.c != 0.0 {
+        let (s, c) = angles.c.to_radians().sin_cos();
+        (out.x, out.y) = (out.x * c - out.y * s, out.x * s + out.y * c);
+    }
+    if angles.b != 0.0 {
+        let (s, c) = angles.b.to_radians().sin_cos();
+        (out.z, out.x) = (out.z * c - out.x * s, out.z * s + out.x * c);
+    }
+    if angles.a != 0.0 {
+        let (s, c) = angles.a.to_radians().sin_cos();
+        (out.y, out.z) = (out.y * c - out.z * s, out.y * s + out.z * c);
+    }
+    out
+}
+
 fn rotate_point(p: Vec3, angles: ViewAngles) -> Vec3 {
     let (sy, cy) = angles.yaw.sin_cos();
     let (sp, cp) = angles.pitch.sin_cos();
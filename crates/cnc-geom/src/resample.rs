@@ -0,0 +1,93 @@
+use crate::geom::Vec3;
+
+/// A point of a resampled polyline, on the edge from `points[segment]` to
+/// `points[segment + 1]` at fraction `t`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PolylineSample {
+    pub point: Vec3,
+    /// Arc length from the polyline's start.
+    pub distance: f64,
+    pub segment: usize,
+    pub t: f64,
+}
+
+impl PolylineSample {
+    /// Interpolates per-point `values`, one for each polyline point. Per-edge values need no
+    /// interpolation: they are `values[sample.segment]`.
+    pub fn interpolate<T: Copy>(&self, values: &[T], lerp: impl Fn(T, T, f64) -> T) -> T {
+        match values.get(self.segment + 1) {
+            Some(&next) => lerp(values[self.segment], next, self.t),
+            None => values[self.segment],
+        }
+    }
+}
+
+/// Samples every `spacing` of arc length along the polyline through `points`, plus its end;
+/// zero-length edges are skipped. Empty for no points or a non-positive spacing.
+pub fn resample_polyline(points: &[Vec3], spacing: f64) -> Vec<PolylineSample> {
+    let mut samples = Vec::new();
+    if points.is_empty() || spacing <= 0.0 {
+        return samples;
+    }
+    let mut travelled = 0.0;
+    for (segment, edge) in points.windows(2).enumerate() {
+        let length = (edge[1] - edge[0]).length();
+        if length == 0.0 {
+            continue;
+        }
+        // Sample positions are multiplied out so spacing errors do not accumulate.
+        let mut next = samples.len() as f64 * spacing;
+        while next <= travelled + length {
+            let t = (next - travelled) / length;
+            samples.push(PolylineSample {
+                point: edge[0].lerp(edge[1], t),
+                distance: next,
+                segment,
+                t,
+            });
+            next = samples.len() as f64 * spacing;
+        }
+        travelled += length;
+    }
+    if samples.last().is_none_or(|last| travelled - last.distance > 1e-9) {
+        let segment = points.len().saturating_sub(2);
+        samples.push(PolylineSample {
+            point: points[points.len() - 1],
+            distance: travelled,
+            segment,
+            t: if points.len() > 1 { 1.0 } else { 0.0 },
+        });
+    }
+    samples
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resampling_spaces_points_evenly_across_edges() {
+        let points = [
+            Vec3::new(0.0, 0.0, 0.0),
+            Vec3::new(3.0, 0.0, 0.0),
+            Vec3::new(3.0, 0.0, 0.0),
+            Vec3::new(3.0, 2.5, 0.0),
+        ];
+        let samples = resample_polyline(&points, 1.0);
+        let summary: Vec<(f64, usize, f64)> =
+            samples.iter().map(|sample| (sample.distance, sample.segment, sample.t)).collect();
+        let third = 1.0 / 3.0;
+        assert_eq!(
+            summary,
+            [(0.0, 0, 0.0), (1.0, 0, third), (2.0, 0, 2.0 * third), (3.0, 0, 1.0), (4.0, 2, 0.4), (5.0, 2, 0.8), (5.5, 2, 1.0)]
+        );
+        assert_eq!(samples[4].point, Vec3::new(3.0, 1.0, 0.0));
+
+        let feeds = [100.0, 200.0, 200.0, 400.0];
+        let feed = samples[5].interpolate(&feeds, |from, to, t| from + (to - from) * t);
+        assert!((feed - 360.0).abs() < 1e-9);
+
+        assert_eq!(resample_polyline(&points[..1], 1.0).len(), 1);
+        assert!(resample_polyline(&points, 0.0).is_empty());
+    }
+}
@@ -1,10 +1,22 @@
+use std::ops::{Range, RangeInclusive};
 use std::path::PathBuf;
 
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
 use ratatui::layout::Rect;
 
 use crate::config::{Action, Config};
-use cnc_geom::{project_point, Bounds2, Bounds3, ProjectionMode, ProjectionParams, Vec2, Vec3, ViewAngles};
-use cnc_gcode::Toolpath;
+use crate::cutting::CuttingConditions;
+use crate::filter::SegmentFilter;
+use crate::graphics::GraphicsProtocol;
+use crate::linemap::map_lines;
+use crate::palette::{parse_command, Command, CommandPalette, OriginCommand, Region, SeekTarget};
+use crate::timeline::TimelineState;
+use cnc_geom::{convex_hull, machine_point, project_point, Bounds2, Bounds3, ProjectionMode, ProjectionParams, RotaryAngles, Vec2, Vec3, ViewAngles};
+use cnc_gcode::{
+    analyze_cut_direction, feed_limit_zones, line_durations, low_rapids, pause_times, restart_preamble, safe_planes, segment_durations, sharp_corners,
+    split_programs, trace_deviations, Corner, CutDirection, DepthMap, Diagnostic, FeedLimitZone, MoveKind, ParseOptions, ProfileLoop, Program, SafePlane, Severity, SpatialIndex,
+    TimeEstimateOptions, Toolpath, Units,
+};
 use std::time::Duration;
 
 #[derive(Debug, Clone)]
@@ -14,6 +26,86 @@ pub struct ViewState {
     pub yaw: f64,
     pub pitch: f64,
     pub projection: ProjectionMode,
+    /// Z exaggeration factor for shallow programs.
+    pub z_scale: f64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorMode {
+    Kind,
+    CutDirection,
+    AirCut,
+    FeedLimit,
+}
+
+impl ColorMode {
+    pub fn next(self) -> Self {
+        match self {
+            ColorMode::Kind => ColorMode::CutDirection,
+            ColorMode::CutDirection => ColorMode::AirCut,
+            ColorMode::AirCut => ColorMode::FeedLimit,
+            ColorMode::FeedLimit => ColorMode::Kind,
+        }
+    }
+
+    /// `kind`, `direction`, `air` or `feed`, as given to `--color`.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "kind" => Some(ColorMode::Kind),
+            "direction" => Some(ColorMode::CutDirection),
+            "air" => Some(ColorMode::AirCut),
+            "feed" => Some(ColorMode::FeedLimit),
+            _ => None,
+        }
+    }
+}
+
+/// Feed moves that remove no stock, with their total estimated time and length.
+#[derive(Debug, Clone, Default)]
+pub struct AirCuts {
+    pub mask: Vec<bool>,
+    pub count: usize,
+    pub time: f64,
+    pub distance: f64,
+}
+
+/// The toolpath before a reload that changed it, drawn faintly until it expires or is
+/// dismissed.
+#[derive(Debug, Clone)]
+pub struct Ghost {
+    pub segments: Vec<cnc_gcode::LineSegment>,
+    pub remaining: Duration,
+}
+
+/// Machine-reported positions and their distance from the programmed path.
+#[derive(Debug, Clone, Default)]
+pub struct PositionTrace {
+    pub points: Vec<Vec3>,
+    /// Per point; `None` when no programmed move is within [`TRACE_SEARCH`].
+    pub deviations: Vec<Option<f64>>,
+}
+
+impl PositionTrace {
+    pub fn is_off_path(&self, index: usize, tolerance: f64) -> bool {
+        self.deviations[index].is_none_or(|deviation| deviation > tolerance)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DepthGraph {
+    Off,
+    Depth,
+    DepthFeed,
+}
+
+impl DepthGraph {
+    pub fn next(self) -> Self {
+        match self {
+            DepthGraph::Off => DepthGraph::Depth,
+            DepthGraph::Depth => DepthGraph::DepthFeed,
+            DepthGraph::DepthFeed => DepthGraph::Off,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -36,13 +128,98 @@ pub struct App {
     pub file_panel: FilePanelState,
     pub playback: PlaybackState,
     pub show_help: bool,
+    pub help_query: String,
     pub canvas_marker: ratatui::symbols::Marker,
+    pub graphics: Option<GraphicsProtocol>,
+    pub canvas_area: Option<Rect>,
+    pub color_mode: ColorMode,
+    /// Air-cut classification; `None` without a `[stock]` block.
+    pub air_cuts: Option<AirCuts>,
+    /// Feed moves that cannot reach their programmed feed; empty without a machine profile.
+    pub feed_limits: Vec<FeedLimitZone>,
+    /// Direction changes of at least `ui.corner_angle` between feed moves.
+    pub corners: Vec<Corner>,
+    pub show_corners: bool,
+    /// Later passes of loops and repeated subprogram calls, merged and in segment order.
+    pub later_passes: Vec<Range<usize>>,
+    /// Draw only the first pass of each loop.
+    pub first_pass_only: bool,
+    /// Rapids moving in XY below `ui.rapid_clearance`, in segment order.
+    pub low_rapids: Vec<usize>,
+    pub ghost: Option<Ghost>,
+    /// Climb/conventional loops, found the first time that color mode is selected.
+    pub profiles: Option<Vec<ProfileLoop>>,
+    pub safe_planes: Vec<SafePlane>,
+    /// XY convex hull of the feed moves, when `ui.show_footprint` is on.
+    pub footprint: Vec<Vec2>,
+    /// `Toolpath::geometry_hash` of the loaded toolpath.
+    pub geometry_hash: u64,
+    pub machine_warnings: Vec<String>,
+    /// Segments leaving the active machine's envelope; 0 without a machine or envelope.
+    pub outside_envelope: usize,
+    pub estimated_time: f64,
+    /// Estimated seconds spent on each source line, including every pass of its loops.
+    pub line_times: Vec<f64>,
+    /// Cumulative estimated time in seconds at the end of each segment.
+    pub segment_end_times: Vec<f64>,
+    /// Dwell and tool change seconds included at the start of each segment's time.
+    pub segment_pauses: Vec<f64>,
+    /// Cumulative path length at the end of each segment.
+    pub segment_end_distances: Vec<f64>,
+    pub depth_graph: DepthGraph,
+    /// Show the min Z map in place of the toolpath view.
+    pub show_depth_map: bool,
+    /// Min Z map and the inner area size it was rasterized for.
+    pub depth_map: Option<((u16, u16), DepthMap)>,
+    pub operations: OperationsPanel,
+    pub programs: ProgramPicker,
+    pub output: OutputPanel,
+    pub timeline: TimelineState,
+    pub restart: Option<RestartBlock>,
+    /// Pre-flight summary popup opened once after loading.
+    pub summary: Option<Vec<String>>,
+    /// Draw the whole program dimmed, with the selected lines bright and thick.
+    pub focus_selection: bool,
+    pub view_history: ViewHistory,
+    pub auto_fit: AutoFit,
+    pub palette: CommandPalette,
+    pub filter: Option<SegmentFilter>,
+    /// Per-segment result of `filter`; empty when no filter is set.
+    pub filter_mask: Vec<bool>,
+    /// Program coordinates shown as X0 Y0 Z0 in readouts and where the axes are drawn.
+    pub display_origin: Vec3,
+    /// Grid spacing in mm set with `grid`; automatic when `None`.
+    pub grid_spacing: Option<f64>,
+    /// Segments entirely above this program Z are hidden.
+    pub clip_z: Option<f64>,
+    pub display_units: Units,
+    /// Readouts in machine coordinates instead of the line's work coordinates.
+    pub machine_coordinates: bool,
+    /// Draw A rotation laid out flat along Y, at `ui.wrap_diameter`.
+    pub unwrap_rotary: bool,
+    /// Built on the first `where` query.
+    pub spatial_index: Option<SpatialIndex>,
+    /// Source lines found by the last `where` query, stepped through with `n`.
+    pub matches: Vec<usize>,
+    /// One-shot message for the info strip, cleared by the next key.
+    pub notice: Option<String>,
+    /// Text waiting for the event loop to put on the clipboard.
+    pub clipboard: Option<String>,
+    /// Recorded machine positions overlaid on the programmed path.
+    pub trace: Option<PositionTrace>,
+    /// Index into `toolpath.stops` of the stop playback is paused at.
+    pub stopped_at: Option<usize>,
+    /// The stop playback was resumed from, so it is not paused at again.
+    resumed_stop: Option<usize>,
+    /// Playback position and the machine time it was reached at, so time-based playback can
+    /// hold through a dwell.
+    playback_clock: Option<(f64, f64)>,
 }
 
 impl App {
     pub fn new(
         config: Config,
-        toolpath: Toolpath,
+        mut toolpath: Toolpath,
         file_path: PathBuf,
         file_lines: Vec<String>,
     ) -> Self {
@@ -52,10 +229,64 @@ impl App {
             yaw: config.projection.yaw_deg.to_radians(),
             pitch: config.projection.pitch_deg.to_radians(),
             projection: config.projection.mode,
+            z_scale: config.projection.z_scale,
         };
         let canvas_marker = config.ui.canvas_marker;
+        let display_units = config.ui.display_units;
+        let graphics = crate::graphics::resolve(config.ui.renderer);
         let playback = PlaybackState::new(config.animation.speed_segments_per_sec);
         let file_panel = FilePanelState::new(file_lines.len());
+        let outside_envelope = segments_outside_envelope(&config, &toolpath);
+        let machine_warnings = machine_limit_warnings(&config, &toolpath, outside_envelope);
+        let segment_end_times: Vec<f64> = segment_durations(&toolpath, &time_options(&config))
+            .into_iter()
+            .scan(0.0, |total, duration| {
+                *total += duration;
+                Some(*total)
+            })
+            .collect();
+        let segment_end_distances: Vec<f64> = toolpath
+            .segments
+            .iter()
+            .scan(0.0, |total, seg| {
+                *total += (seg.end - seg.start).length();
+                Some(*total)
+            })
+            .collect();
+        let segment_pauses = pause_times(&toolpath, &time_options(&config));
+        let line_times = line_durations(&toolpath, &time_options(&config));
+        let estimated_time = segment_end_times.last().copied().unwrap_or(0.0);
+        let air_cuts = config
+            .stock
+            .bounds
+            .map(|stock| find_air_cuts(&config, &toolpath, stock, &segment_end_times, &segment_end_distances));
+        let feed_limits = feed_limit_zones(&toolpath, &time_options(&config), FEED_LIMIT_RATIO);
+        let corners = sharp_corners(&toolpath, config.ui.corner_angle);
+        let later_passes = later_passes(&toolpath);
+        let low_rapids = match config.ui.rapid_clearance {
+            Some(clearance) => flag_low_rapids(&mut toolpath, clearance),
+            None => Vec::new(),
+        };
+        let safe_planes = if config.ui.show_safe_planes {
+            safe_planes(&toolpath, config.ui.safe_plane_min_count)
+        } else {
+            Vec::new()
+        };
+        let footprint = if config.ui.show_footprint {
+            let cuts = toolpath.segments.iter().filter(|seg| seg.kind == MoveKind::Feed);
+            let points: Vec<Vec2> = cuts.flat_map(|seg| [seg.start, seg.end]).map(|p| Vec2::new(p.x, p.y)).collect();
+            convex_hull(&points)
+        } else {
+            Vec::new()
+        };
+        let geometry_hash = toolpath.geometry_hash();
+        let operation_count = toolpath.operations.len();
+        let programs = ProgramPicker::new(split_programs(&file_lines));
+        let timeline = TimelineState::new(&toolpath, &segment_end_times);
+        let auto_fit = AutoFit {
+            enabled: config.ui.auto_fit,
+            ..AutoFit::default()
+        };
         Self {
             config,
             toolpath,
@@ -67,20 +298,506 @@ impl App {
             file_panel,
             playback,
             show_help: false,
+            help_query: String::new(),
             canvas_marker,
+            graphics,
+            canvas_area: None,
+            color_mode: ColorMode::Kind,
+            air_cuts,
+            feed_limits,
+            corners,
+            show_corners: false,
+            later_passes,
+            first_pass_only: false,
+            low_rapids,
+            ghost: None,
+            profiles: None,
+            safe_planes,
+            footprint,
+            geometry_hash,
+            machine_warnings,
+            outside_envelope,
+            estimated_time,
+            line_times,
+            segment_end_times,
+            segment_pauses,
+            segment_end_distances,
+            depth_graph: DepthGraph::Off,
+            show_depth_map: false,
+            depth_map: None,
+            operations: OperationsPanel::new(operation_count),
+            programs,
+            output: OutputPanel::default(),
+            timeline,
+            restart: None,
+            summary: None,
+            focus_selection: false,
+            view_history: ViewHistory::default(),
+            auto_fit,
+            palette: CommandPalette::default(),
+            filter: None,
+            filter_mask: Vec::new(),
+            display_origin: Vec3::default(),
+            grid_spacing: None,
+            clip_z: None,
+            display_units,
+            machine_coordinates: false,
+            unwrap_rotary: false,
+            spatial_index: None,
+            matches: Vec::new(),
+            notice: None,
+            clipboard: None,
+            trace: None,
+            stopped_at: None,
+            resumed_stop: None,
+            playback_clock: None,
+        }
+    }
+
+    /// Re-reads and re-parses the file, keeping the camera when the geometry is unchanged.
+    pub fn reload(&mut self) {
+        let previous = self.toolpath.segments.clone();
+        self.notice = Some(match self.reparse(self.programs.active) {
+            Ok(true) => "reloaded (toolpath unchanged)".to_string(),
+            Ok(false) => {
+                let seconds = self.config.ui.ghost_seconds;
+                self.ghost = (seconds > 0.0).then(|| Ghost {
+                    segments: previous,
+                    remaining: Duration::from_secs_f64(seconds),
+                });
+                "reloaded".to_string()
+            }
+            Err(err) => format!("reload failed: {:#}", err),
+        });
+    }
+
+    /// Share of the ghost's time left, in tenths so redraws stay coarse.
+    pub fn ghost_fade(&self) -> Option<f64> {
+        let ghost = self.ghost.as_ref()?;
+        let left = ghost.remaining.as_secs_f64() / self.config.ui.ghost_seconds;
+        Some((left.min(1.0) * 10.0).ceil() / 10.0)
+    }
+
+    /// Re-parses the file limited to `program` and swaps it in; returns whether the geometry
+    /// is unchanged.
+    fn reparse(&mut self, program: Option<usize>) -> anyhow::Result<bool> {
+        let content = std::fs::read(&self.file_path)?;
+        let lines = program_lines(&content);
+        let mut programs = ProgramPicker::new(split_programs(&lines));
+        if let Some(index) = program.filter(|&index| index < programs.programs.len()) {
+            programs.active = Some(index);
+        }
+        programs.cursor = programs.active.unwrap_or(0);
+        programs.open = self.programs.open && programs.programs.len() > 1;
+        let unwrap = self.config.ui.wrap_diameter.filter(|_| self.unwrap_rotary);
+        let options = programs.parse_options(self.config.parse_options()).with_unwrap_diameter(unwrap);
+        let toolpath = cnc_gcode::parse_bytes(&content, options)?;
+        let mut next = App::new(self.config.clone(), toolpath, self.file_path.clone(), lines);
+        next.programs = programs;
+        let unchanged = next.geometry_hash == self.geometry_hash;
+        if unchanged {
+            next.view = self.view.clone();
+            next.initial_view = self.initial_view.clone();
+            next.view_history = std::mem::take(&mut self.view_history);
+        }
+        let total = next.file_lines.len();
+        let line_map = map_lines(&self.file_lines, &next.file_lines);
+        let map = |line: usize| line_map.get(line).copied().unwrap_or(line).min(total.saturating_sub(1));
+        next.file_panel.focus = self.file_panel.focus;
+        next.file_panel.visual = self.file_panel.visual;
+        next.file_panel.selected = map(self.file_panel.selected);
+        next.file_panel.anchor = map(self.file_panel.anchor);
+        next.file_panel.scroll =
+            (self.file_panel.scroll + next.file_panel.selected).saturating_sub(self.file_panel.selected);
+        next.file_panel.view_height = self.file_panel.view_height;
+        if self.playback.active {
+            next.playback.position = self.mapped_playback_position(&next, &line_map);
+            next.playback.active = true;
+            next.playback.playing = self.playback.playing;
+        }
+        next.output = std::mem::take(&mut self.output);
+        next.canvas_marker = self.canvas_marker;
+        next.set_color_mode(self.color_mode);
+        next.show_corners = self.show_corners;
+        next.first_pass_only = self.first_pass_only;
+        next.depth_graph = self.depth_graph;
+        next.show_depth_map = self.show_depth_map;
+        next.focus_selection = self.focus_selection;
+        next.auto_fit.enabled = self.auto_fit.enabled;
+        next.set_filter(self.filter.take());
+        next.display_origin = self.display_origin;
+        next.grid_spacing = self.grid_spacing;
+        next.clip_z = self.clip_z;
+        next.display_units = self.display_units;
+        next.machine_coordinates = self.machine_coordinates;
+        next.unwrap_rotary = self.unwrap_rotary;
+        if let Some(trace) = self.trace.take() {
+            next.load_trace(trace.points);
+        }
+        next.auto_fit.selection = next.auto_fit.enabled.then(|| next.selection_segment_range());
+        *self = next;
+        Ok(unchanged)
+    }
+
+    /// The playback position at the same move of the same source line in `next`.
+    fn mapped_playback_position(&self, next: &App, line_map: &[usize]) -> f64 {
+        let position = self.playback.position;
+        let Some(line) = self.segment_line(position.floor() as usize) else {
+            return next.toolpath.segments.len() as f64;
+        };
+        let line_start = |app: &App, line: usize| {
+            line.checked_sub(1)
+                .and_then(|prev| app.toolpath.line_segment_ends.get(prev).copied())
+                .unwrap_or(0)
+        };
+        let new_line = line_map.get(line).copied().unwrap_or(line);
+        let start = line_start(next, new_line);
+        let end = next.toolpath.line_segment_ends.get(new_line).copied().unwrap_or(start);
+        (start as f64 + position - line_start(self, line) as f64).min(end as f64)
+    }
+
+    /// Routes typing into the help filter; returns false for keys the bindings should handle.
+    pub fn handle_help_key(&mut self, key: KeyEvent) -> bool {
+        if !self.show_help || self.config.keys.toggle_help.matches(key) {
+            return false;
+        }
+        match key.code {
+            KeyCode::Esc => {
+                self.show_help = false;
+                self.help_query.clear();
+            }
+            KeyCode::Backspace => {
+                self.help_query.pop();
+            }
+            KeyCode::Char(ch) if (key.modifiers - KeyModifiers::SHIFT).is_empty() => {
+                self.help_query.push(ch);
+            }
+            _ => return false,
+        }
+        true
+    }
+
+    /// Edits and runs the command palette while it is open; returns false when it is closed.
+    pub fn handle_palette_key(&mut self, key: KeyEvent) -> bool {
+        if !self.palette.open {
+            return false;
+        }
+        match key.code {
+            KeyCode::Esc => {
+                self.palette.open = false;
+                self.palette.input.clear();
+            }
+            KeyCode::Enter => {
+                self.palette.open = false;
+                let input = std::mem::take(&mut self.palette.input);
+                match parse_command(&input) {
+                    Ok(command) => self.run_command(command),
+                    Err(err) => self.notice = Some(format!("{:#}", err)),
+                }
+            }
+            KeyCode::Backspace => {
+                self.palette.input.pop();
+            }
+            KeyCode::Char(ch) if (key.modifiers - KeyModifiers::SHIFT).is_empty() => {
+                self.palette.input.push(ch);
+            }
+            _ => {}
         }
+        true
+    }
+
+    pub fn run_command(&mut self, command: Command) {
+        match command {
+            Command::Filter(filter) => {
+                self.set_filter(filter);
+                self.notice = Some(match &self.filter {
+                    Some(filter) => {
+                        let shown = self.filter_mask.iter().filter(|&&shown| shown).count();
+                        format!("filter {}: {} of {} segments", filter.source, shown, self.filter_mask.len())
+                    }
+                    None => "filter cleared".to_string(),
+                });
+            }
+            Command::Origin(origin) => self.set_origin(origin),
+            Command::Where(region) => self.find_region(region),
+            Command::View { yaw, pitch } => {
+                self.view_history.checkpoint(&self.view);
+                self.auto_fit.target = None;
+                if let Some(yaw) = yaw {
+                    self.view.yaw = yaw.to_radians();
+                }
+                if let Some(pitch) = pitch {
+                    self.view.pitch = pitch.to_radians();
+                }
+                self.notice = Some(format!(
+                    "view yaw {:.1}° pitch {:.1}°",
+                    self.view.yaw.to_degrees(),
+                    self.view.pitch.to_degrees()
+                ));
+            }
+            Command::Zoom(factor) => {
+                self.view_history.checkpoint(&self.view);
+                self.auto_fit.target = None;
+                self.view.zoom = factor.max(0.05);
+                self.notice = Some(format!("zoom {:.2}×", self.view.zoom));
+            }
+            Command::Grid(spacing) => {
+                let spacing = spacing.map(|step| step * crate::units::scale(self.display_units));
+                let size = self.toolpath.bounds.size();
+                if spacing.is_some_and(|step| size.x.max(size.z) / step > MAX_GRID_LINES as f64) {
+                    self.notice = Some(format!("grid: more than {} lines, spacing unchanged", MAX_GRID_LINES));
+                    return;
+                }
+                self.grid_spacing = spacing;
+                self.notice = Some(match spacing {
+                    Some(step) => format!(
+                        "grid every {} {}",
+                        crate::units::format_length(self.display_units, step),
+                        crate::units::label(self.display_units)
+                    ),
+                    None => "grid spacing automatic".to_string(),
+                });
+            }
+            Command::Clip(z) => {
+                let origin = self.readout_origin(self.file_panel.selected).z;
+                self.clip_z = z.map(|z| z * crate::units::scale(self.display_units) + origin);
+                self.notice = Some(match self.clip_z {
+                    Some(z) => format!(
+                        "hiding moves above Z{}",
+                        crate::units::format_length(self.display_units, z - origin)
+                    ),
+                    None => "clip cleared".to_string(),
+                });
+            }
+            Command::Seek(target) => self.seek(target),
+        }
+    }
+
+    /// Pauses playback with the playhead at `target`.
+    fn seek(&mut self, target: SeekTarget) {
+        let total = self.toolpath.segments.len();
+        let position = match target {
+            SeekTarget::Segment(count) => count.min(total),
+            SeekTarget::Fraction(share) => (share * total as f64).round() as usize,
+            SeekTarget::Time(seconds) => self.segment_end_times.partition_point(|&end| end <= seconds),
+        };
+        self.playback.active = true;
+        self.playback.playing = false;
+        self.playback.position = position as f64;
+        self.stopped_at = None;
+        self.resumed_stop = None;
+        self.playback_clock = None;
+        let time = position
+            .checked_sub(1)
+            .and_then(|last| self.segment_end_times.get(last))
+            .copied()
+            .unwrap_or(0.0);
+        self.notice = Some(format!(
+            "playhead at segment {} of {}, {}",
+            position,
+            total,
+            crate::render::format_duration(time)
+        ));
+    }
+
+    fn set_origin(&mut self, command: OriginCommand) {
+        match command {
+            OriginCommand::Highlighted { with_z } => {
+                let Some(index) = self.highlighted_segment() else {
+                    self.notice = Some("origin: no highlighted point".to_string());
+                    return;
+                };
+                let point = self.toolpath.segments[index].end;
+                self.display_origin.x = point.x;
+                self.display_origin.y = point.y;
+                if with_z {
+                    self.display_origin.z = point.z;
+                }
+            }
+            OriginCommand::At { x, y, z } => {
+                let scale = crate::units::scale(self.display_units);
+                let origin = &mut self.display_origin;
+                origin.x = x.map_or(origin.x, |x| x * scale);
+                origin.y = y.map_or(origin.y, |y| y * scale);
+                origin.z = z.map_or(origin.z, |z| z * scale);
+            }
+            OriginCommand::Reset => self.display_origin = Vec3::default(),
+        }
+        let origin = self.display_origin;
+        let length = |mm| crate::units::format_length(self.display_units, mm);
+        self.notice = Some(format!(
+            "origin at program X{} Y{} Z{}",
+            length(origin.x),
+            length(origin.y),
+            length(origin.z)
+        ));
+    }
+
+    /// Work origin of the first move, where recorded work positions are placed.
+    pub fn trace_work_origin(&self) -> Vec3 {
+        let line = self.segment_line(0).unwrap_or(0);
+        self.toolpath.line_states.get(line).map_or(Vec3::default(), |state| state.work_origin)
+    }
+
+    /// Overlays `points` and measures each against the programmed path.
+    pub fn load_trace(&mut self, points: Vec<Vec3>) {
+        let toolpath = &self.toolpath;
+        let index = self.spatial_index.get_or_insert_with(|| SpatialIndex::new(toolpath));
+        let deviations = trace_deviations(toolpath, index, &points, TRACE_SEARCH);
+        self.trace = Some(PositionTrace { points, deviations });
+    }
+
+    pub fn open_load_summary(&mut self) {
+        self.summary = Some(crate::report::load_summary_lines(self));
+    }
+
+    /// Collects the source lines whose moves pass through `region`, typed in display units
+    /// relative to the readout origin at the cursor, and jumps to the first.
+    fn find_region(&mut self, region: Region) {
+        let scale = crate::units::scale(self.display_units);
+        let origin = self.readout_origin(self.file_panel.selected);
+        let program = |point: Vec2| Vec2::new(point.x * scale + origin.x, point.y * scale + origin.y);
+        let toolpath = &self.toolpath;
+        let index = self.spatial_index.get_or_insert_with(|| SpatialIndex::new(toolpath));
+        let segments = match region {
+            Region::Near { point, radius } => index.segments_near(program(point), radius * scale),
+            Region::Rect(a, b) => index.segments_in_rect(program(a), program(b)),
+        };
+        let mut lines: Vec<usize> = segments
+            .into_iter()
+            .filter_map(|segment| self.segment_line(segment))
+            .collect();
+        lines.dedup();
+        self.matches = lines;
+        let Some(&first) = self.matches.first() else {
+            self.notice = Some("where: no moves pass through there".to_string());
+            return;
+        };
+        self.jump_to_line(first);
+        let listed: Vec<String> = self
+            .matches
+            .iter()
+            .take(WHERE_LISTED)
+            .map(|line| (line + 1).to_string())
+            .collect();
+        let more = if self.matches.len() > WHERE_LISTED { " …" } else { "" };
+        self.notice = Some(format!(
+            "where: {} lines: {}{} (n for next)",
+            self.matches.len(),
+            listed.join(" "),
+            more
+        ));
+    }
+
+    fn next_match(&mut self) {
+        let selected = self.file_panel.selected;
+        let next = self
+            .matches
+            .iter()
+            .position(|&line| line > selected)
+            .unwrap_or(0);
+        let Some(&line) = self.matches.get(next) else {
+            self.notice = Some("no matches; run :where first".to_string());
+            return;
+        };
+        self.jump_to_line(line);
+        self.notice = Some(format!("match {}/{}: line {}", next + 1, self.matches.len(), line + 1));
+    }
+
+    /// Selects 0-based `lines` in the file panel, as a visual range when it spans several.
+    pub fn select_lines(&mut self, lines: RangeInclusive<usize>) {
+        let last = self.file_lines.len().saturating_sub(1);
+        self.file_panel.anchor = (*lines.start()).min(last);
+        self.file_panel.selected = (*lines.end()).min(last);
+        self.file_panel.visual = self.file_panel.anchor != self.file_panel.selected;
+        self.file_panel.ensure_visible();
+    }
+
+    fn jump_to_line(&mut self, line: usize) {
+        self.file_panel.focus = PanelFocus::File;
+        self.file_panel.visual = false;
+        self.file_panel.selected = line;
+        self.file_panel.ensure_visible();
+    }
+
+    /// `point`, reached on source `line`, for coordinate readouts: machine coordinates, or
+    /// relative to the display origin when one is set, else to the line's work origin.
+    pub fn display_point(&self, point: Vec3, line: usize) -> Vec3 {
+        point - self.readout_origin(line)
+    }
+
+    /// Programmed position at the end of segment `index`. Segments with the table turned are
+    /// drawn around the part, so theirs is the line's position, or the drawn point turned back.
+    pub fn machine_position(&self, index: usize) -> Vec3 {
+        let seg = &self.toolpath.segments[index];
+        if seg.rotary == RotaryAngles::default() {
+            return seg.end;
+        }
+        if let Some(line) = self.segment_line(index)
+            && self.toolpath.line_segment_ends[line] == index + 1
+        {
+            return self.toolpath.line_states[line].position;
+        }
+        match self.config.ui.wrap_diameter.filter(|_| self.unwrap_rotary) {
+            Some(diameter) => {
+                let shift = Vec3::new(0.0, seg.rotary.a.to_radians() * diameter / 2.0, 0.0);
+                machine_point(seg.end - shift, RotaryAngles { a: 0.0, ..seg.rotary })
+            }
+            None => machine_point(seg.end, seg.rotary),
+        }
+    }
+
+    fn readout_origin(&self, line: usize) -> Vec3 {
+        let state = self.toolpath.line_states.get(line);
+        if self.machine_coordinates {
+            // The path stays at the tool tip; the machine position is up by the tool length.
+            Vec3::new(0.0, 0.0, -state.and_then(|state| state.tool_length).map_or(0.0, |offset| offset.shift))
+        } else if self.display_origin != Vec3::default() {
+            self.display_origin
+        } else {
+            state.map_or(Vec3::default(), |state| state.work_origin)
+        }
+    }
+
+    fn set_filter(&mut self, filter: Option<SegmentFilter>) {
+        self.filter_mask = filter
+            .as_ref()
+            .map(|filter| filter.mask(&self.toolpath))
+            .unwrap_or_default();
+        self.filter = filter;
     }
 
     pub fn apply_action(&mut self, action: Action) {
+        self.notice = None;
         if self.show_help {
             match action {
                 Action::ToggleHelp | Action::Quit => {
                     self.show_help = false;
+                    self.help_query.clear();
                 }
                 _ => {}
             }
             return;
         }
+        if self.restart.take().is_some() || self.summary.take().is_some() {
+            return;
+        }
+        if self.operations.open && self.apply_operations_action(action) {
+            return;
+        }
+        if self.timeline.open && self.apply_timeline_action(action) {
+            return;
+        }
+        if self.programs.open && self.apply_programs_action(action) {
+            return;
+        }
+        if self.output.open && self.apply_output_action(action) {
+            return;
+        }
+        self.view_history.record(action, &self.view);
+        if changes_view(action) || matches!(action, Action::UndoView | Action::RedoView) {
+            self.auto_fit.target = None;
+        }
         match action {
             Action::PanLeft => self.apply_view_pan(-1.0, 0.0),
             Action::PanRight => self.apply_view_pan(1.0, 0.0),
@@ -92,6 +809,12 @@ impl App {
             Action::RotateRight => self.view.yaw += 5.0_f64.to_radians(),
             Action::RotateUp => self.view.pitch += 5.0_f64.to_radians(),
             Action::RotateDown => self.view.pitch -= 5.0_f64.to_radians(),
+            Action::ZScaleUp => self.view.z_scale = (self.view.z_scale * 2.0).min(Z_SCALE_MAX),
+            Action::ZScaleDown => self.view.z_scale = (self.view.z_scale / 2.0).max(1.0 / Z_SCALE_MAX),
+            Action::SnapRotateLeft => self.view.yaw = snap_angle(self.view.yaw, -1.0),
+            Action::SnapRotateRight => self.view.yaw = snap_angle(self.view.yaw, 1.0),
+            Action::SnapRotateUp => self.view.pitch = snap_angle(self.view.pitch, 1.0),
+            Action::SnapRotateDown => self.view.pitch = snap_angle(self.view.pitch, -1.0),
             Action::Fit => {
                 self.view.pan = Vec2::new(0.0, 0.0);
                 self.view.zoom = 1.0;
@@ -99,6 +822,16 @@ impl App {
             Action::ResetView => {
                 self.view = self.initial_view.clone();
             }
+            Action::UndoView => {
+                if let Some(view) = self.view_history.undo.pop() {
+                    self.view_history.redo.push(std::mem::replace(&mut self.view, view));
+                }
+            }
+            Action::RedoView => {
+                if let Some(view) = self.view_history.redo.pop() {
+                    self.view_history.undo.push(std::mem::replace(&mut self.view, view));
+                }
+            }
             Action::ToggleProjection => {
                 self.view.projection = match self.view.projection {
                     ProjectionMode::Orthographic => ProjectionMode::Perspective,
@@ -108,6 +841,15 @@ impl App {
             Action::TogglePlayback => {
                 let total = self.toolpath.segments.len();
                 self.playback.toggle(total);
+                self.resumed_stop = self.stopped_at.take();
+            }
+            Action::DismissGhost => self.ghost = None,
+            Action::PlayFromSelection => {
+                self.playback.position = self.selection_segment_range().0 as f64;
+                self.playback.active = true;
+                self.playback.playing = true;
+                self.stopped_at = None;
+                self.resumed_stop = None;
             }
             Action::ToggleHelp => {
                 self.show_help = !self.show_help;
@@ -123,7 +865,75 @@ impl App {
                     _ => ratatui::symbols::Marker::Braille,
                 };
             }
+            Action::CycleColorMode => {
+                let mut mode = self.color_mode.next();
+                while !self.color_mode_available(mode) {
+                    mode = mode.next();
+                }
+                self.set_color_mode(mode);
+            }
+            Action::CycleDepthGraph => self.depth_graph = self.depth_graph.next(),
+            Action::ToggleDepthMap => self.show_depth_map = !self.show_depth_map,
+            Action::ToggleTimeline => {
+                if !self.timeline.blocks.is_empty() {
+                    self.timeline.open = true;
+                    self.jump_to_timeline_block(self.timeline.cursor);
+                }
+            }
+            Action::RestartBlock => self.generate_restart_block(),
+            Action::ToggleFocusSelection => self.focus_selection = !self.focus_selection,
+            Action::ToggleCorners => self.show_corners = !self.show_corners,
+            Action::ToggleFirstPass => self.first_pass_only = !self.first_pass_only,
+            Action::OpenPalette => self.palette.open = true,
+            Action::ToggleDisplayUnits => {
+                self.display_units = crate::units::toggle(self.display_units);
+                self.notice = Some(format!("display units {}", crate::units::label(self.display_units)));
+            }
+            Action::ToggleMachineCoordinates => {
+                self.machine_coordinates = !self.machine_coordinates;
+                let frame = if self.machine_coordinates { "machine" } else { "work" };
+                self.notice = Some(format!("readouts in {} coordinates", frame));
+            }
+            Action::ToggleUnwrap => self.toggle_unwrap(),
+            Action::ToggleAutoFit => {
+                self.auto_fit.enabled = !self.auto_fit.enabled;
+                self.auto_fit.selection = None;
+            }
+            Action::ToggleOperations => {
+                if !self.toolpath.operations.is_empty() {
+                    self.operations.open = true;
+                    self.select_operation(self.operations.cursor);
+                }
+            }
+            Action::TogglePrograms => self.programs.open = self.programs.programs.len() > 1,
             Action::ToggleFocus => self.file_panel.toggle_focus(),
+            Action::NextMatch => self.next_match(),
+            Action::CopyLines => {
+                let (start, end) = self.file_panel.selection_range(self.file_lines.len());
+                if let Some(lines) = self.file_lines.get(start..=end) {
+                    self.notice = Some(format!("copied {} line(s)", lines.len()));
+                    self.clipboard = Some(lines.join("\n"));
+                }
+            }
+            Action::CopyPoint => {
+                let Some(index) = self.highlighted_segment() else {
+                    self.notice = Some("copy: no highlighted point".to_string());
+                    return;
+                };
+                let line = self.segment_line(index).unwrap_or(0);
+                let point = self.display_point(self.machine_position(index), line);
+                let length = |mm| crate::units::format_length(self.display_units, mm);
+                let text = format!("X{} Y{} Z{}", length(point.x), length(point.y), length(point.z));
+                self.notice = Some(format!("copied {}", text));
+                self.clipboard = Some(text);
+            }
+            Action::ExportReport => {
+                let path = self.file_path.with_extension("report.md");
+                self.notice = Some(match crate::report::write_review_report(self, &path) {
+                    Ok(()) => format!("report: wrote {}", path.display()),
+                    Err(err) => format!("report: {:#}", err),
+                });
+            }
             Action::LineUp => {
                 if self.file_panel.focus == PanelFocus::File {
                     self.file_panel.move_selection(-1, self.file_lines.len());
@@ -148,6 +958,229 @@ impl App {
         }
     }
 
+    fn apply_operations_action(&mut self, action: Action) -> bool {
+        let count = self.toolpath.operations.len();
+        let step = match action {
+            Action::ToggleOperations => {
+                self.operations.open = false;
+                return true;
+            }
+            Action::TogglePlayback => {
+                let hidden = &mut self.operations.hidden[self.operations.cursor];
+                *hidden = !*hidden;
+                return true;
+            }
+            Action::LineUp => -1,
+            Action::LineDown => 1,
+            Action::PageUp => -(self.file_panel.view_height.max(1) as isize),
+            Action::PageDown => self.file_panel.view_height.max(1) as isize,
+            _ => return false,
+        };
+        let cursor = (self.operations.cursor as isize + step).clamp(0, count as isize - 1);
+        self.select_operation(cursor as usize);
+        true
+    }
+
+    fn apply_programs_action(&mut self, action: Action) -> bool {
+        let count = self.programs.programs.len();
+        let step = match action {
+            Action::TogglePrograms => {
+                self.programs.open = false;
+                return true;
+            }
+            Action::TogglePlayback => {
+                self.select_program(self.programs.cursor);
+                return true;
+            }
+            Action::LineUp => -1,
+            Action::LineDown => 1,
+            _ => return false,
+        };
+        self.programs.cursor = (self.programs.cursor as isize + step).clamp(0, count as isize - 1) as usize;
+        true
+    }
+
+    fn apply_output_action(&mut self, action: Action) -> bool {
+        let count = self.output.lines.len().max(1);
+        let step = match action {
+            Action::DismissGhost => {
+                self.output.open = false;
+                return true;
+            }
+            Action::TogglePlayback | Action::PlayFromSelection => {
+                match self.output.references.get(self.output.cursor).copied().flatten() {
+                    Some(line) => {
+                        self.output.open = false;
+                        self.jump_to_line(line);
+                    }
+                    None => self.notice = Some("no file line on this output line".to_string()),
+                }
+                return true;
+            }
+            Action::LineUp => -1,
+            Action::LineDown => 1,
+            Action::PageUp => -(self.file_panel.view_height.max(1) as isize),
+            Action::PageDown => self.file_panel.view_height.max(1) as isize,
+            _ => return false,
+        };
+        self.output.cursor = (self.output.cursor as isize + step).clamp(0, count as isize - 1) as usize;
+        true
+    }
+
+    /// Opens the output panel on a command's output, at its first reference to the file.
+    pub fn show_output(&mut self, title: String, text: &str) {
+        self.output = OutputPanel {
+            open: true,
+            title,
+            ..OutputPanel::default()
+        };
+        for line in text.lines() {
+            self.append_output(line);
+        }
+    }
+
+    /// Adds a line of a running command's output; the cursor stays on the first file reference.
+    pub fn append_output(&mut self, line: &str) {
+        let name = self
+            .file_path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let reference = file_line_reference(line, &name).filter(|&line| line < self.file_lines.len());
+        if reference.is_some() && self.output.references.iter().all(Option::is_none) {
+            self.output.cursor = self.output.lines.len();
+        }
+        self.output.lines.push(line.to_string());
+        self.output.references.push(reference);
+    }
+
+    /// Re-parses with A rotation unwrapped onto Y, or wrapped back around X.
+    fn toggle_unwrap(&mut self) {
+        if self.config.ui.wrap_diameter.is_none() {
+            self.notice = Some("set ui.wrap_diameter to unwrap A moves".to_string());
+            return;
+        }
+        self.unwrap_rotary = !self.unwrap_rotary;
+        self.notice = Some(match self.reparse(self.programs.active) {
+            Ok(_) if self.unwrap_rotary => "A unwrapped onto Y".to_string(),
+            Ok(_) => "A wrapped around X".to_string(),
+            Err(err) => {
+                self.unwrap_rotary = !self.unwrap_rotary;
+                format!("could not re-parse: {:#}", err)
+            }
+        });
+    }
+
+    /// Renders only program `index` and selects its lines in the file panel.
+    fn select_program(&mut self, index: usize) {
+        if let Err(err) = self.reparse(Some(index)) {
+            self.notice = Some(format!("could not load program: {:#}", err));
+            return;
+        }
+        let Some(program) = self.programs.programs.get(index) else {
+            return;
+        };
+        self.file_panel.visual = true;
+        self.file_panel.anchor = program.lines.start;
+        self.file_panel.selected = program.lines.end.saturating_sub(1).max(program.lines.start);
+        self.file_panel.ensure_visible();
+        self.notice = Some(format!("showing {}", program_label(program)));
+    }
+
+    fn apply_timeline_action(&mut self, action: Action) -> bool {
+        let step = match action {
+            Action::ToggleTimeline => {
+                self.timeline.open = false;
+                return true;
+            }
+            Action::PanLeft => -1,
+            Action::PanRight => 1,
+            _ => return false,
+        };
+        let last = self.timeline.blocks.len() as isize - 1;
+        let cursor = (self.timeline.cursor as isize + step).clamp(0, last);
+        self.jump_to_timeline_block(cursor as usize);
+        true
+    }
+
+    /// Pauses playback at the block's first segment and moves the cursor line there.
+    fn jump_to_timeline_block(&mut self, index: usize) {
+        let Some(block) = self.timeline.blocks.get(index) else {
+            return;
+        };
+        let segment = block.segments.start;
+        self.timeline.cursor = index;
+        if let Some(line) = self.segment_line(segment) {
+            self.file_panel.visual = true;
+            self.file_panel.anchor = line;
+            self.file_panel.selected = line;
+            self.file_panel.ensure_visible();
+        }
+        self.playback.active = true;
+        self.playback.playing = false;
+        self.playback.position = (segment + 1) as f64;
+    }
+
+    /// Writes a restart file (preamble plus the rest of the program) for the cursor line.
+    fn generate_restart_block(&mut self) {
+        let line = self.file_panel.selected;
+        let bounds = self.toolpath.bounds;
+        let safe_z = if bounds.initialized { bounds.max.z } else { 0.0 };
+        let Some(preamble) = restart_preamble(&self.toolpath, line, safe_z) else {
+            return;
+        };
+        let path = self
+            .file_path
+            .with_extension(format!("restart-L{}.nc", line + 1));
+        let mut content = preamble.join("\n");
+        for source in &self.file_lines[line..] {
+            content.push('\n');
+            content.push_str(source);
+        }
+        content.push('\n');
+        let status = match std::fs::write(&path, content) {
+            Ok(()) => format!("saved {}", path.display()),
+            Err(err) => format!("could not save {}: {}", path.display(), err),
+        };
+        self.restart = Some(RestartBlock { preamble, status });
+    }
+
+    /// Moves the operations cursor and selects the operation's lines in the file panel.
+    fn select_operation(&mut self, index: usize) {
+        let Some(op) = self.toolpath.operations.get(index) else {
+            return;
+        };
+        self.operations.cursor = index;
+        self.file_panel.visual = true;
+        self.file_panel.anchor = op.lines.start;
+        self.file_panel.selected = op.lines.end.saturating_sub(1).max(op.lines.start);
+        self.file_panel.ensure_visible();
+    }
+
+    pub fn operation_at(&self, segment: usize) -> Option<usize> {
+        let operations = &self.toolpath.operations;
+        let pos = operations.partition_point(|op| op.segments.end <= segment);
+        operations
+            .get(pos)
+            .filter(|op| op.segments.contains(&segment))
+            .map(|_| pos)
+    }
+
+    pub fn segment_hidden(&self, segment: usize) -> bool {
+        self.filter_mask.get(segment) == Some(&false)
+            || self.clip_z.is_some_and(|z| {
+                let seg = &self.toolpath.segments[segment];
+                seg.start.z.min(seg.end.z) > z
+            })
+            || self
+                .operation_at(segment)
+                .is_some_and(|op| self.operations.hidden[op])
+            || self.first_pass_only && {
+                let idx = self.later_passes.partition_point(|range| range.end <= segment);
+                self.later_passes.get(idx).is_some_and(|range| range.contains(&segment))
+            }
+    }
+
     fn pan_step(&self) -> (f64, f64) {
         if let Some(metrics) = self.last_metrics {
             let step_x = (metrics.half_w * 0.1).max(0.1);
@@ -208,7 +1241,7 @@ impl App {
         }
 
         let size = self.toolpath.bounds.size();
-        let max_dim = size.x.max(size.y).max(size.z).max(1.0);
+        let max_dim = size.x.max(size.y).max(size.z * self.view.z_scale).max(1.0);
         let camera_distance = max_dim * 2.5;
         let target = self.toolpath.bounds.center();
         let params = ProjectionParams {
@@ -219,6 +1252,7 @@ impl App {
             },
             camera_distance,
             target,
+            z_scale: self.view.z_scale,
         };
 
         for corner in bounds_corners(self.toolpath.bounds) {
@@ -235,8 +1269,137 @@ impl App {
     }
 
     pub fn tick(&mut self, delta: Duration) {
-        self.playback
-            .tick(delta, self.toolpath.segments.len());
+        let before = self.playback.position;
+        let was_playing = self.playback.playing;
+        let total = self.toolpath.segments.len();
+        match self.config.animation.time_scale {
+            Some(scale) if self.playback.playing => {
+                let time = self.playback_elapsed() + delta.as_secs_f64() * scale;
+                self.playback.advance_to(self.position_at_time(time), total);
+                self.playback_clock = Some((self.playback.position, time));
+            }
+            _ => self.playback.tick(delta, total),
+        }
+        if was_playing {
+            self.pause_at_stop(before);
+        }
+        self.tick_auto_fit(delta);
+        if let Some(ghost) = &mut self.ghost {
+            ghost.remaining = ghost.remaining.saturating_sub(delta);
+            if ghost.remaining.is_zero() {
+                self.ghost = None;
+            }
+        }
+    }
+
+    /// Estimated machine time at the playback position.
+    pub fn playback_elapsed(&self) -> f64 {
+        let position = self.playback.position;
+        if let Some((at, time)) = self.playback_clock
+            && at == position
+        {
+            return time;
+        }
+        let index = position.floor() as usize;
+        let Some(&end) = self.segment_end_times.get(index) else {
+            return self.estimated_time;
+        };
+        let start = self.segment_start_time(index);
+        if position.fract() == 0.0 {
+            return start;
+        }
+        let motion_start = start + self.segment_pauses[index];
+        motion_start + (end - motion_start) * position.fract()
+    }
+
+    /// Playback position reached after `time` seconds, holding at a segment's start through
+    /// its pauses.
+    fn position_at_time(&self, time: f64) -> f64 {
+        let index = self.segment_end_times.partition_point(|&end| end <= time);
+        let Some(&end) = self.segment_end_times.get(index) else {
+            return self.segment_end_times.len() as f64;
+        };
+        let motion_start = self.segment_start_time(index) + self.segment_pauses[index];
+        if time <= motion_start || end <= motion_start {
+            return index as f64;
+        }
+        index as f64 + (time - motion_start) / (end - motion_start)
+    }
+
+    fn segment_start_time(&self, index: usize) -> f64 {
+        index
+            .checked_sub(1)
+            .and_then(|prev| self.segment_end_times.get(prev).copied())
+            .unwrap_or(0.0)
+    }
+
+    /// Pauses playback at the first M0 (or M1 with `animation.optional_stop`) reached since `before`.
+    fn pause_at_stop(&mut self, before: f64) {
+        let optional = self.config.animation.optional_stop;
+        let position = self.playback.position;
+        let passed = self.toolpath.stops.iter().enumerate().position(|(index, stop)| {
+            let at = stop.segment as f64;
+            (optional || !stop.optional) && Some(index) != self.resumed_stop && at >= before && at <= position
+        });
+        if let Some(index) = passed {
+            self.playback.position = self.toolpath.stops[index].segment as f64;
+            self.playback.playing = false;
+            self.playback.active = true;
+            self.stopped_at = Some(index);
+        }
+    }
+
+    fn tick_auto_fit(&mut self, delta: Duration) {
+        if !self.auto_fit.enabled {
+            return;
+        }
+        let selection = self.selection_segment_range();
+        if self.auto_fit.selection != Some(selection) {
+            self.auto_fit.selection = Some(selection);
+            self.auto_fit.target = self.selection_fit(selection);
+        }
+        let Some((pan, zoom)) = self.auto_fit.target else {
+            return;
+        };
+        let t = 1.0 - (-delta.as_secs_f64() * AUTO_FIT_RATE).exp();
+        self.view.pan = self.view.pan + Vec2::new((pan.x - self.view.pan.x) * t, (pan.y - self.view.pan.y) * t);
+        self.view.zoom *= (zoom / self.view.zoom).powf(t);
+        let settled = (zoom / self.view.zoom - 1.0).abs() < 1e-3
+            && (pan.x - self.view.pan.x).abs() < 1e-6 * (1.0 + pan.x.abs())
+            && (pan.y - self.view.pan.y).abs() < 1e-6 * (1.0 + pan.y.abs());
+        if settled {
+            self.view.pan = pan;
+            self.view.zoom = zoom;
+            self.auto_fit.target = None;
+        }
+    }
+
+    /// Pan and zoom that frame the projected segments in `range` with a small margin.
+    fn selection_fit(&self, (start, end): (usize, usize)) -> Option<(Vec2, f64)> {
+        let (base, camera_distance, target) = self.projected_bounds();
+        let params = ProjectionParams {
+            mode: self.view.projection,
+            angles: ViewAngles {
+                yaw: self.view.yaw,
+                pitch: self.view.pitch,
+            },
+            camera_distance,
+            target,
+            z_scale: self.view.z_scale,
+        };
+        let mut bounds = Bounds2::new();
+        for seg in self.toolpath.segments.get(start..end)? {
+            bounds.include(project_point(seg.start, params));
+            bounds.include(project_point(seg.end, params));
+        }
+        if !bounds.initialized {
+            return None;
+        }
+        let min_size = base.width().max(base.height()) * 0.01;
+        let zoom = (base.width() / bounds.width().max(min_size))
+            .min(base.height() / bounds.height().max(min_size))
+            / AUTO_FIT_MARGIN;
+        Some((bounds.center() - base.center(), zoom.max(0.05)))
     }
 
     pub fn visible_segment_count(&self) -> usize {
@@ -249,10 +1412,22 @@ impl App {
     }
 
     pub fn visible_segment_range(&self) -> (usize, usize) {
-        let total = self.toolpath.segments.len();
-        if total == 0 {
-            return (0, 0);
-        }
+        let (start_idx, end_idx) = self.selection_segment_range();
+        let range_len = end_idx - start_idx;
+        let visible_len = if self.playback.active {
+            self.playback.visible_segments(range_len)
+        } else {
+            range_len
+        };
+        (start_idx, start_idx + visible_len)
+    }
+
+    /// Segments produced by the selected file lines, ignoring playback.
+    pub fn selection_segment_range(&self) -> (usize, usize) {
+        let total = self.toolpath.segments.len();
+        if total == 0 {
+            return (0, 0);
+        }
         let ends = &self.toolpath.line_segment_ends;
         if ends.is_empty() {
             return (0, total);
@@ -266,17 +1441,315 @@ impl App {
         if end_idx < start_idx {
             end_idx = start_idx;
         }
-        let range_len = end_idx - start_idx;
-        let visible_len = if self.playback.active {
-            self.playback.visible_segments(range_len)
+        (start_idx, end_idx)
+    }
+
+    /// Consecutive segments merged into one drawn line when the drawn range exceeds the budget.
+    pub fn lod_stride(&self) -> usize {
+        let budget = self.config.ui.max_segments;
+        let drawn = if self.focus_selection {
+            self.toolpath.segments.len()
         } else {
-            range_len
+            self.visible_segment_count()
         };
-        (start_idx, start_idx + visible_len)
+        if budget == 0 {
+            1
+        } else {
+            drawn.div_ceil(budget).max(1)
+        }
+    }
+
+    /// `range` split into drawn runs of at most [`Self::lod_stride`] segments; a run never
+    /// spans a hidden segment or a change of move kind, so merged runs keep their colour.
+    pub fn lod_runs(&self, range: Range<usize>) -> Vec<Range<usize>> {
+        let stride = self.lod_stride();
+        let class = |idx: usize| (self.segment_hidden(idx), self.toolpath.segments[idx].kind, self.low_rapid(idx));
+        let mut runs = Vec::new();
+        let mut idx = range.start;
+        while idx < range.end {
+            let hidden = self.segment_hidden(idx);
+            let limit = (idx + stride).min(range.end);
+            let end = (idx + 1..limit).find(|&next| class(next) != class(idx)).unwrap_or(limit);
+            if !hidden {
+                runs.push(idx..end);
+            }
+            idx = end;
+        }
+        runs
+    }
+
+    /// The playback head while animating, otherwise the last segment of the cursor line.
+    pub fn highlighted_segment(&self) -> Option<usize> {
+        if self.playback.active {
+            let (start, end) = self.visible_segment_range();
+            return (end > start).then(|| end - 1);
+        }
+        if self.file_panel.focus != PanelFocus::File {
+            return None;
+        }
+        let ends = &self.toolpath.line_segment_ends;
+        let line = self.file_panel.selected;
+        let end = *ends.get(line)?;
+        let start = if line == 0 { 0 } else { ends[line - 1] };
+        (end > start).then(|| end - 1)
     }
+
+    /// Whether `mode` has anything to show for this program.
+    pub fn color_mode_available(&self, mode: ColorMode) -> bool {
+        match mode {
+            ColorMode::AirCut => self.air_cuts.is_some(),
+            ColorMode::FeedLimit => !self.feed_limits.is_empty(),
+            ColorMode::Kind | ColorMode::CutDirection => true,
+        }
+    }
+
+    pub fn low_rapid(&self, index: usize) -> bool {
+        self.low_rapids.binary_search(&index).is_ok()
+    }
+
+    pub fn feed_limited(&self, index: usize) -> bool {
+        let pos = self.feed_limits.partition_point(|zone| zone.segments.end <= index);
+        self.feed_limits.get(pos).is_some_and(|zone| zone.segments.contains(&index))
+    }
+
+    pub fn set_color_mode(&mut self, mode: ColorMode) {
+        if mode == ColorMode::CutDirection && self.profiles.is_none() {
+            self.profiles = Some(analyze_cut_direction(&self.toolpath));
+        }
+        self.color_mode = mode;
+    }
+
+    pub fn cut_direction(&self, index: usize) -> Option<CutDirection> {
+        let profiles = self.profiles.as_deref().unwrap_or_default();
+        let pos = profiles.partition_point(|profile| profile.segments.end <= index);
+        profiles
+            .get(pos)
+            .filter(|profile| profile.segments.contains(&index))
+            .map(|profile| profile.direction)
+    }
+
+    /// Feed and speed data at the playback head or, otherwise, the cursor line.
+    pub fn cutting_conditions(&self) -> Option<CuttingConditions> {
+        let line = if self.playback.active {
+            self.segment_line(self.highlighted_segment()?)?
+        } else {
+            self.file_panel.selected
+        };
+        let state = self.toolpath.line_states.get(line)?;
+        let entry = state.tool.and_then(|tool| self.config.tools.get(tool));
+        CuttingConditions::from_state(state, entry)
+    }
+
+    /// How far through the program execution is once a 0-based line has run.
+    pub fn progress_at_line(&self, line: usize) -> Option<Progress> {
+        let total = self.toolpath.segments.len();
+        if total == 0 {
+            return None;
+        }
+        let done = (*self.toolpath.line_segment_ends.get(line)?).min(total);
+        let fraction = |cumulative: &[f64]| {
+            let whole = cumulative.last().copied().unwrap_or(0.0);
+            match done.checked_sub(1) {
+                Some(last) if whole > 0.0 => cumulative[last] / whole,
+                Some(_) => 1.0,
+                None => 0.0,
+            }
+        };
+        Some(Progress {
+            segments: done as f64 / total as f64,
+            distance: fraction(&self.segment_end_distances),
+            time: fraction(&self.segment_end_times),
+        })
+    }
+
+    /// Stats for the lines of a visual selection; `None` unless part of the file is selected.
+    pub fn selection_stats(&self) -> Option<SelectionStats> {
+        let (line_start, line_end) = self.file_panel.selection_range(self.file_lines.len());
+        let whole = line_start == 0 && line_end + 1 >= self.file_lines.len();
+        if !self.file_panel.visual || whole {
+            return None;
+        }
+        let (start, end) = self.selection_segment_range();
+        let at = |cumulative: &[f64], index: usize| {
+            index
+                .checked_sub(1)
+                .and_then(|last| cumulative.get(last).copied())
+                .unwrap_or(0.0)
+        };
+        let z_range = self.toolpath.segments[start..end]
+            .iter()
+            .flat_map(|seg| [seg.start.z, seg.end.z])
+            .fold(None, |range, z| match range {
+                None => Some((z, z)),
+                Some((low, high)) => Some((f64::min(low, z), f64::max(high, z))),
+            });
+        let mut tools: Vec<u32> = self
+            .toolpath
+            .line_states
+            .iter()
+            .take(line_end + 1)
+            .skip(line_start)
+            .filter_map(|state| state.tool)
+            .collect();
+        tools.sort_unstable();
+        tools.dedup();
+        Some(SelectionStats {
+            segments: end - start,
+            distance: at(&self.segment_end_distances, end) - at(&self.segment_end_distances, start),
+            time: at(&self.segment_end_times, end) - at(&self.segment_end_times, start),
+            z_range,
+            tools,
+        })
+    }
+
+    /// First diagnostic reported for a 0-based file line.
+    pub fn line_diagnostic(&self, line: usize) -> Option<&Diagnostic> {
+        let diagnostics = &self.toolpath.diagnostics;
+        let pos = diagnostics.partition_point(|diag| diag.line < line + 1);
+        diagnostics.get(pos).filter(|diag| diag.line == line + 1)
+    }
+
+    pub fn segment_line(&self, index: usize) -> Option<usize> {
+        self.toolpath.segment_line(index)
+    }
+}
+
+fn find_air_cuts(
+    config: &Config,
+    toolpath: &Toolpath,
+    stock: Bounds3,
+    end_times: &[f64],
+    end_distances: &[f64],
+) -> AirCuts {
+    let radius = |tool: Option<u32>| {
+        let diameter = tool
+            .and_then(|tool| config.tools.get(tool))
+            .and_then(|entry| entry.diameter)
+            .unwrap_or(config.stock.tool_diameter);
+        diameter / 2.0
+    };
+    let mask = cnc_gcode::air_cuts(toolpath, stock, config.stock.resolution, radius);
+    let span = |ends: &[f64], index: usize| {
+        let before = index.checked_sub(1).map_or(0.0, |prev| ends[prev]);
+        ends.get(index).map_or(0.0, |end| end - before)
+    };
+    let mut air = AirCuts::default();
+    for index in (0..mask.len()).filter(|&index| mask[index]) {
+        air.count += 1;
+        air.time += span(end_times, index);
+        air.distance += span(end_distances, index);
+    }
+    air.mask = mask;
+    air
 }
 
-fn bounds_corners(bounds: Bounds3) -> [Vec3; 8] {
+/// The program's lines for display; bytes that are not UTF-8 are replaced, the parser reports them.
+pub fn program_lines(content: &[u8]) -> Vec<String> {
+    String::from_utf8_lossy(content).lines().map(|line| line.to_string()).collect()
+}
+
+pub fn time_options(config: &Config) -> TimeEstimateOptions {
+    match config.machine() {
+        Some(machine) => TimeEstimateOptions {
+            rapid_rate: machine.rapid_rate,
+            max_feed_rate: machine.max_feed_rate,
+            acceleration: Some(machine.acceleration),
+            tool_change_time: machine.tool_change_time,
+            feed_time_factor: machine.feed_time_factor,
+            rapid_time_factor: machine.rapid_time_factor,
+            ..TimeEstimateOptions::default()
+        },
+        None => TimeEstimateOptions::default(),
+    }
+}
+
+/// Segments of every loop pass after the first, merged into sorted ranges.
+fn later_passes(toolpath: &Toolpath) -> Vec<Range<usize>> {
+    let mut ranges: Vec<Range<usize>> = toolpath
+        .repeats
+        .iter()
+        .map(|repeat| repeat.first_pass_end..repeat.segments.end)
+        .filter(|range| !range.is_empty())
+        .collect();
+    ranges.sort_by_key(|range| range.start);
+    let mut merged: Vec<Range<usize>> = Vec::new();
+    for range in ranges {
+        match merged.last_mut() {
+            Some(last) if range.start <= last.end => last.end = last.end.max(range.end),
+            _ => merged.push(range),
+        }
+    }
+    merged
+}
+
+/// Finds rapids below the clearance height and reports each line holding one as an error.
+fn flag_low_rapids(toolpath: &mut Toolpath, clearance: f64) -> Vec<usize> {
+    let low = low_rapids(toolpath, clearance);
+    let mut lines: Vec<usize> = low
+        .iter()
+        .map(|&segment| toolpath.line_segment_ends.partition_point(|&end| end <= segment) + 1)
+        .collect();
+    lines.dedup();
+    toolpath.diagnostics.extend(lines.into_iter().map(|line| Diagnostic {
+        line,
+        severity: Severity::Error,
+        message: format!("rapid moves in XY below clearance Z{}", clearance),
+        lint: None,
+    }));
+    toolpath.diagnostics.sort_by_key(|diag| diag.line);
+    low
+}
+
+fn segments_outside_envelope(config: &Config, toolpath: &Toolpath) -> usize {
+    config.machine().map_or(0, |machine| {
+        toolpath
+            .segments
+            .iter()
+            .filter(|seg| !machine.envelope_contains(seg.start) || !machine.envelope_contains(seg.end))
+            .count()
+    })
+}
+
+fn machine_limit_warnings(config: &Config, toolpath: &Toolpath, outside: usize) -> Vec<String> {
+    let Some(machine) = config.machine() else {
+        return Vec::new();
+    };
+    let mut warnings = Vec::new();
+    if outside > 0 {
+        warnings.push(format!("{} seg outside envelope", outside));
+    }
+    if let Some(max_feed) = machine.max_feed_rate {
+        let over = toolpath
+            .segments
+            .iter()
+            .filter(|seg| seg.kind == MoveKind::Feed && seg.feed.is_some_and(|f| f > max_feed))
+            .count();
+        if over > 0 {
+            warnings.push(format!("{} seg over max feed", over));
+        }
+    }
+    if let (Some((min, max)), Some((lo, hi))) =
+        (machine.spindle_range, toolpath.stats.spindle_speed_range)
+        && (lo < min || hi > max)
+    {
+        warnings.push(format!("spindle S{}-S{} outside range", lo, hi));
+    }
+    if machine.tool_table.is_some() {
+        let missing: Vec<String> = toolpath
+            .stats
+            .tools_used
+            .iter()
+            .filter(|tool| !config.tools.contains(**tool))
+            .map(|tool| format!("T{}", tool))
+            .collect();
+        if !missing.is_empty() {
+            warnings.push(format!("unknown tools {}", missing.join(",")));
+        }
+    }
+    warnings
+}
+
+pub fn bounds_corners(bounds: Bounds3) -> [Vec3; 8] {
     let min = bounds.min;
     let max = bounds.max;
     [
@@ -377,6 +1850,205 @@ impl FilePanelState {
     }
 }
 
+const VIEW_HISTORY_LIMIT: usize = 100;
+
+const SNAP_STEP_DEG: f64 = 15.0;
+const Z_SCALE_MAX: f64 = 1024.0;
+/// Moves peaking below this fraction of their programmed feed are reported as feed-limited.
+pub const FEED_LIMIT_RATIO: f64 = 0.95;
+/// XY radius searched for the programmed move nearest to a trace point, in mm.
+pub const TRACE_SEARCH: f64 = 5.0;
+/// Matching lines listed in the `where` notice; `n` steps through all of them.
+const WHERE_LISTED: usize = 8;
+/// Lines per grid direction above which a typed `grid` spacing is refused.
+const MAX_GRID_LINES: usize = 500;
+
+/// Moves `angle` (radians) to the next multiple of [`SNAP_STEP_DEG`] in `direction`.
+fn snap_angle(angle: f64, direction: f64) -> f64 {
+    let steps = angle.to_degrees() / SNAP_STEP_DEG;
+    let next = if direction > 0.0 {
+        (steps + 1e-6).floor() + 1.0
+    } else {
+        (steps - 1e-6).ceil() - 1.0
+    };
+    (next * SNAP_STEP_DEG).to_radians()
+}
+
+/// Exponential easing rate (1/s) and framing margin for auto-fit.
+const AUTO_FIT_RATE: f64 = 12.0;
+const AUTO_FIT_MARGIN: f64 = 1.15;
+
+/// Re-frames the camera on the file selection whenever it changes.
+#[derive(Debug, Clone, Default)]
+pub struct AutoFit {
+    pub enabled: bool,
+    selection: Option<(usize, usize)>,
+    /// Pan and zoom the view is easing towards.
+    target: Option<(Vec2, f64)>,
+}
+
+/// Camera states for undo/redo; a run of the same view action is one step.
+#[derive(Debug, Clone, Default)]
+pub struct ViewHistory {
+    pub undo: Vec<ViewState>,
+    pub redo: Vec<ViewState>,
+    last: Option<Action>,
+}
+
+impl ViewHistory {
+    fn record(&mut self, action: Action, view: &ViewState) {
+        if !changes_view(action) {
+            self.last = None;
+            return;
+        }
+        if self.last != Some(action) {
+            self.checkpoint(view);
+        }
+        self.redo.clear();
+        self.last = Some(action);
+    }
+
+    /// Saves `view` as its own undo step.
+    fn checkpoint(&mut self, view: &ViewState) {
+        if self.undo.len() == VIEW_HISTORY_LIMIT {
+            self.undo.remove(0);
+        }
+        self.undo.push(view.clone());
+        self.redo.clear();
+        self.last = None;
+    }
+}
+
+fn changes_view(action: Action) -> bool {
+    matches!(
+        action,
+        Action::PanLeft
+            | Action::PanRight
+            | Action::PanUp
+            | Action::PanDown
+            | Action::ZoomIn
+            | Action::ZoomOut
+            | Action::RotateLeft
+            | Action::RotateRight
+            | Action::RotateUp
+            | Action::RotateDown
+            | Action::SnapRotateLeft
+            | Action::SnapRotateRight
+            | Action::SnapRotateUp
+            | Action::SnapRotateDown
+            | Action::ZScaleUp
+            | Action::ZScaleDown
+            | Action::Fit
+            | Action::ResetView
+            | Action::ToggleProjection
+    )
+}
+
+#[derive(Debug, Clone)]
+pub struct RestartBlock {
+    pub preamble: Vec<String>,
+    pub status: String,
+}
+
+/// Completed fractions (0..=1) of the program.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Progress {
+    pub segments: f64,
+    pub distance: f64,
+    pub time: f64,
+}
+
+/// Totals over the moves of a visual selection.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SelectionStats {
+    pub segments: usize,
+    pub distance: f64,
+    pub time: f64,
+    /// Lowest and highest Z reached.
+    pub z_range: Option<(f64, f64)>,
+    pub tools: Vec<u32>,
+}
+
+#[derive(Debug, Clone)]
+pub struct OperationsPanel {
+    pub open: bool,
+    pub cursor: usize,
+    pub hidden: Vec<bool>,
+}
+
+impl OperationsPanel {
+    pub fn new(count: usize) -> Self {
+        Self {
+            open: false,
+            cursor: 0,
+            hidden: vec![false; count],
+        }
+    }
+}
+
+/// Output of the last captured custom command.
+#[derive(Debug, Clone, Default)]
+pub struct OutputPanel {
+    pub open: bool,
+    pub title: String,
+    pub lines: Vec<String>,
+    /// The 0-based file line each output line points at.
+    pub references: Vec<Option<usize>>,
+    pub cursor: usize,
+}
+
+/// The 0-based line of the first `<name>:<line>` in `text`, as compilers and linters print it.
+fn file_line_reference(text: &str, name: &str) -> Option<usize> {
+    if name.is_empty() {
+        return None;
+    }
+    text.match_indices(name).find_map(|(start, _)| {
+        let rest = text[start + name.len()..].strip_prefix(':')?;
+        let digits = rest.len() - rest.trim_start_matches(|ch: char| ch.is_ascii_digit()).len();
+        rest[..digits].parse::<usize>().ok()?.checked_sub(1)
+    })
+}
+
+/// Programs of a multi-program file; only the active one is parsed and drawn.
+#[derive(Debug, Clone)]
+pub struct ProgramPicker {
+    pub programs: Vec<Program>,
+    pub open: bool,
+    pub cursor: usize,
+    pub active: Option<usize>,
+}
+
+impl ProgramPicker {
+    /// Starts on the first program when there are several.
+    pub fn new(programs: Vec<Program>) -> Self {
+        let active = (programs.len() > 1).then_some(0);
+        Self {
+            programs,
+            open: false,
+            cursor: 0,
+            active,
+        }
+    }
+
+    pub fn parse_options(&self, options: ParseOptions) -> ParseOptions {
+        match self.active.and_then(|index| self.programs.get(index)) {
+            Some(program) => options.with_line_range(program.lines.clone()),
+            None => options,
+        }
+    }
+}
+
+pub fn program_label(program: &Program) -> String {
+    let number = match program.number {
+        Some(number) => format!("O{}", number),
+        None => format!("program at line {}", program.lines.start + 1),
+    };
+    match &program.name {
+        Some(name) => format!("{} ({})", number, name),
+        None => number,
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct PlaybackState {
     pub active: bool,
@@ -419,8 +2091,12 @@ impl PlaybackState {
         if !self.playing {
             return;
         }
-        let add = delta.as_secs_f64() * self.speed;
-        self.position += add;
+        self.advance_to(self.position + delta.as_secs_f64() * self.speed, total);
+    }
+
+    /// Moves the playhead, ending playback at the last segment.
+    pub fn advance_to(&mut self, position: f64, total: usize) {
+        self.position = position;
         if self.position >= total as f64 {
             self.position = total as f64;
             self.playing = false;
@@ -440,9 +2116,19 @@ impl PlaybackState {
 mod tests {
     use super::*;
     use crate::config::Config;
-    use cnc_gcode::{Toolpath, ToolpathStats};
+    use cnc_gcode::Toolpath;
     use std::fs;
 
+    fn segment(start: Vec3, end: Vec3, kind: MoveKind) -> cnc_gcode::LineSegment {
+        cnc_gcode::LineSegment {
+            start,
+            end,
+            kind,
+            feed: Some(600.0),
+            rotary: RotaryAngles::default(),
+        }
+    }
+
     #[test]
     fn reset_view_restores_initial() {
         let tmp = std::env::temp_dir().join("cnc_view_tui_test_config.toml");
@@ -457,12 +2143,11 @@ pitch_deg = 40.0
         )
         .unwrap();
 
-        let config = Config::load(Some(tmp)).unwrap();
+        let config = Config::load(Some(tmp), &[]).unwrap();
         let toolpath = Toolpath {
             segments: Vec::new(),
-            bounds: Bounds3::new(),
-            stats: ToolpathStats::default(),
             line_segment_ends: vec![0],
+            ..Toolpath::default()
         };
         let mut app = App::new(config, toolpath, PathBuf::from("demo.nc"), vec!["G0 X0".to_string()]);
 
@@ -479,4 +2164,662 @@ pitch_deg = 40.0
         assert!((app.view.pan.y - app.initial_view.pan.y).abs() < 1e-6);
         assert!((app.view.zoom - app.initial_view.zoom).abs() < 1e-6);
     }
+
+    #[test]
+    fn highlighted_segment_follows_cursor_line() {
+        let feed_to = |x: f64| segment(Vec3::default(), Vec3::new(x, 0.0, 0.0), MoveKind::Feed);
+        let toolpath = Toolpath {
+            segments: vec![feed_to(1.0), feed_to(2.0), feed_to(3.0)],
+            line_segment_ends: vec![0, 2, 2, 3],
+            ..Toolpath::default()
+        };
+        let lines = ["G21", "G1 X1", "(note)", "X3"].map(String::from).to_vec();
+        let config = Config::defaults(&[]).unwrap();
+        let mut app = App::new(config, toolpath, PathBuf::from("demo.nc"), lines);
+        assert_eq!(app.highlighted_segment(), None);
+
+        app.apply_action(Action::ToggleFocus);
+        assert_eq!(app.highlighted_segment(), Some(2));
+        app.file_panel.selected = 1;
+        assert_eq!(app.highlighted_segment(), Some(1));
+        app.file_panel.selected = 2;
+        assert_eq!(app.highlighted_segment(), None);
+        assert_eq!(app.segment_line(1), Some(1));
+        assert_eq!(app.segment_line(2), Some(3));
+    }
+
+    #[test]
+    fn where_lists_lines_crossing_a_region() {
+        let flat = |from: (f64, f64), to: (f64, f64)| segment(Vec3::new(from.0, from.1, 0.0), Vec3::new(to.0, to.1, 0.0), MoveKind::Feed);
+        let toolpath = Toolpath {
+            segments: vec![
+                flat((0.0, 0.0), (10.0, 0.0)),
+                flat((10.0, 0.0), (10.0, 10.0)),
+                flat((10.0, 10.0), (0.0, 10.0)),
+            ],
+            line_segment_ends: vec![1, 2, 3],
+            ..Toolpath::default()
+        };
+        let lines = ["G1 X10", "Y10", "X0"].map(String::from).to_vec();
+        let config = Config::defaults(&[]).unwrap();
+        let mut app = App::new(config, toolpath, PathBuf::from("demo.nc"), lines);
+
+        app.run_command(parse_command("where 9,-1 11,11").unwrap());
+        assert_eq!(app.matches, [0, 1, 2]);
+        assert_eq!(app.file_panel.selected, 0);
+        app.apply_action(Action::NextMatch);
+        assert_eq!(app.highlighted_segment(), Some(1));
+        app.run_command(parse_command("where 5,10.5").unwrap());
+        assert_eq!(app.matches, [2]);
+        app.run_command(parse_command("where 5,5 2").unwrap());
+        assert!(app.matches.is_empty());
+        app.display_origin = Vec3::new(10.0, 0.0, 0.0);
+        app.display_units = Units::Inches;
+        app.run_command(parse_command("where 0,0.2 0.04").unwrap());
+        assert_eq!(app.matches, [1]);
+    }
+
+    #[test]
+    fn copy_actions_queue_lines_and_point_for_the_clipboard() {
+        let toolpath = Toolpath {
+            segments: vec![
+                segment(Vec3::default(), Vec3::new(10.0, 0.0, -1.0), MoveKind::Feed),
+                segment(Vec3::default(), Vec3::new(10.0, 10.0, -1.0), MoveKind::Feed),
+            ],
+            line_segment_ends: vec![1, 2],
+            ..Toolpath::default()
+        };
+        let lines = ["G1 X10 Z-1", "Y10"].map(String::from).to_vec();
+        let config = Config::defaults(&[]).unwrap();
+        let mut app = App::new(config, toolpath, PathBuf::from("demo.nc"), lines);
+
+        app.apply_action(Action::ToggleVisual);
+        app.apply_action(Action::LineDown);
+        app.apply_action(Action::CopyLines);
+        assert_eq!(app.clipboard.take().as_deref(), Some("G1 X10 Z-1\nY10"));
+        app.display_origin = Vec3::new(5.0, 0.0, 0.0);
+        app.apply_action(Action::ToggleFocus);
+        app.apply_action(Action::CopyPoint);
+        assert_eq!(app.clipboard.as_deref(), Some("X5.000 Y10.000 Z-1.000"));
+    }
+
+    #[test]
+    fn selection_stats_cover_only_the_selected_lines() {
+        let state = |tool| cnc_gcode::LineState {
+            tool: Some(tool),
+            ..cnc_gcode::LineState::default()
+        };
+        let toolpath = Toolpath {
+            segments: vec![
+                segment(Vec3::new(0.0, 0.0, 5.0), Vec3::new(10.0, 0.0, 5.0), MoveKind::Rapid),
+                segment(Vec3::new(10.0, 0.0, 5.0), Vec3::new(10.0, 0.0, -1.0), MoveKind::Feed),
+                segment(Vec3::new(10.0, 0.0, -1.0), Vec3::new(10.0, 10.0, -1.0), MoveKind::Feed),
+            ],
+            line_segment_ends: vec![1, 2, 3],
+            line_states: vec![state(1), state(1), state(2)],
+            ..Toolpath::default()
+        };
+        let lines = ["T1 G0 X10 Z5", "G1 Z-1 F600", "T2 Y10"].map(String::from).to_vec();
+        let mut app = App::new(Config::defaults(&[]).unwrap(), toolpath, PathBuf::from("demo.nc"), lines);
+        assert_eq!(app.selection_stats(), None);
+
+        app.apply_action(Action::ToggleFocus);
+        app.apply_action(Action::ToggleVisual);
+        app.apply_action(Action::LineUp);
+        app.apply_action(Action::ToggleVisual);
+        app.apply_action(Action::LineDown);
+        let stats = app.selection_stats().unwrap();
+        assert_eq!((stats.segments, stats.z_range, stats.tools), (2, Some((-1.0, 5.0)), vec![1, 2]));
+        assert!((stats.distance - 16.0).abs() < 1e-9);
+        assert!((stats.time - 1.6).abs() < 1e-9);
+    }
+
+    #[test]
+    fn trace_points_off_the_programmed_path_are_flagged() {
+        let toolpath = Toolpath {
+            segments: vec![segment(Vec3::new(0.0, 0.0, -1.0), Vec3::new(10.0, 0.0, -1.0), MoveKind::Feed)],
+            line_segment_ends: vec![1],
+            ..Toolpath::default()
+        };
+        let config = Config::defaults(&["ui.trace_tolerance=0.1".to_string()]).unwrap();
+        let mut app = App::new(config, toolpath, PathBuf::from("demo.nc"), vec!["G1 X10 Z-1".to_string()]);
+        app.load_trace(vec![Vec3::new(2.0, 0.05, -1.0), Vec3::new(5.0, 0.0, -1.3), Vec3::new(5.0, 20.0, -1.0)]);
+        let trace = app.trace.as_ref().unwrap();
+        let off: Vec<bool> = (0..3).map(|index| trace.is_off_path(index, app.config.ui.trace_tolerance)).collect();
+        assert_eq!(off, [false, true, true]);
+        assert!(Config::defaults(&["ui.trace_tolerance=0".to_string()]).is_err());
+    }
+
+    #[test]
+    fn playback_pauses_at_program_stops() {
+        let feed_to = |x: f64| segment(Vec3::new(x - 1.0, 0.0, 0.0), Vec3::new(x, 0.0, 0.0), MoveKind::Feed);
+        let stop = |segment, optional| cnc_gcode::ProgramStop {
+            line: segment,
+            segment,
+            optional,
+            comment: None,
+        };
+        let toolpath = || Toolpath {
+            segments: (1..=6).map(|x| feed_to(x as f64)).collect(),
+            stops: vec![stop(2, true), stop(4, false)],
+            ..Toolpath::default()
+        };
+        let config = Config::defaults(&["animation.speed_segments_per_sec=1.0".to_string()]).unwrap();
+        let mut app = App::new(config, toolpath(), PathBuf::from("demo.nc"), Vec::new());
+        app.apply_action(Action::TogglePlayback);
+        app.tick(Duration::from_secs(3));
+        assert!(app.playback.playing);
+        app.tick(Duration::from_secs(3));
+        assert_eq!((app.playback.playing, app.playback.position, app.stopped_at), (false, 4.0, Some(1)));
+        app.apply_action(Action::TogglePlayback);
+        assert_eq!(app.stopped_at, None);
+        app.tick(Duration::from_secs(1));
+        assert_eq!(app.playback.position, 5.0);
+
+        let config = Config::defaults(&["animation.speed_segments_per_sec=1.0".to_string(), "animation.optional_stop=true".to_string()]).unwrap();
+        let mut app = App::new(config, toolpath(), PathBuf::from("demo.nc"), Vec::new());
+        app.apply_action(Action::TogglePlayback);
+        app.tick(Duration::from_secs(3));
+        assert_eq!((app.playback.position, app.stopped_at), (2.0, Some(0)));
+
+        let config = Config::defaults(&["animation.speed_segments_per_sec=1.0".to_string()]).unwrap();
+        let mut toolpath = toolpath();
+        toolpath.stops = vec![stop(0, false)];
+        let mut app = App::new(config, toolpath, PathBuf::from("demo.nc"), Vec::new());
+        app.apply_action(Action::TogglePlayback);
+        app.tick(Duration::from_secs(1));
+        assert_eq!((app.playback.playing, app.playback.position, app.stopped_at), (false, 0.0, Some(0)));
+        app.apply_action(Action::TogglePlayback);
+        app.tick(Duration::from_secs(1));
+        assert_eq!((app.playback.playing, app.playback.position), (true, 1.0));
+    }
+
+    #[test]
+    fn play_from_selection_starts_at_the_selected_line() {
+        let toolpath = Toolpath {
+            segments: (1..=4)
+                .map(|x| segment(Vec3::new(x as f64 - 1.0, 0.0, 0.0), Vec3::new(x as f64, 0.0, 0.0), MoveKind::Feed))
+                .collect(),
+            line_segment_ends: vec![1, 2, 3, 4],
+            ..Toolpath::default()
+        };
+        let lines = ["X1", "X2", "X3", "X4"].map(String::from).to_vec();
+        let mut app = App::new(Config::defaults(&[]).unwrap(), toolpath, PathBuf::from("demo.nc"), lines);
+        app.apply_action(Action::ToggleFocus);
+        app.apply_action(Action::ToggleVisual);
+        app.apply_action(Action::LineUp);
+        app.apply_action(Action::PlayFromSelection);
+        assert!(app.playback.active && app.playback.playing);
+        assert_eq!(app.playback.position, 2.0);
+    }
+
+    #[test]
+    fn time_based_playback_holds_through_dwells() {
+        let feed_to = |x: f64| segment(Vec3::new(x - 10.0, 0.0, 0.0), Vec3::new(x, 0.0, 0.0), MoveKind::Feed);
+        let toolpath = Toolpath {
+            segments: vec![feed_to(10.0), feed_to(20.0), feed_to(30.0)],
+            pauses: vec![cnc_gcode::Pause {
+                line: 1,
+                segment: 1,
+                kind: cnc_gcode::PauseKind::Dwell(2.0),
+            }],
+            ..Toolpath::default()
+        };
+        let config = Config::defaults(&["animation.time_scale=1.0".to_string()]).unwrap();
+        let mut app = App::new(config, toolpath, PathBuf::from("demo.nc"), Vec::new());
+        assert_eq!(app.estimated_time, 5.0);
+        app.apply_action(Action::TogglePlayback);
+        app.tick(Duration::from_millis(1500));
+        assert_eq!(app.playback.position, 1.0);
+        app.tick(Duration::from_secs(1));
+        assert_eq!((app.playback.position, app.playback_elapsed()), (1.0, 2.5));
+        app.tick(Duration::from_secs(1));
+        assert_eq!(app.playback.position, 1.5);
+        app.tick(Duration::from_secs(2));
+        assert!(!app.playback.active);
+    }
+
+    #[test]
+    fn air_cuts_are_totalled_and_cycled_into_the_color_modes() {
+        let pass = |z: f64| segment(Vec3::new(0.0, 5.0, z), Vec3::new(10.0, 5.0, z), MoveKind::Feed);
+        let toolpath = Toolpath {
+            segments: vec![pass(1.0), pass(-1.0)],
+            line_segment_ends: vec![1, 2],
+            ..Toolpath::default()
+        };
+        let lines = ["G1 X10 Z1 F600", "G1 X10 Z-1"].map(String::from).to_vec();
+        let overrides = ["stock.min=[0.0, 0.0, -5.0]", "stock.max=[20.0, 10.0, 0.0]"].map(String::from);
+        let config = Config::defaults(&overrides).unwrap();
+        let mut app = App::new(config, toolpath, PathBuf::from("demo.nc"), lines);
+        let air = app.air_cuts.as_ref().unwrap();
+        assert_eq!(air.mask, [true, false]);
+        assert_eq!(air.count, 1);
+        assert!((air.distance - 10.0).abs() < 1e-9);
+
+        assert!(app.profiles.is_none());
+        app.apply_action(Action::CycleColorMode);
+        assert!(app.profiles.is_some());
+        app.apply_action(Action::CycleColorMode);
+        assert_eq!(app.color_mode, ColorMode::AirCut);
+        app.air_cuts = None;
+        app.color_mode = ColorMode::CutDirection;
+        app.apply_action(Action::CycleColorMode);
+        assert_eq!(app.color_mode, ColorMode::Kind);
+    }
+
+    #[test]
+    fn corner_marks_use_the_configured_angle() {
+        let flat = |from: (f64, f64), to: (f64, f64)| segment(Vec3::new(from.0, from.1, 0.0), Vec3::new(to.0, to.1, 0.0), MoveKind::Feed);
+        let toolpath = || Toolpath {
+            segments: vec![flat((0.0, 0.0), (10.0, 0.0)), flat((10.0, 0.0), (10.0, 10.0))],
+            line_segment_ends: vec![1, 2],
+            ..Toolpath::default()
+        };
+        let lines = ["G1 X10", "Y10"].map(String::from).to_vec();
+        let config = Config::defaults(&[]).unwrap();
+        let mut app = App::new(config, toolpath(), PathBuf::from("demo.nc"), lines.clone());
+        assert_eq!(app.corners.len(), 1);
+        app.apply_action(Action::ToggleCorners);
+        assert!(app.show_corners);
+
+        let config = Config::defaults(&["ui.corner_angle=120.0".to_string()]).unwrap();
+        let app = App::new(config, toolpath(), PathBuf::from("demo.nc"), lines);
+        assert!(app.corners.is_empty());
+        assert!(Config::defaults(&["ui.corner_angle=200.0".to_string()]).is_err());
+    }
+
+    #[test]
+    fn rapids_below_the_clearance_are_errors() {
+        let rapid = |from: Vec3, to: Vec3| segment(from, to, MoveKind::Rapid);
+        let toolpath = Toolpath {
+            segments: vec![
+                rapid(Vec3::new(0.0, 0.0, 10.0), Vec3::new(0.0, 0.0, 1.0)),
+                rapid(Vec3::new(0.0, 0.0, 1.0), Vec3::new(10.0, 0.0, 1.0)),
+            ],
+            line_segment_ends: vec![1, 2, 2],
+            diagnostics: vec![Diagnostic {
+                line: 3,
+                severity: Severity::Warning,
+                message: "unknown word".to_string(),
+                lint: None,
+            }],
+            ..Toolpath::default()
+        };
+        let lines = ["G0 Z1", "X10", "Q1"].map(String::from).to_vec();
+        let config = Config::defaults(&["ui.rapid_clearance=2.0".to_string()]).unwrap();
+        let app = App::new(config, toolpath, PathBuf::from("demo.nc"), lines);
+        assert_eq!(app.low_rapids, [1]);
+        assert!(app.low_rapid(1) && !app.low_rapid(0));
+        let lines: Vec<(usize, Severity)> = app.toolpath.diagnostics.iter().map(|diag| (diag.line, diag.severity)).collect();
+        assert_eq!(lines, [(2, Severity::Error), (3, Severity::Warning)]);
+    }
+
+    #[test]
+    fn undo_view_steps_over_runs_of_one_action() {
+        let lines = ["G1 X1 Y1"].map(String::from).to_vec();
+        let toolpath = Toolpath::default();
+        let mut app = App::new(Config::defaults(&[]).unwrap(), toolpath, PathBuf::from("demo.nc"), lines);
+        let yaw = app.view.yaw;
+        app.apply_action(Action::RotateLeft);
+        app.apply_action(Action::RotateLeft);
+        app.apply_action(Action::ZoomIn);
+        let zoomed = app.view.zoom;
+
+        app.apply_action(Action::UndoView);
+        assert_eq!(app.view.zoom, 1.0);
+        assert!((app.view.yaw - (yaw - 10.0_f64.to_radians())).abs() < 1e-9);
+        app.apply_action(Action::UndoView);
+        assert_eq!(app.view.yaw, yaw);
+        app.apply_action(Action::RedoView);
+        app.apply_action(Action::RedoView);
+        assert_eq!(app.view.zoom, zoomed);
+    }
+
+    #[test]
+    fn auto_fit_eases_onto_selection() {
+        let ramp = |x0: f64, x1: f64| segment(Vec3::new(x0, 0.0, 0.0), Vec3::new(x1, 0.0, 1.0), MoveKind::Feed);
+        let mut bounds = Bounds3::new();
+        for point in [Vec3::new(0.0, 0.0, 0.0), Vec3::new(100.0, 0.0, 1.0)] {
+            bounds.include(point);
+        }
+        let toolpath = Toolpath {
+            segments: vec![ramp(0.0, 10.0), ramp(90.0, 100.0)],
+            line_segment_ends: vec![1, 2],
+            bounds,
+            ..Toolpath::default()
+        };
+        let lines = ["G1 X10 Z1", "G0 X90 Z0 G1 X100 Z1"].map(String::from).to_vec();
+        let config = Config::defaults(&["ui.auto_fit=true".to_string()]).unwrap();
+        let mut app = App::new(config, toolpath, PathBuf::from("demo.nc"), lines);
+        app.file_panel.visual = false;
+        let (pan, zoom) = app.selection_fit((1, 2)).unwrap();
+        assert!(zoom > 2.0);
+
+        app.tick(Duration::from_millis(16));
+        assert!(app.view.zoom > 1.0 && app.view.zoom < zoom);
+        for _ in 0..100 {
+            app.tick(Duration::from_millis(16));
+        }
+        assert_eq!(app.view.zoom, zoom);
+        assert_eq!((app.view.pan.x, app.view.pan.y), (pan.x, pan.y));
+    }
+
+    #[test]
+    fn origin_command_shifts_readouts() {
+        let toolpath = Toolpath {
+            segments: vec![segment(Vec3::default(), Vec3::new(10.0, 20.0, -1.0), MoveKind::Feed)],
+            line_segment_ends: vec![1],
+            ..Toolpath::default()
+        };
+        let lines = vec!["G1 X10 Y20 Z-1".to_string()];
+        let mut app = App::new(Config::defaults(&[]).unwrap(), toolpath, PathBuf::from("demo.nc"), lines);
+        app.apply_action(Action::ToggleFocus);
+
+        app.run_command(parse_command("origin").unwrap());
+        assert_eq!(app.display_origin, Vec3::new(10.0, 20.0, 0.0));
+        assert_eq!(app.display_point(Vec3::new(12.0, 20.0, -1.0), 0), Vec3::new(2.0, 0.0, -1.0));
+        app.run_command(parse_command("origin z=-1").unwrap());
+        assert_eq!(app.display_origin, Vec3::new(10.0, 20.0, -1.0));
+        app.run_command(parse_command("origin reset").unwrap());
+        assert_eq!(app.display_origin, Vec3::default());
+    }
+
+    #[test]
+    fn readouts_switch_between_work_and_machine_coordinates() {
+        let toolpath = cnc_gcode::parse_str("G0 X10\nG92 X0\nG0 X5", ParseOptions::default()).unwrap();
+        let lines = ["G0 X10", "G92 X0", "G0 X5"].map(String::from).to_vec();
+        let mut app = App::new(Config::defaults(&[]).unwrap(), toolpath, PathBuf::from("demo.nc"), lines);
+        let end = app.toolpath.segments[1].end;
+        assert_eq!(app.display_point(end, 2), Vec3::new(5.0, 0.0, 0.0));
+        app.apply_action(Action::ToggleMachineCoordinates);
+        assert_eq!(app.display_point(end, 2), Vec3::new(15.0, 0.0, 0.0));
+        assert_eq!(app.notice.as_deref(), Some("readouts in machine coordinates"));
+
+        let options = ParseOptions::default().with_tool_length(1, 100.0);
+        let toolpath = cnc_gcode::parse_str("G43 H1 G0 Z10", options).unwrap();
+        let mut app = App::new(Config::defaults(&[]).unwrap(), toolpath, PathBuf::from("demo.nc"), Vec::new());
+        let end = app.toolpath.segments[0].end;
+        assert_eq!(app.display_point(end, 0), Vec3::new(0.0, 0.0, 10.0));
+        app.apply_action(Action::ToggleMachineCoordinates);
+        assert_eq!(app.display_point(end, 0), Vec3::new(0.0, 0.0, 110.0));
+
+        let program = "G10 L2 P1 X100\nG54 G0 X0 Y0 Z10\nG1 A90 F100\nG2 X10 I5";
+        let toolpath = cnc_gcode::parse_str(program, ParseOptions::default()).unwrap();
+        let mut app = App::new(Config::defaults(&[]).unwrap(), toolpath, PathBuf::from("demo.nc"), Vec::new());
+        let readout = |app: &App, index| {
+            let point = app.display_point(app.machine_position(index), app.segment_line(index).unwrap());
+            Vec3::new((point.x * 1e9).round() / 1e9, (point.y * 1e9).round() / 1e9, (point.z * 1e9).round() / 1e9)
+        };
+        let ends = app.toolpath.line_segment_ends.clone();
+        assert_eq!(readout(&app, ends[2] - 45), Vec3::new(0.0, 0.0, 10.0));
+        assert_eq!(readout(&app, ends[2] - 1), Vec3::new(0.0, 0.0, 10.0));
+        let quarter = (ends[2] + ends[3]) / 2 - 1;
+        assert_eq!(readout(&app, quarter), Vec3::new(5.0, 5.0, 10.0));
+        app.apply_action(Action::ToggleMachineCoordinates);
+        assert_eq!(readout(&app, quarter), Vec3::new(105.0, 5.0, 10.0));
+    }
+
+    #[test]
+    fn timeline_jumps_select_only_the_block_start() {
+        let program = "T1 M6\nG1 X1 F100\nT2 M6\nG1 X2\nG1 X3";
+        let toolpath = cnc_gcode::parse_str(program, ParseOptions::default()).unwrap();
+        let lines = program.lines().map(String::from).collect();
+        let mut app = App::new(Config::defaults(&[]).unwrap(), toolpath, PathBuf::from("demo.nc"), lines);
+        app.apply_action(Action::ToggleTimeline);
+        app.apply_action(Action::PanRight);
+        assert_eq!(app.timeline.cursor, 1);
+        assert_eq!((app.file_panel.anchor, app.file_panel.selected), (3, 3));
+        assert_eq!(app.file_panel.selection_range(app.file_lines.len()), (3, 3));
+    }
+
+    #[test]
+    fn load_summary_lists_the_job_and_closes_on_any_key() {
+        let program = "T2 M6\nG21\nG1 X10 Y5 F600";
+        let toolpath = cnc_gcode::parse_str(program, ParseOptions::default().with_lint(true)).unwrap();
+        let lines = program.lines().map(String::from).collect();
+        let mut app = App::new(Config::defaults(&[]).unwrap(), toolpath, PathBuf::from("demo.nc"), lines);
+        assert!(app.summary.is_none());
+        app.open_load_summary();
+        let summary = app.summary.clone().unwrap();
+        assert!(summary.contains(&"Tools: T2".to_string()));
+        assert!(summary.contains(&"Units: G21 (mm)".to_string()));
+        assert!(summary.ends_with(&["Errors: 0".to_string(), "Warnings: 1".to_string()]));
+        app.apply_action(Action::ToggleMachineCoordinates);
+        assert!(app.summary.is_none() && !app.machine_coordinates);
+
+        let overrides = ["default_machine=\"mill\"", "machine.mill.envelope_min=[0, 0, -10]", "machine.mill.envelope_max=[8, 8, 0]"];
+        let config = Config::defaults(&overrides.map(String::from)).unwrap();
+        let toolpath = cnc_gcode::parse_str(program, ParseOptions::default()).unwrap();
+        let app = App::new(config, toolpath, PathBuf::from("demo.nc"), Vec::new());
+        assert_eq!(app.outside_envelope, 1);
+        let summary = crate::report::load_summary_lines(&app);
+        assert!(summary.iter().any(|line| line.starts_with("Envelope:") && line.ends_with("job exceeds it")));
+    }
+
+    #[test]
+    fn typed_view_values_set_zoom_clip_and_playhead() {
+        let pass = |z: f64| segment(Vec3::new(0.0, 0.0, z), Vec3::new(10.0, 0.0, z), MoveKind::Feed);
+        let toolpath = Toolpath {
+            segments: vec![pass(0.0), pass(-1.0), pass(-2.0), pass(-3.0)],
+            line_segment_ends: vec![4],
+            ..Toolpath::default()
+        };
+        let lines = vec!["G1 X10".to_string()];
+        let mut app = App::new(Config::defaults(&[]).unwrap(), toolpath, PathBuf::from("demo.nc"), lines);
+
+        app.run_command(parse_command("zoom 3").unwrap());
+        assert_eq!(app.view.zoom, 3.0);
+        app.apply_action(Action::UndoView);
+        assert_eq!(app.view.zoom, 1.0);
+
+        app.run_command(parse_command("clip -1.5").unwrap());
+        let hidden: Vec<bool> = (0..4).map(|index| app.segment_hidden(index)).collect();
+        assert_eq!(hidden, [true, true, false, false]);
+        app.display_origin = Vec3::new(0.0, 0.0, -1.0);
+        app.run_command(parse_command("clip -0.5").unwrap());
+        assert_eq!(app.clip_z, Some(-1.5));
+
+        app.display_units = Units::Inches;
+        app.run_command(parse_command("clip 0.5").unwrap());
+        assert_eq!(app.clip_z, Some(11.7));
+        app.run_command(parse_command("grid 0.5").unwrap());
+        assert_eq!(app.grid_spacing, Some(12.7));
+        app.run_command(parse_command("origin x=1 z=-1").unwrap());
+        assert_eq!(app.display_origin, Vec3::new(25.4, 0.0, -25.4));
+
+        app.run_command(parse_command("seek 0:02").unwrap());
+        assert_eq!(app.playback.position, 2.0);
+        assert!(app.playback.active && !app.playback.playing);
+        app.run_command(parse_command("seek 75%").unwrap());
+        assert_eq!(app.playback.position, 3.0);
+        app.run_command(parse_command("seek 99").unwrap());
+        assert_eq!(app.playback.position, 4.0);
+    }
+
+    #[test]
+    fn output_references_jump_the_file_panel() {
+        let lines: Vec<String> = (0..5).map(|line| format!("G1 X{}", line)).collect();
+        let mut app = App::new(Config::defaults(&[]).unwrap(), Toolpath::default(), PathBuf::from("cam/demo.nc"), lines);
+        app.show_output(
+            "lint (exit status: 1)".to_string(),
+            "checking\ncam/demo.nc:4:1: feed too high\nother.nc:2: skipped\ndemo.nc:9: past the end\n",
+        );
+        assert!(app.output.open);
+        assert_eq!(app.output.references, [None, Some(3), None, None]);
+        assert_eq!(app.output.cursor, 1);
+        app.append_output("demo.nc:1: streamed later");
+        assert_eq!(app.output.references[4], Some(0));
+        assert_eq!(app.output.cursor, 1);
+        app.apply_action(Action::PlayFromSelection);
+        assert!(!app.output.open);
+        assert_eq!(app.file_panel.selected, 3);
+    }
+
+    #[test]
+    fn first_pass_mode_hides_later_loop_passes() {
+                let repeat = |segments: std::ops::Range<usize>, first_pass_end| cnc_gcode::Repeat {
+            lines: 0..1,
+            passes: 2,
+            segments,
+            first_pass_end,
+        };
+        let toolpath = Toolpath {
+            segments: (0..6).map(|x| segment(Vec3::new(x as f64, 0.0, 0.0), Vec3::new(x as f64 + 1.0, 0.0, 0.0), MoveKind::Feed)).collect(),
+            line_segment_ends: vec![6],
+            repeats: vec![repeat(1..3, 2), repeat(0..6, 3)],
+            ..Toolpath::default()
+        };
+        let lines = vec!["M98 P100 L2".to_string()];
+        let mut app = App::new(Config::defaults(&[]).unwrap(), toolpath, PathBuf::from("demo.nc"), lines);
+        assert_eq!(app.later_passes.len(), 1);
+        assert_eq!(app.later_passes[0], 2..6);
+
+        app.apply_action(Action::ToggleFirstPass);
+        let hidden: Vec<bool> = (0..6).map(|index| app.segment_hidden(index)).collect();
+        assert_eq!(hidden, [false, false, true, true, true, true]);
+        app.apply_action(Action::ToggleFirstPass);
+        assert!(!app.segment_hidden(4));
+    }
+
+    #[test]
+    fn program_picker_switches_rendered_program() {
+        let path = std::env::temp_dir().join("cnc_view_tui_test_programs.nc");
+        fs::write(&path, "%\nO1000\nG0 X5\nM30\nO2000 (B)\nG0 Y3\nG0 Y4\nM30\n%\n").unwrap();
+        let lines: Vec<String> = fs::read_to_string(&path).unwrap().lines().map(String::from).collect();
+        let programs = ProgramPicker::new(split_programs(&lines));
+        let options = programs.parse_options(ParseOptions::default());
+        let toolpath = cnc_gcode::parse_file_with_options(&path, options).unwrap();
+        let mut app = App::new(Config::defaults(&[]).unwrap(), toolpath, path, lines);
+        assert_eq!(app.programs.active, Some(0));
+        assert_eq!(app.toolpath.segments.len(), 1);
+
+        app.apply_action(Action::TogglePrograms);
+        app.apply_action(Action::LineDown);
+        app.apply_action(Action::TogglePlayback);
+        assert_eq!(app.programs.active, Some(1));
+        assert!(app.programs.open);
+        assert_eq!(app.toolpath.segments.len(), 2);
+        assert_eq!(app.toolpath.segments[0].start, Vec3::new(0.0, 0.0, 0.0));
+        assert_eq!(app.file_panel.selection_range(app.file_lines.len()), (4, 8));
+        assert_eq!(app.notice.as_deref(), Some("showing O2000 (B)"));
+    }
+
+    #[test]
+    fn unwrap_lays_a_moves_along_y() {
+        let path = std::env::temp_dir().join("cnc_view_tui_test_unwrap.nc");
+        fs::write(&path, "G0 Y0 Z10\nG1 A90 F100\n").unwrap();
+        let lines: Vec<String> = fs::read_to_string(&path).unwrap().lines().map(String::from).collect();
+        let toolpath = cnc_gcode::parse_file(&path).unwrap();
+        let mut app = App::new(Config::defaults(&[]).unwrap(), toolpath, path.clone(), lines.clone());
+        app.apply_action(Action::ToggleUnwrap);
+        assert!(!app.unwrap_rotary);
+        assert_eq!(app.notice.as_deref(), Some("set ui.wrap_diameter to unwrap A moves"));
+
+        let config = Config::defaults(&["ui.wrap_diameter=40.0".to_string()]).unwrap();
+        let toolpath = cnc_gcode::parse_file(&path).unwrap();
+        let mut app = App::new(config, toolpath, path, lines);
+        app.apply_action(Action::ToggleUnwrap);
+        let end = app.toolpath.segments.last().unwrap().end;
+        assert!((end.y - std::f64::consts::PI * 10.0).abs() < 1e-9 && end.z == 10.0);
+        app.apply_action(Action::ToggleUnwrap);
+        let end = app.toolpath.segments.last().unwrap().end;
+        assert!((end.y - 10.0).abs() < 1e-9 && end.z.abs() < 1e-9);
+    }
+
+    #[test]
+    fn reload_keeps_the_cursor_and_playhead_on_their_lines() {
+        let path = std::env::temp_dir().join("cnc_view_tui_test_reload.nc");
+        fs::write(&path, "G0 X1\nG1 X2 F100\nG1 X3\nG1 X4\n").unwrap();
+        let lines: Vec<String> = fs::read_to_string(&path).unwrap().lines().map(String::from).collect();
+        let toolpath = cnc_gcode::parse_file(&path).unwrap();
+        let mut app = App::new(Config::defaults(&[]).unwrap(), toolpath, path.clone(), lines);
+        app.apply_action(Action::ToggleFocus);
+        app.apply_action(Action::ToggleVisual);
+        app.apply_action(Action::LineUp);
+        app.apply_action(Action::PlayFromSelection);
+        app.playback.position += 0.5;
+
+        fs::write(&path, "(header)\nG21\nG0 X1\nG1 X2 F100\nG1 X3\nG1 X4\n").unwrap();
+        app.reload();
+        assert_eq!(app.file_panel.selected, 4);
+        assert!(app.playback.playing);
+        assert_eq!(app.playback.position, 2.5);
+    }
+
+    #[test]
+    fn changed_reloads_leave_a_fading_ghost() {
+        let path = std::env::temp_dir().join("cnc_view_tui_test_ghost.nc");
+        fs::write(&path, "G1 X10 F100\n").unwrap();
+        let toolpath = cnc_gcode::parse_file(&path).unwrap();
+        let lines = vec!["G1 X10 F100".to_string()];
+        let config = Config::defaults(&["ui.ghost_seconds=2.0".to_string()]).unwrap();
+        let mut app = App::new(config, toolpath, path.clone(), lines);
+        app.reload();
+        assert!(app.ghost.is_none());
+
+        fs::write(&path, "G1 X20 F100\n").unwrap();
+        app.reload();
+        assert_eq!(app.ghost.as_ref().unwrap().segments[0].end.x, 10.0);
+        app.tick(Duration::from_millis(1500));
+        assert_eq!(app.ghost_fade(), Some(0.3));
+        app.apply_action(Action::DismissGhost);
+        assert!(app.ghost.is_none());
+
+        fs::write(&path, "G1 X30 F100\n").unwrap();
+        app.reload();
+        app.tick(Duration::from_secs(2));
+        assert!(app.ghost.is_none());
+    }
+
+    #[test]
+    fn snap_angle_moves_to_next_increment() {
+        let deg = |angle: f64| (angle.to_degrees() * 1e6).round() / 1e6;
+        assert_eq!(deg(snap_angle(20.0_f64.to_radians(), 1.0)), 30.0);
+        assert_eq!(deg(snap_angle(20.0_f64.to_radians(), -1.0)), 15.0);
+        assert_eq!(deg(snap_angle(30.0_f64.to_radians(), 1.0)), 45.0);
+        assert_eq!(deg(snap_angle(-30.0_f64.to_radians(), -1.0)), -45.0);
+    }
+
+    #[test]
+    fn lod_stride_respects_segment_budget() {
+        let toolpath = Toolpath {
+            segments: vec![segment(Vec3::default(), Vec3::new(1.0, 0.0, 0.0), MoveKind::Feed); 5],
+            line_segment_ends: vec![5],
+            ..Toolpath::default()
+        };
+        let lines = vec!["G1 X1".to_string()];
+        let config = Config::defaults(&["ui.max_segments=2".to_string()]).unwrap();
+        let mut app = App::new(config, toolpath, PathBuf::from("demo.nc"), lines);
+        assert_eq!(app.lod_stride(), 3);
+        assert_eq!(app.lod_runs(0..5), [0..3, 3..5]);
+        app.filter_mask = vec![true, false, true, true, true];
+        assert_eq!(app.lod_runs(0..5), [0..1, 2..5]);
+        app.toolpath.segments[3].kind = MoveKind::Rapid;
+        assert_eq!(app.lod_runs(0..5), [0..1, 2..3, 3..4, 4..5]);
+        app.config.ui.max_segments = 0;
+        assert_eq!(app.lod_stride(), 1);
+    }
+
+    #[test]
+    fn progress_at_line_tracks_segments_and_distance() {
+        let toolpath = Toolpath {
+            segments: vec![
+                segment(Vec3::default(), Vec3::new(1.0, 0.0, 0.0), MoveKind::Feed),
+                segment(Vec3::new(1.0, 0.0, 0.0), Vec3::new(4.0, 0.0, 0.0), MoveKind::Feed),
+            ],
+            line_segment_ends: vec![0, 1, 2],
+            ..Toolpath::default()
+        };
+        let lines = ["G21", "G1 X1 F600", "X4"].map(String::from).to_vec();
+        let app = App::new(Config::defaults(&[]).unwrap(), toolpath, PathBuf::from("demo.nc"), lines);
+
+        let start = app.progress_at_line(0).unwrap();
+        assert_eq!((start.segments, start.distance, start.time), (0.0, 0.0, 0.0));
+        let middle = app.progress_at_line(1).unwrap();
+        assert_eq!(middle.segments, 0.5);
+        assert!((middle.distance - 0.25).abs() < 1e-9);
+        assert!(middle.time > 0.0 && middle.time < 1.0);
+        assert_eq!(app.progress_at_line(2).unwrap().time, 1.0);
+        assert_eq!(app.progress_at_line(3), None);
+    }
 }
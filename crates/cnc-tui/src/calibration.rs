@@ -0,0 +1,189 @@
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::Path;
+
+use anyhow::{anyhow, Context, Result};
+use cnc_gcode::{pause_times, segment_durations, MoveKind, TimeEstimateOptions, Toolpath};
+
+use crate::app::time_options;
+use crate::config::Config;
+use crate::render::format_duration;
+
+const LOG_HEADER: &str = "feed_s,rapid_s,pause_s,actual_s,program";
+
+/// A measured run next to its uncorrected estimate, split by what the factors scale.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RuntimeRecord {
+    pub feed: f64,
+    pub rapid: f64,
+    /// Dwells and tool changes, which the factors leave alone.
+    pub pause: f64,
+    pub actual: f64,
+    pub program: String,
+}
+
+impl RuntimeRecord {
+    pub fn new(toolpath: &Toolpath, options: &TimeEstimateOptions, actual: f64, program: &str) -> Self {
+        let options = TimeEstimateOptions {
+            feed_time_factor: 1.0,
+            rapid_time_factor: 1.0,
+            ..*options
+        };
+        let pauses = pause_times(toolpath, &options);
+        let (mut feed, mut rapid) = (0.0, 0.0);
+        for ((seg, duration), pause) in toolpath.segments.iter().zip(segment_durations(toolpath, &options)).zip(&pauses) {
+            match seg.kind {
+                MoveKind::Rapid => rapid += duration - pause,
+                MoveKind::Feed | MoveKind::Probe => feed += duration - pause,
+            }
+        }
+        Self {
+            feed,
+            rapid,
+            pause: pauses.iter().sum(),
+            actual,
+            program: program.to_string(),
+        }
+    }
+
+    fn parse(line: &str) -> Option<Self> {
+        let mut fields = line.splitn(5, ',');
+        let mut number = || fields.next()?.trim().parse::<f64>().ok();
+        let (feed, rapid, pause, actual) = (number()?, number()?, number()?, number()?);
+        Some(Self {
+            feed,
+            rapid,
+            pause,
+            actual,
+            program: fields.next().unwrap_or_default().to_string(),
+        })
+    }
+}
+
+/// Records in a runtime log; a missing file has none.
+pub fn read_log(path: &Path) -> Result<Vec<RuntimeRecord>> {
+    match std::fs::read_to_string(path) {
+        Ok(text) => Ok(text.lines().filter_map(RuntimeRecord::parse).collect()),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(err) => Err(err).with_context(|| format!("failed to read {}", path.display())),
+    }
+}
+
+fn append_record(path: &Path, record: &RuntimeRecord) -> Result<()> {
+    let new = !path.exists();
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .with_context(|| format!("failed to open {}", path.display()))?;
+    if new {
+        writeln!(file, "{}", LOG_HEADER)?;
+    }
+    writeln!(
+        file,
+        "{:.3},{:.3},{:.3},{:.3},{}",
+        record.feed, record.rapid, record.pause, record.actual, record.program
+    )?;
+    Ok(())
+}
+
+/// Least-squares feed and rapid time factors; one shared factor when the runs cannot tell
+/// feed time from rapid time apart.
+pub fn fit_factors(records: &[RuntimeRecord]) -> Option<(f64, f64)> {
+    let (mut ff, mut fr, mut rr, mut fy, mut ry) = (0.0, 0.0, 0.0, 0.0, 0.0);
+    for record in records {
+        let motion = record.actual - record.pause;
+        ff += record.feed * record.feed;
+        fr += record.feed * record.rapid;
+        rr += record.rapid * record.rapid;
+        fy += record.feed * motion;
+        ry += record.rapid * motion;
+    }
+    let det = ff * rr - fr * fr;
+    if det > 1e-6 * ff * rr {
+        let (feed, rapid) = ((fy * rr - ry * fr) / det, (ry * ff - fy * fr) / det);
+        if feed > 0.0 && rapid > 0.0 {
+            return Some((feed, rapid));
+        }
+    }
+    let total = ff + 2.0 * fr + rr;
+    let shared = (fy + ry) / total;
+    (total > 0.0 && shared > 0.0).then_some((shared, shared))
+}
+
+/// Seconds from `SECONDS`, `M:SS` or `H:MM:SS`.
+pub fn parse_duration(text: &str) -> Result<f64> {
+    let seconds = text
+        .trim()
+        .split(':')
+        .try_fold(0.0, |total, part| part.trim().parse::<f64>().map(|value| total * 60.0 + value));
+    match seconds {
+        Ok(seconds) if seconds > 0.0 => Ok(seconds),
+        _ => Err(anyhow!("invalid runtime '{}': expected seconds, M:SS or H:MM:SS", text)),
+    }
+}
+
+/// Appends a measured runtime to the active machine's `runtime_log` and reports the factors
+/// fitted from every run recorded so far, for `--record-runtime`.
+pub fn record_runtime(config: &Config, toolpath: &Toolpath, file: &Path, actual: &str) -> Result<String> {
+    let machine = config
+        .machine()
+        .ok_or_else(|| anyhow!("--record-runtime needs a machine profile (--machine <NAME>)"))?;
+    let log = machine
+        .runtime_log
+        .as_ref()
+        .ok_or_else(|| anyhow!("machine {}: set runtime_log to record runtimes", machine.name))?;
+    let options = time_options(config);
+    let program = file.file_name().and_then(|name| name.to_str()).unwrap_or("<stdin>");
+    let record = RuntimeRecord::new(toolpath, &options, parse_duration(actual)?, program);
+    append_record(log, &record)?;
+    let estimate = record.feed * options.feed_time_factor + record.rapid * options.rapid_time_factor + record.pause;
+    let mut out = format!(
+        "recorded {} for {} (estimated {}) in {}\n",
+        format_duration(record.actual),
+        program,
+        format_duration(estimate),
+        log.display()
+    );
+    let records = read_log(log)?;
+    match fit_factors(&records) {
+        Some((feed, rapid)) => out.push_str(&format!(
+            "fit from {} run{}; set in [machine.{}]:\nfeed_time_factor = {:.3}\nrapid_time_factor = {:.3}\n",
+            records.len(),
+            if records.len() == 1 { "" } else { "s" },
+            machine.name,
+            feed,
+            rapid
+        )),
+        None => out.push_str("no correction fitted yet\n"),
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fits_feed_and_rapid_factors_from_recorded_runs() {
+        let record = |feed, rapid, pause, actual| RuntimeRecord {
+            feed,
+            rapid,
+            pause,
+            actual,
+            program: "part.nc".to_string(),
+        };
+        let runs = [record(100.0, 20.0, 10.0, 150.0), record(50.0, 40.0, 0.0, 115.0)];
+        let (feed, rapid) = fit_factors(&runs).unwrap();
+        assert!((feed - 1.1).abs() < 1e-9 && (rapid - 1.5).abs() < 1e-9);
+        let (feed, rapid) = fit_factors(&runs[..1]).unwrap();
+        assert!((feed - 140.0 / 120.0).abs() < 1e-9 && feed == rapid);
+        assert_eq!(RuntimeRecord::parse("100.000,20.000,10.000,150.000,part.nc"), Some(runs[0].clone()));
+        assert_eq!(RuntimeRecord::parse(LOG_HEADER), None);
+
+        assert_eq!(parse_duration("754").unwrap(), 754.0);
+        assert_eq!(parse_duration("12:34").unwrap(), 754.0);
+        assert_eq!(parse_duration("1:02:03").unwrap(), 3723.0);
+        assert!(parse_duration("soon").is_err());
+    }
+}
@@ -0,0 +1,58 @@
+use std::io::{self, Write};
+use std::process::{Command, Stdio};
+
+use crate::graphics::base64;
+
+/// Platform clipboard commands, tried in order when not running over SSH.
+const TOOLS: &[(&str, &[&str])] = &[
+    ("pbcopy", &[]),
+    ("wl-copy", &[]),
+    ("xclip", &["-selection", "clipboard"]),
+    ("xsel", &["--clipboard", "--input"]),
+    ("clip.exe", &[]),
+];
+
+/// Copies `text` with a platform clipboard tool, falling back to an OSC 52 sequence on `out`
+/// over SSH or when no tool works.
+pub fn copy(out: &mut impl Write, text: &str) -> io::Result<()> {
+    if std::env::var_os("SSH_CONNECTION").is_none() && TOOLS.iter().any(|(program, args)| run_tool(program, args, text)) {
+        return Ok(());
+    }
+    out.write_all(osc52(text, std::env::var_os("TMUX").is_some()).as_bytes())?;
+    out.flush()
+}
+
+fn run_tool(program: &str, args: &[&str], text: &str) -> bool {
+    let Ok(mut child) = Command::new(program)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+    else {
+        return false;
+    };
+    let written = child.stdin.take().is_some_and(|mut stdin| stdin.write_all(text.as_bytes()).is_ok());
+    child.wait().is_ok_and(|status| status.success()) && written
+}
+
+/// OSC 52 "set clipboard" sequence; tmux only forwards it wrapped in a DCS passthrough.
+fn osc52(text: &str, tmux: bool) -> String {
+    let sequence = format!("\x1b]52;c;{}\x07", base64(text.as_bytes()));
+    if tmux {
+        format!("\x1bPtmux;{}\x1b\\", sequence.replace('\x1b', "\x1b\x1b"))
+    } else {
+        sequence
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn osc52_encodes_and_wraps_for_tmux() {
+        assert_eq!(osc52("G0", false), "\x1b]52;c;RzA=\x07");
+        assert_eq!(osc52("G0", true), "\x1bPtmux;\x1b\x1b]52;c;RzA=\x07\x1b\\");
+    }
+}
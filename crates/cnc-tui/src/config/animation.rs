@@ -4,18 +4,27 @@ use serde::Deserialize;
 #[derive(Debug, Clone)]
 pub struct AnimationSettings {
     pub speed_segments_per_sec: f64,
+    /// Pause at M1 as well as M0, like a machine with its optional stop switch on.
+    pub optional_stop: bool,
+    /// Play at this multiple of estimated machine time instead of a fixed segment rate, so
+    /// dwells and tool changes hold the playhead.
+    pub time_scale: Option<f64>,
 }
 
 #[derive(Debug, Clone, Deserialize)]
 #[serde(default)]
 pub(crate) struct AnimationConfig {
     speed_segments_per_sec: f64,
+    optional_stop: bool,
+    time_scale: Option<f64>,
 }
 
 impl Default for AnimationConfig {
     fn default() -> Self {
         Self {
             speed_segments_per_sec: 800.0,
+            optional_stop: false,
+            time_scale: None,
         }
     }
 }
@@ -27,8 +36,13 @@ impl TryFrom<AnimationConfig> for AnimationSettings {
         if value.speed_segments_per_sec <= 0.0 {
             return Err(anyhow!("animation speed must be positive"));
         }
+        if value.time_scale.is_some_and(|scale| scale <= 0.0) {
+            return Err(anyhow!("animation time_scale must be positive"));
+        }
         Ok(Self {
             speed_segments_per_sec: value.speed_segments_per_sec,
+            optional_stop: value.optional_stop,
+            time_scale: value.time_scale,
         })
     }
 }
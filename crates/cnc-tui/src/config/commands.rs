@@ -0,0 +1,65 @@
+use anyhow::{anyhow, Result};
+use serde::Deserialize;
+use std::path::Path;
+
+use super::parse::parse_key_spec;
+use super::KeySpec;
+
+/// An external program bound to a key. It gets the terminal handed over, or with `capture`
+/// runs in the background while its output streams into the output panel.
+#[derive(Debug, Clone)]
+pub struct CustomCommand {
+    pub name: String,
+    pub key: KeySpec,
+    /// Program and arguments; `{file}`, `{line}`, `{last}` and `{selection}` are expanded.
+    pub run: Vec<String>,
+    /// Re-read the file after the program exits successfully.
+    pub reload: bool,
+    /// Run in the background and stream the program's stdout and stderr into the output panel.
+    pub capture: bool,
+}
+
+impl CustomCommand {
+    pub fn uses_selection(&self) -> bool {
+        self.run.iter().any(|arg| arg.contains("{selection}"))
+    }
+
+    /// `run` with the placeholders filled in; `first` and `last` are 1-based selected lines.
+    pub fn args(&self, file: &Path, first: usize, last: usize, selection: Option<&Path>) -> Vec<String> {
+        let selection = selection.map(|path| path.display().to_string()).unwrap_or_default();
+        self.run
+            .iter()
+            .map(|arg| {
+                arg.replace("{file}", &file.display().to_string())
+                    .replace("{line}", &first.to_string())
+                    .replace("{last}", &last.to_string())
+                    .replace("{selection}", &selection)
+            })
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub(crate) struct CommandConfig {
+    key: String,
+    run: Vec<String>,
+    reload: bool,
+    capture: bool,
+}
+
+impl CommandConfig {
+    pub(crate) fn into_command(self, name: &str) -> Result<CustomCommand> {
+        if self.run.first().is_none_or(|program| program.trim().is_empty()) {
+            return Err(anyhow!("command {}: run must name a program", name));
+        }
+        let key = parse_key_spec(&self.key).map_err(|err| anyhow!("command {}: {}", name, err))?;
+        Ok(CustomCommand {
+            name: name.to_string(),
+            key,
+            run: self.run,
+            reload: self.reload,
+            capture: self.capture,
+        })
+    }
+}
@@ -0,0 +1,43 @@
+use anyhow::{anyhow, Result};
+use serde::Deserialize;
+
+/// Recommended ranges for chip load (mm/tooth) and surface speed (m/min).
+#[derive(Debug, Clone, Default)]
+pub struct CuttingSettings {
+    pub chip_load_min: Option<f64>,
+    pub chip_load_max: Option<f64>,
+    pub surface_speed_min: Option<f64>,
+    pub surface_speed_max: Option<f64>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub(crate) struct CuttingConfig {
+    chip_load_min: Option<f64>,
+    chip_load_max: Option<f64>,
+    surface_speed_min: Option<f64>,
+    surface_speed_max: Option<f64>,
+}
+
+impl TryFrom<CuttingConfig> for CuttingSettings {
+    type Error = anyhow::Error;
+
+    fn try_from(value: CuttingConfig) -> Result<Self> {
+        for (name, min, max) in [
+            ("chip_load", value.chip_load_min, value.chip_load_max),
+            ("surface_speed", value.surface_speed_min, value.surface_speed_max),
+        ] {
+            if let (Some(min), Some(max)) = (min, max)
+                && min > max
+            {
+                return Err(anyhow!("cutting: {}_min exceeds {}_max", name, name));
+            }
+        }
+        Ok(Self {
+            chip_load_min: value.chip_load_min,
+            chip_load_max: value.chip_load_max,
+            surface_speed_min: value.surface_speed_min,
+            surface_speed_max: value.surface_speed_max,
+        })
+    }
+}
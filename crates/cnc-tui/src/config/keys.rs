@@ -29,9 +29,116 @@ pub struct KeyBindings {
     pub toggle_help: KeySpec,
     pub toggle_visual: KeySpec,
     pub toggle_marker: KeySpec,
+    pub cycle_color_mode: KeySpec,
+    pub cycle_depth_graph: KeySpec,
+    pub toggle_operations: KeySpec,
+    pub toggle_timeline: KeySpec,
+    pub restart_block: KeySpec,
+    pub toggle_focus_selection: KeySpec,
+    pub undo_view: KeySpec,
+    pub redo_view: KeySpec,
+    pub toggle_auto_fit: KeySpec,
+    pub open_palette: KeySpec,
+    pub snap_rotate_left: KeySpec,
+    pub snap_rotate_right: KeySpec,
+    pub snap_rotate_up: KeySpec,
+    pub snap_rotate_down: KeySpec,
+    pub z_scale_up: KeySpec,
+    pub z_scale_down: KeySpec,
+    pub toggle_display_units: KeySpec,
+    pub toggle_machine_coordinates: KeySpec,
+    pub toggle_unwrap: KeySpec,
+    pub toggle_programs: KeySpec,
+    pub next_match: KeySpec,
+    pub toggle_corners: KeySpec,
+    pub toggle_depth_map: KeySpec,
+    pub export_report: KeySpec,
+    pub copy_lines: KeySpec,
+    pub copy_point: KeySpec,
+    pub play_from_selection: KeySpec,
+    pub dismiss_ghost: KeySpec,
+    pub toggle_first_pass: KeySpec,
 }
 
 impl KeyBindings {
+    /// Config names and specs of every binding, in lookup order.
+    pub fn bindings(&self) -> Vec<(&'static str, &KeySpec)> {
+        vec![
+            ("quit", &self.quit),
+            ("pan_left", &self.pan_left),
+            ("pan_right", &self.pan_right),
+            ("pan_up", &self.pan_up),
+            ("pan_down", &self.pan_down),
+            ("zoom_in", &self.zoom_in),
+            ("zoom_out", &self.zoom_out),
+            ("rotate_left", &self.rotate_left),
+            ("rotate_right", &self.rotate_right),
+            ("rotate_up", &self.rotate_up),
+            ("rotate_down", &self.rotate_down),
+            ("fit", &self.fit),
+            ("reset_view", &self.reset_view),
+            ("toggle_playback", &self.toggle_playback),
+            ("toggle_focus", &self.toggle_focus),
+            ("line_up", &self.line_up),
+            ("line_down", &self.line_down),
+            ("page_up", &self.page_up),
+            ("page_down", &self.page_down),
+            ("toggle_projection", &self.toggle_projection),
+            ("toggle_help", &self.toggle_help),
+            ("toggle_visual", &self.toggle_visual),
+            ("toggle_marker", &self.toggle_marker),
+            ("cycle_color_mode", &self.cycle_color_mode),
+            ("cycle_depth_graph", &self.cycle_depth_graph),
+            ("toggle_operations", &self.toggle_operations),
+            ("toggle_timeline", &self.toggle_timeline),
+            ("restart_block", &self.restart_block),
+            ("toggle_focus_selection", &self.toggle_focus_selection),
+            ("undo_view", &self.undo_view),
+            ("redo_view", &self.redo_view),
+            ("toggle_auto_fit", &self.toggle_auto_fit),
+            ("open_palette", &self.open_palette),
+            ("snap_rotate_left", &self.snap_rotate_left),
+            ("snap_rotate_right", &self.snap_rotate_right),
+            ("snap_rotate_up", &self.snap_rotate_up),
+            ("snap_rotate_down", &self.snap_rotate_down),
+            ("z_scale_up", &self.z_scale_up),
+            ("z_scale_down", &self.z_scale_down),
+            ("toggle_display_units", &self.toggle_display_units),
+            ("toggle_machine_coordinates", &self.toggle_machine_coordinates),
+            ("toggle_unwrap", &self.toggle_unwrap),
+            ("toggle_programs", &self.toggle_programs),
+            ("next_match", &self.next_match),
+            ("toggle_corners", &self.toggle_corners),
+            ("toggle_depth_map", &self.toggle_depth_map),
+            ("export_report", &self.export_report),
+            ("copy_lines", &self.copy_lines),
+            ("copy_point", &self.copy_point),
+            ("play_from_selection", &self.play_from_selection),
+            ("dismiss_ghost", &self.dismiss_ghost),
+            ("toggle_first_pass", &self.toggle_first_pass),
+        ]
+    }
+
+    /// Keys bound to more than one action, with the action names; only the first one fires.
+    pub fn conflicts(&self) -> Vec<(String, Vec<&'static str>)> {
+        let bindings = self.bindings();
+        let mut conflicts: Vec<(String, Vec<&'static str>)> = Vec::new();
+        for (idx, (_, spec)) in bindings.iter().enumerate() {
+            if bindings[..idx].iter().any(|(_, other)| other == spec) {
+                continue;
+            }
+            let names: Vec<&'static str> = bindings[idx..]
+                .iter()
+                .filter(|(_, other)| other == spec)
+                .map(|(other_name, _)| *other_name)
+                .collect();
+            if names.len() > 1 {
+                conflicts.push((spec.label(), names));
+            }
+        }
+        conflicts
+    }
+
     pub fn action_for(&self, key: KeyEvent) -> Option<Action> {
         if self.quit.matches(key) {
             return Some(Action::Quit);
@@ -102,6 +209,93 @@ impl KeyBindings {
         if self.toggle_marker.matches(key) {
             return Some(Action::ToggleMarker);
         }
+        if self.cycle_color_mode.matches(key) {
+            return Some(Action::CycleColorMode);
+        }
+        if self.cycle_depth_graph.matches(key) {
+            return Some(Action::CycleDepthGraph);
+        }
+        if self.toggle_operations.matches(key) {
+            return Some(Action::ToggleOperations);
+        }
+        if self.toggle_timeline.matches(key) {
+            return Some(Action::ToggleTimeline);
+        }
+        if self.restart_block.matches(key) {
+            return Some(Action::RestartBlock);
+        }
+        if self.toggle_focus_selection.matches(key) {
+            return Some(Action::ToggleFocusSelection);
+        }
+        if self.undo_view.matches(key) {
+            return Some(Action::UndoView);
+        }
+        if self.redo_view.matches(key) {
+            return Some(Action::RedoView);
+        }
+        if self.toggle_auto_fit.matches(key) {
+            return Some(Action::ToggleAutoFit);
+        }
+        if self.open_palette.matches(key) {
+            return Some(Action::OpenPalette);
+        }
+        if self.snap_rotate_left.matches(key) {
+            return Some(Action::SnapRotateLeft);
+        }
+        if self.snap_rotate_right.matches(key) {
+            return Some(Action::SnapRotateRight);
+        }
+        if self.snap_rotate_up.matches(key) {
+            return Some(Action::SnapRotateUp);
+        }
+        if self.snap_rotate_down.matches(key) {
+            return Some(Action::SnapRotateDown);
+        }
+        if self.z_scale_up.matches(key) {
+            return Some(Action::ZScaleUp);
+        }
+        if self.z_scale_down.matches(key) {
+            return Some(Action::ZScaleDown);
+        }
+        if self.toggle_display_units.matches(key) {
+            return Some(Action::ToggleDisplayUnits);
+        }
+        if self.toggle_machine_coordinates.matches(key) {
+            return Some(Action::ToggleMachineCoordinates);
+        }
+        if self.toggle_unwrap.matches(key) {
+            return Some(Action::ToggleUnwrap);
+        }
+        if self.toggle_programs.matches(key) {
+            return Some(Action::TogglePrograms);
+        }
+        if self.next_match.matches(key) {
+            return Some(Action::NextMatch);
+        }
+        if self.toggle_corners.matches(key) {
+            return Some(Action::ToggleCorners);
+        }
+        if self.toggle_depth_map.matches(key) {
+            return Some(Action::ToggleDepthMap);
+        }
+        if self.export_report.matches(key) {
+            return Some(Action::ExportReport);
+        }
+        if self.copy_lines.matches(key) {
+            return Some(Action::CopyLines);
+        }
+        if self.copy_point.matches(key) {
+            return Some(Action::CopyPoint);
+        }
+        if self.play_from_selection.matches(key) {
+            return Some(Action::PlayFromSelection);
+        }
+        if self.dismiss_ghost.matches(key) {
+            return Some(Action::DismissGhost);
+        }
+        if self.toggle_first_pass.matches(key) {
+            return Some(Action::ToggleFirstPass);
+        }
         None
     }
 }
@@ -131,9 +325,38 @@ pub enum Action {
     ToggleHelp,
     ToggleVisual,
     ToggleMarker,
+    CycleColorMode,
+    CycleDepthGraph,
+    ToggleOperations,
+    ToggleTimeline,
+    RestartBlock,
+    ToggleFocusSelection,
+    UndoView,
+    RedoView,
+    ToggleAutoFit,
+    OpenPalette,
+    SnapRotateLeft,
+    SnapRotateRight,
+    SnapRotateUp,
+    SnapRotateDown,
+    ZScaleUp,
+    ZScaleDown,
+    ToggleDisplayUnits,
+    ToggleMachineCoordinates,
+    ToggleUnwrap,
+    TogglePrograms,
+    NextMatch,
+    ToggleCorners,
+    ToggleDepthMap,
+    ExportReport,
+    CopyLines,
+    CopyPoint,
+    PlayFromSelection,
+    DismissGhost,
+    ToggleFirstPass,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct KeySpec {
     pub code: KeyCode,
     pub modifiers: KeyModifiers,
@@ -141,7 +364,48 @@ pub struct KeySpec {
 
 impl KeySpec {
     pub fn matches(&self, key: KeyEvent) -> bool {
-        self.code == key.code && self.modifiers == key.modifiers
+        if self.modifiers != key.modifiers {
+            return false;
+        }
+        match (self.code, key.code) {
+            // Terminals report shifted letters in upper case.
+            (KeyCode::Char(a), KeyCode::Char(b)) if self.modifiers.contains(KeyModifiers::SHIFT) => {
+                a.eq_ignore_ascii_case(&b)
+            }
+            (a, b) => a == b,
+        }
+    }
+
+    /// The binding in config syntax, e.g. `ctrl+r` or `space`.
+    pub fn label(&self) -> String {
+        let mut label = String::new();
+        for (modifier, name) in [
+            (KeyModifiers::CONTROL, "ctrl+"),
+            (KeyModifiers::ALT, "alt+"),
+            (KeyModifiers::SHIFT, "shift+"),
+        ] {
+            if self.modifiers.contains(modifier) {
+                label.push_str(name);
+            }
+        }
+        match self.code {
+            KeyCode::Char(' ') => label.push_str("space"),
+            KeyCode::Char(ch) => label.push(ch),
+            KeyCode::Esc => label.push_str("esc"),
+            KeyCode::Enter => label.push_str("enter"),
+            KeyCode::Tab => label.push_str("tab"),
+            KeyCode::Backspace => label.push_str("backspace"),
+            KeyCode::Left => label.push_str("left"),
+            KeyCode::Right => label.push_str("right"),
+            KeyCode::Up => label.push_str("up"),
+            KeyCode::Down => label.push_str("down"),
+            KeyCode::Home => label.push_str("home"),
+            KeyCode::End => label.push_str("end"),
+            KeyCode::PageUp => label.push_str("pageup"),
+            KeyCode::PageDown => label.push_str("pagedown"),
+            other => label.push_str(&format!("{:?}", other).to_ascii_lowercase()),
+        }
+        label
     }
 }
 
@@ -171,6 +435,35 @@ pub(crate) struct KeysConfig {
     toggle_help: String,
     toggle_visual: String,
     toggle_marker: String,
+    cycle_color_mode: String,
+    cycle_depth_graph: String,
+    toggle_operations: String,
+    toggle_timeline: String,
+    restart_block: String,
+    toggle_focus_selection: String,
+    undo_view: String,
+    redo_view: String,
+    toggle_auto_fit: String,
+    open_palette: String,
+    snap_rotate_left: String,
+    snap_rotate_right: String,
+    snap_rotate_up: String,
+    snap_rotate_down: String,
+    z_scale_up: String,
+    z_scale_down: String,
+    toggle_display_units: String,
+    toggle_machine_coordinates: String,
+    toggle_unwrap: String,
+    toggle_programs: String,
+    next_match: String,
+    toggle_corners: String,
+    toggle_depth_map: String,
+    export_report: String,
+    copy_lines: String,
+    copy_point: String,
+    play_from_selection: String,
+    dismiss_ghost: String,
+    toggle_first_pass: String,
 }
 
 impl Default for KeysConfig {
@@ -199,6 +492,35 @@ impl Default for KeysConfig {
             toggle_help: "?".to_string(),
             toggle_visual: "v".to_string(),
             toggle_marker: "m".to_string(),
+            cycle_color_mode: "c".to_string(),
+            cycle_depth_graph: "z".to_string(),
+            toggle_operations: "o".to_string(),
+            toggle_timeline: "t".to_string(),
+            restart_block: "b".to_string(),
+            toggle_focus_selection: "f".to_string(),
+            undo_view: "u".to_string(),
+            redo_view: "ctrl+r".to_string(),
+            toggle_auto_fit: "ctrl+g".to_string(),
+            open_palette: ":".to_string(),
+            snap_rotate_left: "shift+a".to_string(),
+            snap_rotate_right: "shift+d".to_string(),
+            snap_rotate_up: "shift+w".to_string(),
+            snap_rotate_down: "shift+s".to_string(),
+            z_scale_up: "]".to_string(),
+            z_scale_down: "[".to_string(),
+            toggle_display_units: "i".to_string(),
+            toggle_machine_coordinates: "shift+i".to_string(),
+            toggle_unwrap: "shift+u".to_string(),
+            toggle_programs: "shift+p".to_string(),
+            next_match: "n".to_string(),
+            toggle_corners: "x".to_string(),
+            toggle_depth_map: "e".to_string(),
+            export_report: "shift+r".to_string(),
+            copy_lines: "y".to_string(),
+            copy_point: "shift+y".to_string(),
+            play_from_selection: "enter".to_string(),
+            dismiss_ghost: "esc".to_string(),
+            toggle_first_pass: "shift+l".to_string(),
         }
     }
 }
@@ -231,6 +553,62 @@ impl TryFrom<KeysConfig> for KeyBindings {
             toggle_help: parse_key_spec(&value.toggle_help)?,
             toggle_visual: parse_key_spec(&value.toggle_visual)?,
             toggle_marker: parse_key_spec(&value.toggle_marker)?,
+            cycle_color_mode: parse_key_spec(&value.cycle_color_mode)?,
+            cycle_depth_graph: parse_key_spec(&value.cycle_depth_graph)?,
+            toggle_operations: parse_key_spec(&value.toggle_operations)?,
+            toggle_timeline: parse_key_spec(&value.toggle_timeline)?,
+            restart_block: parse_key_spec(&value.restart_block)?,
+            toggle_focus_selection: parse_key_spec(&value.toggle_focus_selection)?,
+            undo_view: parse_key_spec(&value.undo_view)?,
+            redo_view: parse_key_spec(&value.redo_view)?,
+            toggle_auto_fit: parse_key_spec(&value.toggle_auto_fit)?,
+            open_palette: parse_key_spec(&value.open_palette)?,
+            snap_rotate_left: parse_key_spec(&value.snap_rotate_left)?,
+            snap_rotate_right: parse_key_spec(&value.snap_rotate_right)?,
+            snap_rotate_up: parse_key_spec(&value.snap_rotate_up)?,
+            snap_rotate_down: parse_key_spec(&value.snap_rotate_down)?,
+            z_scale_up: parse_key_spec(&value.z_scale_up)?,
+            z_scale_down: parse_key_spec(&value.z_scale_down)?,
+            toggle_display_units: parse_key_spec(&value.toggle_display_units)?,
+            toggle_machine_coordinates: parse_key_spec(&value.toggle_machine_coordinates)?,
+            toggle_unwrap: parse_key_spec(&value.toggle_unwrap)?,
+            toggle_programs: parse_key_spec(&value.toggle_programs)?,
+            next_match: parse_key_spec(&value.next_match)?,
+            toggle_corners: parse_key_spec(&value.toggle_corners)?,
+            toggle_depth_map: parse_key_spec(&value.toggle_depth_map)?,
+            export_report: parse_key_spec(&value.export_report)?,
+            copy_lines: parse_key_spec(&value.copy_lines)?,
+            copy_point: parse_key_spec(&value.copy_point)?,
+            play_from_selection: parse_key_spec(&value.play_from_selection)?,
+            dismiss_ghost: parse_key_spec(&value.dismiss_ghost)?,
+            toggle_first_pass: parse_key_spec(&value.toggle_first_pass)?,
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reports_keys_bound_twice() {
+        let config = KeysConfig {
+            zoom_in: "ctrl+r".to_string(),
+            ..KeysConfig::default()
+        };
+        let bindings = KeyBindings::try_from(config).unwrap();
+        assert_eq!(
+            bindings.conflicts(),
+            [("ctrl+r".to_string(), vec!["zoom_in", "redo_view"])]
+        );
+        assert!(KeyBindings::try_from(KeysConfig::default()).unwrap().conflicts().is_empty());
+    }
+
+    #[test]
+    fn shifted_letters_match_uppercase_events() {
+        let bindings = KeyBindings::try_from(KeysConfig::default()).unwrap();
+        let key = |ch, modifiers| KeyEvent::new(KeyCode::Char(ch), modifiers);
+        assert_eq!(bindings.action_for(key('A', KeyModifiers::SHIFT)), Some(Action::SnapRotateLeft));
+        assert_eq!(bindings.action_for(key('a', KeyModifiers::NONE)), Some(Action::RotateLeft));
+    }
+}
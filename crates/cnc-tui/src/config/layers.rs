@@ -0,0 +1,75 @@
+use anyhow::{anyhow, Result};
+
+/// Recursively merges `overlay` into `base`; tables merge key by key, other values replace.
+pub fn merge_tables(base: &mut toml::Table, overlay: toml::Table) {
+    for (key, value) in overlay {
+        match (base.get_mut(&key), value) {
+            (Some(toml::Value::Table(existing)), toml::Value::Table(incoming)) => {
+                merge_tables(existing, incoming);
+            }
+            (_, value) => {
+                base.insert(key, value);
+            }
+        }
+    }
+}
+
+/// Applies a `section.key=value` override. Values are parsed as TOML, falling back to a string.
+pub fn apply_override(table: &mut toml::Table, raw: &str) -> Result<()> {
+    let (path, value) = raw
+        .split_once('=')
+        .ok_or_else(|| anyhow!("override must be key=value: {}", raw))?;
+    let keys: Vec<&str> = path.trim().split('.').map(str::trim).collect();
+    if keys.iter().any(|key| key.is_empty()) {
+        return Err(anyhow!("invalid override key: {}", path));
+    }
+    let value = parse_override_value(value.trim());
+
+    let (last, parents) = keys.split_last().expect("split yields at least one key");
+    let mut current = table;
+    for key in parents {
+        let entry = current
+            .entry(key.to_string())
+            .or_insert_with(|| toml::Value::Table(toml::Table::new()));
+        current = match entry {
+            toml::Value::Table(inner) => inner,
+            _ => return Err(anyhow!("override key is not a table: {}", key)),
+        };
+    }
+    current.insert(last.to_string(), value);
+    Ok(())
+}
+
+fn parse_override_value(raw: &str) -> toml::Value {
+    toml::from_str::<toml::Table>(&format!("value = {}", raw))
+        .ok()
+        .and_then(|mut table| table.remove("value"))
+        .unwrap_or_else(|| toml::Value::String(raw.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn merge_keeps_unrelated_keys() {
+        let mut base: toml::Table =
+            toml::from_str("[theme]\nbackground = \"#000000\"\ngrid = \"#111111\"").unwrap();
+        let overlay: toml::Table = toml::from_str("[theme]\ngrid = \"#222222\"").unwrap();
+        merge_tables(&mut base, overlay);
+        let theme = base["theme"].as_table().unwrap();
+        assert_eq!(theme["background"].as_str(), Some("#000000"));
+        assert_eq!(theme["grid"].as_str(), Some("#222222"));
+    }
+
+    #[test]
+    fn override_parses_typed_and_string_values() {
+        let mut table = toml::Table::new();
+        apply_override(&mut table, "projection.yaw_deg=15").unwrap();
+        apply_override(&mut table, "theme.grid=#333333").unwrap();
+        let projection = table["projection"].as_table().unwrap();
+        assert_eq!(projection["yaw_deg"].as_integer(), Some(15));
+        assert_eq!(table["theme"]["grid"].as_str(), Some("#333333"));
+        assert!(apply_override(&mut table, "missing_equals").is_err());
+    }
+}
@@ -0,0 +1,130 @@
+use anyhow::{anyhow, Result};
+use serde::Deserialize;
+use std::path::PathBuf;
+
+use cnc_geom::{Bounds3, Vec3};
+
+#[derive(Debug, Clone)]
+pub struct MachineProfile {
+    pub name: String,
+    pub envelope: Option<Bounds3>,
+    pub rapid_rate: f64,
+    pub max_feed_rate: Option<f64>,
+    pub acceleration: f64,
+    /// Seconds per M6 tool change.
+    pub tool_change_time: f64,
+    /// Estimate corrections fitted from recorded runtimes.
+    pub feed_time_factor: f64,
+    pub rapid_time_factor: f64,
+    pub spindle_range: Option<(f64, f64)>,
+    pub tool_table: Option<PathBuf>,
+    /// CSV of recorded runtimes appended by `--record-runtime`.
+    pub runtime_log: Option<PathBuf>,
+}
+
+impl MachineProfile {
+    pub fn envelope_contains(&self, p: Vec3) -> bool {
+        let Some(envelope) = self.envelope else {
+            return true;
+        };
+        let eps = 1e-6;
+        p.x >= envelope.min.x - eps
+            && p.y >= envelope.min.y - eps
+            && p.z >= envelope.min.z - eps
+            && p.x <= envelope.max.x + eps
+            && p.y <= envelope.max.y + eps
+            && p.z <= envelope.max.z + eps
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub(crate) struct MachineConfig {
+    envelope_min: Option<[f64; 3]>,
+    envelope_max: Option<[f64; 3]>,
+    rapid_rate: f64,
+    max_feed_rate: Option<f64>,
+    acceleration: f64,
+    tool_change_time: f64,
+    feed_time_factor: f64,
+    rapid_time_factor: f64,
+    spindle_min: Option<f64>,
+    spindle_max: Option<f64>,
+    tool_table: Option<PathBuf>,
+    runtime_log: Option<PathBuf>,
+}
+
+impl Default for MachineConfig {
+    fn default() -> Self {
+        Self {
+            envelope_min: None,
+            envelope_max: None,
+            rapid_rate: 5000.0,
+            max_feed_rate: None,
+            acceleration: 500.0,
+            tool_change_time: 0.0,
+            feed_time_factor: 1.0,
+            rapid_time_factor: 1.0,
+            spindle_min: None,
+            spindle_max: None,
+            tool_table: None,
+            runtime_log: None,
+        }
+    }
+}
+
+impl MachineConfig {
+    pub(crate) fn into_profile(self, name: &str) -> Result<MachineProfile> {
+        if self.rapid_rate <= 0.0 {
+            return Err(anyhow!("machine {}: rapid_rate must be positive", name));
+        }
+        if self.acceleration <= 0.0 {
+            return Err(anyhow!("machine {}: acceleration must be positive", name));
+        }
+        if self.tool_change_time < 0.0 {
+            return Err(anyhow!("machine {}: tool_change_time must not be negative", name));
+        }
+        if self.feed_time_factor <= 0.0 || self.rapid_time_factor <= 0.0 {
+            return Err(anyhow!("machine {}: time factors must be positive", name));
+        }
+        let envelope = match (self.envelope_min, self.envelope_max) {
+            (Some(min), Some(max)) => {
+                let mut bounds = Bounds3::new();
+                bounds.include(Vec3::new(min[0], min[1], min[2]));
+                bounds.include(Vec3::new(max[0], max[1], max[2]));
+                Some(bounds)
+            }
+            (None, None) => None,
+            _ => {
+                return Err(anyhow!(
+                    "machine {}: envelope_min and envelope_max must be set together",
+                    name
+                ));
+            }
+        };
+        let spindle_range = match (self.spindle_min, self.spindle_max) {
+            (None, None) => None,
+            (min, max) => {
+                let min = min.unwrap_or(0.0);
+                let max = max.unwrap_or(f64::INFINITY);
+                if min > max {
+                    return Err(anyhow!("machine {}: spindle_min exceeds spindle_max", name));
+                }
+                Some((min, max))
+            }
+        };
+        Ok(MachineProfile {
+            name: name.to_string(),
+            envelope,
+            rapid_rate: self.rapid_rate,
+            max_feed_rate: self.max_feed_rate,
+            acceleration: self.acceleration,
+            tool_change_time: self.tool_change_time,
+            feed_time_factor: self.feed_time_factor,
+            rapid_time_factor: self.rapid_time_factor,
+            spindle_range,
+            tool_table: self.tool_table,
+            runtime_log: self.runtime_log,
+        })
+    }
+}
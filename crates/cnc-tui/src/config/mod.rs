@@ -1,22 +1,36 @@
 use anyhow::{anyhow, Context, Result};
 use serde::Deserialize;
+use std::collections::BTreeMap;
 use std::fs;
 use std::path::{Path, PathBuf};
 
+use layers::{apply_override, merge_tables};
+
 mod animation;
+mod commands;
+mod cutting;
 mod keys;
+mod layers;
+mod machine;
 mod parse;
 mod parser;
 mod projection;
+mod stock;
 mod theme;
+mod tools;
 mod ui;
 
 pub use animation::AnimationSettings;
+pub use commands::CustomCommand;
+pub use cutting::CuttingSettings;
 pub use keys::{Action, KeyBindings, KeySpec};
+pub use machine::MachineProfile;
 pub use parser::ParserSettings;
 pub use projection::ProjectionSettings;
-pub use theme::Theme;
-pub use ui::UiSettings;
+pub use stock::StockSettings;
+pub use theme::{BackgroundPattern, Theme};
+pub use tools::{ToolEntry, ToolTable};
+pub use ui::{Corner, Fade, Renderer, UiSettings};
 
 #[derive(Clone)]
 pub struct Config {
@@ -26,96 +40,331 @@ pub struct Config {
     pub parser: ParserSettings,
     pub animation: AnimationSettings,
     pub ui: UiSettings,
+    pub cutting: CuttingSettings,
+    pub stock: StockSettings,
+    pub machines: BTreeMap<String, MachineProfile>,
+    pub active_machine: Option<String>,
+    pub tools: ToolTable,
+    /// `[command.<name>]` programs, checked after the built-in bindings.
+    pub commands: Vec<CustomCommand>,
 }
 
 impl Config {
-    pub fn load(path: Option<PathBuf>) -> Result<Self> {
-        let cfg_path = match path {
-            Some(p) => Some(p),
-            None => find_default_config(),
-        };
-
-        let file_cfg = match cfg_path {
-            Some(ref p) if p.exists() => {
-                let raw = fs::read_to_string(p)
-                    .with_context(|| format!("failed to read config: {}", p.display()))?;
-                toml::from_str::<FileConfig>(&raw)
-                    .with_context(|| format!("failed to parse config: {}", p.display()))?
+    pub fn load(path: Option<PathBuf>, overrides: &[String]) -> Result<Self> {
+        let mut merged = toml::Table::new();
+        for source in config_sources(path) {
+            if !source.path.exists() {
+                if source.required {
+                    return Err(anyhow!("config file not found: {}", source.path.display()));
+                }
+                continue;
             }
-            Some(p) => {
-                return Err(anyhow!("config file not found: {}", p.display()));
-            }
-            None => FileConfig::default(),
-        };
+            merge_tables(&mut merged, read_layer(&source.path)?);
+        }
+        Self::from_table(merged, overrides)
+    }
+
+    /// Built-in defaults plus `overrides`, without reading any config file.
+    #[cfg(test)]
+    pub fn defaults(overrides: &[String]) -> Result<Self> {
+        Self::from_table(toml::Table::new(), overrides)
+    }
 
+    fn from_table(mut merged: toml::Table, overrides: &[String]) -> Result<Self> {
+        for raw in overrides {
+            apply_override(&mut merged, raw)?;
+        }
+
+        let file_cfg: FileConfig = toml::Value::Table(merged)
+            .try_into()
+            .context("failed to apply config overrides")?;
         file_cfg.try_into()
     }
+
+    pub fn machine(&self) -> Option<&MachineProfile> {
+        self.active_machine
+            .as_ref()
+            .and_then(|name| self.machines.get(name))
+    }
+
+    /// Parser options, with the tool table's diameters for G41/G42 cutter compensation.
+    pub fn parse_options(&self) -> cnc_gcode::ParseOptions {
+        self.tools
+            .tools
+            .iter()
+            .filter_map(|(&tool, entry)| Some((tool, entry.diameter?)))
+            .fold(self.parser.parse_options(), |options, (tool, diameter)| {
+                options.with_tool_diameter(tool, diameter)
+            })
+    }
+
+    pub fn command_for(&self, key: crossterm::event::KeyEvent) -> Option<&CustomCommand> {
+        self.commands.iter().find(|command| command.key.matches(key))
+    }
+
+    pub fn select_machine(&mut self, name: &str) -> Result<()> {
+        let machine = self
+            .machines
+            .get(name)
+            .ok_or_else(|| anyhow!("unknown machine profile: {}", name))?;
+        self.tools = match machine.tool_table.as_ref() {
+            Some(path) => ToolTable::load(path)?,
+            None => ToolTable::default(),
+        };
+        self.active_machine = Some(name.to_string());
+        Ok(())
+    }
+}
+
+const CONFIG_ENV: &str = "NC_VIEW_CONFIG";
+const APP_DIR: &str = "cnc_view_tui";
+
+struct ConfigSource {
+    path: PathBuf,
+    required: bool,
+}
+
+/// Config layers from lowest to highest precedence: system, user, project, `-c`.
+fn config_sources(explicit: Option<PathBuf>) -> Vec<ConfigSource> {
+    let mut sources: Vec<ConfigSource> = system_config_dirs(env_path)
+        .into_iter()
+        .map(|dir| ConfigSource {
+            path: dir.join(APP_DIR).join("config.toml"),
+            required: false,
+        })
+        .collect();
+
+    if let Some(path) = env_path(CONFIG_ENV) {
+        sources.push(ConfigSource {
+            path,
+            required: true,
+        });
+    } else if let Some(path) = user_config_paths().into_iter().find(|candidate| candidate.exists()) {
+        sources.push(ConfigSource {
+            path,
+            required: false,
+        });
+    }
+
+    if let Ok(dir) = std::env::current_dir() {
+        sources.push(ConfigSource {
+            path: dir.join("cnc_view_tui.toml"),
+            required: false,
+        });
+    }
+
+    if let Some(path) = explicit {
+        sources.push(ConfigSource {
+            path,
+            required: true,
+        });
+    }
+    sources
 }
 
-fn find_default_config() -> Option<PathBuf> {
-    let cwd = std::env::current_dir().ok();
-    if let Some(dir) = cwd {
-        let candidate = dir.join("cnc_view_tui.toml");
-        if candidate.exists() {
-            return Some(candidate);
+fn read_layer(path: &Path) -> Result<toml::Table> {
+    let raw = fs::read_to_string(path)
+        .with_context(|| format!("failed to read config: {}", path.display()))?;
+    toml::from_str::<FileConfig>(&raw)
+        .with_context(|| format!("failed to parse config: {}", path.display()))?;
+    let mut table = toml::from_str::<toml::Table>(&raw)
+        .with_context(|| format!("failed to parse config: {}", path.display()))?;
+    resolve_machine_paths(&mut table, path.parent().unwrap_or(Path::new("")));
+    Ok(table)
+}
+
+/// Makes relative `tool_table` and `runtime_log` paths relative to `dir`, the directory of the
+/// config file that declares them.
+fn resolve_machine_paths(table: &mut toml::Table, dir: &Path) {
+    let Some(toml::Value::Table(machines)) = table.get_mut("machine") else {
+        return;
+    };
+    for (_, machine) in machines.iter_mut() {
+        let toml::Value::Table(machine) = machine else {
+            continue;
+        };
+        for key in ["tool_table", "runtime_log"] {
+            if let Some(toml::Value::String(path)) = machine.get_mut(key)
+                && Path::new(path.as_str()).is_relative()
+            {
+                *path = dir.join(path.as_str()).to_string_lossy().into_owned();
+            }
         }
     }
+}
 
-    let home = std::env::var("HOME").ok();
-    if let Some(home_dir) = home {
-        let candidate = Path::new(&home_dir)
-            .join(".config")
-            .join("cnc_view_tui")
-            .join("config.toml");
-        if candidate.exists() {
-            return Some(candidate);
+fn env_path(key: &str) -> Option<PathBuf> {
+    std::env::var_os(key)
+        .filter(|value| !value.is_empty())
+        .map(PathBuf::from)
+}
+
+/// The platform config dir (`$XDG_CONFIG_HOME`, `~/Library/Application Support`, `%APPDATA%`),
+/// then `~/.config` where that is somewhere else.
+fn user_config_paths() -> Vec<PathBuf> {
+    let mut paths: Vec<PathBuf> = directories::ProjectDirs::from("", "", APP_DIR)
+        .map(|dirs| dirs.config_dir().join("config.toml"))
+        .into_iter()
+        .collect();
+    if let Some(base) = directories::BaseDirs::new() {
+        let fallback = base.home_dir().join(".config").join(APP_DIR).join("config.toml");
+        if !paths.contains(&fallback) {
+            paths.push(fallback);
         }
     }
+    paths
+}
 
-    None
+fn system_config_dirs(env: impl Fn(&str) -> Option<PathBuf>) -> Vec<PathBuf> {
+    if cfg!(target_os = "windows") {
+        env("PROGRAMDATA").into_iter().collect()
+    } else if cfg!(target_os = "macos") {
+        vec![PathBuf::from("/Library/Application Support")]
+    } else {
+        vec![PathBuf::from("/etc")]
+    }
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Default, Deserialize)]
 #[serde(default)]
 struct FileConfig {
+    default_machine: Option<String>,
+    machine: BTreeMap<String, machine::MachineConfig>,
+    command: BTreeMap<String, commands::CommandConfig>,
     keys: keys::KeysConfig,
     theme: theme::ThemeConfig,
     projection: projection::ProjectionConfig,
     parser: parser::ParserConfig,
     animation: animation::AnimationConfig,
     ui: ui::UiConfig,
-}
-
-impl Default for FileConfig {
-    fn default() -> Self {
-        Self {
-            keys: keys::KeysConfig::default(),
-            theme: theme::ThemeConfig::default(),
-            projection: projection::ProjectionConfig::default(),
-            parser: parser::ParserConfig::default(),
-            animation: animation::AnimationConfig::default(),
-            ui: ui::UiConfig::default(),
-        }
-    }
+    cutting: cutting::CuttingConfig,
+    stock: stock::StockConfig,
 }
 
 impl TryFrom<FileConfig> for Config {
     type Error = anyhow::Error;
 
     fn try_from(value: FileConfig) -> Result<Self> {
-        let keys = value.keys.try_into()?;
+        let keys: KeyBindings = value.keys.try_into()?;
         let theme = value.theme.try_into()?;
         let projection = value.projection.try_into()?;
         let parser = value.parser.try_into()?;
         let animation = value.animation.try_into()?;
         let ui = value.ui.try_into()?;
-        Ok(Self {
+        let cutting = value.cutting.try_into()?;
+        let stock = value.stock.try_into()?;
+        let mut machines = BTreeMap::new();
+        for (name, machine) in value.machine {
+            let profile = machine.into_profile(&name)?;
+            machines.insert(name, profile);
+        }
+        let mut commands: Vec<CustomCommand> = Vec::new();
+        for (name, command) in value.command {
+            let command = command.into_command(&name)?;
+            let taken = keys
+                .bindings()
+                .into_iter()
+                .find(|(_, spec)| **spec == command.key)
+                .map(|(action, _)| action)
+                .or_else(|| commands.iter().find(|other| other.key == command.key).map(|other| other.name.as_str()));
+            if let Some(taken) = taken {
+                return Err(anyhow!("command {}: {} is already bound to {}", name, command.key.label(), taken));
+            }
+            commands.push(command);
+        }
+        let mut config = Self {
             keys,
             theme,
             projection,
             parser,
             animation,
             ui,
-        })
+            cutting,
+            stock,
+            machines,
+            active_machine: None,
+            tools: ToolTable::default(),
+            commands,
+        };
+        if let Some(name) = value.default_machine.as_deref() {
+            config
+                .select_machine(name)
+                .with_context(|| format!("invalid default_machine: {}", name))?;
+        }
+        Ok(config)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+    #[test]
+    fn user_config_lives_in_the_app_dir() {
+        let paths = user_config_paths();
+        assert!(!paths.is_empty());
+        assert!(paths.iter().all(|path| path.ends_with("config.toml") && path.iter().any(|part| part == APP_DIR)));
+    }
+
+    #[test]
+    fn machine_paths_resolve_against_their_config_file() {
+        let dir = std::env::temp_dir().join("cnc_view_tui_test_machine_paths");
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("tools.toml"), "[[tool]]\nnumber = 1\ndiameter = 6.0\n").unwrap();
+        let path = dir.join("config.toml");
+        let layer = "default_machine = \"mill\"\n[machine.mill]\ntool_table = \"tools.toml\"\nruntime_log = \"/var/log/runs.csv\"\n";
+        fs::write(&path, layer).unwrap();
+        let config = Config::load(Some(path), &[]).unwrap();
+        let machine = config.machine().unwrap();
+        assert_eq!(machine.tool_table.as_deref(), Some(dir.join("tools.toml").as_path()));
+        assert_eq!(machine.runtime_log.as_deref(), Some(Path::new("/var/log/runs.csv")));
+        assert_eq!(config.tools.get(1).and_then(|tool| tool.diameter), Some(6.0));
+        let toolpath = cnc_gcode::parse_str("T1 M6\nG41 G1 X10 F100", config.parse_options()).unwrap();
+        assert_eq!(toolpath.segments[0].end, cnc_geom::Vec3::new(10.0, 3.0, 0.0));
+    }
+
+    #[test]
+    fn work_offsets_are_keyed_by_g_code() {
+        let config = Config::defaults(&["parser.work_offsets={ G55 = [150, 0, -20], \"G59.1\" = [0.5, 0, 0] }".to_string()]).unwrap();
+        let offsets: Vec<(String, [f64; 3])> = config
+            .parser
+            .work_offsets
+            .iter()
+            .map(|(offset, origin)| (offset.code().to_string(), [origin.x, origin.y, origin.z]))
+            .collect();
+        assert_eq!(offsets, [("G55".to_string(), [150.0, 0.0, -20.0]), ("G59.1".to_string(), [0.5, 0.0, 0.0])]);
+        assert!(Config::defaults(&["parser.work_offsets={ G60 = [0, 0, 0] }".to_string()]).is_err());
+    }
+
+    #[test]
+    fn fade_direction_and_curve_are_configurable() {
+        let load = |set: &[&str]| Config::defaults(&set.iter().map(|item| item.to_string()).collect::<Vec<_>>());
+        let ui = load(&["ui.fade=\"end\"", "ui.fade_exponent=1.5", "ui.fade_min_opacity=0.25"]).unwrap().ui;
+        assert_eq!((ui.fade, ui.fade_exponent, ui.fade_min_opacity), (Fade::End, 1.5, 0.25));
+        assert_eq!(load(&["ui.fade=\"off\""]).unwrap().ui.fade, Fade::Off);
+        assert!(load(&["ui.fade=\"sideways\""]).is_err());
+        assert!(load(&["ui.fade_exponent=0"]).is_err());
+        assert!(load(&["ui.fade_min_opacity=1.5"]).is_err());
+    }
+
+    #[test]
+    fn background_pattern_is_a_theme_setting() {
+        let theme = Config::defaults(&["theme.background_pattern=\"blueprint\"".to_string()]).unwrap().theme;
+        assert_eq!(theme.background_pattern, BackgroundPattern::Blueprint);
+        assert_eq!(Config::defaults(&[]).unwrap().theme.background_pattern, BackgroundPattern::Blank);
+        assert!(Config::defaults(&["theme.background_pattern=\"plaid\"".to_string()]).is_err());
+    }
+
+    #[test]
+    fn custom_commands_expand_placeholders_and_reject_taken_keys() {
+        let load = |set: &str| Config::defaults(&[set.to_string()]);
+        let config = load("command.edit={ key = \"ctrl+e\", run = [\"nvim\", \"+{line}\", \"{file}\"], reload = true }").unwrap();
+        let command = config.command_for(KeyEvent::new(KeyCode::Char('e'), KeyModifiers::CONTROL)).unwrap();
+        assert_eq!((command.name.as_str(), command.reload), ("edit", true));
+        assert_eq!(command.args(Path::new("part.nc"), 12, 14, None), ["nvim", "+12", "part.nc"]);
+        assert!(!command.uses_selection());
+        assert!(load("command.edit={ key = \"q\", run = [\"nvim\"] }").is_err());
+        assert!(load("command.edit={ key = \"ctrl+e\", run = [] }").is_err());
     }
 }
@@ -140,6 +140,82 @@ pub fn parse_marker(raw: &str) -> Result<ratatui::symbols::Marker> {
     }
 }
 
+pub fn parse_renderer(raw: &str) -> Result<super::Renderer> {
+    let value = raw.trim().to_ascii_lowercase();
+    match value.as_str() {
+        "canvas" | "braille" => Ok(super::Renderer::Canvas),
+        "auto" => Ok(super::Renderer::Auto),
+        "kitty" => Ok(super::Renderer::Kitty),
+        "iterm2" | "iterm" => Ok(super::Renderer::Iterm2),
+        "sixel" => Ok(super::Renderer::Sixel),
+        _ => Err(anyhow!("unknown renderer: {}", raw)),
+    }
+}
+
+pub fn parse_corner(raw: &str) -> Result<Option<super::Corner>> {
+    match raw.trim().to_ascii_lowercase().replace('_', "-").as_str() {
+        "top-left" => Ok(Some(super::Corner::TopLeft)),
+        "top-right" => Ok(Some(super::Corner::TopRight)),
+        "bottom-left" => Ok(Some(super::Corner::BottomLeft)),
+        "bottom-right" => Ok(Some(super::Corner::BottomRight)),
+        "off" | "none" | "hidden" => Ok(None),
+        _ => Err(anyhow!("unknown HUD corner: {}", raw)),
+    }
+}
+
+pub fn parse_background_pattern(raw: &str) -> Result<super::BackgroundPattern> {
+    match raw.trim().to_ascii_lowercase().as_str() {
+        "blank" | "none" => Ok(super::BackgroundPattern::Blank),
+        "dots" | "dotted" => Ok(super::BackgroundPattern::Dots),
+        "blueprint" => Ok(super::BackgroundPattern::Blueprint),
+        _ => Err(anyhow!("unknown background_pattern: {} (blank, dots or blueprint)", raw)),
+    }
+}
+
+pub fn parse_fade(raw: &str) -> Result<super::Fade> {
+    match raw.trim().to_ascii_lowercase().as_str() {
+        "start" => Ok(super::Fade::Start),
+        "end" => Ok(super::Fade::End),
+        "off" | "none" => Ok(super::Fade::Off),
+        _ => Err(anyhow!("unknown fade: {} (start, end or off)", raw)),
+    }
+}
+
+pub fn parse_dialect(raw: &str) -> Result<cnc_gcode::Dialect> {
+    match raw.trim().to_ascii_lowercase().as_str() {
+        "generic" => Ok(cnc_gcode::Dialect::Generic),
+        "marlin" => Ok(cnc_gcode::Dialect::Marlin),
+        "linuxcnc" => Ok(cnc_gcode::Dialect::LinuxCnc),
+        "fanuc" => Ok(cnc_gcode::Dialect::Fanuc),
+        _ => Err(anyhow!("unknown dialect: {}", raw)),
+    }
+}
+
+pub fn parse_units(raw: &str) -> Result<cnc_gcode::Units> {
+    match raw.trim().to_ascii_lowercase().as_str() {
+        "mm" | "metric" => Ok(cnc_gcode::Units::Millimeters),
+        "in" | "inch" | "inches" => Ok(cnc_gcode::Units::Inches),
+        _ => Err(anyhow!("unknown units: {}", raw)),
+    }
+}
+
+pub fn parse_plane(raw: &str) -> Result<cnc_gcode::Plane> {
+    match raw.trim().to_ascii_lowercase().as_str() {
+        "xy" | "g17" => Ok(cnc_gcode::Plane::XY),
+        "xz" | "zx" | "g18" => Ok(cnc_gcode::Plane::XZ),
+        "yz" | "g19" => Ok(cnc_gcode::Plane::YZ),
+        _ => Err(anyhow!("unknown plane: {}", raw)),
+    }
+}
+
+pub fn parse_distance_mode(raw: &str) -> Result<cnc_gcode::DistanceMode> {
+    match raw.trim().to_ascii_lowercase().as_str() {
+        "absolute" | "abs" | "g90" => Ok(cnc_gcode::DistanceMode::Absolute),
+        "relative" | "incremental" | "rel" | "g91" => Ok(cnc_gcode::DistanceMode::Relative),
+        _ => Err(anyhow!("unknown distance mode: {}", raw)),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -155,4 +231,18 @@ mod tests {
         assert!(matches!(parse_marker("block").unwrap(), ratatui::symbols::Marker::Block));
         assert!(matches!(parse_marker("bar").unwrap(), ratatui::symbols::Marker::Bar));
     }
+
+    #[test]
+    fn parse_modal_values() {
+        assert_eq!(parse_plane("G18").unwrap(), cnc_gcode::Plane::XZ);
+        assert_eq!(parse_distance_mode("incremental").unwrap(), cnc_gcode::DistanceMode::Relative);
+        assert!(parse_plane("xyz").is_err());
+    }
+
+    #[test]
+    fn parse_corner_values() {
+        assert_eq!(parse_corner("Bottom_Right").unwrap(), Some(crate::config::Corner::BottomRight));
+        assert_eq!(parse_corner("off").unwrap(), None);
+        assert!(parse_corner("middle").is_err());
+    }
 }
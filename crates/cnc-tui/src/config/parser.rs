@@ -1,10 +1,64 @@
+use std::collections::BTreeMap;
+
 use anyhow::{anyhow, Result};
+use cnc_gcode::{ArcTessellation, CommentSyntax, Dialect, DistanceMode, Plane, Units, WorkOffset};
+use cnc_geom::Vec3;
 use serde::Deserialize;
 
+use super::parse::{parse_dialect, parse_distance_mode, parse_plane, parse_units};
+
 #[derive(Debug, Clone)]
 pub struct ParserSettings {
     pub ignore_missing_words: Vec<char>,
     pub ignore_unknown_words: bool,
+    pub arc_radius_tolerance: f64,
+    pub arc_tessellation: ArcTessellation,
+    pub dialect: Dialect,
+    /// The dialect's comment rules with `paren_comments`/`comment_prefixes` applied.
+    pub comments: CommentSyntax,
+    /// Units assumed until the program selects G20/G21.
+    pub units: Units,
+    pub plane: Plane,
+    pub distance_mode: DistanceMode,
+    /// Feed rate (in `units` per minute) assumed until the program's first `F`.
+    pub feed_rate: Option<f64>,
+    pub lenient: bool,
+    pub validate_checksums: bool,
+    pub lint: bool,
+    /// Machine coordinates (mm) of the G54–G59.3 origins the program selects.
+    pub work_offsets: Vec<(WorkOffset, Vec3)>,
+    /// Machine coordinates (mm) G28 and G30 return to.
+    pub g28_position: Vec3,
+    pub g30_position: Vec3,
+    /// Tool length offset registers (mm) for G43/G44 `H`.
+    pub tool_lengths: Vec<(u32, f64)>,
+}
+
+impl ParserSettings {
+    pub fn parse_options(&self) -> cnc_gcode::ParseOptions {
+        let options = cnc_gcode::ParseOptions::with_ignore_missing(self.ignore_missing_words.clone())
+            .with_ignore_unknown_words(self.ignore_unknown_words)
+            .with_arc_radius_tolerance(self.arc_radius_tolerance)
+            .with_arc_tessellation(self.arc_tessellation)
+            .with_dialect(self.dialect)
+            .with_comment_syntax(self.comments.clone())
+            .with_initial_units(self.units)
+            .with_initial_plane(self.plane)
+            .with_initial_distance_mode(self.distance_mode)
+            .with_initial_feed_rate(self.feed_rate)
+            .with_lenient(self.lenient)
+            .with_checksum_validation(self.validate_checksums)
+            .with_lint(self.lint)
+            .with_reference_position(false, self.g28_position)
+            .with_reference_position(true, self.g30_position);
+        let options = self
+            .tool_lengths
+            .iter()
+            .fold(options, |options, &(register, length)| options.with_tool_length(register, length));
+        self.work_offsets
+            .iter()
+            .fold(options, |options, &(offset, origin)| options.with_work_offset(offset, origin))
+    }
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -12,6 +66,23 @@ pub struct ParserSettings {
 pub(crate) struct ParserConfig {
     ignore_missing_words: Vec<String>,
     ignore_unknown_words: bool,
+    arc_radius_tolerance: f64,
+    arc_segment_length: f64,
+    arc_chord_error: f64,
+    dialect: String,
+    paren_comments: Option<bool>,
+    comment_prefixes: Option<Vec<String>>,
+    units: String,
+    plane: String,
+    distance: String,
+    feed: f64,
+    lenient: bool,
+    validate_checksums: bool,
+    lint: bool,
+    work_offsets: BTreeMap<String, [f64; 3]>,
+    g28_position: [f64; 3],
+    g30_position: [f64; 3],
+    tool_lengths: BTreeMap<String, f64>,
 }
 
 impl Default for ParserConfig {
@@ -19,6 +90,23 @@ impl Default for ParserConfig {
         Self {
             ignore_missing_words: vec!["E".to_string()],
             ignore_unknown_words: true,
+            arc_radius_tolerance: 0.005,
+            arc_segment_length: 0.5,
+            arc_chord_error: 0.0,
+            dialect: "generic".to_string(),
+            paren_comments: None,
+            comment_prefixes: None,
+            units: "mm".to_string(),
+            plane: "xy".to_string(),
+            distance: "absolute".to_string(),
+            feed: 0.0,
+            lenient: false,
+            validate_checksums: false,
+            lint: false,
+            work_offsets: BTreeMap::new(),
+            g28_position: [0.0; 3],
+            g30_position: [0.0; 3],
+            tool_lengths: BTreeMap::new(),
         }
     }
 }
@@ -45,9 +133,76 @@ impl TryFrom<ParserConfig> for ParserSettings {
             }
             ignore_missing_words.push(ch.to_ascii_uppercase());
         }
+        if value.arc_radius_tolerance < 0.0 {
+            return Err(anyhow!(
+                "arc_radius_tolerance must be non-negative: {}",
+                value.arc_radius_tolerance
+            ));
+        }
+        if value.arc_segment_length <= 0.0 {
+            return Err(anyhow!(
+                "arc_segment_length must be positive: {}",
+                value.arc_segment_length
+            ));
+        }
+        if value.arc_chord_error < 0.0 {
+            return Err(anyhow!("arc_chord_error must be non-negative: {}", value.arc_chord_error));
+        }
+        if value.feed < 0.0 {
+            return Err(anyhow!("feed must be non-negative: {}", value.feed));
+        }
+        let mut work_offsets = Vec::new();
+        for (code, [x, y, z]) in value.work_offsets {
+            let offset = code
+                .trim()
+                .strip_prefix(['G', 'g'])
+                .and_then(|number| number.parse().ok())
+                .and_then(WorkOffset::from_code)
+                .ok_or_else(|| anyhow!("work_offsets: unknown work offset {} (G54-G59, G59.1-G59.3)", code))?;
+            work_offsets.push((offset, Vec3::new(x, y, z)));
+        }
+        let mut tool_lengths = Vec::new();
+        for (register, length) in value.tool_lengths {
+            let number = register
+                .trim()
+                .trim_start_matches(['H', 'h'])
+                .parse()
+                .map_err(|_| anyhow!("tool_lengths: invalid register {} (expected H1, H2, ...)", register))?;
+            tool_lengths.push((number, length));
+        }
+        let dialect = parse_dialect(&value.dialect)?;
+        let mut comments = CommentSyntax::for_dialect(dialect);
+        if let Some(parentheses) = value.paren_comments {
+            comments.parentheses = parentheses;
+        }
+        if let Some(prefixes) = value.comment_prefixes {
+            if prefixes.iter().any(|prefix| prefix.trim().is_empty()) {
+                return Err(anyhow!("comment_prefixes entries must not be empty"));
+            }
+            comments.line_prefixes = prefixes.iter().map(|prefix| prefix.trim().to_string()).collect();
+        }
         Ok(Self {
             ignore_missing_words,
             ignore_unknown_words: value.ignore_unknown_words,
+            arc_radius_tolerance: value.arc_radius_tolerance,
+            arc_tessellation: if value.arc_chord_error > 0.0 {
+                ArcTessellation::ChordError(value.arc_chord_error)
+            } else {
+                ArcTessellation::Length(value.arc_segment_length)
+            },
+            dialect,
+            comments,
+            units: parse_units(&value.units)?,
+            plane: parse_plane(&value.plane)?,
+            distance_mode: parse_distance_mode(&value.distance)?,
+            feed_rate: (value.feed > 0.0).then_some(value.feed),
+            lenient: value.lenient,
+            validate_checksums: value.validate_checksums,
+            lint: value.lint,
+            work_offsets,
+            g28_position: Vec3::new(value.g28_position[0], value.g28_position[1], value.g28_position[2]),
+            g30_position: Vec3::new(value.g30_position[0], value.g30_position[1], value.g30_position[2]),
+            tool_lengths,
         })
     }
 }
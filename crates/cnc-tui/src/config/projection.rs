@@ -8,6 +8,8 @@ pub struct ProjectionSettings {
     pub mode: ProjectionMode,
     pub yaw_deg: f64,
     pub pitch_deg: f64,
+    /// Z exaggeration applied in projection; readouts keep real coordinates.
+    pub z_scale: f64,
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -16,6 +18,7 @@ pub(crate) struct ProjectionConfig {
     mode: String,
     yaw_deg: f64,
     pitch_deg: f64,
+    z_scale: f64,
 }
 
 impl Default for ProjectionConfig {
@@ -24,6 +27,7 @@ impl Default for ProjectionConfig {
             mode: "perspective".to_string(),
             yaw_deg: -45.0,
             pitch_deg: 70.0,
+            z_scale: 1.0,
         }
     }
 }
@@ -36,10 +40,14 @@ impl TryFrom<ProjectionConfig> for ProjectionSettings {
             .mode
             .parse::<ProjectionMode>()
             .map_err(|err| anyhow!(err))?;
+        if value.z_scale.is_nan() || value.z_scale <= 0.0 {
+            return Err(anyhow!("z_scale must be positive: {}", value.z_scale));
+        }
         Ok(Self {
             mode,
             yaw_deg: value.yaw_deg,
             pitch_deg: value.pitch_deg,
+            z_scale: value.z_scale,
         })
     }
 }
@@ -0,0 +1,63 @@
+use anyhow::{anyhow, Result};
+use serde::Deserialize;
+
+use cnc_geom::{Bounds3, Vec3};
+
+/// Raw stock block used to tell cutting moves from air cuts.
+#[derive(Debug, Clone)]
+pub struct StockSettings {
+    /// Block in program coordinates; `None` disables air-cut detection.
+    pub bounds: Option<Bounds3>,
+    /// Height-map cell size in mm.
+    pub resolution: f64,
+    /// Cutter diameter for tools without one in the tool table, in mm.
+    pub tool_diameter: f64,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub(crate) struct StockConfig {
+    min: Option<[f64; 3]>,
+    max: Option<[f64; 3]>,
+    resolution: f64,
+    tool_diameter: f64,
+}
+
+impl Default for StockConfig {
+    fn default() -> Self {
+        Self {
+            min: None,
+            max: None,
+            resolution: 0.5,
+            tool_diameter: 6.0,
+        }
+    }
+}
+
+impl TryFrom<StockConfig> for StockSettings {
+    type Error = anyhow::Error;
+
+    fn try_from(value: StockConfig) -> Result<Self> {
+        if value.resolution <= 0.0 {
+            return Err(anyhow!("stock: resolution must be positive"));
+        }
+        if value.tool_diameter <= 0.0 {
+            return Err(anyhow!("stock: tool_diameter must be positive"));
+        }
+        let bounds = match (value.min, value.max) {
+            (Some(min), Some(max)) => {
+                let mut bounds = Bounds3::new();
+                bounds.include(Vec3::new(min[0], min[1], min[2]));
+                bounds.include(Vec3::new(max[0], max[1], max[2]));
+                Some(bounds)
+            }
+            (None, None) => None,
+            _ => return Err(anyhow!("stock: min and max must be set together")),
+        };
+        Ok(Self {
+            bounds,
+            resolution: value.resolution,
+            tool_diameter: value.tool_diameter,
+        })
+    }
+}
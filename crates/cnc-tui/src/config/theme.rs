@@ -2,11 +2,21 @@ use anyhow::Result;
 use ratatui::style::Color;
 use serde::Deserialize;
 
-use super::parse::parse_color;
+use super::parse::{parse_background_pattern, parse_color};
 
-#[derive(Debug, Clone)]
+/// Screen-fixed backdrop drawn behind the toolpath, unlike the floor grid.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BackgroundPattern {
+    Blank,
+    Dots,
+    Blueprint,
+}
+
+#[derive(Debug, Clone, Hash)]
 pub struct Theme {
     pub background: Color,
+    pub background_pattern: BackgroundPattern,
+    pub pattern: Color,
     pub foreground: Color,
     pub path_feed: Color,
     pub path_rapid: Color,
@@ -21,12 +31,34 @@ pub struct Theme {
     pub code_comment: Color,
     pub code_label: Color,
     pub code_axis: Color,
+    pub envelope: Color,
+    pub climb: Color,
+    pub conventional: Color,
+    pub diagnostic: Color,
+    pub safe_plane: Color,
+    pub spindle_on: Color,
+    pub coolant_on: Color,
+    pub probe: Color,
+    pub air_cut: Color,
+    pub feed_limited: Color,
+    pub corner: Color,
+    pub work_offset: Color,
+    pub footprint: Color,
+    pub depth_shallow: Color,
+    pub depth_deep: Color,
+    pub trace: Color,
+    pub trace_error: Color,
+    pub program_stop: Color,
+    pub low_rapid: Color,
+    pub ghost: Color,
 }
 
 #[derive(Debug, Clone, Deserialize)]
 #[serde(default)]
 pub(crate) struct ThemeConfig {
     background: String,
+    background_pattern: String,
+    pattern: String,
     foreground: String,
     path_feed: String,
     path_rapid: String,
@@ -41,12 +73,34 @@ pub(crate) struct ThemeConfig {
     code_comment: String,
     code_label: String,
     code_axis: String,
+    envelope: String,
+    climb: String,
+    conventional: String,
+    diagnostic: String,
+    safe_plane: String,
+    spindle_on: String,
+    coolant_on: String,
+    probe: String,
+    air_cut: String,
+    feed_limited: String,
+    corner: String,
+    work_offset: String,
+    footprint: String,
+    depth_shallow: String,
+    depth_deep: String,
+    trace: String,
+    trace_error: String,
+    program_stop: String,
+    low_rapid: String,
+    ghost: String,
 }
 
 impl Default for ThemeConfig {
     fn default() -> Self {
         Self {
             background: "#1e1e2e".to_string(),
+            background_pattern: "blank".to_string(),
+            pattern: "#313244".to_string(),
             foreground: "#cdd6f4".to_string(),
             path_feed: "#89b4fa".to_string(),
             path_rapid: "#6c7086".to_string(),
@@ -61,6 +115,26 @@ impl Default for ThemeConfig {
             code_comment: "#6c7086".to_string(),
             code_label: "#f9e2af".to_string(),
             code_axis: "#94e2d5".to_string(),
+            envelope: "#f9e2af".to_string(),
+            climb: "#a6e3a1".to_string(),
+            conventional: "#f38ba8".to_string(),
+            diagnostic: "#f38ba8".to_string(),
+            safe_plane: "#74c7ec".to_string(),
+            spindle_on: "#a6e3a1".to_string(),
+            coolant_on: "#89dceb".to_string(),
+            probe: "#f5c2e7".to_string(),
+            air_cut: "#fab387".to_string(),
+            feed_limited: "#f9e2af".to_string(),
+            corner: "#eba0ac".to_string(),
+            work_offset: "#b4befe".to_string(),
+            footprint: "#f9e2af".to_string(),
+            depth_shallow: "#f9e2af".to_string(),
+            depth_deep: "#8839ef".to_string(),
+            trace: "#94e2d5".to_string(),
+            trace_error: "#f38ba8".to_string(),
+            program_stop: "#f9e2af".to_string(),
+            low_rapid: "#f38ba8".to_string(),
+            ghost: "#9399b2".to_string(),
         }
     }
 }
@@ -71,6 +145,8 @@ impl TryFrom<ThemeConfig> for Theme {
     fn try_from(value: ThemeConfig) -> Result<Self> {
         Ok(Self {
             background: parse_color(&value.background)?,
+            background_pattern: parse_background_pattern(&value.background_pattern)?,
+            pattern: parse_color(&value.pattern)?,
             foreground: parse_color(&value.foreground)?,
             path_feed: parse_color(&value.path_feed)?,
             path_rapid: parse_color(&value.path_rapid)?,
@@ -85,6 +161,26 @@ impl TryFrom<ThemeConfig> for Theme {
             code_comment: parse_color(&value.code_comment)?,
             code_label: parse_color(&value.code_label)?,
             code_axis: parse_color(&value.code_axis)?,
+            envelope: parse_color(&value.envelope)?,
+            climb: parse_color(&value.climb)?,
+            conventional: parse_color(&value.conventional)?,
+            diagnostic: parse_color(&value.diagnostic)?,
+            safe_plane: parse_color(&value.safe_plane)?,
+            spindle_on: parse_color(&value.spindle_on)?,
+            coolant_on: parse_color(&value.coolant_on)?,
+            probe: parse_color(&value.probe)?,
+            air_cut: parse_color(&value.air_cut)?,
+            feed_limited: parse_color(&value.feed_limited)?,
+            corner: parse_color(&value.corner)?,
+            work_offset: parse_color(&value.work_offset)?,
+            footprint: parse_color(&value.footprint)?,
+            depth_shallow: parse_color(&value.depth_shallow)?,
+            depth_deep: parse_color(&value.depth_deep)?,
+            trace: parse_color(&value.trace)?,
+            trace_error: parse_color(&value.trace_error)?,
+            program_stop: parse_color(&value.program_stop)?,
+            low_rapid: parse_color(&value.low_rapid)?,
+            ghost: parse_color(&value.ghost)?,
         })
     }
 }
@@ -0,0 +1,143 @@
+use anyhow::{anyhow, Context, Result};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ToolEntry {
+    /// Cutter diameter in mm.
+    pub diameter: Option<f64>,
+    pub flutes: Option<u32>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ToolTable {
+    pub tools: BTreeMap<u32, ToolEntry>,
+}
+
+impl ToolTable {
+    /// Loads a TOML table (`[[tool]] number = 1`, `diameter`, `flutes`) or a LinuxCNC `.tbl` file.
+    pub fn load(path: &Path) -> Result<Self> {
+        let raw = fs::read_to_string(path)
+            .with_context(|| format!("failed to read tool table: {}", path.display()))?;
+        let is_tbl = path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("tbl"));
+        let table = if is_tbl {
+            parse_linuxcnc_table(&raw)
+        } else {
+            parse_toml_table(&raw)
+        };
+        table.with_context(|| format!("failed to parse tool table: {}", path.display()))
+    }
+
+    pub fn contains(&self, tool: u32) -> bool {
+        self.tools.contains_key(&tool)
+    }
+
+    pub fn get(&self, tool: u32) -> Option<&ToolEntry> {
+        self.tools.get(&tool)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct ToolTableFile {
+    #[serde(default)]
+    tool: Vec<ToolFileEntry>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ToolFileEntry {
+    number: u32,
+    diameter: Option<f64>,
+    flutes: Option<u32>,
+}
+
+fn parse_toml_table(raw: &str) -> Result<ToolTable> {
+    let file: ToolTableFile = toml::from_str(raw)?;
+    let mut tools = BTreeMap::new();
+    for entry in file.tool {
+        if entry.diameter.is_some_and(|diameter| diameter <= 0.0) {
+            return Err(anyhow!("tool {}: diameter must be positive", entry.number));
+        }
+        if entry.flutes == Some(0) {
+            return Err(anyhow!("tool {}: flutes must be positive", entry.number));
+        }
+        tools.insert(
+            entry.number,
+            ToolEntry {
+                diameter: entry.diameter,
+                flutes: entry.flutes,
+            },
+        );
+    }
+    Ok(ToolTable { tools })
+}
+
+fn parse_linuxcnc_table(raw: &str) -> Result<ToolTable> {
+    let mut tools = BTreeMap::new();
+    for (idx, line) in raw.lines().enumerate() {
+        let (fields, comment) = line.split_once(';').unwrap_or((line, ""));
+        let mut number = None;
+        let mut entry = ToolEntry {
+            flutes: comment_flutes(comment),
+            ..ToolEntry::default()
+        };
+        for token in fields.split_whitespace() {
+            let mut chars = token.chars();
+            let letter = chars.next().map(|ch| ch.to_ascii_uppercase());
+            let value = chars.as_str();
+            match letter {
+                Some('T') => {
+                    number = Some(value.parse::<u32>().map_err(|_| {
+                        anyhow!("line {}: invalid tool number: {}", idx + 1, token)
+                    })?);
+                }
+                Some('D') => {
+                    entry.diameter = Some(value.parse::<f64>().map_err(|_| {
+                        anyhow!("line {}: invalid tool diameter: {}", idx + 1, token)
+                    })?);
+                }
+                _ => {}
+            }
+        }
+        if let Some(number) = number {
+            tools.insert(number, entry);
+        }
+    }
+    Ok(ToolTable { tools })
+}
+
+/// `.tbl` files have no flute column, so a flute count is taken from the comment, written as
+/// `2FL`, `2-flute` or `2 flutes`.
+fn comment_flutes(comment: &str) -> Option<u32> {
+    let words: Vec<String> = comment.split_whitespace().map(|word| word.to_ascii_lowercase()).collect();
+    words.iter().enumerate().find_map(|(idx, word)| {
+        let digits = word.find(|ch: char| !ch.is_ascii_digit()).unwrap_or(word.len());
+        let (count, rest) = word.split_at(digits);
+        let unit = match rest.trim_start_matches('-') {
+            "" => words.get(idx + 1)?.as_str(),
+            rest => rest,
+        };
+        let count = count.parse::<u32>().ok().filter(|&count| count > 0)?;
+        matches!(unit.trim_end_matches(|ch: char| !ch.is_ascii_alphabetic()), "fl" | "flute" | "flutes").then_some(count)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn linuxcnc_table_reads_diameter() {
+        let table = parse_linuxcnc_table("T1 P1 D6.0 Z-20 ;6mm endmill\nT4 P4 ;no geometry\n").unwrap();
+        assert_eq!(table.get(1).unwrap().diameter, Some(6.0));
+        assert_eq!(table.get(4), Some(&ToolEntry::default()));
+        assert!(!table.contains(2));
+        let fluted = parse_linuxcnc_table("T1 D6 ;6mm 3FL endmill\nT2 D3 ;2-flute\nT3 D8 ;4 flutes, 30 deg\nT5 D1 ;T5\n").unwrap();
+        let flutes: Vec<Option<u32>> = [1, 2, 3, 5].map(|tool| fluted.get(tool).unwrap().flutes).to_vec();
+        assert_eq!(flutes, [Some(3), Some(2), Some(4), None]);
+    }
+}
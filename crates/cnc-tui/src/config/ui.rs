@@ -1,26 +1,131 @@
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 use serde::Deserialize;
 
-use super::parse::parse_marker;
+use super::parse::{parse_corner, parse_fade, parse_marker, parse_renderer, parse_units};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Renderer {
+    Canvas,
+    Auto,
+    Kitty,
+    Iterm2,
+    Sixel,
+}
+
+/// Which end of the drawn toolpath fades into the background.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Fade {
+    /// The oldest moves are faintest and the playhead brightest.
+    Start,
+    End,
+    Off,
+}
+
+/// Viewport corner a HUD box is pinned to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Corner {
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight,
+}
 
 #[derive(Debug, Clone)]
 pub struct UiSettings {
     pub show_line_numbers: bool,
+    /// Heat-colored estimated time per line in the file panel gutter.
+    pub show_line_times: bool,
     pub canvas_marker: ratatui::symbols::Marker,
+    pub renderer: Renderer,
+    pub show_safe_planes: bool,
+    /// Rapids/retracts needed at one height before it is drawn as a safe plane.
+    pub safe_plane_min_count: usize,
+    /// Outline the XY convex hull of the feed moves at the bottom of the part.
+    pub show_footprint: bool,
+    /// Open the pre-flight summary popup after loading a file.
+    pub load_summary: bool,
+    /// Drawn segment budget before consecutive segments are merged; 0 disables the cap.
+    pub max_segments: usize,
+    /// Start with auto-fit on: changing the file selection re-frames the camera on it.
+    pub auto_fit: bool,
+    /// Units for coordinate, length and feed readouts, independent of the file's G20/G21.
+    pub display_units: cnc_gcode::Units,
+    /// Turn in degrees between consecutive feed moves marked as a sharp corner.
+    pub corner_angle: f64,
+    /// Distance in mm from the programmed path beyond which a position trace point is flagged.
+    pub trace_tolerance: f64,
+    /// Work Z below which a rapid moving in XY is reported as an error.
+    pub rapid_clearance: Option<f64>,
+    /// Diameter in mm that A rotation is laid out flat on in the unwrapped view.
+    pub wrap_diameter: Option<f64>,
+    /// Seconds the previous toolpath stays drawn after a reload changes it; 0 disables.
+    pub ghost_seconds: f64,
+    /// Corners of the axis HUD, position readout, scale bar and color legend; `None` hides one.
+    pub origin_hud: Option<Corner>,
+    pub dro: Option<Corner>,
+    pub scale_bar: Option<Corner>,
+    pub legend: Option<Corner>,
+    pub fade: Fade,
+    /// Curve of the fade: opacity grows with the segment's position to this power.
+    pub fade_exponent: f64,
+    /// Opacity of the faintest segment, 0–1.
+    pub fade_min_opacity: f64,
 }
 
 #[derive(Debug, Clone, Deserialize)]
 #[serde(default)]
 pub(crate) struct UiConfig {
     show_line_numbers: bool,
+    show_line_times: bool,
     canvas_marker: String,
+    renderer: String,
+    show_safe_planes: bool,
+    safe_plane_min_count: usize,
+    show_footprint: bool,
+    load_summary: bool,
+    max_segments: usize,
+    auto_fit: bool,
+    display_units: String,
+    corner_angle: f64,
+    trace_tolerance: f64,
+    rapid_clearance: Option<f64>,
+    wrap_diameter: Option<f64>,
+    ghost_seconds: f64,
+    origin_hud: String,
+    dro: String,
+    scale_bar: String,
+    legend: String,
+    fade: String,
+    fade_exponent: f64,
+    fade_min_opacity: f64,
 }
 
 impl Default for UiConfig {
     fn default() -> Self {
         Self {
             show_line_numbers: false,
+            show_line_times: false,
             canvas_marker: "braille".to_string(),
+            renderer: "canvas".to_string(),
+            show_safe_planes: true,
+            safe_plane_min_count: 3,
+            show_footprint: false,
+            load_summary: true,
+            max_segments: 200_000,
+            auto_fit: false,
+            display_units: "mm".to_string(),
+            corner_angle: 60.0,
+            trace_tolerance: 0.05,
+            rapid_clearance: None,
+            wrap_diameter: None,
+            ghost_seconds: 5.0,
+            origin_hud: "top-left".to_string(),
+            dro: "top-right".to_string(),
+            scale_bar: "bottom-left".to_string(),
+            legend: "bottom-right".to_string(),
+            fade: "start".to_string(),
+            fade_exponent: 0.6,
+            fade_min_opacity: 0.0,
         }
     }
 }
@@ -30,9 +135,49 @@ impl TryFrom<UiConfig> for UiSettings {
 
     fn try_from(value: UiConfig) -> Result<Self> {
         let canvas_marker = parse_marker(&value.canvas_marker)?;
+        let renderer = parse_renderer(&value.renderer)?;
+        if !(0.0..=180.0).contains(&value.corner_angle) {
+            return Err(anyhow!("ui: corner_angle must be between 0 and 180 degrees"));
+        }
+        if value.trace_tolerance <= 0.0 {
+            return Err(anyhow!("ui: trace_tolerance must be positive"));
+        }
+        if value.wrap_diameter.is_some_and(|diameter| diameter <= 0.0) {
+            return Err(anyhow!("ui: wrap_diameter must be positive"));
+        }
+        if value.ghost_seconds < 0.0 {
+            return Err(anyhow!("ui: ghost_seconds must not be negative"));
+        }
+        if value.fade_exponent <= 0.0 {
+            return Err(anyhow!("ui: fade_exponent must be positive"));
+        }
+        if !(0.0..=1.0).contains(&value.fade_min_opacity) {
+            return Err(anyhow!("ui: fade_min_opacity must be between 0 and 1"));
+        }
         Ok(Self {
             show_line_numbers: value.show_line_numbers,
+            show_line_times: value.show_line_times,
             canvas_marker,
+            renderer,
+            show_safe_planes: value.show_safe_planes,
+            safe_plane_min_count: value.safe_plane_min_count,
+            show_footprint: value.show_footprint,
+            load_summary: value.load_summary,
+            max_segments: value.max_segments,
+            auto_fit: value.auto_fit,
+            display_units: parse_units(&value.display_units)?,
+            corner_angle: value.corner_angle,
+            trace_tolerance: value.trace_tolerance,
+            rapid_clearance: value.rapid_clearance,
+            wrap_diameter: value.wrap_diameter,
+            ghost_seconds: value.ghost_seconds,
+            origin_hud: parse_corner(&value.origin_hud)?,
+            dro: parse_corner(&value.dro)?,
+            scale_bar: parse_corner(&value.scale_bar)?,
+            legend: parse_corner(&value.legend)?,
+            fade: parse_fade(&value.fade)?,
+            fade_exponent: value.fade_exponent,
+            fade_min_opacity: value.fade_min_opacity,
         })
     }
 }
@@ -0,0 +1,108 @@
+use cnc_gcode::{LineState, Units};
+
+use crate::config::{CuttingSettings, ToolEntry};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CuttingConditions {
+    pub tool: u32,
+    pub spindle_speed: f64,
+    pub feed_rate: f64,
+    pub entry: ToolEntry,
+}
+
+impl CuttingConditions {
+    pub fn from_state(state: &LineState, entry: Option<&ToolEntry>) -> Option<Self> {
+        Some(Self {
+            tool: state.tool?,
+            spindle_speed: state.spindle_speed.filter(|speed| *speed > 0.0)?,
+            feed_rate: state.feed_rate?,
+            entry: entry.copied().unwrap_or_default(),
+        })
+    }
+
+    /// Surface speed in m/min.
+    pub fn surface_speed(&self) -> Option<f64> {
+        let diameter = self.entry.diameter?;
+        Some(std::f64::consts::PI * diameter * self.spindle_speed / 1000.0)
+    }
+
+    /// Feed per tooth in mm.
+    pub fn chip_load(&self) -> Option<f64> {
+        let flutes = self.entry.flutes?;
+        Some(self.feed_rate / (self.spindle_speed * flutes as f64))
+    }
+
+    /// Summary in `units`; range flags always compare the metric values with `settings`.
+    pub fn describe(&self, settings: &CuttingSettings, units: Units) -> String {
+        let (feed_precision, speed_unit, speed_scale, load_unit, load_precision) = match units {
+            Units::Millimeters => (0, "m/min", 1.0, "mm/t", 3),
+            Units::Inches => (1, "SFM", 1000.0 / (12.0 * crate::units::scale(units)), "in/t", 4),
+        };
+        let feed = self.feed_rate / crate::units::scale(units);
+        let mut text = format!("T{} S{:.0} F{:.*}", self.tool, self.spindle_speed, feed_precision, feed);
+        if let Some(speed) = self.surface_speed() {
+            text.push_str(&format!(
+                "  Vc {:.0} {}{}",
+                speed * speed_scale,
+                speed_unit,
+                range_flag(speed, settings.surface_speed_min, settings.surface_speed_max)
+            ));
+        }
+        if let Some(load) = self.chip_load() {
+            text.push_str(&format!(
+                "  fz {:.*} {}{}",
+                load_precision,
+                load / crate::units::scale(units),
+                load_unit,
+                range_flag(load, settings.chip_load_min, settings.chip_load_max)
+            ));
+        }
+        text
+    }
+}
+
+fn range_flag(value: f64, min: Option<f64>, max: Option<f64>) -> &'static str {
+    if min.is_some_and(|min| value < min) {
+        " (low)"
+    } else if max.is_some_and(|max| value > max) {
+        " (high)"
+    } else {
+        ""
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn computes_and_flags_chip_load_and_surface_speed() {
+        let state = LineState {
+            tool: Some(3),
+            spindle_speed: Some(10000.0),
+            feed_rate: Some(1000.0),
+            ..LineState::default()
+        };
+        let entry = ToolEntry {
+            diameter: Some(6.0),
+            flutes: Some(2),
+        };
+        let conditions = CuttingConditions::from_state(&state, Some(&entry)).unwrap();
+        assert!((conditions.chip_load().unwrap() - 0.05).abs() < 1e-9);
+        assert!((conditions.surface_speed().unwrap() - 188.4956).abs() < 1e-3);
+
+        let settings = CuttingSettings {
+            chip_load_min: Some(0.06),
+            surface_speed_max: Some(150.0),
+            ..CuttingSettings::default()
+        };
+        assert_eq!(
+            conditions.describe(&settings, Units::Millimeters),
+            "T3 S10000 F1000  Vc 188 m/min (high)  fz 0.050 mm/t (low)"
+        );
+        assert_eq!(
+            conditions.describe(&settings, Units::Inches),
+            "T3 S10000 F39.4  Vc 618 SFM (high)  fz 0.0020 in/t (low)"
+        );
+    }
+}
@@ -0,0 +1,66 @@
+use ratatui::layout::Rect;
+use ratatui::style::Style;
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, Paragraph};
+use ratatui::Frame;
+
+use crate::app::App;
+use crate::render::fade_color;
+use crate::units;
+use cnc_gcode::min_z_map;
+
+/// Top-down false-color map of the lowest Z per XY cell, two cells per character (`▀`).
+pub fn draw_depth_map(frame: &mut Frame<'_>, app: &mut App, area: Rect) {
+    let theme = app.config.theme.clone();
+    let inner = Block::default().borders(Borders::ALL).inner(area);
+    let size = (inner.width, inner.height);
+    if app.depth_map.as_ref().is_none_or(|(cached, _)| *cached != size) {
+        let map = min_z_map(&app.toolpath, inner.width as usize, inner.height as usize * 2);
+        app.depth_map = Some((size, map));
+    }
+    let Some((_, map)) = &app.depth_map else {
+        return;
+    };
+    let range = map.z_range();
+    let title = match range {
+        Some((deep, shallow)) => {
+            let value = |z| units::format_length(app.display_units, z - app.display_origin.z);
+            format!(
+                " Min Z map: {} (deep) .. {} {} ",
+                value(deep),
+                value(shallow),
+                units::label(app.display_units)
+            )
+        }
+        None => " Min Z map: no feed moves ".to_string(),
+    };
+    let color = |z: Option<f64>| {
+        let (z, (deep, shallow)) = (z?, range?);
+        let t = if shallow > deep { (shallow - z) / (shallow - deep) } else { 0.0 };
+        Some(fade_color(theme.depth_deep, theme.depth_shallow, t))
+    };
+    let left = (inner.width as usize).saturating_sub(map.columns) / 2;
+    let top = (inner.height as usize * 2).saturating_sub(map.rows) / 2;
+    let mut lines = Vec::new();
+    for y in 0..inner.height as usize {
+        let mut spans = vec![Span::raw(" ".repeat(left))];
+        for column in 0..map.columns {
+            // Pixel rows count down from the top; map rows count up from minimum Y.
+            let cell = |pixel: usize| {
+                let row = map.rows.checked_sub(pixel.checked_sub(top)? + 1)?;
+                map.get(column, row)
+            };
+            let (upper, lower) = (color(cell(y * 2)), color(cell(y * 2 + 1)));
+            let style = Style::default()
+                .fg(upper.unwrap_or(theme.background))
+                .bg(lower.unwrap_or(theme.background));
+            spans.push(Span::styled("▀", style));
+        }
+        lines.push(Line::from(spans));
+    }
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(title)
+        .style(Style::default().bg(theme.background).fg(theme.foreground));
+    frame.render_widget(Paragraph::new(lines).block(block), area);
+}
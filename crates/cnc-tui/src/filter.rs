@@ -0,0 +1,350 @@
+use anyhow::{anyhow, Result};
+use cnc_gcode::{MoveKind, Toolpath};
+
+/// A parsed segment filter such as `kind=feed && z<-2 && tool=3`.
+///
+/// Comparisons on `x`, `y`, `z` (segment end point), `feed`, `tool`, `line` (1-based),
+/// `len`, `kind` (`rapid`/`feed`/`probe`) and `op` (operation name) combine with `&&`, `||`, `!`
+/// and parentheses.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SegmentFilter {
+    pub source: String,
+    expr: Expr,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Expr {
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Not(Box<Expr>),
+    Compare(Field, CmpOp, Value),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Field {
+    Kind,
+    X,
+    Y,
+    Z,
+    Feed,
+    Tool,
+    Line,
+    Length,
+    Op,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CmpOp {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Value {
+    Number(f64),
+    Kind(MoveKind),
+    Text(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Word(String),
+    Cmp(CmpOp),
+    And,
+    Or,
+    Not,
+    Open,
+    Close,
+}
+
+/// Per-segment values a filter compares against.
+struct SegmentFacts<'a> {
+    kind: MoveKind,
+    end: [f64; 3],
+    feed: Option<f64>,
+    tool: Option<u32>,
+    line: usize,
+    length: f64,
+    op: Option<&'a str>,
+}
+
+impl SegmentFilter {
+    pub fn parse(source: &str) -> Result<Self> {
+        let tokens = tokenize(source)?;
+        let mut parser = ExprParser { tokens, pos: 0 };
+        let expr = parser.or()?;
+        if parser.pos < parser.tokens.len() {
+            return Err(anyhow!("unexpected trailing input in filter: {}", source));
+        }
+        Ok(Self {
+            source: source.trim().to_string(),
+            expr,
+        })
+    }
+
+    /// Whether each segment of `toolpath` passes the filter.
+    pub fn mask(&self, toolpath: &Toolpath) -> Vec<bool> {
+        let mut line = 0;
+        toolpath
+            .segments
+            .iter()
+            .enumerate()
+            .map(|(idx, seg)| {
+                while toolpath.line_segment_ends.get(line).is_some_and(|&end| end <= idx) {
+                    line += 1;
+                }
+                let operations = &toolpath.operations;
+                let op = operations
+                    .get(operations.partition_point(|op| op.segments.end <= idx))
+                    .filter(|op| op.segments.contains(&idx))
+                    .map(|op| op.name.as_str());
+                let facts = SegmentFacts {
+                    kind: seg.kind,
+                    end: [seg.end.x, seg.end.y, seg.end.z],
+                    feed: seg.feed,
+                    tool: toolpath.line_states.get(line).and_then(|state| state.tool),
+                    line: line + 1,
+                    length: (seg.end - seg.start).length(),
+                    op,
+                };
+                self.expr.matches(&facts)
+            })
+            .collect()
+    }
+}
+
+impl Expr {
+    fn matches(&self, facts: &SegmentFacts<'_>) -> bool {
+        match self {
+            Expr::And(a, b) => a.matches(facts) && b.matches(facts),
+            Expr::Or(a, b) => a.matches(facts) || b.matches(facts),
+            Expr::Not(inner) => !inner.matches(facts),
+            Expr::Compare(field, op, value) => compare(*field, *op, value, facts),
+        }
+    }
+}
+
+fn compare(field: Field, op: CmpOp, value: &Value, facts: &SegmentFacts<'_>) -> bool {
+    let actual = match (field, value) {
+        (Field::Kind, Value::Kind(kind)) => return (facts.kind == *kind) == (op == CmpOp::Eq),
+        (Field::Op, Value::Text(name)) => {
+            let same = facts.op.is_some_and(|op| op.eq_ignore_ascii_case(name));
+            return same == (op == CmpOp::Eq);
+        }
+        (Field::X, _) => Some(facts.end[0]),
+        (Field::Y, _) => Some(facts.end[1]),
+        (Field::Z, _) => Some(facts.end[2]),
+        (Field::Feed, _) => facts.feed,
+        (Field::Tool, _) => facts.tool.map(f64::from),
+        (Field::Line, _) => Some(facts.line as f64),
+        (Field::Length, _) => Some(facts.length),
+        _ => None,
+    };
+    let (Some(actual), Value::Number(expected)) = (actual, value) else {
+        return op == CmpOp::Ne;
+    };
+    match op {
+        CmpOp::Eq => (actual - expected).abs() < 1e-9,
+        CmpOp::Ne => (actual - expected).abs() >= 1e-9,
+        CmpOp::Lt => actual < *expected,
+        CmpOp::Le => actual <= *expected,
+        CmpOp::Gt => actual > *expected,
+        CmpOp::Ge => actual >= *expected,
+    }
+}
+
+fn tokenize(source: &str) -> Result<Vec<Token>> {
+    let mut tokens = Vec::new();
+    let mut chars = source.chars().peekable();
+    while let Some(&ch) = chars.peek() {
+        let token = match ch {
+            ' ' | '\t' => {
+                chars.next();
+                continue;
+            }
+            '(' | ')' => {
+                chars.next();
+                if ch == '(' { Token::Open } else { Token::Close }
+            }
+            '&' | '|' => {
+                chars.next();
+                if chars.next_if_eq(&ch).is_none() {
+                    return Err(anyhow!("expected '{0}{0}' in filter", ch));
+                }
+                if ch == '&' { Token::And } else { Token::Or }
+            }
+            '!' | '=' | '<' | '>' => {
+                chars.next();
+                let eq = chars.next_if_eq(&'=').is_some();
+                match (ch, eq) {
+                    ('!', false) => Token::Not,
+                    ('!', true) => Token::Cmp(CmpOp::Ne),
+                    ('<', false) => Token::Cmp(CmpOp::Lt),
+                    ('<', true) => Token::Cmp(CmpOp::Le),
+                    ('>', false) => Token::Cmp(CmpOp::Gt),
+                    ('>', true) => Token::Cmp(CmpOp::Ge),
+                    _ => Token::Cmp(CmpOp::Eq),
+                }
+            }
+            '"' => {
+                chars.next();
+                let text: String = chars.by_ref().take_while(|&c| c != '"').collect();
+                Token::Word(text)
+            }
+            _ => {
+                let mut word = String::new();
+                while let Some(c) = chars.next_if(|c| c.is_alphanumeric() || matches!(c, '_' | '-' | '.')) {
+                    word.push(c);
+                }
+                if word.is_empty() {
+                    return Err(anyhow!("unexpected '{}' in filter", ch));
+                }
+                Token::Word(word)
+            }
+        };
+        tokens.push(token);
+    }
+    Ok(tokens)
+}
+
+struct ExprParser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl ExprParser {
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn eat(&mut self, token: &Token) -> bool {
+        let found = self.tokens.get(self.pos) == Some(token);
+        if found {
+            self.pos += 1;
+        }
+        found
+    }
+
+    fn or(&mut self) -> Result<Expr> {
+        let mut expr = self.and()?;
+        while self.eat(&Token::Or) {
+            expr = Expr::Or(Box::new(expr), Box::new(self.and()?));
+        }
+        Ok(expr)
+    }
+
+    fn and(&mut self) -> Result<Expr> {
+        let mut expr = self.unary()?;
+        while self.eat(&Token::And) {
+            expr = Expr::And(Box::new(expr), Box::new(self.unary()?));
+        }
+        Ok(expr)
+    }
+
+    fn unary(&mut self) -> Result<Expr> {
+        if self.eat(&Token::Not) {
+            return Ok(Expr::Not(Box::new(self.unary()?)));
+        }
+        if self.eat(&Token::Open) {
+            let expr = self.or()?;
+            if !self.eat(&Token::Close) {
+                return Err(anyhow!("missing ')' in filter"));
+            }
+            return Ok(expr);
+        }
+        self.comparison()
+    }
+
+    fn comparison(&mut self) -> Result<Expr> {
+        let Some(Token::Word(name)) = self.next() else {
+            return Err(anyhow!("expected a field name in filter"));
+        };
+        let field = match name.to_ascii_lowercase().as_str() {
+            "kind" => Field::Kind,
+            "x" => Field::X,
+            "y" => Field::Y,
+            "z" => Field::Z,
+            "f" | "feed" => Field::Feed,
+            "t" | "tool" => Field::Tool,
+            "line" => Field::Line,
+            "len" | "length" => Field::Length,
+            "op" => Field::Op,
+            _ => return Err(anyhow!("unknown filter field: {}", name)),
+        };
+        let Some(Token::Cmp(op)) = self.next() else {
+            return Err(anyhow!("expected a comparison after {}", name));
+        };
+        let Some(Token::Word(raw)) = self.next() else {
+            return Err(anyhow!("expected a value after {}", name));
+        };
+        let value = match field {
+            Field::Kind => match raw.to_ascii_lowercase().as_str() {
+                "rapid" | "g0" => Value::Kind(MoveKind::Rapid),
+                "feed" | "cut" | "g1" => Value::Kind(MoveKind::Feed),
+                "probe" | "g38" => Value::Kind(MoveKind::Probe),
+                _ => return Err(anyhow!("unknown move kind: {}", raw)),
+            },
+            Field::Op => Value::Text(raw),
+            _ => Value::Number(
+                raw.parse()
+                    .map_err(|_| anyhow!("expected a number for {}: {}", name, raw))?,
+            ),
+        };
+        if matches!(field, Field::Kind | Field::Op) && !matches!(op, CmpOp::Eq | CmpOp::Ne) {
+            return Err(anyhow!("{} only supports = and !=", name));
+        }
+        Ok(Expr::Compare(field, op, value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cnc_gcode::{LineSegment, LineState, Operation};
+    use cnc_geom::{RotaryAngles, Vec3};
+
+    #[test]
+    fn filters_by_kind_depth_tool_and_operation() {
+        let segment = |kind, z: f64| LineSegment {
+            start: Vec3::new(0.0, 0.0, 0.0),
+            end: Vec3::new(1.0, 0.0, z),
+            kind,
+            feed: (kind == MoveKind::Feed).then_some(100.0),
+            rotary: RotaryAngles::default(),
+        };
+        let state = |tool| LineState {
+            tool: Some(tool),
+            ..LineState::default()
+        };
+        let toolpath = Toolpath {
+            segments: vec![
+                segment(MoveKind::Rapid, 5.0),
+                segment(MoveKind::Feed, -3.0),
+                segment(MoveKind::Feed, -1.0),
+                segment(MoveKind::Feed, -3.0),
+            ],
+            line_segment_ends: vec![1, 2, 3, 4],
+            line_states: vec![state(1), state(3), state(3), state(4)],
+            operations: vec![Operation {
+                name: "2D Contour1".to_string(),
+                lines: 2..4,
+                segments: 2..4,
+            }],
+            ..Toolpath::default()
+        };
+        let mask = |source: &str| SegmentFilter::parse(source).unwrap().mask(&toolpath);
+
+        assert_eq!(mask("kind=feed && z<-2 && tool=3"), [false, true, false, false]);
+        assert_eq!(mask("!(kind == rapid) && (z>=-1 || line=4)"), [false, false, true, true]);
+        assert_eq!(mask("op=\"2d contour1\" && feed != 50"), [false, false, true, true]);
+        assert!(SegmentFilter::parse("depth<2").is_err());
+        assert!(SegmentFilter::parse("kind<feed").is_err());
+        assert!(SegmentFilter::parse("(z<1").is_err());
+    }
+}
@@ -0,0 +1,105 @@
+use ratatui::layout::Rect;
+use ratatui::style::Style;
+use ratatui::widgets::canvas::{Canvas, Line};
+use ratatui::widgets::{Block, Borders};
+use ratatui::Frame;
+
+use crate::app::{App, DepthGraph};
+use crate::units;
+use cnc_gcode::MoveKind;
+
+pub const GRAPH_HEIGHT: u16 = 8;
+
+/// Z (and optionally feed) against estimated program time, with the playback head marked.
+pub fn draw_depth_graph(frame: &mut Frame<'_>, app: &App, area: Rect) {
+    let theme = &app.config.theme;
+    let segments = &app.toolpath.segments;
+    let total_time = app.estimated_time.max(1e-6);
+    let bounds = app.toolpath.bounds;
+    let (z_min, z_max) = if bounds.initialized {
+        (bounds.min.z, bounds.max.z.max(bounds.min.z + 1e-6))
+    } else {
+        (0.0, 1.0)
+    };
+    let feed_max = segments
+        .iter()
+        .filter_map(|seg| seg.feed)
+        .fold(0.0_f64, f64::max);
+    let show_feed = app.depth_graph == DepthGraph::DepthFeed && feed_max > 0.0;
+
+    let origin_z = app.display_origin.z;
+    let scale = units::scale(app.display_units);
+    let mut title = format!(
+        " Z {:.2}..{:.2} {}",
+        (z_min - origin_z) / scale,
+        (z_max - origin_z) / scale,
+        units::label(app.display_units)
+    );
+    if show_feed {
+        title.push_str(&format!("  F 0..{:.0}", feed_max / scale));
+    }
+    let head_time = app
+        .highlighted_segment()
+        .and_then(|idx| app.segment_end_times.get(idx).copied());
+
+    let canvas = Canvas::default()
+        .marker(app.canvas_marker)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(title)
+                .style(Style::default().bg(theme.background).fg(theme.foreground)),
+        )
+        .x_bounds([0.0, total_time])
+        .y_bounds([z_min, z_max])
+        .paint(|ctx| {
+            let mut start_time = 0.0;
+            let mut prev_feed: Option<(f64, f64)> = None;
+            for (seg, &end_time) in segments.iter().zip(&app.segment_end_times) {
+                let color = match seg.kind {
+                    MoveKind::Rapid => theme.path_rapid,
+                    MoveKind::Feed => theme.axis_z,
+                    MoveKind::Probe => theme.probe,
+                };
+                ctx.draw(&Line {
+                    x1: start_time,
+                    y1: seg.start.z,
+                    x2: end_time,
+                    y2: seg.end.z,
+                    color,
+                });
+                if show_feed {
+                    let feed = seg.feed.unwrap_or(0.0);
+                    let y = z_min + (z_max - z_min) * feed / feed_max;
+                    if let Some((prev_time, prev_y)) = prev_feed {
+                        ctx.draw(&Line {
+                            x1: prev_time,
+                            y1: prev_y,
+                            x2: start_time,
+                            y2: y,
+                            color: theme.code_number,
+                        });
+                    }
+                    ctx.draw(&Line {
+                        x1: start_time,
+                        y1: y,
+                        x2: end_time,
+                        y2: y,
+                        color: theme.code_number,
+                    });
+                    prev_feed = Some((end_time, y));
+                }
+                start_time = end_time;
+            }
+            if let Some(time) = head_time {
+                ctx.draw(&Line {
+                    x1: time,
+                    y1: z_min,
+                    x2: time,
+                    y2: z_max,
+                    color: theme.foreground,
+                });
+            }
+        });
+    frame.render_widget(canvas, area);
+}
@@ -0,0 +1,442 @@
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::io::{self, Write};
+
+use cnc_geom::ProjectionMode;
+use crossterm::cursor::MoveTo;
+use crossterm::queue;
+
+use ratatui::layout::Rect;
+
+use crate::app::{App, ViewMetrics};
+use crate::config::Renderer;
+use crate::raster::Raster;
+use crate::render::paint_scene;
+use crate::sixel::encode_sixel;
+
+const MAX_IMAGE_DIM: f64 = 1600.0;
+const KITTY_IMAGE_ID: u32 = 7331;
+const KITTY_CHUNK: usize = 4096;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GraphicsProtocol {
+    Kitty,
+    Iterm2,
+    Sixel,
+}
+
+pub fn resolve(renderer: Renderer) -> Option<GraphicsProtocol> {
+    match renderer {
+        Renderer::Canvas => None,
+        Renderer::Kitty => Some(GraphicsProtocol::Kitty),
+        Renderer::Iterm2 => Some(GraphicsProtocol::Iterm2),
+        Renderer::Sixel => Some(GraphicsProtocol::Sixel),
+        Renderer::Auto => detect(|key| std::env::var(key).ok()),
+    }
+}
+
+/// Hash of everything `paint_scene` draws from, so an image is re-sent whenever it would differ.
+fn scene_signature(app: &App, area: Rect, metrics: ViewMetrics) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    (area.x, area.y, area.width, area.height).hash(&mut hasher);
+    for value in [
+        metrics.center.x,
+        metrics.center.y,
+        metrics.half_w,
+        metrics.half_h,
+        metrics.camera_distance,
+        app.view.yaw,
+        app.view.pitch,
+        app.view.z_scale,
+        app.display_origin.x,
+        app.display_origin.y,
+        app.display_origin.z,
+    ] {
+        value.to_bits().hash(&mut hasher);
+    }
+    (app.view.projection == ProjectionMode::Perspective).hash(&mut hasher);
+    app.geometry_hash.hash(&mut hasher);
+    app.config.theme.hash(&mut hasher);
+    app.visible_segment_range().hash(&mut hasher);
+    app.operations.hidden.hash(&mut hasher);
+    app.filter_mask.hash(&mut hasher);
+    (app.focus_selection, app.first_pass_only).hash(&mut hasher);
+    (app.color_mode as u8, app.show_corners).hash(&mut hasher);
+    app.ghost_fade().map(f64::to_bits).hash(&mut hasher);
+    app.grid_spacing.map(f64::to_bits).hash(&mut hasher);
+    app.clip_z.map(f64::to_bits).hash(&mut hasher);
+    (app.safe_planes.len(), app.footprint.len()).hash(&mut hasher);
+    app.trace.as_ref().map(|trace| trace.points.len()).hash(&mut hasher);
+    hasher.finish()
+}
+
+fn detect(env: impl Fn(&str) -> Option<String>) -> Option<GraphicsProtocol> {
+    let term = env("TERM").unwrap_or_default();
+    if env("KITTY_WINDOW_ID").is_some() || term.contains("kitty") || term.contains("ghostty") {
+        return Some(GraphicsProtocol::Kitty);
+    }
+    match env("TERM_PROGRAM").as_deref() {
+        Some("iTerm.app") | Some("WezTerm") => return Some(GraphicsProtocol::Iterm2),
+        Some("ghostty") => return Some(GraphicsProtocol::Kitty),
+        _ => {}
+    }
+    // Stock xterm only speaks Sixel when started as a VT340, so it has to be opted into.
+    ["mlterm", "foot", "yaft", "contour"]
+        .iter()
+        .any(|name| term.contains(name))
+        .then_some(GraphicsProtocol::Sixel)
+}
+
+/// Tracks the last emitted image so it is only re-sent when the view changes.
+#[derive(Default)]
+pub struct GraphicsState {
+    last_signature: Option<u64>,
+}
+
+impl GraphicsState {
+    pub fn invalidate(&mut self) {
+        self.last_signature = None;
+    }
+
+    pub fn present(&mut self, out: &mut impl Write, app: &App) -> io::Result<()> {
+        let Some(protocol) = app.graphics else {
+            return Ok(());
+        };
+        let (Some(area), Some(metrics)) = (app.canvas_area, app.last_metrics) else {
+            return self.hide(out, protocol);
+        };
+        if app.show_help || app.show_depth_map || app.restart.is_some() || app.summary.is_some() || area.width == 0 || area.height == 0 {
+            return self.hide(out, protocol);
+        }
+
+        let signature = scene_signature(app, area, metrics);
+        if self.last_signature == Some(signature) {
+            return Ok(());
+        }
+        self.last_signature = Some(signature);
+
+        let (cell_w, cell_h) = cell_pixel_size();
+        let mut width = area.width as f64 * cell_w;
+        let mut height = area.height as f64 * cell_h;
+        // Sixel cannot be scaled by the terminal, so it is sent at the cell area's pixel size.
+        let scale = if protocol == GraphicsProtocol::Sixel {
+            1.0
+        } else {
+            (MAX_IMAGE_DIM / width.max(height)).min(1.0)
+        };
+        width = (width * scale).max(1.0);
+        height = (height * scale).max(1.0);
+
+        let mut raster = Raster::new(
+            width as usize,
+            height as usize,
+            [metrics.center.x - metrics.half_w, metrics.center.x + metrics.half_w],
+            [metrics.center.y - metrics.half_h, metrics.center.y + metrics.half_h],
+            app.config.theme.background,
+        );
+        paint_scene(&mut raster, app, metrics);
+
+        queue!(out, MoveTo(area.x, area.y))?;
+        match protocol {
+            GraphicsProtocol::Kitty => {
+                let png = encode_png(raster.width, raster.height, &raster.pixels);
+                write!(out, "\x1b_Ga=d,d=i,i={},q=2\x1b\\", KITTY_IMAGE_ID)?;
+                write_kitty(out, &png, area.width, area.height)?;
+            }
+            GraphicsProtocol::Iterm2 => {
+                let png = encode_png(raster.width, raster.height, &raster.pixels);
+                write!(
+                    out,
+                    "\x1b]1337;File=inline=1;size={};width={};height={};preserveAspectRatio=0:{}\x07",
+                    png.len(),
+                    area.width,
+                    area.height,
+                    base64(&png)
+                )?;
+            }
+            GraphicsProtocol::Sixel => {
+                out.write_all(encode_sixel(raster.width, raster.height, &raster.pixels).as_bytes())?;
+            }
+        }
+        out.flush()
+    }
+
+    fn hide(&mut self, out: &mut impl Write, protocol: GraphicsProtocol) -> io::Result<()> {
+        if self.last_signature.take().is_some() && protocol == GraphicsProtocol::Kitty {
+            write!(out, "\x1b_Ga=d,d=i,i={},q=2\x1b\\", KITTY_IMAGE_ID)?;
+            out.flush()?;
+        }
+        Ok(())
+    }
+}
+
+fn cell_pixel_size() -> (f64, f64) {
+    match crossterm::terminal::window_size() {
+        Ok(size) if size.width > 0 && size.height > 0 && size.columns > 0 && size.rows > 0 => (
+            size.width as f64 / size.columns as f64,
+            size.height as f64 / size.rows as f64,
+        ),
+        _ => (8.0, 16.0),
+    }
+}
+
+fn write_kitty(out: &mut impl Write, png: &[u8], cols: u16, rows: u16) -> io::Result<()> {
+    let encoded = base64(png);
+    let chunks: Vec<&[u8]> = encoded.as_bytes().chunks(KITTY_CHUNK).collect();
+    for (idx, chunk) in chunks.iter().enumerate() {
+        let more = u8::from(idx + 1 < chunks.len());
+        if idx == 0 {
+            // z=-1 keeps the image below text so overlays such as the HUD stay visible.
+            write!(
+                out,
+                "\x1b_Ga=T,f=100,i={},q=2,C=1,z=-1,c={},r={},m={};",
+                KITTY_IMAGE_ID, cols, rows, more
+            )?;
+        } else {
+            write!(out, "\x1b_Gm={};", more)?;
+        }
+        out.write_all(chunk)?;
+        out.write_all(b"\x1b\\")?;
+    }
+    Ok(())
+}
+
+pub fn base64(data: &[u8]) -> String {
+    const TABLE: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut out = String::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3) {
+        let b = [chunk[0], *chunk.get(1).unwrap_or(&0), *chunk.get(2).unwrap_or(&0)];
+        let n = (b[0] as u32) << 16 | (b[1] as u32) << 8 | b[2] as u32;
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(TABLE[(n >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+/// Encodes 8-bit RGB pixels as a PNG using fixed-Huffman deflate.
+pub fn encode_png(width: usize, height: usize, rgb: &[u8]) -> Vec<u8> {
+    let stride = width * 3;
+    let mut raw = Vec::with_capacity((stride + 1) * height);
+    for row in rgb.chunks(stride) {
+        raw.push(0);
+        raw.extend_from_slice(row);
+    }
+
+    let mut png = b"\x89PNG\r\n\x1a\n".to_vec();
+    let mut header = Vec::with_capacity(13);
+    header.extend_from_slice(&(width as u32).to_be_bytes());
+    header.extend_from_slice(&(height as u32).to_be_bytes());
+    header.extend_from_slice(&[8, 2, 0, 0, 0]);
+    write_chunk(&mut png, b"IHDR", &header);
+    write_chunk(&mut png, b"IDAT", &zlib_compress(&raw, stride + 1));
+    write_chunk(&mut png, b"IEND", &[]);
+    png
+}
+
+fn write_chunk(out: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    out.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    let start = out.len();
+    out.extend_from_slice(kind);
+    out.extend_from_slice(data);
+    let crc = crc32(&out[start..]);
+    out.extend_from_slice(&crc.to_be_bytes());
+}
+
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = 0xffff_ffffu32;
+    for &byte in data {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 { (crc >> 1) ^ 0xedb8_8320 } else { crc >> 1 };
+        }
+    }
+    !crc
+}
+
+fn adler32(data: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+    for chunk in data.chunks(5552) {
+        for &byte in chunk {
+            a += byte as u32;
+            b += a;
+        }
+        a %= 65521;
+        b %= 65521;
+    }
+    (b << 16) | a
+}
+
+const LENGTH_BASE: [u16; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131,
+    163, 195, 227, 258,
+];
+const LENGTH_EXTRA: [u8; 29] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0,
+];
+const DIST_BASE: [u16; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537,
+    2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577,
+];
+const DIST_EXTRA: [u8; 30] = [
+    0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13,
+    13,
+];
+
+/// Only tries repeats of the previous pixel and of the row above, which covers the
+/// flat backgrounds that dominate toolpath renders.
+fn zlib_compress(data: &[u8], row_len: usize) -> Vec<u8> {
+    let mut writer = BitWriter::default();
+    writer.out.extend_from_slice(&[0x78, 0x01]);
+    writer.bits(1, 1);
+    writer.bits(1, 2);
+
+    let distances: Vec<usize> = [3, row_len]
+        .into_iter()
+        .filter(|d| *d <= 32768)
+        .collect();
+    let mut pos = 0;
+    while pos < data.len() {
+        let mut best = (0, 0);
+        for &dist in &distances {
+            if dist > pos {
+                continue;
+            }
+            let max = (data.len() - pos).min(258);
+            let len = (0..max)
+                .take_while(|&i| data[pos + i] == data[pos + i - dist])
+                .count();
+            if len > best.0 {
+                best = (len, dist);
+            }
+        }
+        if best.0 >= 3 {
+            writer.length(best.0);
+            writer.distance(best.1);
+            pos += best.0;
+        } else {
+            writer.symbol(data[pos] as u32);
+            pos += 1;
+        }
+    }
+    writer.symbol(256);
+    writer.flush();
+    writer.out.extend_from_slice(&adler32(data).to_be_bytes());
+    writer.out
+}
+
+#[derive(Default)]
+struct BitWriter {
+    out: Vec<u8>,
+    acc: u32,
+    count: u32,
+}
+
+impl BitWriter {
+    fn bits(&mut self, value: u32, count: u32) {
+        self.acc |= value << self.count;
+        self.count += count;
+        while self.count >= 8 {
+            self.out.push(self.acc as u8);
+            self.acc >>= 8;
+            self.count -= 8;
+        }
+    }
+
+    fn huffman(&mut self, code: u32, len: u32) {
+        let reversed = code.reverse_bits() >> (32 - len);
+        self.bits(reversed, len);
+    }
+
+    fn symbol(&mut self, sym: u32) {
+        match sym {
+            0..=143 => self.huffman(0x30 + sym, 8),
+            144..=255 => self.huffman(0x190 + sym - 144, 9),
+            256..=279 => self.huffman(sym - 256, 7),
+            _ => self.huffman(0xc0 + sym - 280, 8),
+        }
+    }
+
+    fn length(&mut self, len: usize) {
+        let idx = LENGTH_BASE.iter().rposition(|&base| base as usize <= len).unwrap_or(0);
+        self.symbol(257 + idx as u32);
+        self.bits((len - LENGTH_BASE[idx] as usize) as u32, LENGTH_EXTRA[idx] as u32);
+    }
+
+    fn distance(&mut self, dist: usize) {
+        let idx = DIST_BASE.iter().rposition(|&base| base as usize <= dist).unwrap_or(0);
+        self.huffman(idx as u32, 5);
+        self.bits((dist - DIST_BASE[idx] as usize) as u32, DIST_EXTRA[idx] as u32);
+    }
+
+    fn flush(&mut self) {
+        if self.count > 0 {
+            self.out.push(self.acc as u8);
+            self.acc = 0;
+            self.count = 0;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn base64_pads_output() {
+        assert_eq!(base64(b"f"), "Zg==");
+        assert_eq!(base64(b"fo"), "Zm8=");
+        assert_eq!(base64(b"foo"), "Zm9v");
+    }
+
+    #[test]
+    fn png_has_valid_chunk_crcs() {
+        let png = encode_png(2, 1, &[255, 0, 0, 255, 0, 0]);
+        assert_eq!(&png[..8], b"\x89PNG\r\n\x1a\n");
+        let len = u32::from_be_bytes(png[8..12].try_into().unwrap()) as usize;
+        let crc = u32::from_be_bytes(png[16 + len..20 + len].try_into().unwrap());
+        assert_eq!(crc, crc32(&png[12..16 + len]));
+        assert_eq!(crc32(b"123456789"), 0xcbf4_3926);
+    }
+
+    #[test]
+    fn scene_signature_follows_overlays() {
+        let toolpath = cnc_gcode::parse_str("G0 X1\nG1 X5 F100", cnc_gcode::ParseOptions::default()).unwrap();
+        let lines = vec!["G0 X1".to_string(), "G1 X5 F100".to_string()];
+        let config = crate::config::Config::defaults(&[]).unwrap();
+        let mut app = App::new(config, toolpath, std::path::PathBuf::from("demo.nc"), lines);
+        let area = Rect::new(0, 0, 40, 20);
+        let metrics = ViewMetrics {
+            center: cnc_geom::Vec2::new(0.0, 0.0),
+            half_w: 10.0,
+            half_h: 5.0,
+            camera_distance: 100.0,
+            target: cnc_geom::Vec3::default(),
+        };
+        let before = scene_signature(&app, area, metrics);
+        assert_eq!(scene_signature(&app, area, metrics), before);
+        app.display_origin.x = 1.0;
+        let moved = scene_signature(&app, area, metrics);
+        assert_ne!(moved, before);
+        app.grid_spacing = Some(5.0);
+        let gridded = scene_signature(&app, area, metrics);
+        assert_ne!(gridded, moved);
+        app.apply_action(crate::config::Action::ToggleFirstPass);
+        assert_ne!(scene_signature(&app, area, metrics), gridded);
+    }
+
+    #[test]
+    fn detects_supported_terminals() {
+        let kitty = detect(|key| (key == "KITTY_WINDOW_ID").then(|| "1".to_string()));
+        assert_eq!(kitty, Some(GraphicsProtocol::Kitty));
+        let iterm = detect(|key| (key == "TERM_PROGRAM").then(|| "iTerm.app".to_string()));
+        assert_eq!(iterm, Some(GraphicsProtocol::Iterm2));
+        let sixel = detect(|key| (key == "TERM").then(|| "mlterm".to_string()));
+        assert_eq!(sixel, Some(GraphicsProtocol::Sixel));
+        assert_eq!(detect(|_| None), None);
+    }
+}
@@ -0,0 +1,105 @@
+use std::collections::HashMap;
+
+/// Largest changed region, in old × new lines, diffed exactly.
+const MAX_DIFF_CELLS: usize = 1 << 22;
+
+/// For each line of `old`, the line of `new` it became after an edit. Lines kept by the edit
+/// map to themselves; removed or rewritten lines map just past the last kept line before them.
+pub fn map_lines(old: &[String], new: &[String]) -> Vec<usize> {
+    let prefix = old.iter().zip(new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let (old_mid, new_mid) = (&old[prefix..old.len() - suffix], &new[prefix..new.len() - suffix]);
+    let middle = if old_mid.len() * new_mid.len() <= MAX_DIFF_CELLS {
+        common_subsequence(old_mid, new_mid)
+    } else {
+        unique_matches(old_mid, new_mid)
+    };
+
+    let mut matches: Vec<Option<usize>> = (0..prefix).map(Some).collect();
+    matches.extend(middle.into_iter().map(|line| line.map(|line| line + prefix)));
+    matches.extend((new.len() - suffix..new.len()).map(Some));
+    let last = new.len().saturating_sub(1);
+    let mut previous: Option<usize> = None;
+    matches
+        .into_iter()
+        .map(|line| {
+            let mapped = line.unwrap_or_else(|| previous.map_or(0, |prev| prev + 1).min(last));
+            previous = line.or(previous);
+            mapped
+        })
+        .collect()
+}
+
+/// Matches of a longest common subsequence, by dynamic programming.
+fn common_subsequence(old: &[String], new: &[String]) -> Vec<Option<usize>> {
+    let width = new.len() + 1;
+    let mut lengths = vec![0u32; (old.len() + 1) * width];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lengths[i * width + j] = if old[i] == new[j] {
+                lengths[(i + 1) * width + j + 1] + 1
+            } else {
+                lengths[(i + 1) * width + j].max(lengths[i * width + j + 1])
+            };
+        }
+    }
+    let mut matches = vec![None; old.len()];
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() && j < new.len() {
+        if old[i] == new[j] {
+            matches[i] = Some(j);
+            i += 1;
+            j += 1;
+        } else if lengths[(i + 1) * width + j] >= lengths[i * width + j + 1] {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+    matches
+}
+
+/// In-order matches of lines that occur exactly once on both sides, for regions too large to
+/// diff exactly.
+fn unique_matches(old: &[String], new: &[String]) -> Vec<Option<usize>> {
+    let mut counts: HashMap<&str, (usize, usize, usize)> = HashMap::new();
+    for line in old {
+        counts.entry(line).or_default().0 += 1;
+    }
+    for (idx, line) in new.iter().enumerate() {
+        let entry = counts.entry(line).or_default();
+        entry.1 += 1;
+        entry.2 = idx;
+    }
+    let mut next = 0;
+    old.iter()
+        .map(|line| {
+            let (in_old, in_new, at) = counts[line.as_str()];
+            let matched = in_old == 1 && in_new == 1 && at >= next;
+            if matched {
+                next = at + 1;
+            }
+            matched.then_some(at)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn maps_kept_lines_across_inserts_and_deletes() {
+        let lines = |text: &str| text.split(' ').map(String::from).collect::<Vec<_>>();
+        let old = lines("G21 G0 X1 X2 X3 M30");
+        let new = lines("G21 (hdr) G0 X1 Y9 X3 M30");
+        assert_eq!(map_lines(&old, &new), [0, 2, 3, 4, 5, 6]);
+        assert_eq!(common_subsequence(&old[1..5], &new[1..6]), unique_matches(&old[1..5], &new[1..6]));
+        assert_eq!(map_lines(&old, &[]), [0; 6]);
+    }
+}
@@ -1,4 +1,8 @@
-use std::path::PathBuf;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::{ExitStatus, Stdio};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
 use std::time::Duration;
 
 use anyhow::{anyhow, Result};
@@ -10,8 +14,22 @@ use ratatui::backend::CrosstermBackend;
 use ratatui::Terminal;
 
 mod app;
+mod calibration;
+mod clipboard;
 mod config;
+mod cutting;
+mod depthmap;
+mod filter;
+mod graph;
+mod graphics;
+mod linemap;
+mod palette;
+mod raster;
 mod render;
+mod report;
+mod sixel;
+mod timeline;
+mod units;
 
 use app::App;
 use config::Action;
@@ -25,6 +43,36 @@ struct Args {
     #[arg(short = 'c', long, value_name = "PATH")]
     config: Option<PathBuf>,
 
+    #[arg(long = "set", value_name = "KEY=VALUE")]
+    overrides: Vec<String>,
+
+    #[arg(long, value_name = "NAME")]
+    machine: Option<String>,
+
+    #[arg(long, value_name = "NAME")]
+    dialect: Option<String>,
+
+    #[arg(long, value_name = "UNITS")]
+    units: Option<String>,
+
+    #[arg(long, value_name = "PLANE")]
+    plane: Option<String>,
+
+    #[arg(long, value_name = "MODE")]
+    distance: Option<String>,
+
+    #[arg(long, value_name = "RATE")]
+    feed: Option<f64>,
+
+    #[arg(long, value_name = "MM")]
+    arc_tolerance: Option<f64>,
+
+    #[arg(long)]
+    lenient: bool,
+
+    #[arg(long)]
+    watch: bool,
+
     #[arg(long, value_name = "PATH")]
     export_obj: Option<PathBuf>,
 
@@ -36,18 +84,73 @@ struct Args {
 
     #[arg(long)]
     export_only: bool,
+
+    #[arg(long)]
+    feed_report: bool,
+
+    #[arg(long, value_name = "PATH")]
+    review_report: Option<PathBuf>,
+
+    #[arg(long, value_name = "PATH")]
+    trace: Option<PathBuf>,
+
+    #[arg(long, value_name = "DURATION")]
+    record_runtime: Option<String>,
+
+    #[arg(long, value_name = "VIEW", allow_hyphen_values = true)]
+    view: Option<String>,
+
+    #[arg(long, value_name = "FACTOR")]
+    zoom: Option<f64>,
+
+    #[arg(long, value_name = "LINES")]
+    select: Option<String>,
+
+    #[arg(long, value_name = "MODE")]
+    color: Option<String>,
+
+    #[arg(long)]
+    play: bool,
+
+    #[arg(long)]
+    exit_after_load: bool,
+
+    #[arg(long, value_name = "FORMAT")]
+    report: Option<String>,
 }
 
 fn main() -> Result<()> {
     let args = Args::parse();
-    let config = config::Config::load(args.config)?;
-    let file_content = std::fs::read_to_string(&args.file)?;
-    let file_lines: Vec<String> = file_content.lines().map(|line| line.to_string()).collect();
-    let options = cnc_gcode::ParseOptions::with_ignore_missing(
-        config.parser.ignore_missing_words.clone(),
-    )
-    .with_ignore_unknown_words(config.parser.ignore_unknown_words);
-    let toolpath = cnc_gcode::parse_file_with_options(&args.file, options)?;
+    let mut overrides = args.overrides.clone();
+    if let Some(dialect) = &args.dialect {
+        overrides.push(format!("parser.dialect={}", dialect));
+    }
+    if let Some(units) = &args.units {
+        overrides.push(format!("parser.units={}", units));
+    }
+    if let Some(plane) = &args.plane {
+        overrides.push(format!("parser.plane={}", plane));
+    }
+    if let Some(distance) = &args.distance {
+        overrides.push(format!("parser.distance={}", distance));
+    }
+    if let Some(feed) = args.feed {
+        overrides.push(format!("parser.feed={:?}", feed));
+    }
+    if let Some(tolerance) = args.arc_tolerance {
+        overrides.push(format!("parser.arc_radius_tolerance={:?}", tolerance));
+    }
+    if args.lenient {
+        overrides.push("parser.lenient=true".to_string());
+    }
+    let mut config = config::Config::load(args.config.clone(), &overrides)?;
+    if let Some(name) = args.machine.as_deref() {
+        config.select_machine(name)?;
+    }
+    let file_content = std::fs::read(&args.file)?;
+    let file_lines = app::program_lines(&file_content);
+    let programs = app::ProgramPicker::new(cnc_gcode::split_programs(&file_lines));
+    let toolpath = parse_with_progress(&args.file, &file_content, programs.parse_options(config.parse_options()))?;
 
     if let Some(path) = args.export_obj.as_ref() {
         let export_options = cnc_gcode::ObjExportOptions {
@@ -63,21 +166,160 @@ fn main() -> Result<()> {
         return Err(anyhow!("--export-only requires --export-obj <PATH>"));
     }
 
-    let mut app = App::new(config, toolpath, args.file, file_lines);
+    if args.feed_report {
+        if config.machine().is_none() {
+            return Err(anyhow!("--feed-report needs a machine profile for its acceleration (--machine <NAME>)"));
+        }
+        let zones = cnc_gcode::feed_limit_zones(&toolpath, &app::time_options(&config), app::FEED_LIMIT_RATIO);
+        print!("{}", report::feed_limit_report(&toolpath, &zones, app::FEED_LIMIT_RATIO));
+        return Ok(());
+    }
+
+    if let Some(actual) = args.record_runtime.as_deref() {
+        print!("{}", calibration::record_runtime(&config, &toolpath, &args.file, actual)?);
+        return Ok(());
+    }
+
+    let mut app = App::new(config, toolpath, args.file.clone(), file_lines);
+    if let Some(path) = args.trace.as_ref() {
+        let points = cnc_gcode::parse_position_trace(&std::fs::read_to_string(path)?, app.trace_work_origin());
+        if points.is_empty() {
+            return Err(anyhow!("{}: no positions found", path.display()));
+        }
+        app.load_trace(points);
+    }
+    if let Some(path) = args.review_report.as_ref() {
+        return report::write_review_report(&mut app, path);
+    }
+    if args.exit_after_load {
+        let json = match args.report.as_deref() {
+            None | Some("text") => false,
+            Some("json") => true,
+            Some(other) => return Err(anyhow!("--report expects text or json: {}", other)),
+        };
+        print!("{}", report::load_report(&app, json));
+        std::process::exit(if report::load_passed(&app) { 0 } else { 1 });
+    } else if args.report.is_some() {
+        return Err(anyhow!("--report requires --exit-after-load"));
+    }
+    apply_launch_state(&mut app, &args)?;
+    // A popup would sit over `--play` and swallow the first key.
+    if app.config.ui.load_summary && !args.play {
+        app.open_load_summary();
+    }
 
-    run(&mut app)
+    run(&mut app, args.watch)
+}
+
+/// Applies `--view`, `--zoom`, `--select`, `--color` and `--play`.
+fn apply_launch_state(app: &mut App, args: &Args) -> Result<()> {
+    if let Some(view) = &args.view {
+        let command = palette::parse_command(&format!("view {}", view.replace(',', " ")))
+            .map_err(|err| anyhow!("--view: {}", err))?;
+        app.run_command(command);
+    }
+    if let Some(zoom) = args.zoom {
+        if zoom <= 0.0 {
+            return Err(anyhow!("--zoom must be positive: {}", zoom));
+        }
+        app.run_command(palette::Command::Zoom(zoom));
+    }
+    if let Some(select) = &args.select {
+        let line = |raw: &str| raw.trim().parse::<usize>().ok().filter(|&line| line > 0);
+        let (first, last) = match select.split_once('-') {
+            Some((first, last)) => (line(first), line(last)),
+            None => (line(select), line(select)),
+        };
+        let (Some(first), Some(last)) = (first, last) else {
+            return Err(anyhow!("--select expects <line> or <first>-<last>: {}", select));
+        };
+        app.select_lines(first.min(last) - 1..=first.max(last) - 1);
+    }
+    if let Some(name) = &args.color {
+        let mode = app::ColorMode::from_name(name)
+            .ok_or_else(|| anyhow!("--color expects kind, direction, air or feed: {}", name))?;
+        if !app.color_mode_available(mode) {
+            return Err(anyhow!("--color {} has nothing to show for this file and config", name));
+        }
+        app.set_color_mode(mode);
+    }
+    if args.play {
+        app.apply_action(Action::TogglePlayback);
+    }
+    app.notice = None;
+    Ok(())
+}
+
+const PROGRESS_MIN_BYTES: u64 = 4 << 20;
+
+/// Parses the `content` read from `path`; large files load on a worker thread, printing a
+/// percentage to stderr, and Esc, q or Ctrl+C cancels them when stdin is a terminal.
+fn parse_with_progress(
+    path: &Path,
+    content: &[u8],
+    options: cnc_gcode::ParseOptions,
+) -> Result<cnc_gcode::Toolpath> {
+    if (content.len() as u64) < PROGRESS_MIN_BYTES {
+        return cnc_gcode::parse_bytes(content, options);
+    }
+    let token = cnc_gcode::CancelToken::new();
+    let options = options.with_cancel_token(token.clone());
+    let interactive = enable_raw_mode().is_ok();
+    let hint = if interactive { ", Esc to cancel" } else { "" };
+    let result = std::thread::scope(|scope| {
+        let worker = scope.spawn(|| {
+            let mut shown = None;
+            cnc_gcode::parse_bytes_with_progress(content, options, |progress| {
+                let percent = progress.bytes * 100 / progress.total_bytes.max(1);
+                if shown != Some(percent) {
+                    shown = Some(percent);
+                    eprint!(
+                        "\rparsing {}: {:>3}% ({} lines{})",
+                        path.display(),
+                        percent,
+                        progress.lines,
+                        hint
+                    );
+                }
+            })
+        });
+        while interactive && !worker.is_finished() {
+            if event::poll(Duration::from_millis(50)).unwrap_or(false)
+                && let Ok(Event::Key(key)) = event::read()
+                && is_cancel_key(key)
+            {
+                token.cancel();
+            }
+        }
+        worker.join().unwrap_or_else(|_| Err(anyhow!("parser thread panicked")))
+    });
+    if interactive {
+        disable_raw_mode()?;
+    }
+    eprint!("\r\n");
+    result
+}
+
+fn is_cancel_key(key: event::KeyEvent) -> bool {
+    match key.code {
+        event::KeyCode::Esc | event::KeyCode::Char('q') => true,
+        event::KeyCode::Char('c') => key.modifiers.contains(event::KeyModifiers::CONTROL),
+        _ => false,
+    }
 }
 
 type TerminalBackend = CrosstermBackend<std::io::Stdout>;
 
-fn run(app: &mut App) -> Result<()> {
+const WATCH_INTERVAL: Duration = Duration::from_millis(500);
+
+fn run(app: &mut App, watch: bool) -> Result<()> {
     enable_raw_mode()?;
     let mut stdout = std::io::stdout();
     execute!(stdout, EnterAlternateScreen)?;
     let backend = CrosstermBackend::new(stdout);
     let mut terminal = Terminal::new(backend)?;
 
-    let result = run_loop(&mut terminal, app);
+    let result = run_loop(&mut terminal, app, watch);
 
     restore_terminal(&mut terminal)?;
     result
@@ -90,19 +332,41 @@ fn restore_terminal(terminal: &mut Terminal<TerminalBackend>) -> Result<()> {
     Ok(())
 }
 
-fn run_loop(terminal: &mut Terminal<TerminalBackend>, app: &mut App) -> Result<()> {
+fn run_loop(terminal: &mut Terminal<TerminalBackend>, app: &mut App, watch: bool) -> Result<()> {
     let tick_rate = Duration::from_millis(16);
     let mut last_tick = std::time::Instant::now();
+    let mut graphics = graphics::GraphicsState::default();
+    let modified = |app: &App| std::fs::metadata(&app.file_path).and_then(|meta| meta.modified()).ok();
+    let mut last_modified = modified(app);
+    let mut last_watch = last_tick;
+    let mut running: Option<RunningCommand> = None;
     loop {
         let now = std::time::Instant::now();
         let delta = now.saturating_duration_since(last_tick);
         last_tick = now;
         app.tick(delta);
+        if watch && now.duration_since(last_watch) >= WATCH_INTERVAL {
+            last_watch = now;
+            let current = modified(app);
+            if current != last_modified {
+                last_modified = current;
+                app.reload();
+                graphics.invalidate();
+            }
+        }
+        if running.as_ref().is_some_and(|command| command.drain(app)) {
+            running = None;
+            graphics.invalidate();
+        }
         terminal.draw(|f| render::draw(f, app))?;
+        graphics.present(terminal.backend_mut(), app)?;
 
-        if event::poll(tick_rate)? {
-            if let Event::Key(key) = event::read()? {
-                if key.kind != KeyEventKind::Press {
+        if !event::poll(tick_rate)? {
+            continue;
+        }
+        match event::read()? {
+            Event::Key(key) => {
+                if key.kind != KeyEventKind::Press || app.handle_help_key(key) || app.handle_palette_key(key) {
                     continue;
                 }
                 if let Some(action) = app.config.keys.action_for(key) {
@@ -110,10 +374,165 @@ fn run_loop(terminal: &mut Terminal<TerminalBackend>, app: &mut App) -> Result<(
                         break;
                     }
                     app.apply_action(action);
+                    if let Some(text) = app.clipboard.take()
+                        && let Err(err) = clipboard::copy(terminal.backend_mut(), &text)
+                    {
+                        app.notice = Some(format!("copy failed: {}", err));
+                    }
+                } else if let Some(command) = app.config.command_for(key).cloned() {
+                    if let Some(command) = run_custom_command(terminal, app, &command)? {
+                        running = Some(command);
+                    }
+                    graphics.invalidate();
                 }
             }
+            Event::Resize(_, _) => {
+                terminal.autoresize()?;
+                terminal.clear()?;
+                app.last_metrics = None;
+                graphics.invalidate();
+            }
+            _ => {}
         }
     }
 
     Ok(())
 }
+
+/// Runs a `[command.<name>]` program, handing it the terminal unless its output is captured,
+/// in which case it runs in the background and is returned for its output to be drained.
+fn run_custom_command(
+    terminal: &mut Terminal<TerminalBackend>,
+    app: &mut App,
+    command: &config::CustomCommand,
+) -> Result<Option<RunningCommand>> {
+    let (first, last) = app.file_panel.selection_range(app.file_lines.len());
+    let selection = if command.uses_selection() {
+        Some(write_selection(app.file_lines.get(first..=last).unwrap_or_default())?)
+    } else {
+        None
+    };
+    let args = command.args(&app.file_path, first + 1, last + 1, selection.as_deref());
+    let mut process = std::process::Command::new(&args[0]);
+    process.args(&args[1..]);
+    if command.capture {
+        process
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+        let mut child = match process.spawn() {
+            Ok(child) => child,
+            Err(err) => {
+                if let Some(selection) = &selection {
+                    let _ = std::fs::remove_file(selection);
+                }
+                app.notice = Some(format!("{}: {}", command.name, err));
+                return Ok(None);
+            }
+        };
+        let (sender, output) = mpsc::channel();
+        let (stdout, stderr) = (child.stdout.take(), child.stderr.take());
+        std::thread::spawn(move || {
+            let errors = sender.clone();
+            let stderr = std::thread::spawn(move || forward_lines(stderr, &errors));
+            forward_lines(stdout, &sender);
+            let _ = stderr.join();
+            let status = child.wait();
+            if let Some(selection) = &selection {
+                let _ = std::fs::remove_file(selection);
+            }
+            let _ = sender.send(CommandOutput::Exit(status));
+        });
+        app.show_output(format!("{} (running)", command.name), "");
+        return Ok(Some(RunningCommand {
+            name: command.name.clone(),
+            reload: command.reload,
+            output,
+        }));
+    }
+    restore_terminal(terminal)?;
+    let status = process.status();
+    if let Some(selection) = &selection {
+        let _ = std::fs::remove_file(selection);
+    }
+    enable_raw_mode()?;
+    execute!(terminal.backend_mut(), EnterAlternateScreen)?;
+    terminal.clear()?;
+    app.last_metrics = None;
+    match status {
+        Ok(status) if status.success() && command.reload => app.reload(),
+        Ok(status) if status.success() => app.notice = Some(format!("{} finished", command.name)),
+        Ok(status) => app.notice = Some(format!("{} failed: {}", command.name, status)),
+        Err(err) => app.notice = Some(format!("{}: {}", command.name, err)),
+    }
+    Ok(None)
+}
+
+/// A captured custom command whose output is streamed into the output panel.
+struct RunningCommand {
+    name: String,
+    reload: bool,
+    output: Receiver<CommandOutput>,
+}
+
+enum CommandOutput {
+    Line(String),
+    Exit(std::io::Result<ExitStatus>),
+}
+
+impl RunningCommand {
+    /// Moves the lines printed so far into the output panel; true once the command has exited.
+    fn drain(&self, app: &mut App) -> bool {
+        for output in self.output.try_iter() {
+            match output {
+                CommandOutput::Line(line) => app.append_output(&line),
+                CommandOutput::Exit(Ok(status)) => {
+                    app.output.title = format!("{} ({})", self.name, status);
+                    if status.success() && self.reload {
+                        app.reload();
+                    }
+                    return true;
+                }
+                CommandOutput::Exit(Err(err)) => {
+                    app.notice = Some(format!("{}: {}", self.name, err));
+                    return true;
+                }
+            }
+        }
+        false
+    }
+}
+
+fn forward_lines(reader: Option<impl Read>, sender: &Sender<CommandOutput>) {
+    let Some(reader) = reader else {
+        return;
+    };
+    for line in BufReader::new(reader).split(b'\n').map_while(std::io::Result::ok) {
+        let line = String::from_utf8_lossy(&line).trim_end_matches('\r').to_string();
+        if sender.send(CommandOutput::Line(line)).is_err() {
+            return;
+        }
+    }
+}
+
+/// Writes the selected lines to a new temp file of this process, so concurrent commands and
+/// other users never share or pre-create it.
+fn write_selection(lines: &[String]) -> Result<PathBuf> {
+    static NEXT: AtomicUsize = AtomicUsize::new(0);
+    loop {
+        let name = format!(
+            "cnc_view_tui-selection-{}-{}.nc",
+            std::process::id(),
+            NEXT.fetch_add(1, Ordering::Relaxed)
+        );
+        let path = std::env::temp_dir().join(name);
+        match std::fs::OpenOptions::new().write(true).create_new(true).open(&path) {
+            Ok(mut file) => {
+                file.write_all((lines.join("\n") + "\n").as_bytes())?;
+                return Ok(path);
+            }
+            Err(err) if err.kind() == std::io::ErrorKind::AlreadyExists => continue,
+            Err(err) => return Err(err.into()),
+        }
+    }
+}
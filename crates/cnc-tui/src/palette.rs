@@ -0,0 +1,257 @@
+use anyhow::{anyhow, Result};
+use cnc_geom::Vec2;
+
+use crate::filter::SegmentFilter;
+
+/// The `:` command prompt shown in the info strip.
+#[derive(Debug, Clone, Default)]
+pub struct CommandPalette {
+    pub open: bool,
+    pub input: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Command {
+    /// Restrict drawing to matching segments; `None` clears the filter.
+    Filter(Option<SegmentFilter>),
+    /// Set the camera angles in degrees; `None` keeps the current angle.
+    View { yaw: Option<f64>, pitch: Option<f64> },
+    Origin(OriginCommand),
+    /// List the source lines whose moves pass through an XY region.
+    Where(Region),
+    /// Set the zoom factor relative to the fitted view.
+    Zoom(f64),
+    /// Grid line spacing in display units; `None` returns to automatic spacing.
+    Grid(Option<f64>),
+    /// Hide segments lying entirely above a Z in display units relative to the readout origin;
+    /// `None` shows them again.
+    Clip(Option<f64>),
+    /// Move the paused playhead.
+    Seek(SeekTarget),
+}
+
+/// Playhead position typed into `seek`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SeekTarget {
+    /// Number of segments drawn.
+    Segment(usize),
+    /// Share of the program's segments, 0–1.
+    Fraction(f64),
+    /// Estimated machine time in seconds.
+    Time(f64),
+}
+
+/// Radius used by `where <x>,<y>` without an explicit one, in mm.
+const WHERE_RADIUS: f64 = 1.0;
+
+/// An XY area as typed, in display units relative to the readout origin.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Region {
+    Near { point: Vec2, radius: f64 },
+    /// Rectangle spanned by two opposite corners.
+    Rect(Vec2, Vec2),
+}
+
+/// Display origin changes; readouts and axes are shown relative to the origin.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OriginCommand {
+    /// Put the origin at the highlighted point, in XY only unless `with_z`.
+    Highlighted { with_z: bool },
+    /// Place the origin at explicit program coordinates in display units; missing axes are unchanged.
+    At { x: Option<f64>, y: Option<f64>, z: Option<f64> },
+    Reset,
+}
+
+pub fn parse_command(input: &str) -> Result<Command> {
+    let input = input.trim();
+    let (name, args) = input.split_once(' ').unwrap_or((input, ""));
+    let args = args.trim();
+    match name {
+        "filter" | "f" => {
+            let filter = (!args.is_empty()).then(|| SegmentFilter::parse(args)).transpose()?;
+            Ok(Command::Filter(filter))
+        }
+        "view" | "v" => parse_view(args),
+        "origin" | "o" => parse_origin(args).map(Command::Origin),
+        "where" | "w" => parse_where(args).map(Command::Where),
+        "zoom" | "z" => match number(args) {
+            Ok(factor) if factor > 0.0 => Ok(Command::Zoom(factor)),
+            _ => Err(anyhow!("usage: zoom <factor>, e.g. zoom 2.5")),
+        },
+        "grid" => match args {
+            "auto" => Ok(Command::Grid(None)),
+            _ => match number(args) {
+                Ok(spacing) if spacing > 0.0 => Ok(Command::Grid(Some(spacing))),
+                _ => Err(anyhow!("usage: grid <spacing>|auto")),
+            },
+        },
+        "clip" => match args {
+            "off" => Ok(Command::Clip(None)),
+            _ => number(args)
+                .map(|z| Command::Clip(Some(z)))
+                .map_err(|_| anyhow!("usage: clip <z>|off")),
+        },
+        "seek" | "s" => parse_seek(args).map(Command::Seek),
+        "" => Err(anyhow!("empty command")),
+        _ => Err(anyhow!("unknown command: {}", name)),
+    }
+}
+
+/// `view <yaw> [<pitch>]`, `view yaw=<deg> pitch=<deg>` or `view top|front|right`.
+fn parse_view(args: &str) -> Result<Command> {
+    let preset = match args {
+        "top" => Some((0.0, 0.0)),
+        "front" => Some((0.0, -90.0)),
+        "right" => Some((-90.0, -90.0)),
+        _ => None,
+    };
+    if let Some((yaw, pitch)) = preset {
+        return Ok(Command::View { yaw: Some(yaw), pitch: Some(pitch) });
+    }
+    let (mut yaw, mut pitch) = (None, None);
+    for (idx, arg) in args.split_whitespace().enumerate() {
+        let (slot, raw) = match arg.split_once('=') {
+            Some(("yaw", raw)) => (&mut yaw, raw),
+            Some(("pitch", raw)) => (&mut pitch, raw),
+            Some((name, _)) => return Err(anyhow!("unknown view angle: {}", name)),
+            None if idx == 0 => (&mut yaw, arg),
+            None if idx == 1 => (&mut pitch, arg),
+            None => return Err(anyhow!("usage: view <yaw> [<pitch>]")),
+        };
+        let degrees: f64 = raw
+            .trim_end_matches('°')
+            .parse()
+            .map_err(|_| anyhow!("invalid angle: {}", raw))?;
+        *slot = Some(degrees);
+    }
+    if yaw.is_none() && pitch.is_none() {
+        return Err(anyhow!("usage: view <yaw> [<pitch>]"));
+    }
+    Ok(Command::View { yaw, pitch })
+}
+
+/// `origin [xyz|reset|x=<x> y=<y> z=<z>]`.
+fn parse_origin(args: &str) -> Result<OriginCommand> {
+    match args {
+        "" | "xy" => return Ok(OriginCommand::Highlighted { with_z: false }),
+        "xyz" => return Ok(OriginCommand::Highlighted { with_z: true }),
+        "reset" | "clear" => return Ok(OriginCommand::Reset),
+        _ => {}
+    }
+    let (mut x, mut y, mut z) = (None, None, None);
+    for arg in args.split_whitespace() {
+        let Some((axis, raw)) = arg.split_once('=') else {
+            return Err(anyhow!("usage: origin [xyz|reset|x=<x> y=<y> z=<z>]"));
+        };
+        let slot = match axis.to_ascii_lowercase().as_str() {
+            "x" => &mut x,
+            "y" => &mut y,
+            "z" => &mut z,
+            _ => return Err(anyhow!("unknown axis: {}", axis)),
+        };
+        *slot = Some(raw.parse().map_err(|_| anyhow!("invalid coordinate: {}", raw))?);
+    }
+    Ok(OriginCommand::At { x, y, z })
+}
+
+fn number(raw: &str) -> Result<f64> {
+    raw.trim().parse().map_err(|_| anyhow!("invalid number: {}", raw))
+}
+
+/// `seek <segments>`, `seek <percent>%` or `seek [h:]m:ss`.
+fn parse_seek(args: &str) -> Result<SeekTarget> {
+    let usage = || anyhow!("usage: seek <segments>|<percent>%|<m:ss>");
+    if let Some(percent) = args.strip_suffix('%') {
+        let percent = number(percent).map_err(|_| usage())?;
+        return Ok(SeekTarget::Fraction((percent / 100.0).clamp(0.0, 1.0)));
+    }
+    if args.contains(':') {
+        let mut seconds = 0.0;
+        for part in args.split(':') {
+            let value = number(part).map_err(|_| usage())?;
+            if value < 0.0 {
+                return Err(usage());
+            }
+            seconds = seconds * 60.0 + value;
+        }
+        return Ok(SeekTarget::Time(seconds));
+    }
+    args.parse().map(SeekTarget::Segment).map_err(|_| usage())
+}
+
+/// `where <x>,<y> [<radius>]` or `where <x1>,<y1> <x2>,<y2>`.
+fn parse_where(args: &str) -> Result<Region> {
+    let usage = || anyhow!("usage: where <x>,<y> [<radius>] | where <x1>,<y1> <x2>,<y2>");
+    let number = |raw: &str| raw.trim().parse::<f64>().map_err(|_| anyhow!("invalid coordinate: {}", raw));
+    let point = |raw: &str| -> Result<Vec2> {
+        let (x, y) = raw.split_once(',').ok_or_else(usage)?;
+        Ok(Vec2::new(number(x)?, number(y)?))
+    };
+    let mut parts = args.split_whitespace();
+    let first = point(parts.next().ok_or_else(usage)?)?;
+    let region = match parts.next() {
+        None => Region::Near { point: first, radius: WHERE_RADIUS },
+        Some(corner) if corner.contains(',') => Region::Rect(first, point(corner)?),
+        Some(radius) => Region::Near { point: first, radius: number(radius)? },
+    };
+    if parts.next().is_some() {
+        return Err(usage());
+    }
+    Ok(region)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_commands() {
+        let Command::Filter(Some(filter)) = parse_command("filter  kind=feed && z<-2").unwrap() else {
+            panic!("expected a filter");
+        };
+        assert_eq!(filter.source, "kind=feed && z<-2");
+        assert_eq!(parse_command("filter").unwrap(), Command::Filter(None));
+        assert!(parse_command("frobnicate").is_err());
+        assert_eq!(
+            parse_command("view 30 -15").unwrap(),
+            Command::View { yaw: Some(30.0), pitch: Some(-15.0) }
+        );
+        assert_eq!(
+            parse_command("view pitch=90").unwrap(),
+            Command::View { yaw: None, pitch: Some(90.0) }
+        );
+        assert_eq!(
+            parse_command("view front").unwrap(),
+            Command::View { yaw: Some(0.0), pitch: Some(-90.0) }
+        );
+        assert!(parse_command("view").is_err());
+        assert_eq!(
+            parse_command("origin").unwrap(),
+            Command::Origin(OriginCommand::Highlighted { with_z: false })
+        );
+        assert_eq!(
+            parse_command("origin x=10 z=-2").unwrap(),
+            Command::Origin(OriginCommand::At { x: Some(10.0), y: None, z: Some(-2.0) })
+        );
+        assert!(parse_command("origin q=1").is_err());
+        assert_eq!(
+            parse_command("where 10,5").unwrap(),
+            Command::Where(Region::Near { point: Vec2::new(10.0, 5.0), radius: WHERE_RADIUS })
+        );
+        assert_eq!(
+            parse_command("where -1,2 3,4").unwrap(),
+            Command::Where(Region::Rect(Vec2::new(-1.0, 2.0), Vec2::new(3.0, 4.0)))
+        );
+        assert!(parse_command("where 10").is_err());
+        assert_eq!(parse_command("zoom 2.5").unwrap(), Command::Zoom(2.5));
+        assert!(parse_command("zoom 0").is_err());
+        assert_eq!(parse_command("grid 5").unwrap(), Command::Grid(Some(5.0)));
+        assert_eq!(parse_command("grid auto").unwrap(), Command::Grid(None));
+        assert_eq!(parse_command("clip -2.5").unwrap(), Command::Clip(Some(-2.5)));
+        assert_eq!(parse_command("clip off").unwrap(), Command::Clip(None));
+        assert_eq!(parse_command("seek 120").unwrap(), Command::Seek(SeekTarget::Segment(120)));
+        assert_eq!(parse_command("seek 25%").unwrap(), Command::Seek(SeekTarget::Fraction(0.25)));
+        assert_eq!(parse_command("seek 1:02:30").unwrap(), Command::Seek(SeekTarget::Time(3750.0)));
+        assert!(parse_command("seek soon").is_err());
+    }
+}
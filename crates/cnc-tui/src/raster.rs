@@ -0,0 +1,123 @@
+use cnc_geom::Vec2;
+use ratatui::style::Color;
+
+use crate::render::{color_to_rgb, Painter};
+
+/// RGB image that maps canvas world coordinates onto pixels.
+pub struct Raster {
+    pub width: usize,
+    pub height: usize,
+    pub pixels: Vec<u8>,
+    x_bounds: [f64; 2],
+    y_bounds: [f64; 2],
+}
+
+impl Raster {
+    pub fn new(
+        width: usize,
+        height: usize,
+        x_bounds: [f64; 2],
+        y_bounds: [f64; 2],
+        background: Color,
+    ) -> Self {
+        let (r, g, b) = color_to_rgb(background).unwrap_or((0, 0, 0));
+        let pixels = [r, g, b].repeat(width * height);
+        Self {
+            width,
+            height,
+            pixels,
+            x_bounds,
+            y_bounds,
+        }
+    }
+
+    fn to_pixel(&self, p: Vec2) -> (f64, f64) {
+        let span_x = (self.x_bounds[1] - self.x_bounds[0]).max(1e-9);
+        let span_y = (self.y_bounds[1] - self.y_bounds[0]).max(1e-9);
+        let x = (p.x - self.x_bounds[0]) / span_x * (self.width as f64 - 1.0);
+        let y = (self.y_bounds[1] - p.y) / span_y * (self.height as f64 - 1.0);
+        (x, y)
+    }
+
+    fn blend(&mut self, x: i64, y: i64, rgb: (u8, u8, u8), alpha: f64) {
+        if x < 0 || y < 0 || x >= self.width as i64 || y >= self.height as i64 || alpha <= 0.0 {
+            return;
+        }
+        let idx = (y as usize * self.width + x as usize) * 3;
+        let alpha = alpha.min(1.0);
+        for (offset, channel) in [rgb.0, rgb.1, rgb.2].into_iter().enumerate() {
+            let dst = self.pixels[idx + offset] as f64;
+            self.pixels[idx + offset] = (dst + (channel as f64 - dst) * alpha).round() as u8;
+        }
+    }
+}
+
+impl Painter for Raster {
+    /// Xiaolin Wu anti-aliased line.
+    fn line(&mut self, from: Vec2, to: Vec2, color: Color) {
+        let Some(rgb) = color_to_rgb(color) else {
+            return;
+        };
+        let (mut x0, mut y0) = self.to_pixel(from);
+        let (mut x1, mut y1) = self.to_pixel(to);
+        let limit = 4.0 * (self.width + self.height) as f64;
+        if [x0, y0, x1, y1].iter().any(|v| !v.is_finite() || v.abs() > limit) {
+            return;
+        }
+        let steep = (y1 - y0).abs() > (x1 - x0).abs();
+        if steep {
+            std::mem::swap(&mut x0, &mut y0);
+            std::mem::swap(&mut x1, &mut y1);
+        }
+        if x0 > x1 {
+            std::mem::swap(&mut x0, &mut x1);
+            std::mem::swap(&mut y0, &mut y1);
+        }
+        let dx = x1 - x0;
+        let gradient = if dx.abs() < 1e-9 { 1.0 } else { (y1 - y0) / dx };
+        let plot = |raster: &mut Raster, x: i64, y: i64, alpha: f64| {
+            if steep {
+                raster.blend(y, x, rgb, alpha);
+            } else {
+                raster.blend(x, y, rgb, alpha);
+            }
+        };
+        let start = x0.round() as i64;
+        let end = x1.round() as i64;
+        let mut y = y0 + gradient * (start as f64 - x0);
+        for x in start..=end {
+            let base = y.floor();
+            let frac = y - base;
+            plot(self, x, base as i64, 1.0 - frac);
+            plot(self, x, base as i64 + 1, frac);
+            y += gradient;
+        }
+    }
+
+    fn label(&mut self, _at: Vec2, _text: &'static str, _color: Color) {}
+
+    /// Three parallel one-pixel lines; `dot` is ignored since pixels are finer than canvas dots.
+    fn thick_line(&mut self, from: Vec2, to: Vec2, color: Color, _dot: Vec2) {
+        let span_x = (self.x_bounds[1] - self.x_bounds[0]) / self.width.max(1) as f64;
+        let span_y = (self.y_bounds[1] - self.y_bounds[0]) / self.height.max(1) as f64;
+        self.line(from, to, color);
+        for offset in [Vec2::new(span_x, 0.0), Vec2::new(0.0, span_y)] {
+            self.line(from + offset, to + offset, color);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn line_maps_world_to_pixels() {
+        let mut raster = Raster::new(11, 11, [0.0, 10.0], [0.0, 10.0], Color::Black);
+        raster.line(Vec2::new(0.0, 10.0), Vec2::new(10.0, 10.0), Color::Rgb(255, 0, 0));
+        assert_eq!(&raster.pixels[0..3], &[255, 0, 0]);
+        assert_eq!(&raster.pixels[10 * 3..10 * 3 + 3], &[255, 0, 0]);
+        let bottom = 10 * 11 * 3;
+        assert_eq!(&raster.pixels[bottom..bottom + 3], &[0, 0, 0]);
+    }
+}
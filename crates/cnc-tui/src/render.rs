@@ -1,19 +1,50 @@
 use ratatui::layout::{Constraint, Direction, Layout};
-use ratatui::style::Style;
+use ratatui::style::{Modifier, Style};
 use ratatui::text::{Line as TextLine, Span};
-use ratatui::widgets::canvas::{Canvas, Line};
+use ratatui::style::Color;
+use ratatui::widgets::canvas::{Canvas, Context, Line};
 use ratatui::widgets::{Block, Borders, Clear, Paragraph};
 use ratatui::Frame;
 
-use crate::app::{App, PanelFocus};
-use cnc_geom::{project_point, ProjectionMode, ProjectionParams, Vec3, ViewAngles};
-use cnc_gcode::MoveKind;
+use crate::app::{bounds_corners, program_label, App, ColorMode, DepthGraph, PanelFocus, RestartBlock, ViewMetrics};
+use crate::depthmap::draw_depth_map;
+use crate::graph::{draw_depth_graph, GRAPH_HEIGHT};
+use crate::timeline::{draw_timeline, TIMELINE_HEIGHT};
+use crate::config::{BackgroundPattern, Corner, Fade, UiSettings};
+use crate::units;
+use cnc_geom::{project_point, ProjectionMode, ProjectionParams, Vec2, Vec3, ViewAngles};
+use cnc_gcode::{Coolant, CutDirection, MoveKind, ProgramStop, Spindle, Units};
+
+const MIN_WIDTH: u16 = 40;
+const MIN_HEIGHT: u16 = 12;
+
+/// Strength of a fresh reload ghost against the background.
+const GHOST_OPACITY: f64 = 0.35;
+
+/// Longest scale bar, as a share of the viewport width.
+const SCALE_BAR_SHARE: f64 = 0.25;
 
 pub fn draw(frame: &mut Frame<'_>, app: &mut App) {
     let size = frame.size();
+    if size.width < MIN_WIDTH || size.height < MIN_HEIGHT {
+        app.canvas_area = None;
+        draw_too_small(frame, app, size);
+        return;
+    }
+    let graph_height = match app.depth_graph {
+        DepthGraph::Off => 0,
+        _ => GRAPH_HEIGHT.min(size.height / 3),
+    };
+    let timeline_height = if app.timeline.open { TIMELINE_HEIGHT } else { 0 };
     let main = Layout::default()
         .direction(Direction::Vertical)
-        .constraints([Constraint::Min(1), Constraint::Length(1)])
+        .constraints([
+            Constraint::Min(1),
+            Constraint::Length(graph_height),
+            Constraint::Length(timeline_height),
+            Constraint::Length(1),
+            Constraint::Length(1),
+        ])
         .split(size);
     let body = Layout::default()
         .direction(Direction::Horizontal)
@@ -22,6 +53,7 @@ pub fn draw(frame: &mut Frame<'_>, app: &mut App) {
 
     let metrics = app.compute_view_metrics(body[0]);
     app.last_metrics = Some(metrics);
+    app.canvas_area = Some(Block::default().borders(Borders::ALL).inner(body[0]));
 
     let theme = app.config.theme.clone();
     let canvas = Canvas::default()
@@ -41,25 +73,41 @@ pub fn draw(frame: &mut Frame<'_>, app: &mut App) {
             metrics.center.y + metrics.half_h,
         ])
         .paint(|ctx| {
-            let params = ProjectionParams {
-                mode: app.view.projection,
-                angles: ViewAngles {
-                    yaw: app.view.yaw,
-                    pitch: app.view.pitch,
-                },
-                camera_distance: metrics.camera_distance,
-                target: metrics.target,
-            };
-            draw_plane(ctx, app, params);
-            draw_grid(ctx, app, params);
-            draw_axes(ctx, app, params);
-            draw_toolpath(ctx, app, params);
+            if app.graphics.is_some() {
+                draw_axes(&mut LabelsOnly(ctx), app, scene_params(app, metrics));
+            } else {
+                paint_scene(ctx, app, metrics);
+            }
         });
 
-    frame.render_widget(canvas, body[0]);
+    if app.show_depth_map {
+        draw_depth_map(frame, app, body[0]);
+    } else {
+        frame.render_widget(canvas, body[0]);
+        draw_hud_origin(frame, app);
+        draw_dro(frame, app);
+        draw_scale_bar(frame, app, metrics);
+        draw_legend(frame, app);
+    }
+    if app.output.open {
+        draw_output_panel(frame, app, body[1]);
+    } else if app.operations.open {
+        draw_operations_panel(frame, app, body[1]);
+    } else if app.programs.open {
+        draw_programs_panel(frame, app, body[1]);
+    } else {
+        draw_file_panel(frame, app, body[1]);
+    }
+    if graph_height > 0 {
+        draw_depth_graph(frame, app, main[1]);
+    }
+    if timeline_height > 0 {
+        draw_timeline(frame, app, main[2]);
+    }
 
-    draw_hud_origin(frame, app, body[0]);
-    draw_file_panel(frame, app, body[1]);
+    let info_widget = Paragraph::new(build_info_line(app))
+        .style(Style::default().fg(theme.foreground).bg(theme.background));
+    frame.render_widget(info_widget, main[3]);
 
     let status = build_status_line(app);
     let status_widget = Paragraph::new(status).style(
@@ -67,100 +115,396 @@ pub fn draw(frame: &mut Frame<'_>, app: &mut App) {
             .fg(theme.status_fg)
             .bg(theme.status_bg),
     );
-    frame.render_widget(status_widget, main[1]);
+    frame.render_widget(status_widget, main[4]);
 
     if app.show_help {
         draw_help_popup(frame, app, size);
     }
+    if let Some(restart) = &app.restart {
+        draw_restart_popup(frame, app, restart, size);
+    }
+    if let Some(summary) = &app.summary {
+        draw_summary_popup(frame, app, summary, size);
+    }
+    if let Some(stop) = app.stopped_at.and_then(|index| app.toolpath.stops.get(index)) {
+        draw_stop_banner(frame, app, stop, body[0]);
+    }
+}
+
+pub trait Painter {
+    fn line(&mut self, from: Vec2, to: Vec2, color: Color);
+    fn label(&mut self, at: Vec2, text: &'static str, color: Color);
+
+    /// A line widened by one `dot` (world units of a canvas dot) on each axis.
+    fn thick_line(&mut self, from: Vec2, to: Vec2, color: Color, dot: Vec2) {
+        self.line(from, to, color);
+        for offset in [Vec2::new(dot.x, 0.0), Vec2::new(0.0, dot.y)] {
+            self.line(from + offset, to + offset, color);
+        }
+    }
+}
+
+impl Painter for Context<'_> {
+    fn line(&mut self, from: Vec2, to: Vec2, color: Color) {
+        self.draw(&Line {
+            x1: from.x,
+            y1: from.y,
+            x2: to.x,
+            y2: to.y,
+            color,
+        });
+    }
+
+    fn label(&mut self, at: Vec2, text: &'static str, color: Color) {
+        self.print(
+            at.x,
+            at.y,
+            TextLine::from(Span::styled(text, Style::default().fg(color))),
+        );
+    }
+}
+
+/// Forwards only text labels, for when lines are drawn by a terminal graphics protocol.
+struct LabelsOnly<'a, 'b>(&'a mut Context<'b>);
+
+impl Painter for LabelsOnly<'_, '_> {
+    fn line(&mut self, _from: Vec2, _to: Vec2, _color: Color) {}
+
+    fn thick_line(&mut self, _from: Vec2, _to: Vec2, _color: Color, _dot: Vec2) {}
+
+    fn label(&mut self, at: Vec2, text: &'static str, color: Color) {
+        self.0.label(at, text, color);
+    }
+}
+
+pub fn scene_params(app: &App, metrics: ViewMetrics) -> ProjectionParams {
+    ProjectionParams {
+        mode: app.view.projection,
+        angles: ViewAngles {
+            yaw: app.view.yaw,
+            pitch: app.view.pitch,
+        },
+        camera_distance: metrics.camera_distance,
+        target: metrics.target,
+        z_scale: app.view.z_scale,
+    }
+}
+
+pub fn paint_scene(ctx: &mut impl Painter, app: &App, metrics: ViewMetrics) {
+    let params = scene_params(app, metrics);
+    draw_background(ctx, app, metrics);
+    draw_plane(ctx, app, params);
+    draw_grid(ctx, app, params);
+    draw_safe_planes(ctx, app, params);
+    draw_footprint(ctx, app, params);
+    draw_envelope(ctx, app, params);
+    draw_axes(ctx, app, params);
+    draw_ghost(ctx, app, params);
+    draw_toolpath(ctx, app, params);
+    draw_probe_targets(ctx, app, params);
+    draw_corners(ctx, app, params);
+    draw_work_offsets(ctx, app, params);
+    draw_trace(ctx, app, params);
+}
+
+fn draw_too_small(frame: &mut Frame<'_>, app: &App, area: ratatui::layout::Rect) {
+    let theme = &app.config.theme;
+    let lines = vec![
+        TextLine::from("Terminal too small"),
+        TextLine::from(format!(
+            "{}x{} (need {}x{})",
+            area.width, area.height, MIN_WIDTH, MIN_HEIGHT
+        )),
+    ];
+    let top = area.height.saturating_sub(lines.len() as u16) / 2;
+    let rect = ratatui::layout::Rect {
+        x: area.x,
+        y: area.y + top,
+        width: area.width,
+        height: area.height.saturating_sub(top),
+    };
+    frame.render_widget(
+        Block::default().style(Style::default().bg(theme.background)),
+        area,
+    );
+    frame.render_widget(
+        Paragraph::new(lines)
+            .alignment(ratatui::layout::Alignment::Center)
+            .style(Style::default().bg(theme.background).fg(theme.foreground)),
+        rect,
+    );
 }
 
-fn draw_toolpath(ctx: &mut ratatui::widgets::canvas::Context, app: &App, params: ProjectionParams) {
+/// Rows of the background pattern across the view height.
+const PATTERN_ROWS: f64 = 12.0;
+
+/// Dots or blueprint lines fixed to the view rather than the part; every fifth blueprint line
+/// leans toward the grid color.
+fn draw_background(ctx: &mut impl Painter, app: &App, metrics: ViewMetrics) {
+    let pattern = app.config.theme.background_pattern;
+    if pattern == BackgroundPattern::Blank || metrics.half_h <= 0.0 {
+        return;
+    }
+    let step = metrics.half_h * 2.0 / PATTERN_ROWS;
+    let (left, bottom) = (metrics.center.x - metrics.half_w, metrics.center.y - metrics.half_h);
+    let (right, top) = (metrics.center.x + metrics.half_w, metrics.center.y + metrics.half_h);
+    let columns = (metrics.half_w * 2.0 / step).ceil() as usize;
+    let color = app.config.theme.pattern;
+    let major = fade_color(color, app.config.theme.grid, 0.5);
+    match pattern {
+        BackgroundPattern::Blank => {}
+        BackgroundPattern::Dots => {
+            for col in 1..=columns {
+                for row in 1..PATTERN_ROWS as usize {
+                    let point = Vec2::new(left + col as f64 * step, bottom + row as f64 * step);
+                    ctx.line(point, point, color);
+                }
+            }
+        }
+        BackgroundPattern::Blueprint => {
+            for col in 1..=columns {
+                let x = left + col as f64 * step;
+                let color = if col % 5 == 0 { major } else { color };
+                ctx.line(Vec2::new(x, bottom), Vec2::new(x, top), color);
+            }
+            for row in 1..PATTERN_ROWS as usize {
+                let y = bottom + row as f64 * step;
+                let color = if row % 5 == 0 { major } else { color };
+                ctx.line(Vec2::new(left, y), Vec2::new(right, y), color);
+            }
+        }
+    }
+}
+
+fn draw_toolpath(ctx: &mut impl Painter, app: &App, params: ProjectionParams) {
     let (start_idx, end_idx) = app.visible_segment_range();
     let total_visible = end_idx.saturating_sub(start_idx);
     let background = app.config.theme.background;
-    for (idx, seg) in app
-        .toolpath
-        .segments
-        .iter()
-        .skip(start_idx)
-        .take(total_visible)
-        .enumerate()
-    {
+    let (skip, take) = if app.focus_selection {
+        (0, app.toolpath.segments.len())
+    } else {
+        (start_idx, total_visible)
+    };
+    let dot = canvas_dot(app);
+    let segments = &app.toolpath.segments;
+    for run in app.lod_runs(skip..skip + take) {
+        let idx = run.start;
+        let seg = &segments[idx];
+        let last = &segments[run.end - 1];
         let start = project_point(seg.start, params);
-        let end = project_point(seg.end, params);
-        let fade = segment_fade(idx, total_visible);
+        let end = project_point(last.end, params);
+        let selected = (start_idx..end_idx).contains(&idx);
+        let fade = match (app.focus_selection, selected) {
+            (false, _) => segment_fade(idx - start_idx, total_visible, &app.config.ui),
+            (true, true) => 1.0,
+            (true, false) => 0.2,
+        };
         let color = match seg.kind {
+            MoveKind::Rapid if app.low_rapid(idx) => fade_color(app.config.theme.low_rapid, background, fade),
             MoveKind::Rapid => fade_color(app.config.theme.path_rapid, background, fade * 0.7),
-            MoveKind::Feed => fade_color(app.config.theme.path_feed, background, fade),
+            MoveKind::Feed => fade_color(segment_color(app, idx), background, fade),
+            MoveKind::Probe => fade_color(app.config.theme.probe, background, fade),
         };
-        ctx.draw(&Line {
-            x1: start.x,
-            y1: start.y,
-            x2: end.x,
-            y2: end.y,
-            color,
-        });
+        if seg.kind == MoveKind::Probe {
+            dashed_line(ctx, start, end, color, dot);
+        } else if app.focus_selection && selected {
+            ctx.thick_line(start, end, color, dot);
+        } else {
+            ctx.line(
+                start,
+                end,
+                color,
+            );
+        }
+    }
+}
+
+fn draw_ghost(ctx: &mut impl Painter, app: &App, params: ProjectionParams) {
+    let (Some(ghost), Some(fade)) = (&app.ghost, app.ghost_fade()) else {
+        return;
+    };
+    let color = fade_color(app.config.theme.ghost, app.config.theme.background, fade * GHOST_OPACITY);
+    let budget = app.config.ui.max_segments;
+    let stride = if budget == 0 { 1 } else { ghost.segments.len().div_ceil(budget).max(1) };
+    let segments = &ghost.segments;
+    let mut idx = 0;
+    while idx < segments.len() {
+        let limit = (idx + stride).min(segments.len());
+        let end = (idx + 1..limit).find(|&next| segments[next].kind != segments[idx].kind).unwrap_or(limit);
+        ctx.line(project_point(segments[idx].start, params), project_point(segments[end - 1].end, params), color);
+        idx = end;
+    }
+}
+
+/// Dashes about four canvas dots long, so probe moves stand out from feeds.
+fn dashed_line(ctx: &mut impl Painter, from: Vec2, to: Vec2, color: Color, dot: Vec2) {
+    let (dx, dy) = (to.x - from.x, to.y - from.y);
+    let dash = dot.x.max(dot.y) * 4.0;
+    let pieces = if dash > 0.0 { ((dx * dx + dy * dy).sqrt() / dash) as usize } else { 0 };
+    if pieces < 3 {
+        ctx.line(from, to, color);
+        return;
+    }
+    let at = |piece: usize| {
+        let t = piece as f64 / pieces as f64;
+        Vec2::new(from.x + dx * t, from.y + dy * t)
+    };
+    for piece in (0..pieces).step_by(2) {
+        ctx.line(at(piece), at(piece + 1), color);
+    }
+}
+
+/// Marks where each visible probe move is expected to touch (or leave) the workpiece.
+fn draw_probe_targets(ctx: &mut impl Painter, app: &App, params: ProjectionParams) {
+    let (start_idx, end_idx) = app.visible_segment_range();
+    for probe in &app.toolpath.probes {
+        if !(start_idx..end_idx).contains(&probe.segment) || app.segment_hidden(probe.segment) {
+            continue;
+        }
+        let seg = &app.toolpath.segments[probe.segment];
+        let target = if probe.mode.toward { seg.end } else { seg.start };
+        ctx.label(project_point(target, params), "◎", app.config.theme.probe);
+    }
+}
+
+fn draw_corners(ctx: &mut impl Painter, app: &App, params: ProjectionParams) {
+    if !app.show_corners {
+        return;
+    }
+    let (start_idx, end_idx) = app.visible_segment_range();
+    let first = app.corners.partition_point(|corner| corner.segment < start_idx);
+    for corner in app.corners[first..].iter().take_while(|corner| corner.segment < end_idx) {
+        if app.segment_hidden(corner.segment) {
+            continue;
+        }
+        let point = app.toolpath.segments[corner.segment].start;
+        ctx.label(project_point(point, params), "◆", app.config.theme.corner);
+    }
+}
+
+/// Configured work coordinate origins, so multi-fixture jobs can be checked against the path.
+fn draw_work_offsets(ctx: &mut impl Painter, app: &App, params: ProjectionParams) {
+    let dot = canvas_dot(app);
+    for (offset, origin) in &app.config.parser.work_offsets {
+        let at = project_point(*origin, params);
+        ctx.label(at, "⊕", app.config.theme.work_offset);
+        ctx.label(at + Vec2::new(dot.x * 4.0, 0.0), offset.code(), app.config.theme.work_offset);
     }
 }
 
-fn draw_axes(ctx: &mut ratatui::widgets::canvas::Context, app: &App, params: ProjectionParams) {
+/// Machine-reported path, with steps ending off the programmed path in the error color.
+fn draw_trace(ctx: &mut impl Painter, app: &App, params: ProjectionParams) {
+    let Some(trace) = &app.trace else {
+        return;
+    };
+    let theme = &app.config.theme;
+    for (index, pair) in trace.points.windows(2).enumerate() {
+        let color = if trace.is_off_path(index + 1, app.config.ui.trace_tolerance) {
+            theme.trace_error
+        } else {
+            theme.trace
+        };
+        ctx.line(project_point(pair[0], params), project_point(pair[1], params), color);
+    }
+}
+
+/// World size of one braille dot in the toolpath canvas.
+fn canvas_dot(app: &App) -> Vec2 {
+    match (app.last_metrics, app.canvas_area) {
+        (Some(metrics), Some(area)) if area.width > 0 && area.height > 0 => Vec2::new(
+            metrics.half_w / area.width as f64,
+            metrics.half_h / (area.height as f64 * 2.0),
+        ),
+        _ => Vec2::new(0.0, 0.0),
+    }
+}
+
+fn segment_color(app: &App, index: usize) -> Color {
+    let theme = &app.config.theme;
+    match app.color_mode {
+        ColorMode::Kind => theme.path_feed,
+        ColorMode::CutDirection => match app.cut_direction(index) {
+            Some(CutDirection::Climb) => theme.climb,
+            Some(CutDirection::Conventional) => theme.conventional,
+            None => theme.path_feed,
+        },
+        ColorMode::AirCut => match app.air_cuts.as_ref().and_then(|air| air.mask.get(index)) {
+            Some(true) => theme.air_cut,
+            _ => theme.path_feed,
+        },
+        ColorMode::FeedLimit if app.feed_limited(index) => theme.feed_limited,
+        ColorMode::FeedLimit => theme.path_feed,
+    }
+}
+
+fn draw_envelope(ctx: &mut impl Painter, app: &App, params: ProjectionParams) {
+    let Some(envelope) = app.config.machine().and_then(|machine| machine.envelope) else {
+        return;
+    };
+    let corners = bounds_corners(envelope).map(|corner| project_point(corner, params));
+    const EDGES: [(usize, usize); 12] = [
+        (0, 1),
+        (1, 3),
+        (3, 2),
+        (2, 0),
+        (4, 5),
+        (5, 7),
+        (7, 6),
+        (6, 4),
+        (0, 4),
+        (1, 5),
+        (2, 6),
+        (3, 7),
+    ];
+    for (a, b) in EDGES {
+        ctx.line(
+            Vec2::new(corners[a].x, corners[a].y),
+            Vec2::new(corners[b].x, corners[b].y),
+            app.config.theme.envelope,
+        );
+    }
+}
+
+fn draw_axes(ctx: &mut impl Painter, app: &App, params: ProjectionParams) {
     let bounds = app.toolpath.bounds;
     let size = bounds.size();
     let max_dim = size.x.max(size.y).max(size.z).max(1.0);
     let axis_len = max_dim * 0.4;
 
-    let origin = Vec3::new(0.0, 0.0, 0.0);
-    let x_end = Vec3::new(axis_len, 0.0, 0.0);
-    let y_end = Vec3::new(0.0, axis_len, 0.0);
-    let z_end = Vec3::new(0.0, 0.0, axis_len);
+    let origin = app.display_origin;
+    let x_end = origin + Vec3::new(axis_len, 0.0, 0.0);
+    let y_end = origin + Vec3::new(0.0, axis_len, 0.0);
+    let z_end = origin + Vec3::new(0.0, 0.0, axis_len);
 
     let o = project_point(origin, params);
     let x = project_point(x_end, params);
     let y = project_point(y_end, params);
     let z = project_point(z_end, params);
 
-    ctx.draw(&Line {
-        x1: o.x,
-        y1: o.y,
-        x2: x.x,
-        y2: x.y,
-        color: app.config.theme.axis_x,
-    });
-    draw_arrow(ctx, o, x, app.config.theme.axis_x);
-    ctx.draw(&Line {
-        x1: o.x,
-        y1: o.y,
-        x2: y.x,
-        y2: y.y,
-        color: app.config.theme.axis_y,
-    });
-    draw_arrow(ctx, o, y, app.config.theme.axis_y);
-    ctx.draw(&Line {
-        x1: o.x,
-        y1: o.y,
-        x2: z.x,
-        y2: z.y,
-        color: app.config.theme.axis_z,
-    });
-    draw_arrow(ctx, o, z, app.config.theme.axis_z);
-    ctx.print(
-        x.x,
-        x.y,
-        TextLine::from(Span::styled("X", Style::default().fg(app.config.theme.axis_x))),
+    ctx.line(
+        o,
+        x,
+        app.config.theme.axis_x,
     );
-    ctx.print(
-        y.x,
-        y.y,
-        TextLine::from(Span::styled("Y", Style::default().fg(app.config.theme.axis_y))),
+    draw_arrow(ctx, o, x, app.config.theme.axis_x);
+    ctx.line(
+        o,
+        y,
+        app.config.theme.axis_y,
     );
-    ctx.print(
-        z.x,
-        z.y,
-        TextLine::from(Span::styled("Z", Style::default().fg(app.config.theme.axis_z))),
+    draw_arrow(ctx, o, y, app.config.theme.axis_y);
+    ctx.line(
+        o,
+        z,
+        app.config.theme.axis_z,
     );
+    draw_arrow(ctx, o, z, app.config.theme.axis_z);
+    ctx.label(x, "X", app.config.theme.axis_x);
+    ctx.label(y, "Y", app.config.theme.axis_y);
+    ctx.label(z, "Z", app.config.theme.axis_z);
 }
 
-fn draw_grid(ctx: &mut ratatui::widgets::canvas::Context, app: &App, params: ProjectionParams) {
+fn draw_grid(ctx: &mut impl Painter, app: &App, params: ProjectionParams) {
     let bounds = app.toolpath.bounds;
     if !bounds.initialized {
         return;
@@ -168,7 +512,7 @@ fn draw_grid(ctx: &mut ratatui::widgets::canvas::Context, app: &App, params: Pro
 
     let size = bounds.size();
     let max_dim = size.x.max(size.z).max(1.0);
-    let step = (max_dim / 10.0).max(1.0);
+    let step = app.grid_spacing.unwrap_or((max_dim / 10.0).max(1.0));
 
     let start_x = (bounds.min.x / step).floor() * step;
     let end_x = (bounds.max.x / step).ceil() * step;
@@ -179,13 +523,11 @@ fn draw_grid(ctx: &mut ratatui::widgets::canvas::Context, app: &App, params: Pro
     while x <= end_x {
         let p1 = project_point(Vec3::new(x, 0.0, start_z), params);
         let p2 = project_point(Vec3::new(x, 0.0, end_z), params);
-        ctx.draw(&Line {
-            x1: p1.x,
-            y1: p1.y,
-            x2: p2.x,
-            y2: p2.y,
-            color: app.config.theme.grid,
-        });
+        ctx.line(
+            p1,
+            p2,
+            app.config.theme.grid,
+        );
         x += step;
     }
 
@@ -193,18 +535,16 @@ fn draw_grid(ctx: &mut ratatui::widgets::canvas::Context, app: &App, params: Pro
     while z <= end_z {
         let p1 = project_point(Vec3::new(start_x, 0.0, z), params);
         let p2 = project_point(Vec3::new(end_x, 0.0, z), params);
-        ctx.draw(&Line {
-            x1: p1.x,
-            y1: p1.y,
-            x2: p2.x,
-            y2: p2.y,
-            color: app.config.theme.grid,
-        });
+        ctx.line(
+            p1,
+            p2,
+            app.config.theme.grid,
+        );
         z += step;
     }
 }
 
-fn draw_plane(ctx: &mut ratatui::widgets::canvas::Context, app: &App, params: ProjectionParams) {
+fn draw_plane(ctx: &mut impl Painter, app: &App, params: ProjectionParams) {
     let bounds = app.toolpath.bounds;
     if !bounds.initialized {
         return;
@@ -221,6 +561,149 @@ fn draw_plane(ctx: &mut ratatui::widgets::canvas::Context, app: &App, params: Pr
     fill_polygon(ctx, &[p1, p2, p3, p4], fill, step);
 }
 
+fn draw_safe_planes(ctx: &mut impl Painter, app: &App, params: ProjectionParams) {
+    let bounds = app.toolpath.bounds;
+    if !bounds.initialized {
+        return;
+    }
+    let size = bounds.size();
+    let step = (size.x.max(size.y).max(1.0) / 30.0).max(0.5);
+    let theme = &app.config.theme;
+    let fill = fade_color(theme.safe_plane, theme.background, 0.12);
+    let edge = fade_color(theme.safe_plane, theme.background, 0.5);
+    for plane in &app.safe_planes {
+        let corners = [
+            (bounds.min.x, bounds.min.y),
+            (bounds.max.x, bounds.min.y),
+            (bounds.max.x, bounds.max.y),
+            (bounds.min.x, bounds.max.y),
+        ]
+        .map(|(x, y)| project_point(Vec3::new(x, y, plane.z), params));
+        fill_polygon(ctx, &corners, fill, step);
+        for i in 0..corners.len() {
+            ctx.line(corners[i], corners[(i + 1) % corners.len()], edge);
+        }
+    }
+}
+
+fn draw_footprint(ctx: &mut impl Painter, app: &App, params: ProjectionParams) {
+    let z = app.toolpath.bounds.min.z;
+    let corners: Vec<Vec2> = app
+        .footprint
+        .iter()
+        .map(|p| project_point(Vec3::new(p.x, p.y, z), params))
+        .collect();
+    for i in 0..corners.len() {
+        ctx.line(corners[i], corners[(i + 1) % corners.len()], app.config.theme.footprint);
+    }
+}
+
+fn build_info_line(app: &App) -> String {
+    if app.palette.open {
+        return format!(":{}▏", app.palette.input);
+    }
+    if let Some(notice) = &app.notice {
+        return format!(" {}", notice);
+    }
+    if app.file_panel.focus == PanelFocus::File
+        && let Some(diag) = app.line_diagnostic(app.file_panel.selected)
+    {
+        return format!(" ! L{} {}", diag.line, diag.message);
+    }
+    let mut parts = Vec::new();
+    if let Some(index) = app.highlighted_segment() {
+        parts.push(describe_segment(app, index));
+        if let Some(op) = app.operation_at(index) {
+            parts.push(format!("op {}", app.toolpath.operations[op].name));
+        }
+    }
+    if let Some(conditions) = app.cutting_conditions() {
+        parts.push(conditions.describe(&app.config.cutting, app.display_units));
+    }
+    if app.file_panel.focus == PanelFocus::File
+        && !app.playback.active
+        && let Some(progress) = app.progress_at_line(app.file_panel.selected)
+    {
+        parts.push(format!(
+            "done {:.0}% segs  {:.0}% dist  {:.0}% time",
+            progress.segments * 100.0,
+            progress.distance * 100.0,
+            progress.time * 100.0
+        ));
+    }
+    if let Some(stats) = app.selection_stats() {
+        let value = |mm| units::format_length(app.display_units, mm);
+        let mut text = format!(
+            "sel {} moves  {} {}  {}",
+            stats.segments,
+            value(stats.distance),
+            units::label(app.display_units),
+            format_duration(stats.time)
+        );
+        if let Some((low, high)) = stats.z_range {
+            let z = app.display_origin.z;
+            text.push_str(&format!("  Z {}..{}", value(low - z), value(high - z)));
+        }
+        for tool in &stats.tools {
+            text.push_str(&format!("  T{}", tool));
+        }
+        parts.push(text);
+    }
+    if parts.is_empty() {
+        return String::new();
+    }
+    format!(" {}", parts.join("  |  "))
+}
+
+fn describe_segment(app: &App, index: usize) -> String {
+    let seg = &app.toolpath.segments[index];
+    let line = app.segment_line(index).unwrap_or(0);
+    let start = index.checked_sub(1).map_or(seg.start, |prev| app.machine_position(prev));
+    let (start, end) = (app.display_point(start, line), app.display_point(app.machine_position(index), line));
+    let length = (seg.end - seg.start).length();
+    let units = app.display_units;
+    let value = |mm| units::format_length(units, mm);
+    let kind = match seg.kind {
+        MoveKind::Rapid => "rapid",
+        MoveKind::Feed => "feed",
+        MoveKind::Probe => {
+            let probes = &app.toolpath.probes;
+            match probes.binary_search_by_key(&index, |probe| probe.segment) {
+                Ok(pos) => probes[pos].mode.code(),
+                Err(_) => "probe",
+            }
+        }
+    };
+    let feed = match (seg.feed, units) {
+        (Some(feed), Units::Millimeters) => format!("F{:.0}", feed),
+        (Some(feed), Units::Inches) => format!("F{:.1}", feed / units::scale(units)),
+        (None, _) => "F-".to_string(),
+    };
+    let source = match app.segment_line(index) {
+        Some(line) => format!(
+            "L{} {}",
+            line + 1,
+            app.file_lines.get(line).map(|text| text.trim()).unwrap_or("")
+        ),
+        None => "L?".to_string(),
+    };
+    format!(
+        "#{} {}  ({}, {}, {}) -> ({}, {}, {}) {}  len {}  {}  |  {}",
+        index + 1,
+        kind,
+        value(start.x),
+        value(start.y),
+        value(start.z),
+        value(end.x),
+        value(end.y),
+        value(end.z),
+        units::label(units),
+        value(length),
+        feed,
+        source
+    )
+}
+
 fn build_status_line(app: &App) -> String {
     let (status_icon, status_label) = if app.toolpath.segments.is_empty() {
         ("", "empty toolpath")
@@ -251,6 +734,16 @@ fn build_status_line(app: &App) -> String {
         .file_name()
         .and_then(|s| s.to_str())
         .unwrap_or("<stdin>");
+    let file = match app.programs.active {
+        Some(index) => format!(
+            "{} {} ({}/{})",
+            file,
+            program_label(&app.programs.programs[index]),
+            index + 1,
+            app.programs.programs.len()
+        ),
+        None => file.to_string(),
+    };
     let projection = match app.view.projection {
         ProjectionMode::Orthographic => "ortho",
         ProjectionMode::Perspective => "persp",
@@ -268,11 +761,129 @@ fn build_status_line(app: &App) -> String {
     } else {
         "off"
     };
+    let eta = if app.playback.active {
+        format!("{} / {}", format_duration(app.playback_elapsed()), format_duration(app.estimated_time))
+    } else {
+        format_duration(app.estimated_time)
+    };
+    let distances = format!(
+        " | cut {} / rapid {} {}",
+        units::format_length(app.display_units, app.toolpath.stats.feed_distance),
+        units::format_length(app.display_units, app.toolpath.stats.rapid_distance),
+        units::label(app.display_units)
+    );
     let visible = app.visible_segment_count();
     let (line_start, line_end) = app.file_panel.selection_range(app.file_lines.len());
     let mode = if app.file_panel.visual { "visual" } else { "single" };
+    let machine = match app.config.machine() {
+        Some(machine) if !app.machine_warnings.is_empty() => format!(
+            " |  {}  {}",
+            machine.name,
+            app.machine_warnings.join("; ")
+        ),
+        Some(machine) => format!(" |  {}", machine.name),
+        None => String::new(),
+    };
+    let diagnostics = match app.toolpath.diagnostics.len() {
+        0 => String::new(),
+        count => format!(" |  {} issue{}", count, if count == 1 { "" } else { "s" }),
+    };
+    let line = app
+        .highlighted_segment()
+        .and_then(|index| app.segment_line(index))
+        .unwrap_or(app.file_panel.selected);
+    let mut units = match app.toolpath.line_states.get(line).map(|state| state.units) {
+        Some(file_units) if file_units != app.display_units => format!(
+            " | {} (shown in {})",
+            units::label(file_units),
+            units::label(app.display_units)
+        ),
+        Some(file_units) => format!(" | {}", units::label(file_units)),
+        None => String::new(),
+    };
+    if app.toolpath.stats.mixes_units() {
+        units.push_str(" (mixed G20/G21!)");
+    }
+    let color_mode = match app.color_mode {
+        ColorMode::Kind => String::new(),
+        ColorMode::CutDirection => {
+            let count = |direction| {
+                app.profiles
+                    .iter()
+                    .flatten()
+                    .filter(|profile| profile.direction == direction)
+                    .count()
+            };
+            format!(
+                " | climb:{} conv:{}",
+                count(CutDirection::Climb),
+                count(CutDirection::Conventional)
+            )
+        }
+        ColorMode::AirCut => " | air cuts highlighted".to_string(),
+        ColorMode::FeedLimit => format!(
+            " | feed-limited: {} zones, +{}",
+            app.feed_limits.len(),
+            format_duration(app.feed_limits.iter().map(|zone| zone.time_lost).sum())
+        ),
+    };
+    let corners = if app.show_corners {
+        format!(" | corners ≥{}°: {}", app.config.ui.corner_angle, app.corners.len())
+    } else {
+        String::new()
+    };
+    let air = match &app.air_cuts {
+        Some(air) => format!(
+            " | air {} ({} {}, {} moves)",
+            format_duration(air.time),
+            units::format_length(app.display_units, air.distance),
+            units::label(app.display_units),
+            air.count
+        ),
+        None => String::new(),
+    };
+    let trace = match &app.trace {
+        Some(trace) => {
+            let tolerance = app.config.ui.trace_tolerance;
+            let off = (0..trace.points.len()).filter(|&index| trace.is_off_path(index, tolerance)).count();
+            let max = trace.deviations.iter().flatten().fold(0.0_f64, |max, &deviation| max.max(deviation));
+            format!(
+                " | trace max {} {}, {}/{} off path",
+                units::format_length(app.display_units, max),
+                units::label(app.display_units),
+                off,
+                trace.points.len()
+            )
+        }
+        None => String::new(),
+    };
+    let z_scale = if app.view.z_scale == 1.0 {
+        String::new()
+    } else {
+        format!(" | Z×{}", app.view.z_scale)
+    };
+    let origin = app.display_origin;
+    let origin = if origin == Vec3::default() {
+        String::new()
+    } else {
+        let value = |mm| units::format_length(app.display_units, mm);
+        format!(" | origin X{} Y{} Z{}", value(origin.x), value(origin.y), value(origin.z))
+    };
+    let filter = match &app.filter {
+        Some(filter) => format!(" | filter {}", filter.source),
+        None => String::new(),
+    };
+    let passes = if app.first_pass_only && !app.later_passes.is_empty() {
+        " | loops: first pass"
+    } else {
+        ""
+    };
+    let lod = match app.lod_stride() {
+        1 => String::new(),
+        stride => format!(" | LOD 1:{} (ui.max_segments)", stride),
+    };
     format!(
-        "{} {} | {} {} | {} sel:{}-{} | {} {} | {} seg:{}/{} | {} zoom:{:.2} | {} {} | {} {} | {} {}",
+        "{} {} | {} {} | {} sel:{}-{} | {} {} | {} seg:{}/{} | {} zoom:{:.2} | {} {} | {} {} | {} {} |  {}{}{}{}{}{}{}{}{}{}{}{}{}{}",
         status_icon,
         status_label,
         file_icon,
@@ -292,19 +903,44 @@ fn build_status_line(app: &App) -> String {
         playback_icon,
         playback,
         focus_icon,
-        focus
+        focus,
+        eta,
+        distances,
+        machine,
+        diagnostics,
+        units,
+        color_mode,
+        air,
+        corners,
+        trace,
+        z_scale,
+        origin,
+        filter,
+        passes,
+        lod
     )
 }
 
-fn segment_fade(index: usize, total: usize) -> f64 {
-    if total <= 1 {
+pub fn format_duration(seconds: f64) -> String {
+    let total = seconds.max(0.0).round() as u64;
+    let (hours, minutes, secs) = (total / 3600, (total / 60) % 60, total % 60);
+    if hours > 0 {
+        format!("{}:{:02}:{:02}", hours, minutes, secs)
+    } else {
+        format!("{}:{:02}", minutes, secs)
+    }
+}
+
+fn segment_fade(index: usize, total: usize, ui: &UiSettings) -> f64 {
+    if total <= 1 || ui.fade == Fade::Off {
         return 1.0;
     }
     let t = index as f64 / (total - 1) as f64;
-    t.powf(0.6)
+    let t = if ui.fade == Fade::End { 1.0 - t } else { t };
+    ui.fade_min_opacity + (1.0 - ui.fade_min_opacity) * t.powf(ui.fade_exponent)
 }
 
-fn fade_color(base: ratatui::style::Color, background: ratatui::style::Color, t: f64) -> ratatui::style::Color {
+pub fn fade_color(base: ratatui::style::Color, background: ratatui::style::Color, t: f64) -> ratatui::style::Color {
     let t = t.clamp(0.0, 1.0);
     let base_rgb = color_to_rgb(base);
     let bg_rgb = color_to_rgb(background);
@@ -317,7 +953,7 @@ fn fade_color(base: ratatui::style::Color, background: ratatui::style::Color, t:
     base
 }
 
-fn color_to_rgb(color: ratatui::style::Color) -> Option<(u8, u8, u8)> {
+pub fn color_to_rgb(color: ratatui::style::Color) -> Option<(u8, u8, u8)> {
     match color {
         ratatui::style::Color::Black => Some((0, 0, 0)),
         ratatui::style::Color::Red => Some((205, 49, 49)),
@@ -350,21 +986,34 @@ fn apply_line_style(mut spans: Vec<Span<'_>>, style: Style) -> Vec<Span<'_>> {
     spans
 }
 
-fn highlight_gcode_line(line: &str, theme: &crate::config::Theme) -> Vec<Span<'static>> {
+/// Underlines the spans of a highlighted line that start inside one of the byte `ranges`.
+fn underline_ranges(spans: &mut [Span<'_>], ranges: &[std::ops::Range<usize>]) {
+    let mut offset = 0;
+    for span in spans {
+        if ranges.iter().any(|range| range.contains(&offset)) {
+            span.style = span.style.add_modifier(Modifier::UNDERLINED);
+        }
+        offset += span.content.len();
+    }
+}
+
+fn highlight_gcode_line(line: &str, app: &App) -> Vec<Span<'static>> {
+    let theme = &app.config.theme;
+    let comments = &app.config.parser.comments;
     let mut spans = Vec::new();
-    let mut chars = line.chars().peekable();
-    while let Some(ch) = chars.peek() {
-        if *ch == ';' {
-            let rest: String = chars.by_ref().collect();
+    let mut chars = line.char_indices().peekable();
+    while let Some(&(idx, ch)) = chars.peek() {
+        let rest = &line[idx..];
+        if comments.line_prefix_len(rest).is_some() {
             spans.push(Span::styled(
-                rest,
+                rest.to_string(),
                 Style::default().fg(theme.code_comment),
             ));
             break;
         }
-        if *ch == '(' {
+        if comments.parentheses && ch == '(' {
             let mut comment = String::new();
-            while let Some(c) = chars.next() {
+            for (_, c) in chars.by_ref() {
                 comment.push(c);
                 if c == ')' {
                     break;
@@ -385,9 +1034,9 @@ fn highlight_gcode_line(line: &str, theme: &crate::config::Theme) -> Vec<Span<'s
             let letter = ch.to_ascii_uppercase();
             chars.next();
             let mut number = String::new();
-            while let Some(n) = chars.peek() {
-                if n.is_ascii_digit() || *n == '.' || *n == '-' || *n == '+' {
-                    number.push(*n);
+            while let Some(&(_, n)) = chars.peek() {
+                if n.is_ascii_digit() || n == '.' || n == '-' || n == '+' {
+                    number.push(n);
                     chars.next();
                 } else {
                     break;
@@ -436,17 +1085,48 @@ fn draw_file_panel(frame: &mut Frame<'_>, app: &mut App, area: ratatui::layout::
     let total = app.file_lines.len();
     let width = total.to_string().len().max(2);
     let show_numbers = app.config.ui.show_line_numbers;
+    let hottest = app
+        .config
+        .ui
+        .show_line_times
+        .then(|| app.line_times.iter().fold(0.0_f64, |max, &seconds| max.max(seconds)));
     let start = app.file_panel.scroll.min(total);
     let end = (start + view_height).min(total);
     let (sel_start, sel_end) = app.file_panel.selection_range(total);
+    let show_diagnostics = !app.toolpath.diagnostics.is_empty();
+    let show_machine_state = app
+        .toolpath
+        .line_states
+        .iter()
+        .any(|state| state.spindle != Spindle::Off || state.coolant != Coolant::Off);
+    let repeat_width = app.toolpath.repeats.iter().map(|repeat| repeat.passes.to_string().len() + 1).max();
+    let highlighted_segment = app.highlighted_segment();
+    let highlighted_line = highlighted_segment.and_then(|index| app.segment_line(index));
     let mut lines = Vec::new();
     for idx in start..end {
         let mut spans = Vec::new();
+        if show_diagnostics {
+            let marker = if app.line_diagnostic(idx).is_some() { "!" } else { " " };
+            spans.push(Span::styled(marker, Style::default().fg(theme.diagnostic)));
+        }
+        if show_machine_state {
+            spans.extend(machine_state_gutter(app, idx));
+        }
+        if let Some(width) = repeat_width {
+            spans.push(repeat_gutter(app, idx, width));
+        }
+        if let Some(hottest) = hottest {
+            spans.push(line_time_gutter(app, idx, hottest));
+        }
         if show_numbers {
             let number = format!("{:>width$} ", idx + 1, width = width);
             spans.push(Span::styled(number, Style::default().fg(theme.code_label)));
         }
-        spans.extend(highlight_gcode_line(&app.file_lines[idx], theme));
+        let mut code = highlight_gcode_line(&app.file_lines[idx], app);
+        if let Some(segment) = highlighted_segment.filter(|_| highlighted_line == Some(idx)) {
+            underline_ranges(&mut code, app.toolpath.segment_motion_spans(segment));
+        }
+        spans.extend(code);
 
         let mut line_style = Style::default();
         if idx >= sel_start && idx <= sel_end {
@@ -464,10 +1144,190 @@ fn draw_file_panel(frame: &mut Frame<'_>, app: &mut App, area: ratatui::layout::
     frame.render_widget(paragraph, inner);
 }
 
+/// Spindle and coolant strips for a line; feed moves with the spindle off are marked `!`.
+fn machine_state_gutter(app: &App, line: usize) -> [Span<'static>; 3] {
+    let theme = &app.config.theme;
+    let Some(state) = app.toolpath.line_states.get(line) else {
+        return [Span::raw(" "), Span::raw(" "), Span::raw(" ")];
+    };
+    let ends = &app.toolpath.line_segment_ends;
+    let first = if line == 0 { 0 } else { ends[line - 1] };
+    let cuts = app.toolpath.segments[first..ends[line]]
+        .iter()
+        .any(|seg| seg.kind == MoveKind::Feed);
+    let spindle = match state.spindle {
+        Spindle::Off if cuts => Span::styled("!", Style::default().fg(theme.diagnostic)),
+        Spindle::Off => Span::raw(" "),
+        Spindle::Clockwise | Spindle::CounterClockwise => {
+            Span::styled("▌", Style::default().fg(theme.spindle_on))
+        }
+    };
+    let coolant = match state.coolant {
+        Coolant::Off => Span::raw(" "),
+        Coolant::Mist => Span::styled("┆", Style::default().fg(theme.coolant_on)),
+        Coolant::Flood => Span::styled("▌", Style::default().fg(theme.coolant_on)),
+    };
+    [spindle, coolant, Span::raw(" ")]
+}
+
+/// Pass count on the first line of each loop or repeated call, and a bar along the rest of a loop.
+fn repeat_gutter(app: &App, line: usize, width: usize) -> Span<'static> {
+    let repeats = &app.toolpath.repeats;
+    let text = match repeats.iter().find(|repeat| repeat.lines.start == line) {
+        Some(repeat) => format!("×{}", repeat.passes),
+        None if repeats.iter().any(|repeat| repeat.lines.contains(&line)) => "│".to_string(),
+        None => String::new(),
+    };
+    Span::styled(
+        format!("{:<width$} ", text, width = width),
+        Style::default().fg(app.config.theme.code_label),
+    )
+}
+
+/// The line's estimated time, shading from comment gray to the diagnostic color at the slowest line.
+fn line_time_gutter(app: &App, line: usize, hottest: f64) -> Span<'static> {
+    let seconds = app.line_times.get(line).copied().unwrap_or(0.0);
+    if seconds <= 0.0 {
+        return Span::raw(" ".repeat(6));
+    }
+    let text = match seconds {
+        s if s < 10.0 => format!("{:.1}s", s),
+        s if s < 60.0 => format!("{:.0}s", s),
+        s if s < 3600.0 => format!("{}m{:02}s", (s / 60.0) as u64, (s % 60.0) as u64),
+        s => format!("{}h{:02}m", (s / 3600.0) as u64, ((s / 60.0) % 60.0) as u64),
+    };
+    let theme = &app.config.theme;
+    let color = fade_color(theme.diagnostic, theme.code_comment, seconds / hottest);
+    Span::styled(format!("{:>5} ", text), Style::default().fg(color))
+}
+
+fn draw_operations_panel(frame: &mut Frame<'_>, app: &App, area: ratatui::layout::Rect) {
+    let theme = &app.config.theme;
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title("Operations")
+        .style(Style::default().fg(theme.axis_z));
+    let inner = block.inner(area);
+    let view_height = (inner.height as usize).max(1);
+    let cursor = app.operations.cursor;
+    let start = cursor.saturating_sub(view_height - 1);
+    let mut lines = Vec::new();
+    for (idx, op) in app
+        .toolpath
+        .operations
+        .iter()
+        .enumerate()
+        .skip(start)
+        .take(view_height)
+    {
+        let mark = if app.operations.hidden[idx] { "[ ]" } else { "[x]" };
+        let segment_time = |segment: usize| {
+            segment
+                .checked_sub(1)
+                .and_then(|last| app.segment_end_times.get(last).copied())
+                .unwrap_or(0.0)
+        };
+        let duration = segment_time(op.segments.end) - segment_time(op.segments.start);
+        let tool = op
+            .lines
+            .end
+            .checked_sub(1)
+            .and_then(|line| app.toolpath.line_states.get(line))
+            .and_then(|state| state.tool)
+            .map(|tool| format!("  T{}", tool))
+            .unwrap_or_default();
+        let text = format!(
+            "{} {}  {} segs  {}{}",
+            mark,
+            op.name,
+            op.segments.len(),
+            format_duration(duration),
+            tool
+        );
+        let mut style = Style::default().fg(theme.foreground);
+        if idx == cursor {
+            style = style.bg(theme.axis_z).fg(theme.background);
+        }
+        lines.push(TextLine::from(Span::styled(text, style)));
+    }
+
+    let paragraph = Paragraph::new(lines).style(Style::default().bg(theme.background));
+    frame.render_widget(block, area);
+    frame.render_widget(paragraph, inner);
+}
+
+fn draw_programs_panel(frame: &mut Frame<'_>, app: &App, area: ratatui::layout::Rect) {
+    let theme = &app.config.theme;
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title("Programs")
+        .style(Style::default().fg(theme.axis_z));
+    let inner = block.inner(area);
+    let view_height = (inner.height as usize).max(1);
+    let cursor = app.programs.cursor;
+    let start = cursor.saturating_sub(view_height - 1);
+    let mut lines = Vec::new();
+    for (idx, program) in app
+        .programs
+        .programs
+        .iter()
+        .enumerate()
+        .skip(start)
+        .take(view_height)
+    {
+        let mark = if app.programs.active == Some(idx) { "(*)" } else { "( )" };
+        let text = format!(
+            "{} {}  L{}-{}",
+            mark,
+            program_label(program),
+            program.lines.start + 1,
+            program.lines.end
+        );
+        let mut style = Style::default().fg(theme.foreground);
+        if idx == cursor {
+            style = style.bg(theme.axis_z).fg(theme.background);
+        }
+        lines.push(TextLine::from(Span::styled(text, style)));
+    }
+
+    let paragraph = Paragraph::new(lines).style(Style::default().bg(theme.background));
+    frame.render_widget(block, area);
+    frame.render_widget(paragraph, inner);
+}
+
+fn draw_output_panel(frame: &mut Frame<'_>, app: &App, area: ratatui::layout::Rect) {
+    let theme = &app.config.theme;
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(app.output.title.as_str())
+        .style(Style::default().fg(theme.axis_z));
+    let inner = block.inner(area);
+    let view_height = (inner.height as usize).max(1);
+    let cursor = app.output.cursor;
+    let start = cursor.saturating_sub(view_height - 1);
+    let mut lines = Vec::new();
+    for (idx, text) in app.output.lines.iter().enumerate().skip(start).take(view_height) {
+        let color = if app.output.references[idx].is_some() {
+            theme.foreground
+        } else {
+            theme.code_comment
+        };
+        let mut style = Style::default().fg(color);
+        if idx == cursor {
+            style = style.bg(theme.axis_z).fg(theme.background);
+        }
+        lines.push(TextLine::from(Span::styled(text.as_str(), style)));
+    }
+
+    let paragraph = Paragraph::new(lines).style(Style::default().bg(theme.background));
+    frame.render_widget(block, area);
+    frame.render_widget(paragraph, inner);
+}
+
 fn draw_arrow(
-    ctx: &mut ratatui::widgets::canvas::Context,
-    from: cnc_geom::Vec2,
-    to: cnc_geom::Vec2,
+    ctx: &mut impl Painter,
+    from: Vec2,
+    to: Vec2,
     color: ratatui::style::Color,
 ) {
     let dx = to.x - from.x;
@@ -487,25 +1347,21 @@ fn draw_arrow(
         to.x - arrow_len * (angle - phi).cos(),
         to.y - arrow_len * (angle - phi).sin(),
     );
-    ctx.draw(&Line {
-        x1: to.x,
-        y1: to.y,
-        x2: left.0,
-        y2: left.1,
+    ctx.line(
+        to,
+        Vec2::new(left.0, left.1),
         color,
-    });
-    ctx.draw(&Line {
-        x1: to.x,
-        y1: to.y,
-        x2: right.0,
-        y2: right.1,
+    );
+    ctx.line(
+        to,
+        Vec2::new(right.0, right.1),
         color,
-    });
+    );
 }
 
 fn fill_polygon(
-    ctx: &mut ratatui::widgets::canvas::Context,
-    points: &[cnc_geom::Vec2],
+    ctx: &mut impl Painter,
+    points: &[Vec2],
     color: ratatui::style::Color,
     step: f64,
 ) {
@@ -535,31 +1391,21 @@ fn fill_polygon(
         xs.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
         for pair in xs.chunks(2) {
             if pair.len() == 2 {
-                ctx.draw(&Line {
-                    x1: pair[0],
-                    y1: y,
-                    x2: pair[1],
-                    y2: y,
+                ctx.line(
+                    Vec2::new(pair[0], y),
+                    Vec2::new(pair[1], y),
                     color,
-                });
+                );
             }
         }
         y += step;
     }
 }
 
-fn draw_hud_origin(frame: &mut Frame<'_>, app: &App, area: ratatui::layout::Rect) {
+fn draw_hud_origin(frame: &mut Frame<'_>, app: &App) {
     let theme = &app.config.theme;
-    let width = 12;
-    let height = 5;
-    if area.width < width + 2 || area.height < height + 2 {
+    let Some(rect) = hud_rect(app, app.config.ui.origin_hud, 12, 5) else {
         return;
-    }
-    let rect = ratatui::layout::Rect {
-        x: area.x + 1,
-        y: area.y + 1,
-        width,
-        height,
     };
     frame.render_widget(Clear, rect);
     let block = Block::default()
@@ -588,10 +1434,198 @@ fn draw_hud_origin(frame: &mut Frame<'_>, app: &App, area: ratatui::layout::Rect
     );
 }
 
+/// A round length bar in a viewport corner; at the camera target's depth in
+/// perspective.
+fn draw_scale_bar(frame: &mut Frame<'_>, app: &App, metrics: ViewMetrics) {
+    let Some(area) = app.canvas_area.filter(|area| area.width >= 16 && area.height >= 8) else {
+        return;
+    };
+    let Some(corner) = app.config.ui.scale_bar else {
+        return;
+    };
+    let cell_mm = metrics.half_w * 2.0 / area.width as f64;
+    let max_cells = (area.width as f64 * SCALE_BAR_SHARE) as u16;
+    let Some((length, cells)) = scale_bar(cell_mm, max_cells, app.display_units) else {
+        return;
+    };
+    let decimals = (-length.log10().floor()).max(0.0) as usize;
+    let text = format!(
+        "├{}┤ {:.*} {}",
+        "─".repeat(cells as usize - 2),
+        decimals,
+        length,
+        units::label(app.display_units)
+    );
+    let width = (text.chars().count() as u16).min(area.width - 2);
+    let Some(rect) = hud_rect(app, Some(corner), width + 2, 1) else {
+        return;
+    };
+    let rect = ratatui::layout::Rect { x: rect.x + 1, width, ..rect };
+    let theme = &app.config.theme;
+    frame.render_widget(
+        Paragraph::new(text).style(Style::default().fg(theme.foreground).bg(theme.background)),
+        rect,
+    );
+}
+
+/// Tool position at the highlighted segment's end, in display units from the display origin.
+fn draw_dro(frame: &mut Frame<'_>, app: &App) {
+    let Some(index) = app.highlighted_segment() else {
+        return;
+    };
+    let Some(rect) = hud_rect(app, app.config.ui.dro, 14, 5) else {
+        return;
+    };
+    let theme = &app.config.theme;
+    let point = app.display_point(app.machine_position(index), app.segment_line(index).unwrap_or(0));
+    let axis = |label: &'static str, color: Color, mm: f64| {
+        TextLine::from(vec![
+            Span::styled(label, Style::default().fg(color)),
+            Span::raw(format!("{:>10}", units::format_length(app.display_units, mm))),
+        ])
+    };
+    let lines = vec![
+        axis("X", theme.axis_x, point.x),
+        axis("Y", theme.axis_y, point.y),
+        axis("Z", theme.axis_z, point.z),
+    ];
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(if app.machine_coordinates { "DRO MCS" } else { "DRO WCS" })
+        .style(Style::default().bg(theme.background).fg(theme.foreground));
+    let inner = block.inner(rect);
+    frame.render_widget(Clear, rect);
+    frame.render_widget(block, rect);
+    frame.render_widget(
+        Paragraph::new(lines).style(Style::default().bg(theme.background)),
+        inner,
+    );
+}
+
+/// Swatches for the colors of the active color mode; the default kind coloring has none.
+fn draw_legend(frame: &mut Frame<'_>, app: &App) {
+    let theme = &app.config.theme;
+    let (title, entries) = match app.color_mode {
+        ColorMode::Kind => return,
+        ColorMode::CutDirection => (
+            "Direction",
+            vec![("climb", theme.climb), ("conventional", theme.conventional), ("other", theme.path_feed)],
+        ),
+        ColorMode::AirCut => ("Air cuts", vec![("air cut", theme.air_cut), ("cutting", theme.path_feed)]),
+        ColorMode::FeedLimit => (
+            "Feed limit",
+            vec![("feed-limited", theme.feed_limited), ("full feed", theme.path_feed)],
+        ),
+    };
+    let label_width = entries.iter().map(|(label, _)| label.len()).max().unwrap_or(0);
+    let width = (label_width + 3).max(title.len()) as u16 + 2;
+    let Some(rect) = hud_rect(app, app.config.ui.legend, width, entries.len() as u16 + 2) else {
+        return;
+    };
+    let lines: Vec<TextLine> = entries
+        .into_iter()
+        .map(|(label, color)| {
+            TextLine::from(vec![Span::styled("━━ ", Style::default().fg(color)), Span::raw(label)])
+        })
+        .collect();
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(title)
+        .style(Style::default().bg(theme.background).fg(theme.foreground));
+    let inner = block.inner(rect);
+    frame.render_widget(Clear, rect);
+    frame.render_widget(block, rect);
+    frame.render_widget(
+        Paragraph::new(lines).style(Style::default().bg(theme.background)),
+        inner,
+    );
+}
+
+/// A `width` × `height` box in `corner` of the canvas, if it fits.
+fn hud_rect(app: &App, corner: Option<Corner>, width: u16, height: u16) -> Option<ratatui::layout::Rect> {
+    let (area, corner) = (app.canvas_area?, corner?);
+    if area.width < width || area.height < height {
+        return None;
+    }
+    let x = match corner {
+        Corner::TopLeft | Corner::BottomLeft => area.x,
+        Corner::TopRight | Corner::BottomRight => area.x + area.width - width,
+    };
+    let y = match corner {
+        Corner::TopLeft | Corner::TopRight => area.y,
+        Corner::BottomLeft | Corner::BottomRight => area.y + area.height - height,
+    };
+    Some(ratatui::layout::Rect { x, y, width, height })
+}
+
+/// The longest 1, 2 or 5 × 10ⁿ display-unit length that fits in `max_cells` cells of
+/// `cell_mm`, with its width in cells.
+fn scale_bar(cell_mm: f64, max_cells: u16, display_units: Units) -> Option<(f64, u16)> {
+    let unit = units::scale(display_units);
+    let max = cell_mm * max_cells as f64 / unit;
+    if !(max.is_finite() && max > 0.0) {
+        return None;
+    }
+    let power = 10f64.powf(max.log10().floor());
+    let length = [5.0, 2.0, 1.0].into_iter().map(|step| step * power).find(|&len| len <= max)?;
+    let cells = (length * unit / cell_mm).round() as u16;
+    (cells >= 3).then_some((length, cells))
+}
+
+const HELP_ENTRIES: &[(&str, &str)] = &[
+    ("h/j/k/l", " Pan view"),
+    ("w/s/a/d", " Rotate view"),
+    ("W/S/A/D", " Rotate to next 15°"),
+    ("+ / -", " Zoom in/out"),
+    ("[ / ]", " Z exaggeration down/up"),
+    ("r", " Reset pan+zoom"),
+    ("g", " Fit to toolpath"),
+    ("ctrl+g", " Auto-fit selection"),
+    ("p", " Toggle projection"),
+    ("space", "/ Play/Pause animation"),
+    ("enter", " Play from selected line"),
+    ("esc", " Dismiss reload ghost"),
+    ("tab", "/ Toggle focus (view/file)"),
+    ("v", " Visual select (range)"),
+    ("m", " Toggle marker"),
+    ("i", " Toggle mm/in readouts"),
+    ("I", " Toggle work/machine coordinates"),
+    ("U", " Toggle unwrapped A axis"),
+    ("c", " Cycle color mode"),
+    ("z", " Cycle depth graph"),
+    ("e", " Toggle min Z map"),
+    ("L", " Loops: first pass / all passes"),
+    ("o", " Operations panel (space toggles)"),
+    ("P", " Programs picker (space shows one)"),
+    ("t", " Timeline (h/l jump)"),
+    ("b", " Restart block for cursor line"),
+    ("R", " Write review report (.report.md)"),
+    ("y", " Copy selected lines"),
+    ("Y", " Copy highlighted point"),
+    ("f", " Focus selection (dim the rest)"),
+    (":", " Command palette (filter <expr>)"),
+    ("u", " Undo view change"),
+    ("ctrl+r", " Redo view change"),
+    ("↑ / ↓", " Select file line"),
+    ("PgUp/PgDn", "/ Page scroll"),
+    ("q", " Quit"),
+    ("?", " Close help"),
+];
+
 fn draw_help_popup(frame: &mut Frame<'_>, app: &App, area: ratatui::layout::Rect) {
     let theme = &app.config.theme;
-    let width = area.width.saturating_sub(10).min(60).max(30);
-    let height = area.height.saturating_sub(6).min(18).max(10);
+    let query = app.help_query.to_lowercase();
+    let entries: Vec<(&str, &str)> = HELP_ENTRIES
+        .iter()
+        .copied()
+        .filter(|(key, desc)| {
+            key.to_lowercase().contains(&query) || desc.to_lowercase().contains(&query)
+        })
+        .collect();
+    let conflicts = app.config.keys.conflicts();
+    let conflict_rows = if conflicts.is_empty() { 0 } else { conflicts.len() + 2 };
+    let width = area.width.saturating_sub(10).clamp(30, 60).min(area.width);
+    let height = ((entries.len() + conflict_rows) as u16 + 4).min(area.height);
     let rect = ratatui::layout::Rect {
         x: area.x + (area.width.saturating_sub(width)) / 2,
         y: area.y + (area.height.saturating_sub(height)) / 2,
@@ -600,9 +1634,14 @@ fn draw_help_popup(frame: &mut Frame<'_>, app: &App, area: ratatui::layout::Rect
     };
 
     frame.render_widget(Clear, rect);
+    let title = if app.help_query.is_empty() {
+        "Help (type to filter)".to_string()
+    } else {
+        format!("Help /{}", app.help_query)
+    };
     let block = Block::default()
         .borders(Borders::ALL)
-        .title("Help")
+        .title(title)
         .style(Style::default().bg(theme.background).fg(theme.foreground));
     let inner = block.inner(rect);
 
@@ -615,26 +1654,117 @@ fn draw_help_popup(frame: &mut Frame<'_>, app: &App, area: ratatui::layout::Rect
         Span::styled(" Description", Style::default().fg(theme.axis_x)),
     ]));
     lines.push(TextLine::from(""));
-    let entries = [
-        ("h/j/k/l", " Pan view"),
-        ("w/s/a/d", " Rotate view"),
-        ("+ / -", " Zoom in/out"),
-        ("r", " Reset pan+zoom"),
-        ("g", " Fit to toolpath"),
-        ("p", " Toggle projection"),
-        ("space", "/ Play/Pause animation"),
-        ("tab", "/ Toggle focus (view/file)"),
-        ("v", " Visual select (range)"),
-        ("m", " Toggle marker"),
-        ("↑ / ↓", " Select file line"),
-        ("PgUp/PgDn", "/ Page scroll"),
-        ("q", " Quit"),
-        ("?", " Close help"),
-    ];
     let key_width = 10usize;
     for (key, desc) in entries {
         lines.push(help_line(key, desc, key_width, key_style, desc_style));
     }
+    if !conflicts.is_empty() {
+        lines.push(TextLine::from(""));
+        lines.push(TextLine::from(Span::styled(
+            " Conflicting bindings (first action wins)",
+            Style::default().fg(theme.diagnostic),
+        )));
+        for (key, names) in conflicts {
+            lines.push(TextLine::from(format!(" {:<width$} {}", key, names.join(", "), width = key_width)));
+        }
+    }
+
+    frame.render_widget(block, rect);
+    frame.render_widget(
+        Paragraph::new(lines).style(Style::default().bg(theme.background)),
+        inner,
+    );
+}
+
+/// Banner across the top of the toolpath view while playback waits at an M0/M1.
+fn draw_stop_banner(frame: &mut Frame<'_>, app: &App, stop: &ProgramStop, area: ratatui::layout::Rect) {
+    let theme = &app.config.theme;
+    let rect = ratatui::layout::Rect {
+        x: area.x + 1,
+        y: area.y + 1,
+        width: area.width.saturating_sub(2),
+        height: 3.min(area.height.saturating_sub(2)),
+    };
+    let title = format!(
+        " {} at line {} ",
+        if stop.optional { "M1 optional stop" } else { "M0 program stop" },
+        stop.line + 1
+    );
+    let text = format!(
+        "{}  (space resumes)",
+        stop.comment.as_deref().unwrap_or("no comment")
+    );
+    frame.render_widget(Clear, rect);
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(title)
+        .style(Style::default().bg(theme.background).fg(theme.program_stop));
+    frame.render_widget(Paragraph::new(text).block(block), rect);
+}
+
+fn draw_restart_popup(
+    frame: &mut Frame<'_>,
+    app: &App,
+    restart: &RestartBlock,
+    area: ratatui::layout::Rect,
+) {
+    let theme = &app.config.theme;
+    let width = area.width.saturating_sub(10).clamp(30, 60).min(area.width);
+    let height = (restart.preamble.len() as u16 + 4).min(area.height);
+    let rect = ratatui::layout::Rect {
+        x: area.x + (area.width.saturating_sub(width)) / 2,
+        y: area.y + (area.height.saturating_sub(height)) / 2,
+        width,
+        height,
+    };
+
+    frame.render_widget(Clear, rect);
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title("Restart block")
+        .style(Style::default().bg(theme.background).fg(theme.foreground));
+    let inner = block.inner(rect);
+    let mut lines: Vec<TextLine> = restart
+        .preamble
+        .iter()
+        .map(|line| TextLine::from(highlight_gcode_line(line, app)))
+        .collect();
+    lines.push(TextLine::from(""));
+    lines.push(TextLine::from(Span::styled(
+        restart.status.clone(),
+        Style::default().fg(theme.code_comment),
+    )));
+
+    frame.render_widget(block, rect);
+    frame.render_widget(
+        Paragraph::new(lines).style(Style::default().bg(theme.background)),
+        inner,
+    );
+}
+
+fn draw_summary_popup(frame: &mut Frame<'_>, app: &App, summary: &[String], area: ratatui::layout::Rect) {
+    let theme = &app.config.theme;
+    let width = area.width.saturating_sub(10).clamp(30, 80).min(area.width);
+    let height = (summary.len() as u16 + 4).min(area.height);
+    let rect = ratatui::layout::Rect {
+        x: area.x + (area.width.saturating_sub(width)) / 2,
+        y: area.y + (area.height.saturating_sub(height)) / 2,
+        width,
+        height,
+    };
+
+    frame.render_widget(Clear, rect);
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title("Pre-flight summary")
+        .style(Style::default().bg(theme.background).fg(theme.foreground));
+    let inner = block.inner(rect);
+    let mut lines: Vec<TextLine> = summary.iter().map(|line| TextLine::from(line.as_str())).collect();
+    lines.push(TextLine::from(""));
+    lines.push(TextLine::from(Span::styled(
+        "any key closes; ui.load_summary = false turns this off",
+        Style::default().fg(theme.code_comment),
+    )));
 
     frame.render_widget(block, rect);
     frame.render_widget(
@@ -0,0 +1,451 @@
+use std::path::Path;
+
+use anyhow::Result;
+use cnc_gcode::{program_events, FeedLimitZone, ProgramEventKind, Severity, Toolpath, Units};
+use cnc_geom::{Bounds3, ProjectionMode, Vec2, Vec3};
+use ratatui::layout::Rect;
+
+use crate::app::{App, ViewState};
+use crate::graphics::{base64, encode_png};
+use crate::raster::Raster;
+use crate::render::{format_duration, paint_scene};
+use crate::units;
+
+/// Pixel size of the view snapshots embedded in a review report.
+const SNAPSHOT_SIZE: (u16, u16) = (800, 600);
+
+/// Plain-text listing of feed-limited zones by source line, for `--feed-report`.
+pub fn feed_limit_report(toolpath: &Toolpath, zones: &[FeedLimitZone], ratio: f64) -> String {
+    let line_of = |segment: usize| toolpath.line_segment_ends.partition_point(|&end| end <= segment) + 1;
+    let mut out = format!(
+        "feed-limited zones (peak below {:.0}% of programmed feed): {}\n",
+        ratio * 100.0,
+        zones.len()
+    );
+    for zone in zones {
+        let (first, last) = (line_of(zone.segments.start), line_of(zone.segments.end - 1));
+        out.push_str(&format!(
+            "  lines {}-{}: {} moves, F{:.0} programmed, {:.0} mm/min reached, +{:.1} s\n",
+            first,
+            last,
+            zone.segments.len(),
+            zone.programmed,
+            zone.reached,
+            zone.time_lost
+        ));
+    }
+    let lost: f64 = zones.iter().map(|zone| zone.time_lost).sum();
+    out.push_str(&format!("time lost: {}\n", format_duration(lost)));
+    out
+}
+
+/// Writes a job review report: Markdown, or HTML when `path` ends in `.html`/`.htm`.
+pub fn write_review_report(app: &mut App, path: &Path) -> Result<()> {
+    let html = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("html") || ext.eq_ignore_ascii_case("htm"));
+    std::fs::write(path, review_report(app, html))?;
+    Ok(())
+}
+
+/// Stats table, diagnostics and PNG snapshots of the preset views, with images inlined as data
+/// URIs so the report is a single self-contained file.
+pub fn review_report(app: &mut App, html: bool) -> String {
+    let file = app.file_path.display().to_string();
+    let rows = report_rows(app);
+    let mut diagnostics: Vec<String> = app
+        .toolpath
+        .diagnostics
+        .iter()
+        .map(|diag| {
+            let severity = match diag.severity {
+                Severity::Warning => "warning",
+                Severity::Error => "error",
+            };
+            format!("line {}: {}: {}", diag.line, severity, diag.message)
+        })
+        .collect();
+    diagnostics.extend(app.machine_warnings.iter().map(|warning| format!("machine: {}", warning)));
+    let ortho = ProjectionMode::Orthographic;
+    let views = [
+        ("Top", 0.0, 0.0, ortho),
+        ("Front", 0.0, -90.0, ortho),
+        ("Right", -90.0, -90.0, ortho),
+        ("Default", app.initial_view.yaw.to_degrees(), app.initial_view.pitch.to_degrees(), app.initial_view.projection),
+    ];
+    let snapshots: Vec<(&str, String)> = views
+        .into_iter()
+        .map(|(name, yaw, pitch, projection)| (name, base64(&snapshot(app, yaw, pitch, projection))))
+        .collect();
+
+    let mut out = String::new();
+    if html {
+        let escape = |text: &str| text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;");
+        out.push_str(&format!(
+            "<!DOCTYPE html>\n<html>\n<head><meta charset=\"utf-8\"><title>Review report: {0}</title></head>\n<body>\n<h1>Review report: {0}</h1>\n<table>\n",
+            escape(&file)
+        ));
+        for (label, value) in &rows {
+            out.push_str(&format!("<tr><th>{}</th><td>{}</td></tr>\n", label, escape(value)));
+        }
+        out.push_str("</table>\n<h2>Diagnostics</h2>\n");
+        if diagnostics.is_empty() {
+            out.push_str("<p>None.</p>\n");
+        } else {
+            out.push_str("<ul>\n");
+            for diag in &diagnostics {
+                out.push_str(&format!("<li>{}</li>\n", escape(diag)));
+            }
+            out.push_str("</ul>\n");
+        }
+        out.push_str("<h2>Views</h2>\n");
+        for (name, png) in &snapshots {
+            out.push_str(&format!(
+                "<h3>{0}</h3>\n<img alt=\"{0}\" src=\"data:image/png;base64,{1}\">\n",
+                name, png
+            ));
+        }
+        out.push_str("</body>\n</html>\n");
+    } else {
+        out.push_str(&format!("# Review report: {}\n\n| | |\n|---|---|\n", file));
+        for (label, value) in &rows {
+            out.push_str(&format!("| {} | {} |\n", label, value));
+        }
+        out.push_str("\n## Diagnostics\n\n");
+        if diagnostics.is_empty() {
+            out.push_str("None.\n");
+        }
+        for diag in &diagnostics {
+            out.push_str(&format!("- {}\n", diag));
+        }
+        out.push_str("\n## Views\n");
+        for (name, png) in &snapshots {
+            out.push_str(&format!("\n### {0}\n\n![{0}](data:image/png;base64,{1})\n", name, png));
+        }
+    }
+    out
+}
+
+/// Whether a loaded job passes the `--exit-after-load` checks: no error diagnostics, machine
+/// limit warnings or rapids below the clearance plane.
+pub fn load_passed(app: &App) -> bool {
+    !app.toolpath.diagnostics.iter().any(|diag| diag.severity == Severity::Error)
+        && app.machine_warnings.is_empty()
+        && app.low_rapids.is_empty()
+}
+
+/// Summary printed by `--exit-after-load`: the report table and problems as text, or a JSON
+/// object for scripts.
+pub fn load_report(app: &App, json: bool) -> String {
+    let severity = |severity| match severity {
+        Severity::Warning => "warning",
+        Severity::Error => "error",
+    };
+    if !json {
+        let mut out = format!("{}: {}\n", app.file_path.display(), if load_passed(app) { "ok" } else { "failed" });
+        for (label, value) in report_rows(app) {
+            out.push_str(&format!("  {}: {}\n", label, value));
+        }
+        for diag in &app.toolpath.diagnostics {
+            out.push_str(&format!("line {}: {}: {}\n", diag.line, severity(diag.severity), diag.message));
+        }
+        for warning in &app.machine_warnings {
+            out.push_str(&format!("machine: {}\n", warning));
+        }
+        if !app.low_rapids.is_empty() {
+            out.push_str(&format!("{} rapids below the clearance plane\n", app.low_rapids.len()));
+        }
+        return out;
+    }
+    let stats = &app.toolpath.stats;
+    let point = |p: Vec3| format!("[{}, {}, {}]", p.x, p.y, p.z);
+    let bounds = |bounds: Bounds3| {
+        if bounds.initialized {
+            format!("{{\"min\": {}, \"max\": {}}}", point(bounds.min), point(bounds.max))
+        } else {
+            "null".to_string()
+        }
+    };
+    let diagnostics: Vec<String> = app
+        .toolpath
+        .diagnostics
+        .iter()
+        .map(|diag| {
+            format!(
+                "{{\"line\": {}, \"severity\": \"{}\", \"message\": {}}}",
+                diag.line,
+                severity(diag.severity),
+                json_string(&diag.message)
+            )
+        })
+        .collect();
+    let warnings: Vec<String> = app.machine_warnings.iter().map(|warning| json_string(warning)).collect();
+    let tools: Vec<String> = stats.tools_used.iter().map(u32::to_string).collect();
+    let events: Vec<String> = program_events(&app.toolpath)
+        .iter()
+        .map(|event| format!("{{\"line\": {}, \"code\": \"{}\"}}", event.line + 1, event.kind.code()))
+        .collect();
+    let fields = [
+        ("file", json_string(&app.file_path.display().to_string())),
+        ("ok", load_passed(app).to_string()),
+        ("lines", stats.line_count.to_string()),
+        ("segments", stats.segment_count.to_string()),
+        ("rapid_moves", stats.rapid_moves.to_string()),
+        ("feed_moves", stats.feed_moves.to_string()),
+        ("arc_moves", stats.arc_moves.to_string()),
+        ("feed_distance_mm", stats.feed_distance.to_string()),
+        ("rapid_distance_mm", stats.rapid_distance.to_string()),
+        ("estimated_seconds", app.estimated_time.to_string()),
+        ("tools", format!("[{}]", tools.join(", "))),
+        (
+            "program_units",
+            stats.program_units().map_or("null".to_string(), |units| json_string(units::label(units))),
+        ),
+        ("unit_switches", stats.unit_switches.to_string()),
+        ("bounds_mm", bounds(app.toolpath.bounds)),
+        ("bounds_in", bounds(app.toolpath.bounds_in(Units::Inches))),
+        ("events", format!("[{}]", events.join(", "))),
+        ("diagnostics", format!("[{}]", diagnostics.join(", "))),
+        ("machine_warnings", format!("[{}]", warnings.join(", "))),
+        ("low_rapids", app.low_rapids.len().to_string()),
+    ];
+    let body: Vec<String> = fields.iter().map(|(key, value)| format!("  \"{}\": {}", key, value)).collect();
+    format!("{{\n{}\n}}\n", body.join(",\n"))
+}
+
+/// Pre-flight summary shown once after loading: the report table, how the part sits in the
+/// machine envelope and the problems `--exit-after-load` would fail on.
+pub fn load_summary_lines(app: &App) -> Vec<String> {
+    let mut lines: Vec<String> = report_rows(app).into_iter().map(|(label, value)| format!("{}: {}", label, value)).collect();
+    if let Some(envelope) = app.config.machine().and_then(|machine| machine.envelope) {
+        let size = envelope.size();
+        let length = |mm| units::format_length(app.display_units, mm);
+        lines.push(format!(
+            "Envelope: X {} × Y {} × Z {} {}, {}",
+            length(size.x),
+            length(size.y),
+            length(size.z),
+            units::label(app.display_units),
+            if app.outside_envelope == 0 { "job fits" } else { "job exceeds it" }
+        ));
+    }
+    let count = |severity| app.toolpath.diagnostics.iter().filter(|diag| diag.severity == severity).count();
+    lines.push(format!("Errors: {}", count(Severity::Error)));
+    lines.push(format!("Warnings: {}", count(Severity::Warning)));
+    lines.extend(app.machine_warnings.iter().map(|warning| format!("Machine: {}", warning)));
+    if !app.low_rapids.is_empty() {
+        lines.push(format!("{} rapids below the clearance plane", app.low_rapids.len()));
+    }
+    lines
+}
+
+fn json_string(text: &str) -> String {
+    let mut out = String::from('"');
+    for ch in text.chars() {
+        match ch {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            ch if (ch as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", ch as u32)),
+            ch => out.push(ch),
+        }
+    }
+    out.push('"');
+    out
+}
+
+fn report_rows(app: &App) -> Vec<(&'static str, String)> {
+    let stats = &app.toolpath.stats;
+    let length = |mm: f64| format!("{} {}", units::format_length(app.display_units, mm), units::label(app.display_units));
+    let mut rows = vec![
+        ("Lines", stats.line_count.to_string()),
+        (
+            "Moves",
+            format!(
+                "{} ({} rapid, {} feed, {} arc, {} probe)",
+                stats.segment_count, stats.rapid_moves, stats.feed_moves, stats.arc_moves, stats.probe_moves
+            ),
+        ),
+        (
+            "Tools",
+            if stats.tools_used.is_empty() {
+                "none".to_string()
+            } else {
+                stats.tools_used.iter().map(|tool| format!("T{}", tool)).collect::<Vec<_>>().join(", ")
+            },
+        ),
+        (
+            "Spindle",
+            stats
+                .spindle_speed_range
+                .map_or("none".to_string(), |(min, max)| format!("S{:.0}-S{:.0}", min, max)),
+        ),
+        (
+            "Program end",
+            app.toolpath.ends.last().map_or("none".to_string(), |end| {
+                let kind = ProgramEventKind::End { rewind: end.rewind };
+                format!("{} (line {})", kind.code(), end.line + 1)
+            }),
+        ),
+    ];
+    rows.push((
+        "Units",
+        match stats.program_units() {
+            Some(Units::Millimeters) => "G21 (mm)".to_string(),
+            Some(Units::Inches) => "G20 (in)".to_string(),
+            None if stats.mixes_units() => format!("mixed G20/G21, {} switches", stats.unit_switches),
+            None => "no G20/G21".to_string(),
+        },
+    ));
+    if app.toolpath.bounds.initialized {
+        let size = app.toolpath.bounds.size();
+        let other = units::toggle(app.display_units);
+        let in_other = |mm| units::format_length(other, mm);
+        rows.push((
+            "Extent",
+            format!(
+                "X {} × Y {} × Z {} ({} × {} × {} {})",
+                length(size.x),
+                length(size.y),
+                length(size.z),
+                in_other(size.x),
+                in_other(size.y),
+                in_other(size.z),
+                units::label(other)
+            ),
+        ));
+    }
+    rows.push(("Path length", length(app.segment_end_distances.last().copied().unwrap_or(0.0))));
+    rows.push(("Estimated time", format_duration(app.estimated_time)));
+    if let Some(air) = &app.air_cuts {
+        rows.push(("Air cuts", format!("{} moves, {}, {}", air.count, length(air.distance), format_duration(air.time))));
+    }
+    if app.config.machine().is_some() {
+        let lost: f64 = app.feed_limits.iter().map(|zone| zone.time_lost).sum();
+        rows.push(("Feed-limited zones", format!("{}, +{}", app.feed_limits.len(), format_duration(lost))));
+    }
+    if let Some(trace) = &app.trace {
+        let tolerance = app.config.ui.trace_tolerance;
+        let off = (0..trace.points.len()).filter(|&index| trace.is_off_path(index, tolerance)).count();
+        rows.push(("Position trace", format!("{} of {} points off path by more than {}", off, trace.points.len(), length(tolerance))));
+    }
+    rows.push((
+        "Sharp corners",
+        format!("{} at ≥{:.0}°", app.corners.len(), app.config.ui.corner_angle),
+    ));
+    rows
+}
+
+/// Renders the whole scene from `yaw`/`pitch` (degrees), fitted to the snapshot, as a PNG.
+fn snapshot(app: &mut App, yaw: f64, pitch: f64, projection: ProjectionMode) -> Vec<u8> {
+    let saved = app.view.clone();
+    app.view = ViewState {
+        pan: Vec2::new(0.0, 0.0),
+        zoom: 1.0,
+        yaw: yaw.to_radians(),
+        pitch: pitch.to_radians(),
+        projection,
+        ..saved.clone()
+    };
+    let (width, height) = SNAPSHOT_SIZE;
+    let metrics = app.compute_view_metrics(Rect::new(0, 0, width, height));
+    let mut raster = Raster::new(
+        width as usize,
+        height as usize,
+        [metrics.center.x - metrics.half_w, metrics.center.x + metrics.half_w],
+        [metrics.center.y - metrics.half_h, metrics.center.y + metrics.half_h],
+        app.config.theme.background,
+    );
+    paint_scene(&mut raster, app, metrics);
+    app.view = saved;
+    encode_png(raster.width, raster.height, &raster.pixels)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use cnc_gcode::{Diagnostic, LineSegment, MoveKind};
+    use cnc_geom::RotaryAngles;
+    use std::path::PathBuf;
+
+    #[test]
+    fn zones_are_listed_by_source_line() {
+        let toolpath = Toolpath {
+            line_segment_ends: vec![0, 2, 5],
+            ..Toolpath::default()
+        };
+        let zones = [FeedLimitZone {
+            segments: 1..4,
+            programmed: 3000.0,
+            reached: 420.0,
+            time_lost: 75.0,
+        }];
+        let report = feed_limit_report(&toolpath, &zones, 0.95);
+        assert!(report.contains("lines 2-3: 3 moves, F3000 programmed, 420 mm/min reached, +75.0 s"));
+        assert!(report.ends_with("time lost: 1:15\n"));
+    }
+
+    #[test]
+    fn review_report_lists_stats_diagnostics_and_views() {
+        let mut toolpath = Toolpath {
+            segments: vec![LineSegment {
+                start: Vec3::new(0.0, 0.0, 0.0),
+                end: Vec3::new(10.0, 5.0, -1.0),
+                kind: MoveKind::Feed,
+                feed: Some(500.0),
+                rotary: RotaryAngles::default(),
+            }],
+            line_segment_ends: vec![1],
+            diagnostics: vec![Diagnostic {
+                line: 1,
+                severity: Severity::Warning,
+                message: "feed <0".to_string(),
+                lint: None,
+            }],
+            ..Toolpath::default()
+        };
+        toolpath.bounds.include(Vec3::new(0.0, 0.0, 0.0));
+        toolpath.bounds.include(Vec3::new(10.0, 5.0, -1.0));
+        let config = Config::defaults(&[]).unwrap();
+        let mut app = App::new(config, toolpath, PathBuf::from("demo.nc"), vec!["G1 X10 Y5 Z-1".to_string()]);
+        let markdown = review_report(&mut app, false);
+        assert!(markdown.starts_with("# Review report: demo.nc\n"));
+        assert!(markdown.contains("| Extent | X 10.000 mm × Y 5.000 mm × Z 1.000 mm (0.3937 × 0.1969 × 0.0394 in) |"));
+        assert!(markdown.contains("- line 1: warning: feed <0\n"));
+        assert_eq!(markdown.matches("](data:image/png;base64,iVBORw0KGgo").count(), 4);
+
+        let html = review_report(&mut app, true);
+        assert!(html.contains("<li>line 1: warning: feed &lt;0</li>"));
+        assert_eq!(html.matches("<img alt=").count(), 4);
+        assert_eq!(app.view.yaw, app.initial_view.yaw);
+    }
+
+    #[test]
+    fn load_report_fails_on_errors_and_escapes_json() {
+        let toolpath = Toolpath {
+            line_segment_ends: vec![0],
+            diagnostics: vec![Diagnostic {
+                line: 1,
+                severity: Severity::Error,
+                message: "bad \"word\"\n".to_string(),
+                lint: None,
+            }],
+            ..Toolpath::default()
+        };
+        let config = Config::defaults(&[]).unwrap();
+        let mut app = App::new(config, toolpath, PathBuf::from("demo.nc"), vec!["X".to_string()]);
+        assert!(!load_passed(&app));
+        let json = load_report(&app, true);
+        assert!(json.contains("\"ok\": false,"));
+        assert!(json.contains("\"bounds_mm\": null,"));
+        assert!(json.contains("\"message\": \"bad \\\"word\\\"\\n\"}"));
+        assert!(json.contains("\"events\": [],"));
+        let text = load_report(&app, false);
+        assert!(text.starts_with("demo.nc: failed\n") && text.contains("  Program end: none\n"));
+
+        app.toolpath.diagnostics[0].severity = Severity::Warning;
+        assert!(load_passed(&app));
+    }
+}
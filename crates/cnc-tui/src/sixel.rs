@@ -0,0 +1,126 @@
+use std::collections::HashMap;
+use std::fmt::Write;
+
+const MAX_COLORS: usize = 256;
+
+/// Encodes RGB pixels as a Sixel image, keeping the most frequent colors exact.
+pub fn encode_sixel(width: usize, height: usize, rgb: &[u8]) -> String {
+    let (palette, indices) = quantize(rgb);
+    let mut out = String::new();
+    write!(out, "\x1bP0;1;0q\"1;1;{};{}", width, height).ok();
+    for (idx, (r, g, b)) in palette.iter().enumerate() {
+        write!(
+            out,
+            "#{};2;{};{};{}",
+            idx,
+            *r as u32 * 100 / 255,
+            *g as u32 * 100 / 255,
+            *b as u32 * 100 / 255
+        )
+        .ok();
+    }
+
+    let mut bits = vec![0u8; width];
+    for band in (0..height).step_by(6) {
+        let rows = (height - band).min(6);
+        let mut used = vec![false; palette.len()];
+        for row in band..band + rows {
+            for &color in &indices[row * width..(row + 1) * width] {
+                used[color as usize] = true;
+            }
+        }
+        let mut first = true;
+        for (color, _) in used.iter().enumerate().filter(|(_, used)| **used) {
+            bits.iter_mut().for_each(|bit| *bit = 0);
+            for offset in 0..rows {
+                let row = &indices[(band + offset) * width..(band + offset + 1) * width];
+                for (x, &pixel) in row.iter().enumerate() {
+                    if pixel as usize == color {
+                        bits[x] |= 1 << offset;
+                    }
+                }
+            }
+            if !first {
+                out.push('$');
+            }
+            first = false;
+            write!(out, "#{}", color).ok();
+            write_runs(&mut out, &bits);
+        }
+        out.push('-');
+    }
+    out.push_str("\x1b\\");
+    out
+}
+
+fn write_runs(out: &mut String, bits: &[u8]) {
+    let mut x = 0;
+    while x < bits.len() {
+        let value = bits[x];
+        let run = bits[x..].iter().take_while(|&&bit| bit == value).count();
+        if value == 0 && x + run == bits.len() {
+            break;
+        }
+        let ch = (63 + value) as char;
+        if run > 3 {
+            write!(out, "!{}{}", run, ch).ok();
+        } else {
+            (0..run).for_each(|_| out.push(ch));
+        }
+        x += run;
+    }
+}
+
+fn quantize(rgb: &[u8]) -> (Vec<(u8, u8, u8)>, Vec<u8>) {
+    let mut counts: HashMap<(u8, u8, u8), usize> = HashMap::new();
+    for px in rgb.chunks_exact(3) {
+        *counts.entry((px[0], px[1], px[2])).or_default() += 1;
+    }
+    let mut by_count: Vec<_> = counts.into_iter().collect();
+    by_count.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+    let palette: Vec<(u8, u8, u8)> = by_count
+        .iter()
+        .take(MAX_COLORS)
+        .map(|(color, _)| *color)
+        .collect();
+
+    let mut lookup: HashMap<(u8, u8, u8), u8> = HashMap::new();
+    let indices = rgb
+        .chunks_exact(3)
+        .map(|px| {
+            let color = (px[0], px[1], px[2]);
+            *lookup.entry(color).or_insert_with(|| nearest(&palette, color))
+        })
+        .collect();
+    (palette, indices)
+}
+
+fn nearest(palette: &[(u8, u8, u8)], color: (u8, u8, u8)) -> u8 {
+    let distance = |candidate: &(u8, u8, u8)| {
+        let dr = candidate.0 as i32 - color.0 as i32;
+        let dg = candidate.1 as i32 - color.1 as i32;
+        let db = candidate.2 as i32 - color.2 as i32;
+        dr * dr + dg * dg + db * db
+    };
+    palette
+        .iter()
+        .enumerate()
+        .min_by_key(|(_, candidate)| distance(candidate))
+        .map(|(idx, _)| idx as u8)
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encodes_bands_with_run_lengths() {
+        let mut rgb = [0u8, 0, 0].repeat(8 * 7);
+        rgb[0..3].copy_from_slice(&[255, 255, 255]);
+        let sixel = encode_sixel(8, 7, &rgb);
+        assert!(sixel.starts_with("\x1bP0;1;0q\"1;1;8;7#0;2;0;0;0#1;2;100;100;100"));
+        assert!(sixel.contains("#0}!7~$#1@-#0!8@-"));
+        assert!(sixel.ends_with("\x1b\\"));
+    }
+}
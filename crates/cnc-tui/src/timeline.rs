@@ -0,0 +1,183 @@
+use std::ops::Range;
+
+use ratatui::layout::Rect;
+use ratatui::style::{Modifier, Style};
+use ratatui::text::{Line as TextLine, Span};
+use ratatui::widgets::{Block, Borders, Paragraph};
+use ratatui::Frame;
+
+use crate::app::App;
+use crate::render::format_duration;
+use cnc_gcode::Toolpath;
+
+pub const TIMELINE_HEIGHT: u16 = 3;
+
+/// A run of segments sharing one operation and tool.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TimelineBlock {
+    pub label: String,
+    pub segments: Range<usize>,
+    pub start_time: f64,
+    pub end_time: f64,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct TimelineState {
+    pub open: bool,
+    pub cursor: usize,
+    pub blocks: Vec<TimelineBlock>,
+}
+
+impl TimelineState {
+    pub fn new(toolpath: &Toolpath, segment_end_times: &[f64]) -> Self {
+        Self {
+            blocks: timeline_blocks(toolpath, segment_end_times),
+            ..Self::default()
+        }
+    }
+}
+
+/// Splits the program into blocks wherever the operation or active tool changes.
+pub fn timeline_blocks(toolpath: &Toolpath, segment_end_times: &[f64]) -> Vec<TimelineBlock> {
+    let key = |idx: usize| {
+        let operations = &toolpath.operations;
+        let op = Some(operations.partition_point(|op| op.segments.end <= idx))
+            .filter(|&pos| operations.get(pos).is_some_and(|op| op.segments.contains(&idx)));
+        let line = toolpath.line_segment_ends.partition_point(|&end| end <= idx);
+        let tool = toolpath.line_states.get(line).and_then(|state| state.tool);
+        (op, tool)
+    };
+    let mut blocks: Vec<TimelineBlock> = Vec::new();
+    let mut current = None;
+    for (idx, &end_time) in segment_end_times.iter().enumerate() {
+        let next = key(idx);
+        if current == Some(next)
+            && let Some(block) = blocks.last_mut()
+        {
+            block.segments.end = idx + 1;
+            block.end_time = end_time;
+            continue;
+        }
+        current = Some(next);
+        let (op, tool) = next;
+        let mut label = op
+            .map(|op| toolpath.operations[op].name.clone())
+            .unwrap_or_default();
+        if let Some(tool) = tool {
+            if !label.is_empty() {
+                label.push(' ');
+            }
+            label.push_str(&format!("T{}", tool));
+        }
+        let start_time = blocks.last().map(|block| block.end_time).unwrap_or(0.0);
+        blocks.push(TimelineBlock {
+            label,
+            segments: idx..idx + 1,
+            start_time,
+            end_time,
+        });
+    }
+    blocks
+}
+
+/// Blocks laid out proportionally to estimated time, with the selection and playback head marked.
+pub fn draw_timeline(frame: &mut Frame<'_>, app: &App, area: Rect) {
+    let theme = &app.config.theme;
+    let blocks = &app.timeline.blocks;
+    let mut title = " Timeline".to_string();
+    if let Some(block) = blocks.get(app.timeline.cursor) {
+        title.push_str(&format!(
+            "  {}  {}..{} ",
+            block.label,
+            format_duration(block.start_time),
+            format_duration(block.end_time)
+        ));
+    }
+    let frame_block = Block::default()
+        .borders(Borders::ALL)
+        .title(title)
+        .style(Style::default().bg(theme.background).fg(theme.axis_z));
+    let inner = frame_block.inner(area);
+    frame.render_widget(frame_block, area);
+
+    let width = inner.width as usize;
+    let total = app.estimated_time.max(1e-6);
+    let palette = [
+        theme.axis_x,
+        theme.axis_y,
+        theme.code_number,
+        theme.code_label,
+        theme.path_feed,
+    ];
+    let head = app
+        .highlighted_segment()
+        .and_then(|idx| app.segment_end_times.get(idx).copied())
+        .map(|time| ((time / total * width as f64) as usize).min(width.saturating_sub(1)));
+    let mut spans = Vec::new();
+    let mut block_idx = 0;
+    let mut label_pos = 0;
+    for column in 0..width {
+        let time = (column as f64 + 0.5) / width as f64 * total;
+        while block_idx + 1 < blocks.len() && blocks[block_idx].end_time < time {
+            block_idx += 1;
+            label_pos = 0;
+        }
+        let Some(block) = blocks.get(block_idx) else {
+            break;
+        };
+        let mut style = Style::default()
+            .bg(palette[block_idx % palette.len()])
+            .fg(theme.background);
+        if block_idx == app.timeline.cursor {
+            style = style.add_modifier(Modifier::REVERSED | Modifier::BOLD);
+        }
+        let ch = if head == Some(column) {
+            '│'
+        } else {
+            block.label.chars().nth(label_pos).unwrap_or(' ')
+        };
+        label_pos += 1;
+        spans.push(Span::styled(ch.to_string(), style));
+    }
+    frame.render_widget(Paragraph::new(TextLine::from(spans)), inner);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cnc_gcode::{LineSegment, LineState, MoveKind, Operation};
+    use cnc_geom::{RotaryAngles, Vec3};
+
+    #[test]
+    fn splits_on_operation_and_tool_changes() {
+        let segment = LineSegment {
+            start: Vec3::new(0.0, 0.0, 0.0),
+            end: Vec3::new(1.0, 0.0, 0.0),
+            kind: MoveKind::Feed,
+            feed: Some(100.0),
+            rotary: RotaryAngles::default(),
+        };
+        let state = |tool| LineState {
+            tool: Some(tool),
+            ..LineState::default()
+        };
+        let toolpath = Toolpath {
+            segments: vec![segment; 4],
+            line_segment_ends: vec![1, 2, 3, 4],
+            line_states: vec![state(1), state(1), state(2), state(2)],
+            operations: vec![Operation {
+                name: "Face1".to_string(),
+                lines: 0..2,
+                segments: 0..2,
+            }],
+            ..Toolpath::default()
+        };
+
+        let blocks = timeline_blocks(&toolpath, &[1.0, 2.0, 3.0, 4.0]);
+        assert_eq!(blocks.len(), 2);
+        assert_eq!(blocks[0].label, "Face1 T1");
+        assert_eq!(blocks[0].segments, 0..2);
+        assert_eq!(blocks[1].label, "T2");
+        assert_eq!((blocks[1].start_time, blocks[1].end_time), (2.0, 4.0));
+    }
+}
@@ -0,0 +1,29 @@
+use cnc_gcode::Units;
+
+/// Millimetres per display unit.
+pub fn scale(units: Units) -> f64 {
+    units.mm_per_unit()
+}
+
+pub fn label(units: Units) -> &'static str {
+    match units {
+        Units::Millimeters => "mm",
+        Units::Inches => "in",
+    }
+}
+
+/// A millimetre length as a display-unit number, with one more decimal for inches.
+pub fn format_length(units: Units, mm: f64) -> String {
+    let precision = match units {
+        Units::Millimeters => 3,
+        Units::Inches => 4,
+    };
+    format!("{:.*}", precision, mm / scale(units))
+}
+
+pub fn toggle(units: Units) -> Units {
+    match units {
+        Units::Millimeters => Units::Inches,
+        Units::Inches => Units::Millimeters,
+    }
+}